@@ -54,7 +54,7 @@ async fn main() -> Result<()> {
             .expect("token query null value!")
             .as_bigint()
             .expect("token wrong type!");
-        assert_eq!(t, qt);
+        assert_eq!(t, i128::from(qt));
         println!("token for {}: {}", pk, t);
     }
 
@@ -3,6 +3,7 @@ use std::borrow::Cow;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 
 use scylla_cql::frame::request::SerializableRequest;
+use scylla_cql::frame::response::result::{CqlValue, Row};
 use scylla_cql::frame::value::SerializedValues;
 use scylla_cql::frame::value::ValueList;
 use scylla_cql::frame::{request::query, Compression, SerializedRequest};
@@ -16,7 +17,9 @@ fn make_query<'a>(contents: &'a str, values: &'a SerializedValues) -> query::Que
             values: Cow::Borrowed(values),
             page_size: None,
             paging_state: None,
+            skip_metadata: false,
             timestamp: None,
+            keyspace: None,
         },
     }
 }
@@ -43,7 +46,9 @@ fn serialized_request_make_bench(c: &mut Criterion) {
                     let _ = criterion::black_box(SerializedRequest::make(
                         query,
                         Some(Compression::Lz4),
+                        0,
                         false,
+                        None,
                     ));
                 })
             },
@@ -51,5 +56,55 @@ fn serialized_request_make_bench(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, serialized_request_make_bench);
+fn large_blob_request_make_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SerializedRequest.LargeBlob");
+    for blob_size in [1 << 16, 1 << 20, 16 << 20] {
+        let blob = vec![0xab_u8; blob_size];
+        let values = (1234, &blob[..]).serialized().unwrap().into_owned();
+        let query = make_query("INSERT INTO ks.blobs (id, data) VALUES (?, ?)", &values);
+        group.bench_with_input(
+            BenchmarkId::new("SerializedRequest::make", blob_size),
+            &query,
+            |b, query| {
+                b.iter(|| {
+                    let _ =
+                        criterion::black_box(SerializedRequest::make(query, None, 0, false, None));
+                })
+            },
+        );
+    }
+}
+
+fn row_access_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Row.TextAccess");
+    for text_size in [16, 1 << 10, 1 << 16] {
+        let row = Row {
+            columns: vec![Some(CqlValue::Text("a".repeat(text_size)))],
+        };
+        group.bench_with_input(BenchmarkId::new("get_str", text_size), &row, |b, row| {
+            b.iter(|| {
+                let _ = criterion::black_box(row.get_str(0).unwrap());
+            })
+        });
+        // into_typed consumes the row, so the owned path pays for cloning it
+        group.bench_with_input(BenchmarkId::new("into_typed", text_size), &row, |b, row| {
+            b.iter(|| {
+                let _ = criterion::black_box(
+                    Row {
+                        columns: row.columns.clone(),
+                    }
+                    .into_typed::<(String,)>()
+                    .unwrap(),
+                );
+            })
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    serialized_request_make_bench,
+    large_blob_request_make_bench,
+    row_access_bench
+);
 criterion_main!(benches);
@@ -2,7 +2,9 @@
 
 use crate::frame::frame_errors::{FrameError, ParseError};
 use crate::frame::protocol_features::ProtocolFeatures;
-use crate::frame::value::SerializeValuesError;
+use crate::frame::response::result::ColumnType;
+use crate::frame::value::{NamedValuesMismatch, SerializeValuesError};
+use crate::frame::ProtocolVersion;
 use crate::Consistency;
 use bytes::Bytes;
 use std::io::ErrorKind;
@@ -49,6 +51,10 @@ pub enum QueryError {
     /// Address translation failed
     #[error("Address translation failed: {0}")]
     TranslationError(#[from] TranslationError),
+
+    /// The session was shut down, so it can't execute any more requests
+    #[error("Session is closed")]
+    SessionClosed,
 }
 
 /// An error sent from the database in response to a query
@@ -340,14 +346,44 @@ pub enum BadQuery {
     #[error("Serializing values failed: {0} ")]
     SerializeValuesError(#[from] SerializeValuesError),
 
+    /// Names of values passed to a prepared statement don't match its bind markers
+    #[error("{0}")]
+    NamedValuesMismatch(#[from] NamedValuesMismatch),
+
+    /// The number of values passed to a prepared statement doesn't match the number of its bind markers
+    #[error("Prepared statement has {expected} bind markers, but {provided} values were provided")]
+    WrongNumberOfValues { expected: usize, provided: usize },
+
     /// Serialized values are too long to compute partition key
     #[error("Serialized values are too long to compute partition key! Length: {0}, Max allowed length: {1}")]
     ValuesTooLongForKey(usize, usize),
 
+    /// The partitioner used by the cluster is not supported by the driver, so tokens can't be computed
+    #[error("Partitioner {0} is unsupported for token awareness")]
+    UnsupportedPartitioner(String),
+
+    /// A value bound to an `ascii` marker of a prepared statement contains non-ASCII characters
+    #[error("Value bound to ascii marker {0} contains non-ASCII characters")]
+    NonAsciiValue(String),
+
+    /// A value bound to a prepared statement is not a valid value of the type of its bind marker.
+    /// Checked only if enabled with `PreparedStatement::set_check_value_types`.
+    #[error("Value bound to marker {name} is not a valid value of type {expected:?}")]
+    ValueTypeMismatch { name: String, expected: ColumnType },
+
     /// Passed invalid keyspace name to use
     #[error("Passed invalid keyspace name to use: {0}")]
     BadKeyspaceName(#[from] BadKeyspaceName),
 
+    /// Too many statements in a batch - the protocol allows at most 65535 statements
+    #[error("Number of statements in a batch is {0}, which exceeds the maximum of 65535")]
+    TooManyQueriesInBatchStatement(usize),
+
+    /// The keyspace of a single request was set, but the connection uses a protocol
+    /// version which doesn't support it - it's supported since protocol v5
+    #[error("Setting the keyspace of a single request requires protocol v5, but the connection uses {0}")]
+    PerRequestKeyspaceUnsupported(ProtocolVersion),
+
     /// Other reasons of bad query
     #[error("{0}")]
     Other(String),
@@ -403,6 +439,14 @@ pub enum NewSessionError {
     /// Address translation failed
     #[error("Address translation failed: {0}")]
     TranslationError(#[from] TranslationError),
+
+    /// The session was shut down while it was being created
+    #[error("Session is closed")]
+    SessionClosed,
+
+    /// The default page size set in the session config is not positive
+    #[error("Invalid default page size: {0}, must be larger than 0")]
+    InvalidPageSize(i32),
 }
 
 /// Invalid keyspace name given to `Session::use_keyspace()`
@@ -439,6 +483,12 @@ impl From<SerializeValuesError> for QueryError {
     }
 }
 
+impl From<NamedValuesMismatch> for QueryError {
+    fn from(mismatch_err: NamedValuesMismatch) -> QueryError {
+        QueryError::BadQuery(BadQuery::NamedValuesMismatch(mismatch_err))
+    }
+}
+
 impl From<ParseError> for QueryError {
     fn from(parse_error: ParseError) -> QueryError {
         QueryError::InvalidMessage(format!("Error parsing message: {}", parse_error))
@@ -478,6 +528,7 @@ impl From<QueryError> for NewSessionError {
             QueryError::UnableToAllocStreamId => NewSessionError::UnableToAllocStreamId,
             QueryError::RequestTimeout(msg) => NewSessionError::RequestTimeout(msg),
             QueryError::TranslationError(e) => NewSessionError::TranslationError(e),
+            QueryError::SessionClosed => NewSessionError::SessionClosed,
         }
     }
 }
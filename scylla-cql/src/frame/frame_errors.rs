@@ -13,14 +13,20 @@ pub enum FrameError {
     FrameFromClient,
     #[error("Received frame marked as coming from the server")]
     FrameFromServer,
-    #[error("Received a frame from version {0}, but only 4 is supported")]
+    #[error("Received a frame from version {0}, but only versions 4 and 5 are supported")]
     VersionNotSupported(u8),
     #[error("Connection was closed before body was read: missing {0} out of {1}")]
     ConnectionClosed(usize, usize),
+    #[error("Received a frame with a body of {0} bytes, but at most {1} bytes are allowed")]
+    FrameTooBig(usize, usize),
     #[error("Frame decompression failed.")]
     FrameDecompression,
     #[error("Frame compression failed.")]
     FrameCompression,
+    #[error("Segment header checksum mismatch: expected {0:#08x}, computed {1:#08x}")]
+    SegmentHeaderChecksumMismatch(u32, u32),
+    #[error("Segment payload checksum mismatch: expected {0:#010x}, computed {1:#010x}")]
+    SegmentPayloadChecksumMismatch(u32, u32),
     #[error(transparent)]
     StdIoError(#[from] std::io::Error),
     #[error("Unrecognized opcode{0}")]
@@ -37,6 +43,11 @@ pub enum ParseError {
     BadDataToSerialize(String),
     #[error("Could not deserialize frame: {0}")]
     BadIncomingData(String),
+    /// The server sent a result without column metadata (the `NO_METADATA` flag was set),
+    /// but the cached result metadata describes a different number of columns.
+    /// The metadata may have been outdated by a schema change.
+    #[error("Result has {0} columns, but the cached result metadata describes {1}")]
+    CachedMetadataMismatch(usize, usize),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
     #[error("type not yet implemented, id: {0}")]
@@ -2,6 +2,7 @@ pub mod frame_errors;
 pub mod protocol_features;
 pub mod request;
 pub mod response;
+pub mod segment;
 pub mod server_event_type;
 pub mod types;
 pub mod value;
@@ -14,6 +15,7 @@ use bytes::{Buf, BufMut, Bytes};
 use tokio::io::{AsyncRead, AsyncReadExt};
 use uuid::Uuid;
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
 use request::SerializableRequest;
@@ -27,6 +29,59 @@ const FLAG_TRACING: u8 = 0x02;
 const FLAG_CUSTOM_PAYLOAD: u8 = 0x04;
 const FLAG_WARNING: u8 = 0x08;
 
+/// A version of the CQL native protocol supported by the driver.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum ProtocolVersion {
+    /// Version 4, supported both by Scylla and Cassandra.
+    #[default]
+    V4,
+    /// Version 5, supported by Cassandra 4.0 and newer.
+    /// Frames are sent in self-contained segments protected by checksums.
+    V5,
+}
+
+impl ProtocolVersion {
+    /// The highest version supported by the driver.
+    pub const MAX: ProtocolVersion = ProtocolVersion::V5;
+
+    /// The number of the version, as sent in frame headers.
+    pub fn as_u8(self) -> u8 {
+        match self {
+            ProtocolVersion::V4 => 4,
+            ProtocolVersion::V5 => 5,
+        }
+    }
+
+    /// The version preceding this one, if the driver supports it.
+    pub fn lower(self) -> Option<ProtocolVersion> {
+        match self {
+            ProtocolVersion::V4 => None,
+            ProtocolVersion::V5 => Some(ProtocolVersion::V4),
+        }
+    }
+}
+
+impl TryFrom<u8> for ProtocolVersion {
+    type Error = FrameError;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            4 => Ok(ProtocolVersion::V4),
+            5 => Ok(ProtocolVersion::V5),
+            _ => Err(FrameError::VersionNotSupported(version)),
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.as_u8())
+    }
+}
+
+/// The maximum size of a frame body allowed by the protocol - 256 MiB.
+pub const MAX_FRAME_BODY_SIZE: usize = 256 * 1024 * 1024;
+
 // All of the Authenticators supported by Scylla
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Authenticator {
@@ -57,30 +112,95 @@ impl ToString for Compression {
 
 pub struct SerializedRequest {
     data: Vec<u8>,
+    request_size: usize,
+}
+
+// The size of the custom payload as written by `types::write_bytes_map`
+fn custom_payload_size(custom_payload: &HashMap<String, Bytes>) -> usize {
+    2 + custom_payload
+        .iter()
+        .map(|(key, value)| 2 + key.len() + 4 + value.len())
+        .sum::<usize>()
 }
 
 impl SerializedRequest {
+    /// Serializes the request into a frame of protocol v4.\
+    /// Bodies shorter than `compression_threshold` bytes are sent uncompressed,
+    /// even if `compression` is set.\
+    /// If `custom_payload` is set, it is sent along with the request.
     pub fn make<R: SerializableRequest>(
         req: &R,
         compression: Option<Compression>,
+        compression_threshold: usize,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Bytes>>,
+    ) -> Result<SerializedRequest, FrameError> {
+        Self::make_with_version(
+            req,
+            ProtocolVersion::V4,
+            compression,
+            compression_threshold,
+            tracing,
+            custom_payload,
+        )
+    }
+
+    /// Serializes the request into a frame of the given protocol version.\
+    /// In protocol v5 frames are compressed as a part of segments (see [`segment`]),
+    /// so `compression` and `compression_threshold` are ignored.
+    pub fn make_with_version<R: SerializableRequest>(
+        req: &R,
+        version: ProtocolVersion,
+        compression: Option<Compression>,
+        compression_threshold: usize,
+        tracing: bool,
+        custom_payload: Option<&HashMap<String, Bytes>>,
     ) -> Result<SerializedRequest, FrameError> {
         let mut flags = 0;
-        let mut data = vec![0; HEADER_SIZE];
+        let compression = compression.filter(|_| version == ProtocolVersion::V4);
+
+        // Reserving the whole body up front avoids reallocating (and copying)
+        // the buffer while large values are written into it
+        let body_size_hint =
+            custom_payload.map_or(0, custom_payload_size) + req.serialized_size_hint();
+        let mut data = Vec::with_capacity(HEADER_SIZE + body_size_hint);
+        data.resize(HEADER_SIZE, 0);
+
+        // The custom payload precedes the request body
+        let mut request_size = 0;
+        let mut serialize_body = |buf: &mut Vec<u8>| -> Result<(), FrameError> {
+            if let Some(custom_payload) = custom_payload {
+                types::write_bytes_map(custom_payload, buf)?;
+            }
+            let len_before = buf.len();
+            req.serialize_with_version(version, buf)?;
+            request_size = buf.len() - len_before;
+            Ok(())
+        };
+
+        match compression {
+            Some(compression) => {
+                let mut body = Vec::with_capacity(body_size_hint);
+                serialize_body(&mut body)?;
+                if body.len() >= compression_threshold {
+                    flags |= FLAG_COMPRESSION;
+                    compress_append(&body, compression, &mut data)?;
+                } else {
+                    data.extend_from_slice(&body);
+                }
+            }
+            None => serialize_body(&mut data)?,
+        }
 
-        if let Some(compression) = compression {
-            flags |= FLAG_COMPRESSION;
-            let body = req.to_bytes()?;
-            compress_append(&body, compression, &mut data)?;
-        } else {
-            req.serialize(&mut data)?;
+        if custom_payload.is_some() {
+            flags |= FLAG_CUSTOM_PAYLOAD;
         }
 
         if tracing {
             flags |= FLAG_TRACING;
         }
 
-        data[0] = 4; // We only support version 4 for now
+        data[0] = version.as_u8();
         data[1] = flags;
         // Leave space for the stream number
         data[4] = R::OPCODE as u8;
@@ -88,7 +208,13 @@ impl SerializedRequest {
         let req_size = (data.len() - HEADER_SIZE) as u32;
         data[5..9].copy_from_slice(&req_size.to_be_bytes());
 
-        Ok(Self { data })
+        Ok(Self { data, request_size })
+    }
+
+    /// Returns the size of the serialized request in bytes,
+    /// before compression and without the custom payload.
+    pub fn get_request_size(&self) -> usize {
+        self.request_size
     }
 
     pub fn set_stream(&mut self, stream: i16) {
@@ -111,7 +237,7 @@ pub struct FrameParams {
 impl Default for FrameParams {
     fn default() -> Self {
         Self {
-            version: 0x04,
+            version: ProtocolVersion::V4.as_u8(),
             flags: 0x00,
             stream: 0,
         }
@@ -120,20 +246,49 @@ impl Default for FrameParams {
 
 pub async fn read_response_frame(
     reader: &mut (impl AsyncRead + Unpin),
+) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
+    read_response_frame_with_max_body_size(reader, MAX_FRAME_BODY_SIZE).await
+}
+
+/// Reads a response frame, failing with [`FrameError::FrameTooBig`] if its header
+/// announces a body longer than `max_body_size` bytes.\
+/// The body of such a frame is not read, so nothing is allocated for it.
+pub async fn read_response_frame_with_max_body_size(
+    reader: &mut (impl AsyncRead + Unpin),
+    max_body_size: usize,
 ) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
     let mut raw_header = [0u8; HEADER_SIZE];
     reader.read_exact(&mut raw_header[..]).await?;
 
+    let (frame_params, opcode, length) = parse_response_header(&raw_header, max_body_size)?;
+
+    let mut raw_body = Vec::with_capacity(length).limit(length);
+    while raw_body.has_remaining_mut() {
+        let n = reader.read_buf(&mut raw_body).await?;
+        if n == 0 {
+            // EOF, too early
+            return Err(FrameError::ConnectionClosed(
+                raw_body.remaining_mut(),
+                length,
+            ));
+        }
+    }
+
+    Ok((frame_params, opcode, raw_body.into_inner().into()))
+}
+
+// Parses the header of a response frame, returning the length of its body
+fn parse_response_header(
+    raw_header: &[u8; HEADER_SIZE],
+    max_body_size: usize,
+) -> Result<(FrameParams, ResponseOpcode, usize), FrameError> {
     let mut buf = &raw_header[..];
 
-    // TODO: Validate version
     let version = buf.get_u8();
     if version & 0x80 != 0x80 {
         return Err(FrameError::FrameFromClient);
     }
-    if version & 0x7F != 0x04 {
-        return Err(FrameError::VersionNotSupported(version & 0x7f));
-    }
+    ProtocolVersion::try_from(version & 0x7F)?;
 
     let flags = buf.get_u8();
     let stream = buf.get_i16();
@@ -146,38 +301,40 @@ pub async fn read_response_frame(
 
     let opcode = ResponseOpcode::try_from(buf.get_u8())?;
 
-    // TODO: Guard from frames that are too large
     let length = buf.get_u32() as usize;
-
-    let mut raw_body = Vec::with_capacity(length).limit(length);
-    while raw_body.has_remaining_mut() {
-        let n = reader.read_buf(&mut raw_body).await?;
-        if n == 0 {
-            // EOF, too early
-            return Err(FrameError::ConnectionClosed(
-                raw_body.remaining_mut(),
-                length,
-            ));
-        }
+    if length > max_body_size {
+        return Err(FrameError::FrameTooBig(length, max_body_size));
     }
 
-    Ok((frame_params, opcode, raw_body.into_inner().into()))
+    Ok((frame_params, opcode, length))
 }
 
 pub struct ResponseBodyWithExtensions {
     pub trace_id: Option<Uuid>,
     pub warnings: Vec<String>,
+    pub custom_payload: Option<HashMap<String, Bytes>>,
     pub body: Bytes,
 }
 
 pub fn parse_response_body_extensions(
+    flags: u8,
+    compression: Option<Compression>,
+    body: Bytes,
+) -> Result<ResponseBodyWithExtensions, FrameError> {
+    parse_response_body_extensions_with_max_body_size(flags, compression, body, MAX_FRAME_BODY_SIZE)
+}
+
+/// Parses the extensions of a response body, failing with [`FrameError::FrameTooBig`]
+/// if a compressed body claims to decompress to more than `max_body_size` bytes.
+pub fn parse_response_body_extensions_with_max_body_size(
     flags: u8,
     compression: Option<Compression>,
     mut body: Bytes,
+    max_body_size: usize,
 ) -> Result<ResponseBodyWithExtensions, FrameError> {
     if flags & FLAG_COMPRESSION != 0 {
         if let Some(compression) = compression {
-            body = decompress(&body, compression)?.into();
+            body = decompress(&body, compression, max_body_size)?.into();
         } else {
             return Err(FrameError::NoCompressionNegotiated);
         }
@@ -203,19 +360,24 @@ pub fn parse_response_body_extensions(
         Vec::new()
     };
 
-    if flags & FLAG_CUSTOM_PAYLOAD != 0 {
-        // TODO: Do something useful with the custom payload map
-        // For now, just skip it
+    let custom_payload = if flags & FLAG_CUSTOM_PAYLOAD != 0 {
         let body_len = body.len();
         let buf = &mut &*body;
-        types::read_bytes_map(buf)?;
+        let custom_payload = types::read_bytes_map(buf)?
+            .into_iter()
+            .map(|(key, value)| (key, Bytes::from(value)))
+            .collect();
         let buf_len = buf.len();
         body.advance(body_len - buf_len);
-    }
+        Some(custom_payload)
+    } else {
+        None
+    };
 
     Ok(ResponseBodyWithExtensions {
         trace_id,
         warnings,
+        custom_payload,
         body,
     })
 }
@@ -246,22 +408,102 @@ fn compress_append(
     }
 }
 
-fn decompress(mut comp_body: &[u8], compression: Compression) -> Result<Vec<u8>, FrameError> {
+fn decompress(
+    mut comp_body: &[u8],
+    compression: Compression,
+    max_body_size: usize,
+) -> Result<Vec<u8>, FrameError> {
+    // The decompressed length comes from the server and is allocated up front,
+    // so it has to be checked before decompressing
     match compression {
         Compression::Lz4 => {
+            if comp_body.len() < std::mem::size_of::<u32>() {
+                return Err(FrameError::FrameDecompression);
+            }
             let uncomp_len = comp_body.get_u32() as usize;
+            if uncomp_len > max_body_size {
+                return Err(FrameError::FrameTooBig(uncomp_len, max_body_size));
+            }
             let uncomp_body = lz4_flex::decompress(comp_body, uncomp_len)?;
             Ok(uncomp_body)
         }
-        Compression::Snappy => snap::raw::Decoder::new()
-            .decompress_vec(comp_body)
-            .map_err(|_| FrameError::FrameDecompression),
+        Compression::Snappy => {
+            let uncomp_len =
+                snap::raw::decompress_len(comp_body).map_err(|_| FrameError::FrameDecompression)?;
+            if uncomp_len > max_body_size {
+                return Err(FrameError::FrameTooBig(uncomp_len, max_body_size));
+            }
+            snap::raw::Decoder::new()
+                .decompress_vec(comp_body)
+                .map_err(|_| FrameError::FrameDecompression)
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::frame::value::ValueList;
+    use std::borrow::Cow;
+
+    #[tokio::test]
+    async fn test_oversized_frame_rejected() {
+        // Header of a RESULT frame claiming a body of 4 GiB - 1
+        let header: &[u8] = &[0x84, 0x00, 0x00, 0x01, 0x08, 0xff, 0xff, 0xff, 0xff];
+        let err = read_response_frame(&mut &header[..]).await.unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::FrameTooBig(0xffff_ffff, MAX_FRAME_BODY_SIZE)
+        ));
+
+        // The limit can be lowered
+        let mut frame = vec![0x84, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x08];
+        frame.extend_from_slice(&[0u8; 8]);
+        let err = read_response_frame_with_max_body_size(&mut &frame[..], 4)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FrameError::FrameTooBig(8, 4)));
+
+        let (_, opcode, body) = read_response_frame_with_max_body_size(&mut &frame[..], 8)
+            .await
+            .unwrap();
+        assert_eq!(opcode, ResponseOpcode::Result);
+        assert_eq!(&body[..], &[0u8; 8]);
+    }
+
+    #[test]
+    fn test_oversized_decompressed_body_rejected() {
+        // A tiny LZ4 body claiming to decompress to 4 GiB - 1
+        let lz4_body = Bytes::from_static(&[0xff, 0xff, 0xff, 0xff, 0x10, 0x00]);
+        // A tiny Snappy body claiming to decompress to 1 GiB (varint-encoded length)
+        let snappy_body = Bytes::from_static(&[0x80, 0x80, 0x80, 0x80, 0x04, 0x00]);
+
+        for (compression, body, claimed_len) in [
+            (Compression::Lz4, lz4_body, 0xffff_ffff),
+            (Compression::Snappy, snappy_body, 1 << 30),
+        ] {
+            let err = parse_response_body_extensions(FLAG_COMPRESSION, Some(compression), body)
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err,
+                FrameError::FrameTooBig(len, MAX_FRAME_BODY_SIZE) if len == claimed_len
+            ));
+        }
+
+        // The limit can be lowered
+        let mut comp_body = Vec::new();
+        compress_append(&[0u8; 100], Compression::Lz4, &mut comp_body).unwrap();
+        let err = parse_response_body_extensions_with_max_body_size(
+            FLAG_COMPRESSION,
+            Some(Compression::Lz4),
+            comp_body.into(),
+            50,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, FrameError::FrameTooBig(100, 50)));
+    }
 
     #[test]
     fn test_lz4_compress() {
@@ -282,8 +524,214 @@ mod test {
         let uncomp_body = "Hello, World!".repeat(100);
         let compression = Compression::Lz4;
         compress_append(uncomp_body.as_bytes(), compression, &mut comp_body).unwrap();
-        let result = decompress(&comp_body[..], compression).unwrap();
+        let result = decompress(&comp_body[..], compression, MAX_FRAME_BODY_SIZE).unwrap();
         assert_eq!(32, comp_body.len());
         assert_eq!(uncomp_body.as_bytes(), result);
     }
+
+    #[test]
+    fn test_snappy_round_trip() {
+        let mut comp_body = Vec::new();
+        let uncomp_body = "Hello, World!".repeat(100);
+        let compression = Compression::Snappy;
+        compress_append(uncomp_body.as_bytes(), compression, &mut comp_body).unwrap();
+        assert!(comp_body.len() < uncomp_body.len());
+        let result = decompress(&comp_body[..], compression, MAX_FRAME_BODY_SIZE).unwrap();
+        assert_eq!(uncomp_body.as_bytes(), result);
+    }
+
+    #[test]
+    fn test_compression_threshold() {
+        let request = request::Query {
+            contents: Cow::Owned("SELECT * FROM ks.tab".repeat(100)),
+            parameters: Default::default(),
+        };
+        let body = request.to_bytes().unwrap();
+
+        for compression in [Compression::Lz4, Compression::Snappy] {
+            // Body below the threshold is sent uncompressed
+            let serialized =
+                SerializedRequest::make(&request, Some(compression), body.len() + 1, false, None)
+                    .unwrap();
+            let data = serialized.get_data();
+            assert_eq!(data[1] & FLAG_COMPRESSION, 0);
+            assert_eq!(&data[HEADER_SIZE..], &body[..]);
+
+            // Body at the threshold is compressed
+            let serialized =
+                SerializedRequest::make(&request, Some(compression), body.len(), false, None)
+                    .unwrap();
+            let data = serialized.get_data();
+            assert_ne!(data[1] & FLAG_COMPRESSION, 0);
+            assert_eq!(
+                decompress(&data[HEADER_SIZE..], compression, MAX_FRAME_BODY_SIZE).unwrap(),
+                &body[..]
+            );
+        }
+    }
+
+    #[test]
+    fn test_v5_request_frame() {
+        let request = request::Query {
+            contents: Cow::Owned("SELECT * FROM ks.tab".repeat(100)),
+            parameters: Default::default(),
+        };
+
+        // Frames of protocol v5 are compressed as a part of segments, not by themselves
+        let serialized = SerializedRequest::make_with_version(
+            &request,
+            ProtocolVersion::V5,
+            Some(Compression::Lz4),
+            0,
+            false,
+            None,
+        )
+        .unwrap();
+        let data = serialized.get_data();
+        assert_eq!(data[0], 0x05);
+        assert_eq!(data[1] & FLAG_COMPRESSION, 0);
+        let mut body = Vec::new();
+        request
+            .serialize_with_version(ProtocolVersion::V5, &mut body)
+            .unwrap();
+        assert_eq!(&data[HEADER_SIZE..], &body[..]);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_version_rejected() {
+        for version in [0x83, 0x86] {
+            let header: &[u8] = &[version, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00];
+            let err = read_response_frame(&mut &header[..]).await.unwrap_err();
+            assert!(matches!(err, FrameError::VersionNotSupported(v) if v == version & 0x7f));
+        }
+
+        let header: &[u8] = &[0x85, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00];
+        let (params, _, _) = read_response_frame(&mut &header[..]).await.unwrap();
+        assert_eq!(params.version, 0x85);
+    }
+
+    #[test]
+    fn test_request_size_hint() {
+        let mut values = value::SerializedValues::new();
+        values.add_value(&42).unwrap();
+        values.add_value(&"text").unwrap();
+        let parameters = request::query::QueryParameters {
+            consistency: Default::default(),
+            serial_consistency: Some(types::SerialConsistency::LocalSerial),
+            timestamp: Some(1234),
+            page_size: Some(100),
+            paging_state: Some(Bytes::from_static(b"paging state")),
+            skip_metadata: false,
+            values: Cow::Owned(values),
+            keyspace: None,
+        };
+        let query = request::Query {
+            contents: Cow::Borrowed("SELECT * FROM ks.tab WHERE a = ? AND b = ?"),
+            parameters,
+        };
+        assert_eq!(
+            query.serialized_size_hint(),
+            query.to_bytes().unwrap().len()
+        );
+
+        let execute = request::Execute {
+            id: Bytes::from_static(b"statement id"),
+            result_metadata_id: None,
+            parameters: query.parameters,
+        };
+        assert_eq!(
+            execute.serialized_size_hint(),
+            execute.to_bytes().unwrap().len()
+        );
+
+        let defaults = request::Query {
+            contents: Cow::Borrowed("SELECT * FROM ks.tab"),
+            parameters: Default::default(),
+        };
+        assert_eq!(
+            defaults.serialized_size_hint(),
+            defaults.to_bytes().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_large_blob_request_allocated_once() {
+        let blob = vec![0xab_u8; 16 << 20];
+        let values = (1, &blob[..]).serialized().unwrap().into_owned();
+        let request = request::Query {
+            contents: Cow::Borrowed("INSERT INTO ks.blobs (id, data) VALUES (?, ?)"),
+            parameters: request::query::QueryParameters {
+                values: Cow::Owned(values),
+                ..Default::default()
+            },
+        };
+        let custom_payload: HashMap<String, Bytes> =
+            [("key".to_string(), Bytes::from_static(b"value"))]
+                .into_iter()
+                .collect();
+
+        for custom_payload in [None, Some(&custom_payload)] {
+            let serialized =
+                SerializedRequest::make(&request, None, 0, false, custom_payload).unwrap();
+            // The frame buffer is allocated with its final size up front,
+            // so the blob is copied into it exactly once, without reallocations
+            assert_eq!(serialized.data.capacity(), serialized.data.len());
+            assert!(serialized.data.len() > blob.len());
+        }
+    }
+
+    #[test]
+    fn test_custom_payload_round_trip() {
+        let request = request::Query {
+            contents: Cow::Borrowed("SELECT * FROM ks.tab"),
+            parameters: Default::default(),
+        };
+        let body = request.to_bytes().unwrap();
+        let custom_payload: HashMap<String, Bytes> = [
+            ("key1".to_string(), Bytes::from_static(b"value1")),
+            ("key2".to_string(), Bytes::new()),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut expected_body = Vec::new();
+        types::write_bytes_map(&custom_payload, &mut expected_body).unwrap();
+        let payload_len = expected_body.len();
+        expected_body.extend_from_slice(&body);
+
+        for compression in [None, Some(Compression::Lz4)] {
+            let serialized =
+                SerializedRequest::make(&request, compression, 0, false, Some(&custom_payload))
+                    .unwrap();
+            // The request size doesn't depend on the payload or compression
+            assert_eq!(serialized.get_request_size(), body.len());
+            let data = serialized.get_data();
+            assert_ne!(data[1] & FLAG_CUSTOM_PAYLOAD, 0);
+
+            // The frame body, as seen by the server, starts with the payload
+            let parsed = parse_response_body_extensions(
+                data[1] & !FLAG_CUSTOM_PAYLOAD,
+                compression,
+                Bytes::copy_from_slice(&data[HEADER_SIZE..]),
+            )
+            .unwrap();
+            assert_eq!(&parsed.body[..], &expected_body[..]);
+
+            // The payload is extracted from responses
+            let parsed = parse_response_body_extensions(
+                data[1],
+                compression,
+                Bytes::copy_from_slice(&data[HEADER_SIZE..]),
+            )
+            .unwrap();
+            assert_eq!(parsed.custom_payload.as_ref(), Some(&custom_payload));
+            assert_eq!(&parsed.body[..], &expected_body[payload_len..]);
+        }
+
+        // No flag and no payload if it wasn't requested
+        let serialized = SerializedRequest::make(&request, None, 0, false, None).unwrap();
+        let data = serialized.get_data();
+        assert_eq!(data[1] & FLAG_CUSTOM_PAYLOAD, 0);
+        assert_eq!(&data[HEADER_SIZE..], &body[..]);
+    }
 }
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use crate::frame::ProtocolVersion;
+
 const RATE_LIMIT_ERROR_EXTENSION: &str = "SCYLLA_RATE_LIMIT_ERROR";
 pub const SCYLLA_LWT_ADD_METADATA_MARK_EXTENSION: &str = "SCYLLA_LWT_ADD_METADATA_MARK";
 pub const LWT_OPTIMIZATION_META_BIT_MASK_KEY: &str = "LWT_OPTIMIZATION_META_BIT_MASK";
@@ -8,6 +10,8 @@ pub const LWT_OPTIMIZATION_META_BIT_MASK_KEY: &str = "LWT_OPTIMIZATION_META_BIT_
 pub struct ProtocolFeatures {
     pub rate_limit_error: Option<i32>,
     pub lwt_optimization_meta_bit_mask: Option<u32>,
+    /// Version of the protocol negotiated for the connection. Some responses differ between versions.
+    pub protocol_version: ProtocolVersion,
 }
 
 // TODO: Log information about options which failed to parse
@@ -19,6 +23,7 @@ impl ProtocolFeatures {
             lwt_optimization_meta_bit_mask: Self::maybe_parse_lwt_optimization_meta_bit_mask(
                 supported,
             ),
+            protocol_version: ProtocolVersion::default(),
         }
     }
 
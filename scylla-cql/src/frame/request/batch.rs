@@ -3,9 +3,10 @@ use std::{borrow::Cow, convert::TryInto};
 
 use crate::frame::{
     frame_errors::ParseError,
-    request::{RequestOpcode, SerializableRequest},
+    request::{query, RequestOpcode, SerializableRequest},
     types::{self, SerialConsistency},
     value::{BatchValues, BatchValuesIterator, SerializedValues},
+    ProtocolVersion,
 };
 
 use super::DeserializableRequest;
@@ -77,6 +78,14 @@ where
     const OPCODE: RequestOpcode = RequestOpcode::Batch;
 
     fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError> {
+        self.serialize_with_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_with_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
         // Serializing type of batch
         buf.put_u8(self.batch_type as u8);
 
@@ -127,7 +136,7 @@ where
             flags |= FLAG_WITH_DEFAULT_TIMESTAMP;
         }
 
-        buf.put_u8(flags);
+        query::write_flags(flags, version, buf);
 
         if let Some(serial_consistency) = self.serial_consistency {
             types::write_serial_consistency(serial_consistency, buf);
@@ -4,6 +4,7 @@ use bytes::{BufMut, Bytes};
 use crate::{
     frame::request::{query, RequestOpcode, SerializableRequest},
     frame::types,
+    frame::ProtocolVersion,
 };
 
 use super::{query::QueryParameters, DeserializableRequest};
@@ -11,6 +12,9 @@ use super::{query::QueryParameters, DeserializableRequest};
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct Execute<'a> {
     pub id: Bytes,
+    /// Id of the result metadata known to the driver, sent only in protocol v5.
+    /// If it doesn't match the current one, the server sends the new metadata along with the rows.
+    pub result_metadata_id: Option<Bytes>,
     pub parameters: query::QueryParameters<'a>,
 }
 
@@ -18,13 +22,30 @@ impl SerializableRequest for Execute<'_> {
     const OPCODE: RequestOpcode = RequestOpcode::Execute;
 
     fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError> {
+        self.serialize_with_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_with_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
         // Serializing statement id
         types::write_short_bytes(&self.id[..], buf)?;
 
+        if version >= ProtocolVersion::V5 {
+            let result_metadata_id = self.result_metadata_id.as_deref().unwrap_or_default();
+            types::write_short_bytes(result_metadata_id, buf)?;
+        }
+
         // Serializing params
-        self.parameters.serialize(buf)?;
+        self.parameters.serialize_with_version(version, buf)?;
         Ok(())
     }
+
+    fn serialized_size_hint(&self) -> usize {
+        2 + self.id.len() + self.parameters.serialized_size()
+    }
 }
 
 impl<'e> DeserializableRequest for Execute<'e> {
@@ -32,6 +53,10 @@ impl<'e> DeserializableRequest for Execute<'e> {
         let id = types::read_short_bytes(buf)?.to_vec().into();
         let parameters = QueryParameters::deserialize(buf)?;
 
-        Ok(Self { id, parameters })
+        Ok(Self {
+            id,
+            result_metadata_id: None,
+            parameters,
+        })
     }
 }
@@ -7,7 +7,10 @@ pub mod query;
 pub mod register;
 pub mod startup;
 
-use crate::{frame::frame_errors::ParseError, Consistency};
+use crate::{
+    frame::{frame_errors::ParseError, ProtocolVersion},
+    Consistency,
+};
 use bytes::{BufMut, Bytes};
 use num_enum::TryFromPrimitive;
 
@@ -42,6 +45,24 @@ pub trait SerializableRequest {
 
     fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError>;
 
+    /// Serializes the request in the format of the given protocol version.
+    /// Most requests are the same in all versions, so by default it's equivalent to [`Self::serialize`].
+    fn serialize_with_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
+        let _ = version;
+        self.serialize(buf)
+    }
+
+    /// Returns the expected size of the serialized request in bytes,
+    /// so that buffers can be allocated up front instead of growing during serialization.
+    /// It's only a hint - an inaccurate value affects performance, but not correctness.
+    fn serialized_size_hint(&self) -> usize {
+        0
+    }
+
     fn to_bytes(&self) -> Result<Bytes, ParseError> {
         let mut v = Vec::new();
         self.serialize(&mut v)?;
@@ -106,14 +127,17 @@ mod tests {
 
     use crate::{
         frame::{
+            frame_errors::ParseError,
             request::{
                 batch::{Batch, BatchStatement, BatchType},
                 execute::Execute,
+                prepare::Prepare,
                 query::{Query, QueryParameters},
                 DeserializableRequest, SerializableRequest,
             },
             types::{self, SerialConsistency},
             value::SerializedValues,
+            ProtocolVersion,
         },
         Consistency,
     };
@@ -128,11 +152,13 @@ mod tests {
             timestamp: None,
             page_size: Some(323),
             paging_state: Some(vec![2, 1, 3, 7].into()),
+            skip_metadata: false,
             values: {
                 let mut vals = SerializedValues::new();
                 vals.add_value(&2137).unwrap();
                 Cow::Owned(vals)
             },
+            keyspace: None,
         };
         let query = Query {
             contents,
@@ -155,14 +181,20 @@ mod tests {
             timestamp: Some(3423434),
             page_size: None,
             paging_state: None,
+            skip_metadata: true,
             values: {
                 let mut vals = SerializedValues::new();
                 vals.add_named_value("the_answer", &42).unwrap();
                 vals.add_named_value("really?", &2137).unwrap();
                 Cow::Owned(vals)
             },
+            keyspace: None,
+        };
+        let execute = Execute {
+            id,
+            result_metadata_id: None,
+            parameters,
         };
-        let execute = Execute { id, parameters };
         {
             let mut buf = Vec::new();
             execute.serialize(&mut buf).unwrap();
@@ -212,7 +244,9 @@ mod tests {
             timestamp: None,
             page_size: None,
             paging_state: None,
+            skip_metadata: false,
             values: Cow::Owned(SerializedValues::new()),
+            keyspace: None,
         };
         let query = Query {
             contents: contents.clone(),
@@ -283,4 +317,177 @@ mod tests {
             let _parse_error = Batch::deserialize(&mut &buf[..]).unwrap_err();
         }
     }
+
+    #[test]
+    fn serial_consistency_flag() {
+        const FLAG_WITH_SERIAL_CONSISTENCY: u8 = 0x10;
+
+        // Without serial consistency neither the flag nor the value are written
+        let parameters = QueryParameters {
+            consistency: Consistency::One,
+            serial_consistency: None,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        parameters.serialize(&mut buf).unwrap();
+        assert_eq!(buf, vec![0x00, 0x01, 0x00]);
+
+        let parameters = QueryParameters {
+            consistency: Consistency::One,
+            serial_consistency: Some(SerialConsistency::LocalSerial),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        parameters.serialize(&mut buf).unwrap();
+        assert_eq!(
+            buf,
+            vec![0x00, 0x01, FLAG_WITH_SERIAL_CONSISTENCY, 0x00, 0x09]
+        );
+    }
+
+    #[test]
+    fn skip_metadata_flag() {
+        const FLAG_SKIP_METADATA: u8 = 0x02;
+
+        let parameters = QueryParameters {
+            consistency: Consistency::One,
+            skip_metadata: true,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        parameters.serialize(&mut buf).unwrap();
+        assert_eq!(buf, vec![0x00, 0x01, FLAG_SKIP_METADATA]);
+
+        let parameters_deserialized = QueryParameters::deserialize(&mut &buf[..]).unwrap();
+        assert!(parameters_deserialized.skip_metadata);
+    }
+
+    #[test]
+    fn v5_request_format() {
+        const FLAG_SKIP_METADATA: u8 = 0x02;
+
+        // Flags are an int instead of a byte
+        let parameters = QueryParameters {
+            consistency: Consistency::One,
+            skip_metadata: true,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        parameters
+            .serialize_with_version(ProtocolVersion::V5, &mut buf)
+            .unwrap();
+        assert_eq!(buf, vec![0x00, 0x01, 0x00, 0x00, 0x00, FLAG_SKIP_METADATA]);
+
+        // The id of the result metadata follows the statement id
+        let execute = Execute {
+            id: Bytes::from_static(&[0xaa]),
+            result_metadata_id: Some(Bytes::from_static(&[0xbb, 0xcc])),
+            parameters,
+        };
+        let mut buf = Vec::new();
+        execute
+            .serialize_with_version(ProtocolVersion::V5, &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..7], &[0x00, 0x01, 0xaa, 0x00, 0x02, 0xbb, 0xcc]);
+
+        // ... but only in protocol v5
+        let mut v4_buf = Vec::new();
+        execute
+            .serialize_with_version(ProtocolVersion::V4, &mut v4_buf)
+            .unwrap();
+        assert_eq!(v4_buf, execute.to_bytes().unwrap());
+        assert_eq!(
+            v4_buf,
+            vec![0x00, 0x01, 0xaa, 0x00, 0x01, FLAG_SKIP_METADATA]
+        );
+
+        // PREPARE is followed by flags
+        let prepare = Prepare {
+            query: "SELECT 1",
+            keyspace: None,
+        };
+        let mut buf = Vec::new();
+        prepare
+            .serialize_with_version(ProtocolVersion::V5, &mut buf)
+            .unwrap();
+        assert_eq!(&buf[buf.len() - 4..], &[0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(buf.len(), prepare.to_bytes().unwrap().len() + 4);
+    }
+
+    #[test]
+    fn keyspace_flag() {
+        const FLAG_WITH_KEYSPACE: u8 = 0x80;
+
+        // The keyspace follows the timestamp
+        let parameters = QueryParameters {
+            consistency: Consistency::One,
+            timestamp: Some(1),
+            keyspace: Some(Cow::Borrowed("ks")),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        parameters
+            .serialize_with_version(ProtocolVersion::V5, &mut buf)
+            .unwrap();
+        assert_eq!(buf.len(), parameters.serialized_size() + 3);
+        assert_eq!(buf[5] & FLAG_WITH_KEYSPACE, FLAG_WITH_KEYSPACE);
+        assert_eq!(&buf[buf.len() - 4..], &[0x00, 0x02, b'k', b's']);
+
+        // PREPARE has its own flag for the keyspace
+        let prepare = Prepare {
+            query: "SELECT 1",
+            keyspace: Some("ks"),
+        };
+        let mut buf = Vec::new();
+        prepare
+            .serialize_with_version(ProtocolVersion::V5, &mut buf)
+            .unwrap();
+        assert_eq!(
+            &buf[buf.len() - 8..],
+            &[0x00, 0x00, 0x00, 0x01, 0x00, 0x02, b'k', b's']
+        );
+
+        // Protocol v4 can't carry the keyspace
+        let mut buf = Vec::new();
+        parameters.serialize(&mut buf).unwrap_err();
+        prepare.serialize(&mut buf).unwrap_err();
+    }
+
+    #[test]
+    fn batch_ser_rejects_values_count_mismatch() {
+        let statements = vec![
+            BatchStatement::Query {
+                text: Cow::Borrowed("INSERT INTO ks.t (a) VALUES (?)"),
+            },
+            BatchStatement::Prepared {
+                id: Cow::Borrowed(&[1, 2, 3]),
+            },
+        ];
+        let mut values = SerializedValues::new();
+        values.add_value(&42).unwrap();
+
+        let batch_with_values = |values: Vec<SerializedValues>| Batch {
+            statements: Cow::Borrowed(&statements),
+            batch_type: BatchType::Unlogged,
+            consistency: Consistency::One,
+            serial_consistency: None,
+            timestamp: None,
+            values,
+        };
+
+        batch_with_values(vec![values.clone(), values.clone()])
+            .serialize(&mut Vec::new())
+            .unwrap();
+
+        for wrong_values in [
+            vec![],
+            vec![values.clone()],
+            vec![values.clone(), values.clone(), values],
+        ] {
+            let err = batch_with_values(wrong_values)
+                .serialize(&mut Vec::new())
+                .unwrap_err();
+            assert!(matches!(err, ParseError::BadDataToSerialize(_)));
+        }
+    }
 }
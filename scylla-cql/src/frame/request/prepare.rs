@@ -2,19 +2,45 @@ use crate::frame::frame_errors::ParseError;
 use bytes::BufMut;
 
 use crate::{
-    frame::request::{RequestOpcode, SerializableRequest},
+    frame::request::{query::check_keyspace_supported, RequestOpcode, SerializableRequest},
     frame::types,
+    frame::ProtocolVersion,
 };
 
+// Prepare flags, only in protocol v5
+const FLAG_WITH_KEYSPACE: i32 = 0x01;
+
 pub struct Prepare<'a> {
     pub query: &'a str,
+    /// Keyspace in which the statement is prepared (and then executed), instead of
+    /// the current keyspace of the connection. It can be sent only in protocol v5.
+    pub keyspace: Option<&'a str>,
 }
 
 impl<'a> SerializableRequest for Prepare<'a> {
     const OPCODE: RequestOpcode = RequestOpcode::Prepare;
 
     fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError> {
+        self.serialize_with_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_with_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
+        check_keyspace_supported(self.keyspace, version)?;
+
         types::write_long_string(self.query, buf)?;
+        if version >= ProtocolVersion::V5 {
+            match self.keyspace {
+                Some(keyspace) => {
+                    types::write_int(FLAG_WITH_KEYSPACE, buf);
+                    types::write_string(keyspace, buf)?;
+                }
+                None => types::write_int(0, buf),
+            }
+        }
         Ok(())
     }
 }
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use crate::frame::{frame_errors::ParseError, types::SerialConsistency};
+use crate::frame::{frame_errors::ParseError, types::SerialConsistency, ProtocolVersion};
 use bytes::{Buf, BufMut, Bytes};
 
 use crate::{
@@ -19,6 +19,8 @@ const FLAG_WITH_PAGING_STATE: u8 = 0x08;
 const FLAG_WITH_SERIAL_CONSISTENCY: u8 = 0x10;
 const FLAG_WITH_DEFAULT_TIMESTAMP: u8 = 0x20;
 const FLAG_WITH_NAMES_FOR_VALUES: u8 = 0x40;
+// Only in protocol v5
+const FLAG_WITH_KEYSPACE: u8 = 0x80;
 const ALL_FLAGS: u8 = FLAG_VALUES
     | FLAG_SKIP_METADATA
     | FLAG_PAGE_SIZE
@@ -37,10 +39,22 @@ impl SerializableRequest for Query<'_> {
     const OPCODE: RequestOpcode = RequestOpcode::Query;
 
     fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError> {
+        self.serialize_with_version(ProtocolVersion::V4, buf)
+    }
+
+    fn serialize_with_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
         types::write_long_string(&self.contents, buf)?;
-        self.parameters.serialize(buf)?;
+        self.parameters.serialize_with_version(version, buf)?;
         Ok(())
     }
+
+    fn serialized_size_hint(&self) -> usize {
+        4 + self.contents.len() + self.parameters.serialized_size()
+    }
 }
 
 impl<'q> DeserializableRequest for Query<'q> {
@@ -61,7 +75,12 @@ pub struct QueryParameters<'a> {
     pub timestamp: Option<i64>,
     pub page_size: Option<i32>,
     pub paging_state: Option<Bytes>,
+    pub skip_metadata: bool,
     pub values: Cow<'a, SerializedValues>,
+    /// Keyspace in which the request is executed, instead of the current keyspace
+    /// of the connection. It can be sent only in protocol v5, and only in QUERY requests -
+    /// the keyspace of a prepared statement is set when preparing it.
+    pub keyspace: Option<Cow<'a, str>>,
 }
 
 impl Default for QueryParameters<'_> {
@@ -72,13 +91,54 @@ impl Default for QueryParameters<'_> {
             timestamp: None,
             page_size: None,
             paging_state: None,
+            skip_metadata: false,
             values: Cow::Borrowed(SerializedValues::EMPTY),
+            keyspace: None,
         }
     }
 }
 
 impl QueryParameters<'_> {
+    /// Returns the number of bytes written by [`Self::serialize`]
+    /// (in protocol v5 the flags take 3 more bytes).
+    pub fn serialized_size(&self) -> usize {
+        // consistency and flags
+        let mut size = 3;
+        if !self.values.is_empty() {
+            size += 2 + self.values.size();
+        }
+        if self.page_size.is_some() {
+            size += 4;
+        }
+        if let Some(paging_state) = &self.paging_state {
+            size += 4 + paging_state.len();
+        }
+        if self.serial_consistency.is_some() {
+            size += 2;
+        }
+        if self.timestamp.is_some() {
+            size += 8;
+        }
+        if let Some(keyspace) = &self.keyspace {
+            size += 2 + keyspace.len();
+        }
+        size
+    }
+
     pub fn serialize(&self, buf: &mut impl BufMut) -> Result<(), ParseError> {
+        self.serialize_with_version(ProtocolVersion::V4, buf)
+    }
+
+    /// Serializes the parameters in the format of the given protocol version:
+    /// flags are a byte in protocol v4 and an int in protocol v5.
+    /// Fails if the keyspace is set, but the version doesn't support it.
+    pub fn serialize_with_version(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Result<(), ParseError> {
+        check_keyspace_supported(self.keyspace.as_deref(), version)?;
+
         types::write_consistency(self.consistency, buf);
 
         let mut flags = 0;
@@ -86,6 +146,10 @@ impl QueryParameters<'_> {
             flags |= FLAG_VALUES;
         }
 
+        if self.skip_metadata {
+            flags |= FLAG_SKIP_METADATA;
+        }
+
         if self.page_size.is_some() {
             flags |= FLAG_PAGE_SIZE;
         }
@@ -106,7 +170,11 @@ impl QueryParameters<'_> {
             flags |= FLAG_WITH_NAMES_FOR_VALUES;
         }
 
-        buf.put_u8(flags);
+        if self.keyspace.is_some() {
+            flags |= FLAG_WITH_KEYSPACE;
+        }
+
+        write_flags(flags, version, buf);
 
         if !self.values.is_empty() {
             self.values.write_to_request(buf);
@@ -128,10 +196,38 @@ impl QueryParameters<'_> {
             types::write_long(timestamp, buf);
         }
 
+        if let Some(keyspace) = &self.keyspace {
+            types::write_string(keyspace, buf)?;
+        }
+
         Ok(())
     }
 }
 
+/// Fails if a keyspace is given, but the given protocol version doesn't allow to set it in requests.
+pub(crate) fn check_keyspace_supported(
+    keyspace: Option<&str>,
+    version: ProtocolVersion,
+) -> Result<(), ParseError> {
+    match keyspace {
+        Some(keyspace) if version < ProtocolVersion::V5 => {
+            Err(ParseError::BadDataToSerialize(format!(
+                "Keyspace {} can't be set in a request in protocol {}, it requires protocol v5",
+                keyspace, version
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Writes flags of QUERY, EXECUTE and BATCH requests in the format of the given protocol version.
+pub(crate) fn write_flags(flags: u8, version: ProtocolVersion, buf: &mut impl BufMut) {
+    match version {
+        ProtocolVersion::V4 => buf.put_u8(flags),
+        ProtocolVersion::V5 => types::write_int(flags as i32, buf),
+    }
+}
+
 impl<'q> QueryParameters<'q> {
     pub fn deserialize(buf: &mut &[u8]) -> Result<Self, ParseError> {
         let consistency = types::read_consistency(buf)?;
@@ -145,6 +241,7 @@ impl<'q> QueryParameters<'q> {
             )));
         }
         let values_flag = (flags & FLAG_VALUES) != 0;
+        let skip_metadata = (flags & FLAG_SKIP_METADATA) != 0;
         let page_size_flag = (flags & FLAG_PAGE_SIZE) != 0;
         let paging_state_flag = (flags & FLAG_WITH_PAGING_STATE) != 0;
         let serial_consistency_flag = (flags & FLAG_WITH_SERIAL_CONSISTENCY) != 0;
@@ -188,7 +285,9 @@ impl<'q> QueryParameters<'q> {
             timestamp,
             page_size,
             paging_state,
+            skip_metadata,
             values,
+            keyspace: None,
         })
     }
 }
@@ -1,7 +1,7 @@
 use super::result::{CqlValue, Row};
 use crate::frame::value::{Counter, CqlDuration};
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
 use num_bigint::BigInt;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::{BuildHasher, Hash};
@@ -9,6 +9,8 @@ use std::net::IpAddr;
 use thiserror::Error;
 use uuid::Uuid;
 
+#[cfg(feature = "serde")]
+use crate::frame::value::Json;
 #[cfg(feature = "secret")]
 use secrecy::{Secret, Zeroize};
 
@@ -171,6 +173,42 @@ impl FromCqlVal<CqlValue> for crate::frame::value::Timestamp {
     }
 }
 
+impl FromCqlVal<CqlValue> for crate::frame::value::CqlBlob {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        match cql_val {
+            CqlValue::Blob(bytes) => Ok(Self(bytes)),
+            _ => Err(FromCqlValError::BadCqlType),
+        }
+    }
+}
+
+impl FromCqlVal<CqlValue> for crate::frame::value::CqlTimeuuid {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        match cql_val {
+            CqlValue::Timeuuid(uuid) => Ok(uuid.into()),
+            _ => Err(FromCqlValError::BadCqlType),
+        }
+    }
+}
+
+impl FromCqlVal<CqlValue> for NaiveTime {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        let nanoseconds = match cql_val {
+            CqlValue::Time(d) => d.num_nanoseconds().ok_or(FromCqlValError::BadVal)?,
+            _ => return Err(FromCqlValError::BadCqlType),
+        };
+
+        let secs: u32 = (nanoseconds / 1_000_000_000)
+            .try_into()
+            .map_err(|_| FromCqlValError::BadVal)?;
+        let nanos: u32 = (nanoseconds % 1_000_000_000)
+            .try_into()
+            .map_err(|_| FromCqlValError::BadVal)?;
+
+        NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos).ok_or(FromCqlValError::BadVal)
+    }
+}
+
 impl FromCqlVal<CqlValue> for DateTime<Utc> {
     fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
         let timestamp = cql_val.as_bigint().ok_or(FromCqlValError::BadCqlType)?;
@@ -181,6 +219,111 @@ impl FromCqlVal<CqlValue> for DateTime<Utc> {
     }
 }
 
+#[cfg(feature = "time")]
+impl FromCqlVal<CqlValue> for time::Date {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        // Days since -5877641-06-23 i.e. 2^31 days before unix epoch
+        let date_days: u32 = match cql_val {
+            CqlValue::Date(days) => days,
+            _ => return Err(FromCqlValError::BadCqlType),
+        };
+        let days_since_epoch = time::Duration::days(date_days as i64 - (1 << 31));
+
+        time::Date::from_calendar_date(1970, time::Month::January, 1)
+            .unwrap()
+            .checked_add(days_since_epoch)
+            .ok_or(FromCqlValError::BadVal)
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromCqlVal<CqlValue> for time::Time {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        let nanoseconds = match cql_val {
+            CqlValue::Time(d) => d.num_nanoseconds().ok_or(FromCqlValError::BadVal)?,
+            _ => return Err(FromCqlValError::BadCqlType),
+        };
+
+        // Valid values are in the range 0 to 86399999999999
+        if !(0..=86399999999999).contains(&nanoseconds) {
+            return Err(FromCqlValError::BadVal);
+        }
+        let seconds = nanoseconds / 1_000_000_000;
+
+        time::Time::from_hms_nano(
+            (seconds / 3600) as u8,
+            (seconds / 60 % 60) as u8,
+            (seconds % 60) as u8,
+            (nanoseconds % 1_000_000_000) as u32,
+        )
+        .map_err(|_| FromCqlValError::BadVal)
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromCqlVal<CqlValue> for time::OffsetDateTime {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        let millis = match cql_val {
+            CqlValue::Timestamp(d) => d.num_milliseconds(),
+            _ => return Err(FromCqlValError::BadCqlType),
+        };
+
+        time::OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+            .map_err(|_| FromCqlValError::BadVal)
+    }
+}
+
+// PrimitiveDateTime is read as a UTC date and time
+#[cfg(feature = "time")]
+impl FromCqlVal<CqlValue> for time::PrimitiveDateTime {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        let datetime = time::OffsetDateTime::from_cql(cql_val)?;
+        Ok(time::PrimitiveDateTime::new(
+            datetime.date(),
+            datetime.time(),
+        ))
+    }
+}
+
+// Fails for values which can't be represented as Decimal exactly, instead of rounding them
+#[cfg(feature = "rust_decimal")]
+impl FromCqlVal<CqlValue> for rust_decimal::Decimal {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        // Maximum scale supported by Decimal
+        const MAX_SCALE: i64 = 28;
+
+        let decimal = cql_val.into_decimal().ok_or(FromCqlValError::BadCqlType)?;
+        let (mut mantissa, mut scale) = decimal.as_bigint_and_exponent();
+        // Decimal doesn't support negative scales, the mantissa has to be scaled up instead
+        if scale < 0 {
+            if -scale > MAX_SCALE {
+                return Err(FromCqlValError::BadVal);
+            }
+            mantissa *= BigInt::from(10).pow((-scale) as u32);
+            scale = 0;
+        }
+        if scale > MAX_SCALE {
+            return Err(FromCqlValError::BadVal);
+        }
+
+        let mantissa = i128::try_from(&mantissa).map_err(|_| FromCqlValError::BadVal)?;
+        rust_decimal::Decimal::try_from_i128_with_scale(mantissa, scale as u32)
+            .map_err(|_| FromCqlValError::BadVal)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::de::DeserializeOwned> FromCqlVal<CqlValue> for Json<T> {
+    fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
+        match cql_val {
+            CqlValue::Text(text) | CqlValue::Ascii(text) => serde_json::from_str(&text)
+                .map(Json)
+                .map_err(|_| FromCqlValError::BadVal),
+            _ => Err(FromCqlValError::BadCqlType),
+        }
+    }
+}
+
 #[cfg(feature = "secret")]
 impl<V: FromCqlVal<CqlValue> + Zeroize> FromCqlVal<CqlValue> for Secret<V> {
     fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
@@ -213,27 +356,35 @@ impl<T1: FromCqlVal<CqlValue> + Eq + Hash, T2: FromCqlVal<CqlValue>, T3: BuildHa
     }
 }
 
+// Sets can be read both from CQL sets and CQL lists. Duplicates are ignored when reading a list,
+// but a CQL set must never contain them, so it is considered a bad value.
 impl<T: FromCqlVal<CqlValue> + Eq + Hash, S: BuildHasher + Default> FromCqlVal<CqlValue>
     for HashSet<T, S>
 {
     fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
-        cql_val
-            .into_vec()
-            .ok_or(FromCqlValError::BadCqlType)?
-            .into_iter()
-            .map(T::from_cql)
-            .collect::<Result<HashSet<T, S>, FromCqlValError>>()
+        let is_set = matches!(cql_val, CqlValue::Set(_));
+        let vec = cql_val.into_vec().ok_or(FromCqlValError::BadCqlType)?;
+        let mut res = HashSet::with_capacity_and_hasher(vec.len(), S::default());
+        for elem in vec {
+            if !res.insert(T::from_cql(elem)?) && is_set {
+                return Err(FromCqlValError::BadVal);
+            }
+        }
+        Ok(res)
     }
 }
 
 impl<T: FromCqlVal<CqlValue> + Ord> FromCqlVal<CqlValue> for BTreeSet<T> {
     fn from_cql(cql_val: CqlValue) -> Result<Self, FromCqlValError> {
-        cql_val
-            .into_vec()
-            .ok_or(FromCqlValError::BadCqlType)?
-            .into_iter()
-            .map(T::from_cql)
-            .collect::<Result<BTreeSet<T>, FromCqlValError>>()
+        let is_set = matches!(cql_val, CqlValue::Set(_));
+        let vec = cql_val.into_vec().ok_or(FromCqlValError::BadCqlType)?;
+        let mut res = BTreeSet::new();
+        for elem in vec {
+            if !res.insert(T::from_cql(elem)?) && is_set {
+                return Err(FromCqlValError::BadVal);
+            }
+        }
+        Ok(res)
     }
 }
 
@@ -331,11 +482,16 @@ macro_rules! impl_tuple_from_cql {
 
                 let mut tuple_fields_iter = tuple_fields.into_iter();
 
-                Ok((
-                    $(
-                        $Ti::from_cql(tuple_fields_iter.next().ok_or(FromCqlValError::BadCqlType) ?) ?
-                    ,)+
-                ))
+                let result = ($(
+                    $Ti::from_cql(tuple_fields_iter.next().ok_or(FromCqlValError::BadCqlType) ?) ?
+                ,)+);
+
+                // The arity of the CQL tuple has to match the arity of the Rust tuple
+                if tuple_fields_iter.next().is_some() {
+                    return Err(FromCqlValError::BadCqlType);
+                }
+
+                Ok(result)
             }
         }
     }
@@ -362,13 +518,13 @@ impl_tuple_from_cql!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14
 mod tests {
     use super::{CqlValue, FromCqlVal, FromCqlValError, FromRow, FromRowError, Row};
     use crate as scylla;
-    use crate::frame::value::Counter;
-    use crate::macros::FromRow;
+    use crate::frame::value::{Counter, CqlBlob};
+    use crate::macros::{FromRow, FromUserType};
     use bigdecimal::BigDecimal;
-    use chrono::{Duration, NaiveDate};
+    use chrono::{Duration, NaiveDate, NaiveTime};
     use num_bigint::{BigInt, ToBigInt};
-    use std::collections::HashSet;
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
     use std::str::FromStr;
     use uuid::Uuid;
 
@@ -399,11 +555,35 @@ mod tests {
     #[test]
     fn i8_from_cql() {
         assert_eq!(Ok(6), i8::from_cql(CqlValue::TinyInt(6)));
+        assert_eq!(Ok(i8::MIN), i8::from_cql(CqlValue::TinyInt(i8::MIN)));
+        assert_eq!(Ok(i8::MAX), i8::from_cql(CqlValue::TinyInt(i8::MAX)));
+
+        // Other integer types are not silently truncated
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            i8::from_cql(CqlValue::SmallInt(6))
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            i8::from_cql(CqlValue::Int(6))
+        );
     }
 
     #[test]
     fn i16_from_cql() {
         assert_eq!(Ok(16), i16::from_cql(CqlValue::SmallInt(16)));
+        assert_eq!(Ok(i16::MIN), i16::from_cql(CqlValue::SmallInt(i16::MIN)));
+        assert_eq!(Ok(i16::MAX), i16::from_cql(CqlValue::SmallInt(i16::MAX)));
+
+        // Other integer types are not silently converted
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            i16::from_cql(CqlValue::TinyInt(16))
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            i16::from_cql(CqlValue::Int(16))
+        );
     }
 
     #[test]
@@ -424,10 +604,28 @@ mod tests {
         assert_eq!(Ok(val), <[u8; 4]>::from_cql(CqlValue::Blob(val.to_vec())));
     }
 
+    #[test]
+    fn blob_from_cql() {
+        let bytes = vec![0, 1, 0, 0xff, 0];
+        assert_eq!(
+            Ok(CqlBlob(bytes.clone())),
+            CqlBlob::from_cql(CqlValue::Blob(bytes))
+        );
+
+        // A list of tinyints is not a blob
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            CqlBlob::from_cql(CqlValue::List(vec![CqlValue::TinyInt(1)]))
+        );
+    }
+
     #[test]
     fn ip_addr_from_cql() {
         let ip_addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
         assert_eq!(Ok(ip_addr), IpAddr::from_cql(CqlValue::Inet(ip_addr)));
+
+        let ipv6_addr = IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1));
+        assert_eq!(Ok(ipv6_addr), IpAddr::from_cql(CqlValue::Inet(ipv6_addr)));
     }
 
     #[test]
@@ -448,10 +646,88 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_from_cql() {
+        use crate::frame::value::Json;
+
+        let text = CqlValue::Text(r#"{"a": 1, "b": [true, null]}"#.to_string());
+        assert_eq!(
+            Ok(Json(serde_json::json!({"a": 1, "b": [true, null]}))),
+            Json::<serde_json::Value>::from_cql(text)
+        );
+
+        let map = Json::<HashMap<String, i32>>::from_cql(CqlValue::Ascii(r#"{"x":7}"#.into()));
+        assert_eq!(map.unwrap().0, HashMap::from([("x".to_string(), 7)]));
+
+        // Text that isn't valid JSON for the requested type
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            Json::<i32>::from_cql(CqlValue::Text("\"not a number\"".to_string()))
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            Json::<i32>::from_cql(CqlValue::Int(1))
+        );
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn rust_decimal_from_cql() {
+        use rust_decimal::Decimal;
+
+        let tests = [
+            "4.2",
+            "0",
+            "-0.001",
+            "997",
+            "1.9999999999999999999999999999",
+            "79228162514264337593543950335",
+            "-79228162514264337593543950335",
+        ];
+        for test in tests {
+            assert_eq!(
+                Ok(Decimal::from_str(test).unwrap()),
+                Decimal::from_cql(CqlValue::Decimal(BigDecimal::from_str(test).unwrap()))
+            );
+        }
+
+        // Negative scale
+        assert_eq!(
+            Ok(Decimal::from(1200)),
+            Decimal::from_cql(CqlValue::Decimal(BigDecimal::new(12.into(), -2)))
+        );
+
+        // Values exceeding the precision or the range of Decimal are not rounded
+        let too_large = [
+            "1.999999999999999999999999999999999999999",
+            "123456789012345678901234567890.1234567890",
+            "-123456789012345678901234567890.1234567890",
+            "79228162514264337593543950336",
+            "0.00000000000000000000000000001",
+        ];
+        for test in too_large {
+            assert_eq!(
+                Err(FromCqlValError::BadVal),
+                Decimal::from_cql(CqlValue::Decimal(BigDecimal::from_str(test).unwrap()))
+            );
+        }
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            Decimal::from_cql(CqlValue::Decimal(BigDecimal::new(1.into(), -29)))
+        );
+    }
+
     #[test]
     fn counter_from_cql() {
         let counter = Counter(1);
         assert_eq!(Ok(counter), Counter::from_cql(CqlValue::Counter(counter)));
+
+        // Only counter columns can be read as a Counter
+        assert_eq!(
+            Counter::from_cql(CqlValue::BigInt(1)),
+            Err(FromCqlValError::BadCqlType)
+        );
     }
 
     #[test]
@@ -540,6 +816,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn naive_time_from_cql() {
+        let midnight = CqlValue::Time(Duration::nanoseconds(0));
+        assert_eq!(
+            Ok(NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+            NaiveTime::from_cql(midnight)
+        );
+
+        let max_time = CqlValue::Time(Duration::nanoseconds(86399999999999));
+        assert_eq!(
+            Ok(NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap()),
+            NaiveTime::from_cql(max_time)
+        );
+
+        let some_time = CqlValue::Time(Duration::nanoseconds(3_723_000_000_004));
+        assert_eq!(
+            Ok(NaiveTime::from_hms_nano_opt(1, 2, 3, 4).unwrap()),
+            NaiveTime::from_cql(some_time)
+        );
+
+        // Values outside of the 0..=86399999999999 range can't be represented
+        for invalid in [-1, 86399999999999 + 1] {
+            let invalid_time = CqlValue::Time(Duration::nanoseconds(invalid));
+            assert_eq!(
+                Err(FromCqlValError::BadVal),
+                NaiveTime::from_cql(invalid_time)
+            );
+        }
+
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            NaiveTime::from_cql(CqlValue::Int(0))
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_crate_from_cql() {
+        use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+        // Days since -5877641-06-23 i.e. 2^31 days before unix epoch
+        let unix_epoch: CqlValue = CqlValue::Date(2_u32.pow(31));
+        assert_eq!(
+            Ok(Date::from_calendar_date(1970, Month::January, 1).unwrap()),
+            Date::from_cql(unix_epoch)
+        );
+        let before_epoch: CqlValue = CqlValue::Date(2_u32.pow(31) - 30);
+        assert_eq!(
+            Ok(Date::from_calendar_date(1969, Month::December, 2).unwrap()),
+            Date::from_cql(before_epoch)
+        );
+        // Dates outside of the supported range are rejected
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            Date::from_cql(CqlValue::Date(0))
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            Date::from_cql(CqlValue::Date(u32::MAX))
+        );
+
+        let time_duration = Duration::nanoseconds(((13 * 60 + 37) * 60 + 42) * 1_000_000_000 + 123);
+        assert_eq!(
+            Ok(Time::from_hms_nano(13, 37, 42, 123).unwrap()),
+            Time::from_cql(CqlValue::Time(time_duration))
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            Time::from_cql(CqlValue::Time(Duration::hours(24)))
+        );
+
+        let timestamp = CqlValue::Timestamp(Duration::milliseconds(-1_234_567));
+        let expected = OffsetDateTime::from_unix_timestamp_nanos(-1_234_567_000_000).unwrap();
+        assert_eq!(Ok(expected), OffsetDateTime::from_cql(timestamp.clone()));
+        assert_eq!(
+            Ok(PrimitiveDateTime::new(expected.date(), expected.time())),
+            PrimitiveDateTime::from_cql(timestamp)
+        );
+
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            OffsetDateTime::from_cql(CqlValue::BigInt(0))
+        );
+    }
+
     #[test]
     fn timestamp_from_cql() {
         use crate::frame::value::Timestamp;
@@ -570,6 +931,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn datetime_before_epoch_from_cql() {
+        use chrono::{DateTime, Duration, TimeZone, Utc};
+
+        let before_epoch = Utc.with_ymd_and_hms(1969, 7, 20, 20, 17, 40).unwrap();
+        assert_eq!(
+            before_epoch,
+            DateTime::<Utc>::from_cql(CqlValue::Timestamp(Duration::milliseconds(-14182940000)))
+                .unwrap()
+        );
+
+        let one_ms_before_epoch = Utc.timestamp_millis_opt(-1).unwrap();
+        assert_eq!(
+            one_ms_before_epoch,
+            DateTime::<Utc>::from_cql(CqlValue::Timestamp(Duration::milliseconds(-1))).unwrap()
+        );
+
+        // Timestamps out of DateTime<Utc> range can't be converted
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            DateTime::<Utc>::from_cql(CqlValue::Timestamp(Duration::milliseconds(-i64::MAX)))
+        );
+    }
+
     #[test]
     fn uuid_from_cql() {
         let test_uuid: Uuid = Uuid::parse_str("8e14e760-7fa8-11eb-bc66-000000000001").unwrap();
@@ -593,7 +978,31 @@ mod tests {
 
     #[test]
     fn set_from_cql() {
-        let cql_val = CqlValue::Set(vec![
+        let cql_val = CqlValue::Set(vec![CqlValue::Int(3), CqlValue::Int(1), CqlValue::Int(2)]);
+        assert_eq!(
+            Ok(vec![1, 2, 3]),
+            HashSet::<i32>::from_cql(cql_val.clone()).map(|value| {
+                let mut values = value.into_iter().collect::<Vec<_>>();
+                values.sort_unstable();
+                values
+            })
+        );
+        assert_eq!(
+            Ok(BTreeSet::from([1, 2, 3])),
+            BTreeSet::<i32>::from_cql(cql_val)
+        );
+
+        let text_set = CqlValue::Set(vec![
+            CqlValue::Text("b".to_string()),
+            CqlValue::Text("a".to_string()),
+        ]);
+        assert_eq!(
+            Ok(BTreeSet::from(["a".to_string(), "b".to_string()])),
+            BTreeSet::<String>::from_cql(text_set)
+        );
+
+        // Duplicates in a list are ignored when converting it to a set
+        let list_with_duplicates = CqlValue::List(vec![
             CqlValue::Int(1),
             CqlValue::Int(2),
             CqlValue::Int(3),
@@ -603,12 +1012,68 @@ mod tests {
         ]);
         assert_eq!(
             Ok(vec![1, 2, 3]),
-            HashSet::<i32>::from_cql(cql_val).map(|value| {
+            HashSet::<i32>::from_cql(list_with_duplicates.clone()).map(|value| {
                 let mut values = value.into_iter().collect::<Vec<_>>();
                 values.sort_unstable();
                 values
             })
         );
+        assert_eq!(
+            Ok(BTreeSet::from([1, 2, 3])),
+            BTreeSet::<i32>::from_cql(list_with_duplicates)
+        );
+
+        // A CQL set can't contain duplicates
+        let set_with_duplicates =
+            CqlValue::Set(vec![CqlValue::Int(1), CqlValue::Int(2), CqlValue::Int(1)]);
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            HashSet::<i32>::from_cql(set_with_duplicates.clone())
+        );
+        assert_eq!(
+            Err(FromCqlValError::BadVal),
+            BTreeSet::<i32>::from_cql(set_with_duplicates)
+        );
+    }
+
+    #[test]
+    fn map_from_cql() {
+        let cql_val = CqlValue::Map(vec![
+            (CqlValue::Text("a".to_string()), CqlValue::Int(1)),
+            (CqlValue::Text("b".to_string()), CqlValue::Int(2)),
+        ]);
+        assert_eq!(
+            Ok(HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)])),
+            HashMap::<String, i32>::from_cql(cql_val.clone())
+        );
+        assert_eq!(
+            Ok(BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)])),
+            BTreeMap::<String, i32>::from_cql(cql_val)
+        );
+
+        // Values of a map can be collections themselves
+        let nested_cql_val = CqlValue::Map(vec![
+            (
+                CqlValue::Int(1),
+                CqlValue::List(vec![
+                    CqlValue::Text("x".to_string()),
+                    CqlValue::Text("y".to_string()),
+                ]),
+            ),
+            (CqlValue::Int(2), CqlValue::List(vec![])),
+        ]);
+        assert_eq!(
+            Ok(BTreeMap::from([
+                (1, vec!["x".to_string(), "y".to_string()]),
+                (2, vec![]),
+            ])),
+            BTreeMap::<i32, Vec<String>>::from_cql(nested_cql_val)
+        );
+
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            HashMap::<String, i32>::from_cql(CqlValue::List(vec![]))
+        );
     }
 
     #[test]
@@ -627,6 +1092,52 @@ mod tests {
         assert_eq!(c, None);
     }
 
+    #[test]
+    fn large_tuple_from_row() {
+        let row = Row {
+            columns: (0..12).map(|i| Some(CqlValue::Int(i))).collect(),
+        };
+
+        type LargeTuple = (i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32);
+        let tuple = LargeTuple::from_row(row).unwrap();
+        assert_eq!(tuple, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11));
+    }
+
+    #[test]
+    fn tuple_from_cql() {
+        let tuple = CqlValue::Tuple(vec![
+            Some(CqlValue::Int(1)),
+            Some(CqlValue::Text("some_text".to_string())),
+            None,
+        ]);
+
+        assert_eq!(
+            <(i32, String, Option<bool>)>::from_cql(tuple.clone()),
+            Ok((1, "some_text".to_string(), None))
+        );
+
+        // A null element can be read only into an Option
+        assert_eq!(
+            <(i32, String, bool)>::from_cql(tuple.clone()),
+            Err(FromCqlValError::ValIsNull)
+        );
+
+        // The arity has to match
+        assert_eq!(
+            <(i32, String)>::from_cql(tuple.clone()),
+            Err(FromCqlValError::BadCqlType)
+        );
+        assert_eq!(
+            <(i32, String, Option<bool>, i32)>::from_cql(tuple),
+            Err(FromCqlValError::BadCqlType)
+        );
+
+        assert_eq!(
+            <(i32,)>::from_cql(CqlValue::Int(1)),
+            Err(FromCqlValError::BadCqlType)
+        );
+    }
+
     #[test]
     fn from_cql_null() {
         assert_eq!(i32::from_cql(None), Err(FromCqlValError::ValIsNull));
@@ -772,4 +1283,135 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn struct_from_row_bad_value() {
+        #[derive(FromRow, PartialEq, Eq, Debug)]
+        struct MyRow {
+            a: i32,
+            b: String,
+            c: Option<i64>,
+        }
+
+        let wrong_type_row = Row {
+            columns: vec![
+                Some(CqlValue::Int(16)),
+                Some(CqlValue::Int(17)),
+                Some(CqlValue::BigInt(18)),
+            ],
+        };
+        let unexpected_null_row = Row {
+            columns: vec![Some(CqlValue::Int(16)), None, None],
+        };
+
+        // The error points at the column which couldn't be converted
+        assert_eq!(
+            MyRow::from_row(wrong_type_row),
+            Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::BadCqlType,
+                column: 1
+            })
+        );
+        assert_eq!(
+            MyRow::from_row(unexpected_null_row),
+            Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::ValIsNull,
+                column: 1
+            })
+        );
+    }
+
+    #[test]
+    fn struct_from_udt() {
+        #[derive(FromUserType, Debug, PartialEq)]
+        struct Address {
+            street: String,
+            zip: i32,
+            note: Option<String>,
+        }
+
+        let udt = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "address".to_string(),
+            fields: vec![
+                (
+                    "street".to_string(),
+                    Some(CqlValue::Text("Main St".to_string())),
+                ),
+                ("zip".to_string(), Some(CqlValue::Int(12345))),
+                ("note".to_string(), None),
+            ],
+        };
+
+        assert_eq!(
+            Ok(Address {
+                street: "Main St".to_string(),
+                zip: 12345,
+                note: None,
+            }),
+            Address::from_cql(udt)
+        );
+
+        // A field missing from the received value maps to None for Option fields
+        let udt_missing_note = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "address".to_string(),
+            fields: vec![
+                (
+                    "street".to_string(),
+                    Some(CqlValue::Text("Main St".to_string())),
+                ),
+                ("zip".to_string(), Some(CqlValue::Int(12345))),
+            ],
+        };
+
+        assert_eq!(
+            Ok(Address {
+                street: "Main St".to_string(),
+                zip: 12345,
+                note: None,
+            }),
+            Address::from_cql(udt_missing_note)
+        );
+
+        // ...but it's an error for non-Option fields
+        let udt_missing_zip = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "address".to_string(),
+            fields: vec![(
+                "street".to_string(),
+                Some(CqlValue::Text("Main St".to_string())),
+            )],
+        };
+
+        assert_eq!(
+            Err(FromCqlValError::ValIsNull),
+            Address::from_cql(udt_missing_zip)
+        );
+
+        // Received fields that don't exist in the struct cause an error
+        let udt_extra_field = CqlValue::UserDefinedType {
+            keyspace: "ks".to_string(),
+            type_name: "address".to_string(),
+            fields: vec![
+                (
+                    "street".to_string(),
+                    Some(CqlValue::Text("Main St".to_string())),
+                ),
+                ("zip".to_string(), Some(CqlValue::Int(12345))),
+                ("note".to_string(), None),
+                ("city".to_string(), None),
+            ],
+        };
+
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            Address::from_cql(udt_extra_field)
+        );
+
+        assert_eq!(
+            Err(FromCqlValError::BadCqlType),
+            Address::from_cql(CqlValue::Int(1))
+        );
+    }
 }
@@ -2,6 +2,7 @@ use crate::errors::{DbError, OperationType, QueryError, WriteType};
 use crate::frame::frame_errors::ParseError;
 use crate::frame::protocol_features::ProtocolFeatures;
 use crate::frame::types;
+use crate::frame::ProtocolVersion;
 use byteorder::ReadBytesExt;
 use bytes::Bytes;
 
@@ -44,7 +45,7 @@ impl Error {
                 consistency: types::read_consistency(buf)?,
                 received: types::read_int(buf)?,
                 required: types::read_int(buf)?,
-                numfailures: types::read_int(buf)?,
+                numfailures: read_numfailures(features, buf)?,
                 data_present: buf.read_u8()? != 0,
             },
             0x1400 => DbError::FunctionFailure {
@@ -56,7 +57,7 @@ impl Error {
                 consistency: types::read_consistency(buf)?,
                 received: types::read_int(buf)?,
                 required: types::read_int(buf)?,
-                numfailures: types::read_int(buf)?,
+                numfailures: read_numfailures(features, buf)?,
                 write_type: WriteType::from(types::read_string(buf)?),
             },
             0x2000 => DbError::SyntaxError,
@@ -81,6 +82,19 @@ impl Error {
     }
 }
 
+// Protocol v5 sends the addresses of the replicas which failed along with the failure codes
+// instead of just the number of failures. Only the number of failures is kept.
+fn read_numfailures(features: &ProtocolFeatures, buf: &mut &[u8]) -> Result<i32, ParseError> {
+    let numfailures = types::read_int(buf)?;
+    if features.protocol_version >= ProtocolVersion::V5 {
+        for _ in 0..numfailures {
+            types::read_inetaddr(buf)?;
+            types::read_short(buf)?;
+        }
+    }
+    Ok(numfailures)
+}
+
 impl From<Error> for QueryError {
     fn from(error: Error) -> QueryError {
         QueryError::DbError(error.error, error.reason)
@@ -92,6 +106,7 @@ mod tests {
     use super::Error;
     use crate::errors::{DbError, OperationType, WriteType};
     use crate::frame::protocol_features::ProtocolFeatures;
+    use crate::frame::ProtocolVersion;
     use crate::Consistency;
     use bytes::Bytes;
     use std::convert::TryInto;
@@ -308,6 +323,46 @@ mod tests {
         assert_eq!(error.reason, "message 2");
     }
 
+    #[test]
+    fn deserialize_write_failure_v5() {
+        let features = ProtocolFeatures {
+            protocol_version: ProtocolVersion::V5,
+            ..Default::default()
+        };
+
+        let mut bytes = make_error_request_bytes(0x1500, "message 2");
+
+        bytes.extend(0x0000_i16.to_be_bytes());
+        bytes.extend(2_i32.to_be_bytes());
+        bytes.extend(4_i32.to_be_bytes());
+
+        // Failed replicas mapped to failure codes instead of their number
+        bytes.extend(2_i32.to_be_bytes());
+        bytes.extend([4, 127, 0, 0, 1]);
+        bytes.extend(0x0001_i16.to_be_bytes());
+        bytes.push(16);
+        bytes.extend(std::net::Ipv6Addr::LOCALHOST.octets());
+        bytes.extend(0x0002_i16.to_be_bytes());
+
+        let write_type_str = "COUNTER";
+        let write_type_str_len: u16 = write_type_str.len().try_into().unwrap();
+        bytes.extend(write_type_str_len.to_be_bytes());
+        bytes.extend(write_type_str.as_bytes());
+
+        let error: Error = Error::deserialize(&features, &mut bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            error.error,
+            DbError::WriteFailure {
+                consistency: Consistency::Any,
+                received: 2,
+                required: 4,
+                numfailures: 2,
+                write_type: WriteType::Counter,
+            }
+        );
+    }
+
     #[test]
     fn deserialize_already_exists() {
         let features = ProtocolFeatures::default();
@@ -42,6 +42,17 @@ impl Response {
         features: &ProtocolFeatures,
         opcode: ResponseOpcode,
         buf: &mut &[u8],
+    ) -> Result<Response, ParseError> {
+        Self::deserialize_with_cached_metadata(features, opcode, buf, None)
+    }
+
+    /// Like [`Response::deserialize`], but `Rows` results sent without column metadata
+    /// take their column specs from `cached_metadata`.
+    pub fn deserialize_with_cached_metadata(
+        features: &ProtocolFeatures,
+        opcode: ResponseOpcode,
+        buf: &mut &[u8],
+        cached_metadata: Option<&result::ResultMetadata>,
     ) -> Result<Response, ParseError> {
         let response = match opcode {
             ResponseOpcode::Error => Response::Error(Error::deserialize(features, buf)?),
@@ -50,7 +61,11 @@ impl Response {
                 Response::Authenticate(authenticate::Authenticate::deserialize(buf)?)
             }
             ResponseOpcode::Supported => Response::Supported(Supported::deserialize(buf)?),
-            ResponseOpcode::Result => Response::Result(result::deserialize(buf)?),
+            ResponseOpcode::Result => {
+                let result =
+                    result::deserialize_with_cached_metadata(features, buf, cached_metadata)?;
+                Response::Result(result)
+            }
             ResponseOpcode::Event => Response::Event(event::Event::deserialize(buf)?),
             ResponseOpcode::AuthChallenge => {
                 Response::AuthChallenge(authenticate::AuthChallenge::deserialize(buf)?)
@@ -1,8 +1,9 @@
-use crate::cql_to_rust::{FromRow, FromRowError};
+use crate::cql_to_rust::{FromCqlValError, FromRow, FromRowError};
+use crate::frame::protocol_features::ProtocolFeatures;
 use crate::frame::response::event::SchemaChangeEvent;
 use crate::frame::types::vint_decode;
 use crate::frame::value::{Counter, CqlDuration};
-use crate::frame::{frame_errors::ParseError, types};
+use crate::frame::{frame_errors::ParseError, types, ProtocolVersion};
 use bigdecimal::BigDecimal;
 use byteorder::{BigEndian, ReadBytesExt};
 use bytes::{Buf, Bytes};
@@ -329,6 +330,78 @@ impl CqlValue {
     // TODO
 }
 
+/// Serializes a value into the JSON data model.
+///
+/// Values which have no JSON counterpart are represented as strings:
+/// blobs are base64-encoded, uuids are hyphenated, timestamps and dates use
+/// ISO-8601 and varints and decimals are written out in full so that no
+/// precision is lost. Lists, sets and tuples become arrays, while maps and
+/// user defined types become objects.
+#[cfg(feature = "serde")]
+impl serde::Serialize for CqlValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        use base64::Engine;
+
+        match self {
+            CqlValue::Ascii(s) | CqlValue::Text(s) => serializer.serialize_str(s),
+            CqlValue::Boolean(b) => serializer.serialize_bool(*b),
+            CqlValue::Blob(b) => {
+                serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(b))
+            }
+            CqlValue::Counter(c) => serializer.serialize_i64(c.0),
+            CqlValue::Decimal(d) => serializer.collect_str(d),
+            CqlValue::Date(days) => match self.as_date() {
+                Some(date) => serializer.collect_str(&date),
+                None => serializer.serialize_u32(*days),
+            },
+            CqlValue::Double(d) => serializer.serialize_f64(*d),
+            CqlValue::Duration(d) => serializer.collect_str(&format_args!(
+                "{}mo{}d{}ns",
+                d.months, d.days, d.nanoseconds
+            )),
+            CqlValue::Empty => serializer.serialize_none(),
+            CqlValue::Float(f) => serializer.serialize_f32(*f),
+            CqlValue::Int(i) => serializer.serialize_i32(*i),
+            CqlValue::BigInt(i) => serializer.serialize_i64(*i),
+            CqlValue::SmallInt(i) => serializer.serialize_i16(*i),
+            CqlValue::TinyInt(i) => serializer.serialize_i8(*i),
+            CqlValue::Timestamp(ts) => {
+                let millis = ts.num_milliseconds();
+                match Utc.timestamp_millis_opt(millis).single() {
+                    Some(datetime) => serializer.collect_str(&format_args!(
+                        "{}T{}Z",
+                        datetime.date_naive(),
+                        datetime.time()
+                    )),
+                    None => serializer.serialize_i64(millis),
+                }
+            }
+            CqlValue::Time(t) => {
+                let time = u32::try_from(t.num_seconds()).ok().and_then(|secs| {
+                    NaiveTime::from_num_seconds_from_midnight_opt(secs, t.subsec_nanos() as u32)
+                });
+                match time {
+                    Some(time) => serializer.collect_str(&time),
+                    // Not a valid time of day, written out as an ISO-8601 duration
+                    None => serializer.collect_str(t),
+                }
+            }
+            CqlValue::Inet(addr) => serializer.collect_str(addr),
+            CqlValue::List(l) | CqlValue::Set(l) => serializer.collect_seq(l),
+            // Keys which are not strings are converted by the serializer if it can
+            // (e.g. serde_json writes out numbers as strings), frozen collections
+            // used as keys are rejected by it.
+            CqlValue::Map(m) => serializer.collect_map(m.iter().map(|(k, v)| (k, v))),
+            CqlValue::UserDefinedType { fields, .. } => {
+                serializer.collect_map(fields.iter().map(|(name, v)| (name, v)))
+            }
+            CqlValue::Tuple(t) => serializer.collect_seq(t),
+            CqlValue::Uuid(u) | CqlValue::Timeuuid(u) => serializer.collect_str(u),
+            CqlValue::Varint(v) => serializer.collect_str(v),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ColumnSpec {
     pub table_spec: TableSpec,
@@ -336,10 +409,14 @@ pub struct ColumnSpec {
     pub typ: ColumnType,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ResultMetadata {
     col_count: usize,
     pub paging_state: Option<Bytes>,
+    /// Id of the metadata, sent only in protocol v5: in the result of PREPARE,
+    /// and in rows returned by EXECUTE if the metadata has changed since the statement
+    /// was prepared (e.g. because a column was added).
+    pub metadata_id: Option<Bytes>,
     pub col_specs: Vec<ColumnSpec>,
 }
 
@@ -371,6 +448,42 @@ impl Row {
     pub fn into_typed<RowT: FromRow>(self) -> StdResult<RowT, FromRowError> {
         RowT::from_row(self)
     }
+
+    /// Borrows an `ascii` or `text` column as `&str`, without copying it.
+    /// Returns `Ok(None)` if the value is null.
+    pub fn get_str(&self, index: usize) -> StdResult<Option<&str>, FromRowError> {
+        match self.get_column(index)? {
+            Some(CqlValue::Ascii(s)) | Some(CqlValue::Text(s)) => Ok(Some(s.as_str())),
+            Some(_) => Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::BadCqlType,
+                column: index,
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Borrows a `blob` column as `&[u8]`, without copying it.
+    /// Returns `Ok(None)` if the value is null.
+    pub fn get_bytes(&self, index: usize) -> StdResult<Option<&[u8]>, FromRowError> {
+        match self.get_column(index)? {
+            Some(CqlValue::Blob(b)) => Ok(Some(b.as_slice())),
+            Some(_) => Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::BadCqlType,
+                column: index,
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn get_column(&self, index: usize) -> StdResult<Option<&CqlValue>, FromRowError> {
+        match self.columns.get(index) {
+            Some(value) => Ok(value.as_ref()),
+            None => Err(FromRowError::WrongRowSize {
+                expected: index + 1,
+                actual: self.columns.len(),
+            }),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -493,10 +606,19 @@ fn deser_col_specs(
 }
 
 fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseError> {
+    deser_result_metadata_and_flag(buf).map(|(metadata, _no_metadata)| metadata)
+}
+
+// Also returns whether the `NO_METADATA` flag was set, i.e. the server skipped the column specs
+fn deser_result_metadata_and_flag(
+    buf: &mut &[u8],
+) -> StdResult<(ResultMetadata, bool), ParseError> {
     let flags = types::read_int(buf)?;
     let global_tables_spec = flags & 0x0001 != 0;
     let has_more_pages = flags & 0x0002 != 0;
     let no_metadata = flags & 0x0004 != 0;
+    // Only sent in protocol v5
+    let metadata_changed = flags & 0x0008 != 0;
 
     let col_count: usize = types::read_int(buf)?.try_into()?;
 
@@ -506,12 +628,22 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
         None
     };
 
+    let metadata_id = if metadata_changed {
+        Some(types::read_short_bytes(buf)?.to_owned().into())
+    } else {
+        None
+    };
+
     if no_metadata {
-        return Ok(ResultMetadata {
-            col_count,
-            paging_state,
-            col_specs: vec![],
-        });
+        return Ok((
+            ResultMetadata {
+                col_count,
+                paging_state,
+                metadata_id,
+                col_specs: vec![],
+            },
+            true,
+        ));
     }
 
     let global_table_spec = if global_tables_spec {
@@ -522,11 +654,15 @@ fn deser_result_metadata(buf: &mut &[u8]) -> StdResult<ResultMetadata, ParseErro
 
     let col_specs = deser_col_specs(buf, &global_table_spec, col_count)?;
 
-    Ok(ResultMetadata {
-        col_count,
-        paging_state,
-        col_specs,
-    })
+    Ok((
+        ResultMetadata {
+            col_count,
+            paging_state,
+            metadata_id,
+            col_specs,
+        },
+        false,
+    ))
 }
 
 fn deser_prepared_metadata(buf: &mut &[u8]) -> StdResult<PreparedMetadata, ParseError> {
@@ -835,16 +971,37 @@ pub fn deser_cql_value(typ: &ColumnType, buf: &mut &[u8]) -> StdResult<CqlValue,
     })
 }
 
-fn deser_rows(buf: &mut &[u8]) -> StdResult<Rows, ParseError> {
-    let metadata = deser_result_metadata(buf)?;
+fn deser_rows(
+    buf: &mut &[u8],
+    cached_metadata: Option<&ResultMetadata>,
+) -> StdResult<Rows, ParseError> {
+    let (mut metadata, no_metadata) = deser_result_metadata_and_flag(buf)?;
 
     let original_size = buf.len();
 
-    // TODO: the protocol allows an optimization (which must be explicitly requested on query by
-    // the driver) where the column metadata is not sent with the result.
-    // Implement this optimization. We'll then need to take the column types by a parameter.
+    // If the server skipped sending the column specs (because the driver asked it to),
+    // take them from the metadata cached when the statement was prepared.
     // Beware of races; our column types may be outdated.
-    assert!(metadata.col_count == metadata.col_specs.len());
+    if no_metadata {
+        match cached_metadata {
+            Some(cached) if cached.col_specs.len() == metadata.col_count => {
+                metadata.col_specs = cached.col_specs.clone();
+            }
+            Some(cached) => {
+                return Err(ParseError::CachedMetadataMismatch(
+                    metadata.col_count,
+                    cached.col_specs.len(),
+                ))
+            }
+            None if metadata.col_count == 0 => {}
+            None => {
+                return Err(ParseError::BadIncomingData(
+                    "Result does not contain column metadata and no cached metadata is available"
+                        .to_string(),
+                ))
+            }
+        }
+    }
 
     let rows_count: usize = types::read_int(buf)?.try_into()?;
 
@@ -875,12 +1032,21 @@ fn deser_set_keyspace(buf: &mut &[u8]) -> StdResult<SetKeyspace, ParseError> {
     Ok(SetKeyspace { keyspace_name })
 }
 
-fn deser_prepared(buf: &mut &[u8]) -> StdResult<Prepared, ParseError> {
+fn deser_prepared(
+    buf: &mut &[u8],
+    protocol_version: ProtocolVersion,
+) -> StdResult<Prepared, ParseError> {
     let id_len = types::read_short(buf)? as usize;
     let id: Bytes = buf[0..id_len].to_owned().into();
     buf.advance(id_len);
+    let result_metadata_id = if protocol_version >= ProtocolVersion::V5 {
+        Some(types::read_short_bytes(buf)?.to_owned().into())
+    } else {
+        None
+    };
     let prepared_metadata = deser_prepared_metadata(buf)?;
-    let result_metadata = deser_result_metadata(buf)?;
+    let mut result_metadata = deser_result_metadata(buf)?;
+    result_metadata.metadata_id = result_metadata_id;
     Ok(Prepared {
         id,
         prepared_metadata,
@@ -896,12 +1062,22 @@ fn deser_schema_change(buf: &mut &[u8]) -> StdResult<SchemaChange, ParseError> {
 }
 
 pub fn deserialize(buf: &mut &[u8]) -> StdResult<Result, ParseError> {
+    deserialize_with_cached_metadata(&ProtocolFeatures::default(), buf, None)
+}
+
+/// Deserializes a RESULT response body to a request that was sent with the `skip_metadata` flag.
+/// Column specs missing from a `Rows` result are taken from `cached_metadata`.
+pub fn deserialize_with_cached_metadata(
+    features: &ProtocolFeatures,
+    buf: &mut &[u8],
+    cached_metadata: Option<&ResultMetadata>,
+) -> StdResult<Result, ParseError> {
     use self::Result::*;
     Ok(match types::read_int(buf)? {
         0x0001 => Void,
-        0x0002 => Rows(deser_rows(buf)?),
+        0x0002 => Rows(deser_rows(buf, cached_metadata)?),
         0x0003 => SetKeyspace(deser_set_keyspace(buf)?),
-        0x0004 => Prepared(deser_prepared(buf)?),
+        0x0004 => Prepared(deser_prepared(buf, features.protocol_version)?),
         0x0005 => SchemaChange(deser_schema_change(buf)?),
         k => {
             return Err(ParseError::BadIncomingData(format!(
@@ -915,13 +1091,17 @@ pub fn deserialize(buf: &mut &[u8]) -> StdResult<Result, ParseError> {
 #[cfg(test)]
 mod tests {
     use crate as scylla;
+    use crate::frame::protocol_features::ProtocolFeatures;
     use crate::frame::value::{Counter, CqlDuration};
+    use crate::frame::ProtocolVersion;
     use bigdecimal::BigDecimal;
     use chrono::Duration;
     use chrono::NaiveDate;
     use num_bigint::BigInt;
     use num_bigint::ToBigInt;
+    use scylla::frame::response::cql_to_rust::FromCqlVal;
     use scylla::frame::response::result::{ColumnType, CqlValue};
+    use scylla::frame::value::Value;
     use std::str::FromStr;
     use uuid::Uuid;
 
@@ -961,6 +1141,18 @@ mod tests {
         let max_ip_slice = &mut &max_ip_buf[..];
         let max_ip_serialize = super::deser_cql_value(&ColumnType::Inet, max_ip_slice).unwrap();
         assert_eq!(max_ip_serialize, CqlValue::Inet(max_ip));
+
+        let my_ipv4 = "192.168.0.1".parse().unwrap();
+        let ipv4_buf: Vec<u8> = vec![192, 168, 0, 1];
+        let ipv4_slice = &mut &ipv4_buf[..];
+        let ipv4_serialize = super::deser_cql_value(&ColumnType::Inet, ipv4_slice).unwrap();
+        assert_eq!(ipv4_serialize, CqlValue::Inet(my_ipv4));
+
+        // Only 4 (IPv4) and 16 (IPv6) bytes long inet values are valid
+        for invalid_len in [1, 3, 5, 15, 17] {
+            let invalid_buf: Vec<u8> = vec![1; invalid_len];
+            super::deser_cql_value(&ColumnType::Inet, &mut &invalid_buf[..]).unwrap_err();
+        }
     }
 
     #[test]
@@ -1126,6 +1318,23 @@ mod tests {
         let bigint_slice = &mut &bigint_buf[..];
         let bigint_serialized = super::deser_cql_value(&ColumnType::BigInt, bigint_slice).unwrap();
         assert_eq!(bigint_serialized, CqlValue::BigInt(4));
+
+        for smallint in [i16::MIN, -1, 0, i16::MAX] {
+            let buf = smallint.to_be_bytes();
+            let deserialized =
+                super::deser_cql_value(&ColumnType::SmallInt, &mut &buf[..]).unwrap();
+            assert_eq!(deserialized, CqlValue::SmallInt(smallint));
+        }
+
+        for tinyint in [i8::MIN, -1, 0, i8::MAX] {
+            let buf = tinyint.to_be_bytes();
+            let deserialized = super::deser_cql_value(&ColumnType::TinyInt, &mut &buf[..]).unwrap();
+            assert_eq!(deserialized, CqlValue::TinyInt(tinyint));
+        }
+
+        // Values of wrong length are rejected
+        super::deser_cql_value(&ColumnType::SmallInt, &mut &[0, 0, 4][..]).unwrap_err();
+        super::deser_cql_value(&ColumnType::TinyInt, &mut &[0, 4][..]).unwrap_err();
     }
 
     #[test]
@@ -1362,6 +1571,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_duration_mixed_signs_roundtrip() {
+        use crate::frame::value::Value;
+
+        let tests = [
+            CqlDuration {
+                months: -1,
+                days: 1,
+                nanoseconds: -2,
+            },
+            CqlDuration {
+                months: 12,
+                days: -30,
+                nanoseconds: 86399999999999,
+            },
+            CqlDuration {
+                months: 0,
+                days: 0,
+                nanoseconds: -1,
+            },
+            CqlDuration {
+                months: i32::MIN,
+                days: i32::MAX,
+                nanoseconds: i64::MIN,
+            },
+            CqlDuration {
+                months: i32::MAX,
+                days: i32::MIN,
+                nanoseconds: i64::MAX,
+            },
+        ];
+
+        for duration in tests {
+            let mut serialized = Vec::new();
+            duration.serialize(&mut serialized).unwrap();
+
+            // Skip the [bytes] length prefix
+            let cql_value: CqlValue =
+                super::deser_cql_value(&ColumnType::Duration, &mut &serialized[4..]).unwrap();
+            assert_eq!(cql_value, CqlValue::Duration(duration));
+        }
+
+        // Months and days that don't fit in i32 are rejected
+        let mut too_big_months = Vec::new();
+        crate::frame::types::vint_encode(i32::MAX as i64 + 1, &mut too_big_months);
+        crate::frame::types::vint_encode(0, &mut too_big_months);
+        crate::frame::types::vint_encode(0, &mut too_big_months);
+        super::deser_cql_value(&ColumnType::Duration, &mut &too_big_months[..]).unwrap_err();
+    }
+
     #[test]
     fn test_deserialize_empty_payload() {
         for (test_type, res_cql) in [
@@ -1443,4 +1702,519 @@ mod tests {
             }
         }
     }
+
+    // Serializes the value and deserializes it back as a value of the given type
+    fn serialize_and_deserialize<T>(value: &T, typ: &ColumnType) -> T
+    where
+        T: Value + FromCqlVal<CqlValue>,
+    {
+        let mut buf: Vec<u8> = Vec::new();
+        value.serialize(&mut buf).unwrap();
+        // Skip the length of the serialized value
+        let cql_val = super::deser_cql_value(typ, &mut &buf[4..]).unwrap();
+        T::from_cql(cql_val).unwrap()
+    }
+
+    #[test]
+    fn test_nested_collections_round_trip() {
+        use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+        // map<text, frozen<list<int>>>
+        let map_of_lists: HashMap<String, Vec<i32>> = [
+            ("a".to_string(), vec![1, 2, 3]),
+            ("b".to_string(), vec![]),
+            ("c".to_string(), vec![-1]),
+        ]
+        .into_iter()
+        .collect();
+        let typ = ColumnType::Map(
+            Box::new(ColumnType::Text),
+            Box::new(ColumnType::List(Box::new(ColumnType::Int))),
+        );
+        assert_eq!(serialize_and_deserialize(&map_of_lists, &typ), map_of_lists);
+
+        // list<frozen<set<text>>>
+        let list_of_sets: Vec<BTreeSet<String>> = vec![
+            ["x".to_string(), "y".to_string()].into_iter().collect(),
+            BTreeSet::new(),
+        ];
+        let typ = ColumnType::List(Box::new(ColumnType::Set(Box::new(ColumnType::Text))));
+        assert_eq!(serialize_and_deserialize(&list_of_sets, &typ), list_of_sets);
+
+        // map<int, frozen<map<text, frozen<list<bigint>>>>>
+        let three_levels: BTreeMap<i32, BTreeMap<String, Vec<i64>>> = [
+            (
+                1,
+                [
+                    ("one".to_string(), vec![1, 11]),
+                    ("two".to_string(), vec![2]),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            (2, BTreeMap::new()),
+            (3, [("empty".to_string(), vec![])].into_iter().collect()),
+        ]
+        .into_iter()
+        .collect();
+        let typ = ColumnType::Map(
+            Box::new(ColumnType::Int),
+            Box::new(ColumnType::Map(
+                Box::new(ColumnType::Text),
+                Box::new(ColumnType::List(Box::new(ColumnType::BigInt))),
+            )),
+        );
+        assert_eq!(serialize_and_deserialize(&three_levels, &typ), three_levels);
+
+        // list<frozen<list<frozen<list<int>>>>>
+        let nested_lists: Vec<Vec<Vec<i32>>> =
+            vec![vec![vec![1, 2], vec![]], vec![], vec![vec![3]]];
+        let typ = ColumnType::List(Box::new(ColumnType::List(Box::new(ColumnType::List(
+            Box::new(ColumnType::Int),
+        )))));
+        assert_eq!(serialize_and_deserialize(&nested_lists, &typ), nested_lists);
+    }
+
+    // Builds a Rows result body sent with the `no_metadata` flag, holding rows of (int, text)
+    fn rows_without_metadata(rows: &[(i32, &str)]) -> Vec<u8> {
+        use bytes::BufMut;
+
+        let mut buf = Vec::new();
+        buf.put_i32(0x0002); // Rows
+        buf.put_i32(0x0004); // no_metadata
+        buf.put_i32(2); // col_count
+        buf.put_i32(rows.len() as i32);
+        for (a, b) in rows {
+            a.serialize(&mut buf).unwrap();
+            b.serialize(&mut buf).unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn test_deserialize_rows_with_cached_metadata() {
+        use super::{ColumnSpec, ResultMetadata, Row, TableSpec};
+
+        let features = ProtocolFeatures::default();
+
+        let table_spec = TableSpec {
+            ks_name: "ks".to_string(),
+            table_name: "t".to_string(),
+        };
+        let col_specs = vec![
+            ColumnSpec {
+                table_spec: table_spec.clone(),
+                name: "a".to_string(),
+                typ: ColumnType::Int,
+            },
+            ColumnSpec {
+                table_spec,
+                name: "b".to_string(),
+                typ: ColumnType::Text,
+            },
+        ];
+        let cached = ResultMetadata {
+            col_count: 2,
+            paging_state: None,
+            metadata_id: None,
+            col_specs: col_specs.clone(),
+        };
+
+        let body = rows_without_metadata(&[(1, "one"), (2, "two")]);
+        let result =
+            super::deserialize_with_cached_metadata(&features, &mut &body[..], Some(&cached))
+                .unwrap();
+        let rows = match result {
+            super::Result::Rows(rows) => rows,
+            other => panic!("Expected Rows, got {:?}", other),
+        };
+        assert_eq!(rows.metadata.col_specs, col_specs);
+        assert_eq!(
+            rows.rows,
+            vec![
+                Row {
+                    columns: vec![
+                        Some(CqlValue::Int(1)),
+                        Some(CqlValue::Text("one".to_string()))
+                    ]
+                },
+                Row {
+                    columns: vec![
+                        Some(CqlValue::Int(2)),
+                        Some(CqlValue::Text("two".to_string()))
+                    ]
+                },
+            ]
+        );
+
+        // Without cached metadata the column types are unknown
+        super::deserialize(&mut &body[..]).unwrap_err();
+
+        // Cached metadata describing a different number of columns is rejected
+        let outdated = ResultMetadata {
+            col_count: 1,
+            paging_state: None,
+            metadata_id: None,
+            col_specs: col_specs[..1].to_vec(),
+        };
+        let err =
+            super::deserialize_with_cached_metadata(&features, &mut &body[..], Some(&outdated))
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            super::ParseError::CachedMetadataMismatch(2, 1)
+        ));
+    }
+
+    #[test]
+    fn test_row_borrowed_access() {
+        use super::Row;
+        use crate::cql_to_rust::{FromCqlValError, FromRowError};
+
+        let row = Row {
+            columns: vec![
+                Some(CqlValue::Text("some text".to_string())),
+                Some(CqlValue::Blob(vec![1, 2, 3])),
+                None,
+                Some(CqlValue::Int(7)),
+            ],
+        };
+
+        // The borrowed values point into the row, nothing is copied
+        let text = row.get_str(0).unwrap().unwrap();
+        assert_eq!(text, "some text");
+        assert_eq!(
+            text.as_ptr(),
+            row.columns[0].as_ref().unwrap().as_text().unwrap().as_ptr()
+        );
+        let blob = row.get_bytes(1).unwrap().unwrap();
+        assert_eq!(blob, &[1, 2, 3]);
+        assert_eq!(
+            blob.as_ptr(),
+            row.columns[1].as_ref().unwrap().as_blob().unwrap().as_ptr()
+        );
+
+        // Owned access has to copy the value out of a cloned row
+        let (owned_text,): (String,) = Row {
+            columns: row.columns[..1].to_vec(),
+        }
+        .into_typed()
+        .unwrap();
+        assert_eq!(owned_text, text);
+        assert_ne!(owned_text.as_ptr(), text.as_ptr());
+
+        assert_eq!(row.get_str(2), Ok(None));
+        assert_eq!(row.get_bytes(2), Ok(None));
+        assert_eq!(
+            row.get_str(3),
+            Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::BadCqlType,
+                column: 3
+            })
+        );
+        assert_eq!(
+            row.get_bytes(0),
+            Err(FromRowError::BadCqlVal {
+                err: FromCqlValError::BadCqlType,
+                column: 0
+            })
+        );
+        assert_eq!(
+            row.get_str(4),
+            Err(FromRowError::WrongRowSize {
+                expected: 5,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_deserialize_v5_metadata_ids() {
+        use crate::frame::types;
+        use bytes::BufMut;
+
+        let features = ProtocolFeatures {
+            protocol_version: ProtocolVersion::V5,
+            ..Default::default()
+        };
+
+        // In protocol v5 the id of the result metadata follows the statement id
+        let mut body = Vec::new();
+        body.put_i32(0x0004); // Prepared
+        types::write_short_bytes(b"statement id", &mut body).unwrap();
+        types::write_short_bytes(b"metadata id", &mut body).unwrap();
+        body.put_i32(0x0001); // prepared metadata: global_tables_spec
+        body.put_i32(0); // col_count
+        body.put_i32(0); // pk_count
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        body.put_i32(0x0004); // result metadata: no_metadata
+        body.put_i32(1); // col_count
+
+        let prepared =
+            match super::deserialize_with_cached_metadata(&features, &mut &body[..], None).unwrap()
+            {
+                super::Result::Prepared(prepared) => prepared,
+                other => panic!("Expected Prepared, got {:?}", other),
+            };
+        assert_eq!(&prepared.id[..], b"statement id");
+        assert_eq!(
+            prepared.result_metadata.metadata_id.as_deref(),
+            Some(&b"metadata id"[..])
+        );
+
+        // Rows announce a new id if the metadata has changed
+        let mut body = Vec::new();
+        body.put_i32(0x0002); // Rows
+        body.put_i32(0x0001 | 0x0008); // global_tables_spec, metadata_changed
+        body.put_i32(1); // col_count
+        types::write_short_bytes(b"new metadata id", &mut body).unwrap();
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        types::write_string("a", &mut body).unwrap();
+        body.put_u16(0x0009); // int
+        body.put_i32(1); // rows_count
+        7_i32.serialize(&mut body).unwrap();
+
+        let rows = match super::deserialize_with_cached_metadata(&features, &mut &body[..], None)
+            .unwrap()
+        {
+            super::Result::Rows(rows) => rows,
+            other => panic!("Expected Rows, got {:?}", other),
+        };
+        assert_eq!(
+            rows.metadata.metadata_id.as_deref(),
+            Some(&b"new metadata id"[..])
+        );
+        assert_eq!(rows.rows[0].columns, vec![Some(CqlValue::Int(7))]);
+    }
+
+    #[test]
+    fn test_walk_heterogeneous_row() {
+        use crate::frame::types;
+        use bytes::BufMut;
+
+        // Name, ids of the type as sent by the database and the decoded type of each column
+        let columns: [(&str, &[u16], ColumnType); 7] = [
+            ("id", &[0x0009], ColumnType::Int),
+            ("name", &[0x000D], ColumnType::Text),
+            ("active", &[0x0004], ColumnType::Boolean),
+            ("score", &[0x0007], ColumnType::Double),
+            (
+                "tags",
+                &[0x0020, 0x0001],
+                ColumnType::List(Box::new(ColumnType::Ascii)),
+            ),
+            ("data", &[0x0003], ColumnType::Blob),
+            ("missing", &[0x0002], ColumnType::BigInt),
+        ];
+
+        let mut body = Vec::new();
+        body.put_i32(0x0002); // Rows
+        body.put_i32(0x0001); // global_tables_spec
+        body.put_i32(columns.len() as i32);
+        types::write_string("ks", &mut body).unwrap();
+        types::write_string("t", &mut body).unwrap();
+        for (name, type_ids, _) in &columns {
+            types::write_string(name, &mut body).unwrap();
+            for type_id in type_ids.iter() {
+                body.put_u16(*type_id);
+            }
+        }
+        body.put_i32(1); // rows_count
+        7_i32.serialize(&mut body).unwrap();
+        "seven".serialize(&mut body).unwrap();
+        true.serialize(&mut body).unwrap();
+        0.5_f64.serialize(&mut body).unwrap();
+        vec!["a", "b"].serialize(&mut body).unwrap();
+        (&[0xca_u8, 0xfe][..]).serialize(&mut body).unwrap();
+        None::<i64>.serialize(&mut body).unwrap();
+
+        let rows = match super::deserialize(&mut &body[..]).unwrap() {
+            super::Result::Rows(rows) => rows,
+            other => panic!("Expected Rows, got {:?}", other),
+        };
+        let decoded_types: Vec<&ColumnType> = rows
+            .metadata
+            .col_specs
+            .iter()
+            .map(|spec| &spec.typ)
+            .collect();
+        let expected_types: Vec<&ColumnType> = columns.iter().map(|(_, _, typ)| typ).collect();
+        assert_eq!(decoded_types, expected_types);
+
+        // Without knowing the schema upfront, walk the columns along with their specs
+        let row = &rows.rows[0];
+        let described: Vec<String> = rows
+            .metadata
+            .col_specs
+            .iter()
+            .zip(row.columns.iter())
+            .map(|(spec, value)| {
+                let value = match value {
+                    None => "null".to_string(),
+                    Some(CqlValue::Int(v)) => v.to_string(),
+                    Some(CqlValue::Text(v)) => format!("{:?}", v),
+                    Some(CqlValue::Boolean(v)) => v.to_string(),
+                    Some(CqlValue::Double(v)) => v.to_string(),
+                    Some(CqlValue::List(elements)) => format!("{} elements", elements.len()),
+                    Some(CqlValue::Blob(v)) => format!("{} bytes", v.len()),
+                    Some(other) => panic!("Unexpected value {:?}", other),
+                };
+                format!("{}: {}", spec.name, value)
+            })
+            .collect();
+
+        assert_eq!(
+            described,
+            vec![
+                "id: 7",
+                "name: \"seven\"",
+                "active: true",
+                "score: 0.5",
+                "tags: 2 elements",
+                "data: 2 bytes",
+                "missing: null",
+            ]
+        );
+        assert_eq!(
+            row.columns[4],
+            Some(CqlValue::List(vec![
+                CqlValue::Ascii("a".to_string()),
+                CqlValue::Ascii("b".to_string())
+            ]))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_cql_value_to_json() {
+        use serde_json::json;
+        use std::net::{IpAddr, Ipv4Addr};
+
+        let to_json = |value: CqlValue| serde_json::to_value(value).unwrap();
+
+        // Scalars
+        assert_eq!(to_json(CqlValue::Text("text".to_string())), json!("text"));
+        assert_eq!(
+            to_json(CqlValue::Ascii("ascii".to_string())),
+            json!("ascii")
+        );
+        assert_eq!(to_json(CqlValue::Boolean(true)), json!(true));
+        assert_eq!(to_json(CqlValue::TinyInt(-8)), json!(-8));
+        assert_eq!(to_json(CqlValue::SmallInt(16)), json!(16));
+        assert_eq!(to_json(CqlValue::Int(32)), json!(32));
+        assert_eq!(to_json(CqlValue::BigInt(i64::MAX)), json!(i64::MAX));
+        assert_eq!(to_json(CqlValue::Counter(Counter(-64))), json!(-64));
+        assert_eq!(to_json(CqlValue::Float(0.5)), json!(0.5));
+        assert_eq!(to_json(CqlValue::Double(-1.25)), json!(-1.25));
+        assert_eq!(to_json(CqlValue::Empty), json!(null));
+
+        // Values with no JSON counterpart are strings
+        assert_eq!(
+            to_json(CqlValue::Blob(vec![0, 1, 2, 254, 255])),
+            json!("AAEC/v8=")
+        );
+        let uuid = Uuid::from_str("8e14e760-7fa8-11eb-bc66-000000000001").unwrap();
+        assert_eq!(
+            to_json(CqlValue::Uuid(uuid)),
+            json!("8e14e760-7fa8-11eb-bc66-000000000001")
+        );
+        assert_eq!(
+            to_json(CqlValue::Timeuuid(uuid)),
+            json!("8e14e760-7fa8-11eb-bc66-000000000001")
+        );
+        assert_eq!(
+            to_json(CqlValue::Inet(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)))),
+            json!("127.0.0.1")
+        );
+        assert_eq!(
+            to_json(CqlValue::Varint(
+                BigInt::from_str("123456789012345678901234567890").unwrap()
+            )),
+            json!("123456789012345678901234567890")
+        );
+        assert_eq!(
+            to_json(CqlValue::Decimal(
+                BigDecimal::from_str("-1234567890.0987654321").unwrap()
+            )),
+            json!("-1234567890.0987654321")
+        );
+        assert_eq!(
+            to_json(CqlValue::Duration(CqlDuration {
+                months: 1,
+                days: 2,
+                nanoseconds: 3
+            })),
+            json!("1mo2d3ns")
+        );
+
+        // Dates and times use ISO-8601
+        assert_eq!(to_json(CqlValue::Date(1 << 31)), json!("1970-01-01"));
+        assert_eq!(
+            to_json(CqlValue::Date((1 << 31) + 19_000)),
+            json!("2022-01-08")
+        );
+        assert_eq!(
+            to_json(CqlValue::Timestamp(Duration::milliseconds(
+                1_641_645_296_789
+            ))),
+            json!("2022-01-08T12:34:56.789Z")
+        );
+        assert_eq!(
+            to_json(CqlValue::Timestamp(Duration::milliseconds(-1000))),
+            json!("1969-12-31T23:59:59Z")
+        );
+        assert_eq!(
+            to_json(CqlValue::Time(Duration::nanoseconds(45_296_000_000_123))),
+            json!("12:34:56.000000123")
+        );
+
+        // Collections
+        assert_eq!(
+            to_json(CqlValue::List(vec![CqlValue::Int(1), CqlValue::Int(2)])),
+            json!([1, 2])
+        );
+        assert_eq!(
+            to_json(CqlValue::Set(vec![CqlValue::Text("a".to_string())])),
+            json!(["a"])
+        );
+        assert_eq!(
+            to_json(CqlValue::Tuple(vec![
+                Some(CqlValue::Int(1)),
+                None,
+                Some(CqlValue::Text("c".to_string()))
+            ])),
+            json!([1, null, "c"])
+        );
+        assert_eq!(
+            to_json(CqlValue::Map(vec![
+                (CqlValue::Text("a".to_string()), CqlValue::Int(1)),
+                (CqlValue::Text("b".to_string()), CqlValue::Int(2)),
+            ])),
+            json!({"a": 1, "b": 2})
+        );
+        assert_eq!(
+            to_json(CqlValue::Map(vec![(
+                CqlValue::Int(1),
+                CqlValue::List(vec![CqlValue::Boolean(false)])
+            )])),
+            json!({"1": [false]})
+        );
+        assert_eq!(
+            to_json(CqlValue::UserDefinedType {
+                keyspace: "ks".to_string(),
+                type_name: "address".to_string(),
+                fields: vec![
+                    (
+                        "street".to_string(),
+                        Some(CqlValue::Text("Main".to_string()))
+                    ),
+                    ("number".to_string(), Some(CqlValue::Int(7))),
+                    ("flat".to_string(), None),
+                ],
+            }),
+            json!({"street": "Main", "number": 7, "flat": null})
+        );
+    }
 }
@@ -0,0 +1,403 @@
+//! Framing used by protocol v5.
+//!
+//! Once a connection using protocol v5 is established (i.e. after the STARTUP request
+//! is answered), frames are no longer sent directly. Instead, they are sent in segments,
+//! whose headers and payloads are protected by checksums. Segments are optionally
+//! compressed with LZ4 - in protocol v5 frames themselves are never compressed.
+//!
+//! A segment whose payload consists of whole frames is marked as self-contained.
+//! Frames which don't fit into a single segment are split into multiple segments
+//! which are not self-contained.
+
+use bytes::{Buf, Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::frame_errors::FrameError;
+use super::response::ResponseOpcode;
+use super::{parse_response_header, FrameParams, HEADER_SIZE};
+
+/// The maximum size of a segment payload (before compression) - 128 KiB - 1.
+pub const MAX_PAYLOAD_SIZE: usize = (1 << 17) - 1;
+
+const UNCOMPRESSED_HEADER_SIZE: usize = 3;
+const COMPRESSED_HEADER_SIZE: usize = 5;
+const HEADER_CRC_SIZE: usize = 3;
+const PAYLOAD_CRC_SIZE: usize = 4;
+
+// Both header formats share the meaning of the lowest 17 bits (the length of the payload)
+const PAYLOAD_SIZE_BITS: u32 = 17;
+const SELF_CONTAINED_FLAG_UNCOMPRESSED: u64 = 1 << PAYLOAD_SIZE_BITS;
+const SELF_CONTAINED_FLAG_COMPRESSED: u64 = 1 << (2 * PAYLOAD_SIZE_BITS);
+
+const CRC24_INIT: u32 = 0x875060;
+const CRC24_POLY: u32 = 0x1974F0B;
+
+// Checksums of payloads are computed as if these bytes preceded the payload
+const CRC32_INITIAL_BYTES: [u8; 4] = [0xfa, 0x2d, 0x55, 0xca];
+const CRC32_TABLE: [u32; 256] = make_crc32_table();
+
+// CRC24 of the lowest `len` bytes of `value`, taken in little endian order
+fn crc24(mut value: u64, len: usize) -> u32 {
+    let mut crc = CRC24_INIT;
+    for _ in 0..len {
+        crc ^= ((value & 0xff) as u32) << 16;
+        value >>= 8;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc
+}
+
+// Table of the reflected CRC32 (IEEE 802.3) polynomial
+const fn make_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc = CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+fn crc32(payload: &[u8]) -> u32 {
+    let crc = crc32_update(!0, &CRC32_INITIAL_BYTES);
+    !crc32_update(crc, payload)
+}
+
+/// Writes `frames` (one or more serialized frames) to `out` as segments.\
+/// If `compress` is set, the segments are in the compressed (LZ4) format.
+/// Payloads shorter than `compression_threshold` bytes, as well as the ones
+/// which LZ4 can't make smaller, are sent uncompressed even then.
+pub fn write_segments(
+    frames: &[u8],
+    compress: bool,
+    compression_threshold: usize,
+    out: &mut Vec<u8>,
+) {
+    let self_contained = frames.len() <= MAX_PAYLOAD_SIZE;
+    for payload in frames.chunks(MAX_PAYLOAD_SIZE) {
+        if compress {
+            write_compressed_segment(payload, self_contained, compression_threshold, out);
+        } else {
+            write_uncompressed_segment(payload, self_contained, out);
+        }
+    }
+}
+
+fn write_uncompressed_segment(payload: &[u8], self_contained: bool, out: &mut Vec<u8>) {
+    let mut header = payload.len() as u64;
+    if self_contained {
+        header |= SELF_CONTAINED_FLAG_UNCOMPRESSED;
+    }
+    write_header(header, UNCOMPRESSED_HEADER_SIZE, out);
+    write_payload(payload, out);
+}
+
+fn write_compressed_segment(
+    payload: &[u8],
+    self_contained: bool,
+    compression_threshold: usize,
+    out: &mut Vec<u8>,
+) {
+    let compressed = if payload.len() >= compression_threshold {
+        Some(lz4_flex::compress(payload)).filter(|compressed| compressed.len() < payload.len())
+    } else {
+        None
+    };
+
+    // The uncompressed length is set to 0 if the payload is sent uncompressed
+    let (sent_payload, uncompressed_len) = match &compressed {
+        Some(compressed) => (&compressed[..], payload.len()),
+        None => (payload, 0),
+    };
+
+    let mut header = sent_payload.len() as u64 | (uncompressed_len as u64) << PAYLOAD_SIZE_BITS;
+    if self_contained {
+        header |= SELF_CONTAINED_FLAG_COMPRESSED;
+    }
+    write_header(header, COMPRESSED_HEADER_SIZE, out);
+    write_payload(sent_payload, out);
+}
+
+fn write_header(header: u64, header_size: usize, out: &mut Vec<u8>) {
+    out.extend_from_slice(&header.to_le_bytes()[..header_size]);
+    out.extend_from_slice(&crc24(header, header_size).to_le_bytes()[..HEADER_CRC_SIZE]);
+}
+
+fn write_payload(payload: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(payload);
+    out.extend_from_slice(&crc32(payload).to_le_bytes());
+}
+
+/// Reads a single segment in the compressed (LZ4) format if `compressed` is set,
+/// and in the uncompressed format otherwise. Returns its (decompressed) payload.\
+/// Checksums of the header and the payload are verified.
+pub async fn read_segment(
+    reader: &mut (impl AsyncRead + Unpin),
+    compressed: bool,
+) -> Result<Bytes, FrameError> {
+    let header_size = if compressed {
+        COMPRESSED_HEADER_SIZE
+    } else {
+        UNCOMPRESSED_HEADER_SIZE
+    };
+
+    let mut raw_header = [0u8; COMPRESSED_HEADER_SIZE + HEADER_CRC_SIZE];
+    let raw_header = &mut raw_header[..header_size + HEADER_CRC_SIZE];
+    reader.read_exact(raw_header).await?;
+
+    let mut header = [0u8; 8];
+    header[..header_size].copy_from_slice(&raw_header[..header_size]);
+    let header = u64::from_le_bytes(header);
+
+    let mut header_crc = [0u8; 4];
+    header_crc[..HEADER_CRC_SIZE].copy_from_slice(&raw_header[header_size..]);
+    let header_crc = u32::from_le_bytes(header_crc);
+    let computed_header_crc = crc24(header, header_size);
+    if header_crc != computed_header_crc {
+        return Err(FrameError::SegmentHeaderChecksumMismatch(
+            header_crc,
+            computed_header_crc,
+        ));
+    }
+
+    let payload_len = (header & MAX_PAYLOAD_SIZE as u64) as usize;
+    let uncompressed_len = if compressed {
+        ((header >> PAYLOAD_SIZE_BITS) & MAX_PAYLOAD_SIZE as u64) as usize
+    } else {
+        0
+    };
+
+    let mut payload = vec![0u8; payload_len + PAYLOAD_CRC_SIZE];
+    reader.read_exact(&mut payload).await?;
+
+    let payload_crc = (&payload[payload_len..]).get_u32_le();
+    payload.truncate(payload_len);
+    let computed_payload_crc = crc32(&payload);
+    if payload_crc != computed_payload_crc {
+        return Err(FrameError::SegmentPayloadChecksumMismatch(
+            payload_crc,
+            computed_payload_crc,
+        ));
+    }
+
+    if uncompressed_len != 0 {
+        Ok(lz4_flex::decompress(&payload, uncompressed_len)?.into())
+    } else {
+        Ok(payload.into())
+    }
+}
+
+/// Reads response frames sent in segments.\
+/// A segment may contain multiple frames, and a frame may span multiple segments,
+/// so payloads of the segments are buffered until they contain a whole frame.
+pub struct SegmentReader {
+    compressed: bool,
+    buffer: BytesMut,
+}
+
+impl SegmentReader {
+    /// Creates a reader of segments in the compressed (LZ4) format if `compressed` is set,
+    /// and in the uncompressed format otherwise.
+    pub fn new(compressed: bool) -> Self {
+        Self {
+            compressed,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Reads a response frame, failing with [`FrameError::FrameTooBig`] if its header
+    /// announces a body longer than `max_body_size` bytes.
+    pub async fn read_response_frame(
+        &mut self,
+        reader: &mut (impl AsyncRead + Unpin),
+        max_body_size: usize,
+    ) -> Result<(FrameParams, ResponseOpcode, Bytes), FrameError> {
+        self.fill(reader, HEADER_SIZE).await?;
+        let mut raw_header = [0u8; HEADER_SIZE];
+        raw_header.copy_from_slice(&self.buffer[..HEADER_SIZE]);
+        let (frame_params, opcode, length) = parse_response_header(&raw_header, max_body_size)?;
+
+        self.fill(reader, HEADER_SIZE + length).await?;
+        self.buffer.advance(HEADER_SIZE);
+        let body = self.buffer.split_to(length).freeze();
+
+        Ok((frame_params, opcode, body))
+    }
+
+    // Reads segments until at least `len` bytes are buffered
+    async fn fill(
+        &mut self,
+        reader: &mut (impl AsyncRead + Unpin),
+        len: usize,
+    ) -> Result<(), FrameError> {
+        while self.buffer.len() < len {
+            let payload = read_segment(reader, self.compressed).await?;
+            self.buffer.extend_from_slice(&payload);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // A RESULT (Void) frame of protocol v5 on stream 1
+    const VOID_RESULT_FRAME: [u8; 13] = [
+        0x85, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01,
+    ];
+
+    #[test]
+    fn test_checksums() {
+        // The standard CRC32 check value, then the same input seeded as in segments
+        assert_eq!(!crc32_update(!0, b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b"123456789"), 0xe2a261a7);
+        assert_eq!(crc32(b""), 0x44777ed3);
+
+        assert_eq!(crc24(0, 3), 0x7de777);
+        assert_eq!(crc24(9 | SELF_CONTAINED_FLAG_UNCOMPRESSED, 3), 0xc1c8a4);
+        assert_eq!(crc24(0x12_3456_789a, 5), 0x40d313);
+    }
+
+    #[test]
+    fn test_uncompressed_segment_layout() {
+        let mut out = Vec::new();
+        write_segments(&VOID_RESULT_FRAME, false, 0, &mut out);
+
+        // Length 13 with the self-contained flag, little endian, followed by its CRC24
+        let header = 13 | SELF_CONTAINED_FLAG_UNCOMPRESSED;
+        assert_eq!(&out[..3], &header.to_le_bytes()[..3]);
+        assert_eq!(&out[3..6], &crc24(header, 3).to_le_bytes()[..3]);
+        assert_eq!(&out[6..19], &VOID_RESULT_FRAME);
+        assert_eq!(&out[19..], &crc32(&VOID_RESULT_FRAME).to_le_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_segment_round_trip() {
+        let compressible = vec![7u8; 1000];
+        let mut rng = StdRng::seed_from_u64(0);
+        let incompressible: Vec<u8> = (0..1000).map(|_| rng.gen()).collect();
+        let oversized: Vec<u8> = (0..MAX_PAYLOAD_SIZE * 2 + 10)
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        for payload in [&compressible, &incompressible, &oversized] {
+            for (compress, threshold) in [(false, 0), (true, 0), (true, usize::MAX)] {
+                let mut out = Vec::new();
+                write_segments(payload, compress, threshold, &mut out);
+
+                let mut reader = &out[..];
+                let mut read = Vec::new();
+                while !reader.is_empty() {
+                    read.extend_from_slice(&read_segment(&mut reader, compress).await.unwrap());
+                }
+                assert_eq!(&read, payload);
+            }
+        }
+
+        // Compression is used only when it helps
+        let mut out = Vec::new();
+        write_segments(&compressible, true, 0, &mut out);
+        assert!(out.len() < compressible.len());
+        let mut out = Vec::new();
+        write_segments(&incompressible, true, 0, &mut out);
+        assert_eq!(
+            out.len(),
+            COMPRESSED_HEADER_SIZE + HEADER_CRC_SIZE + incompressible.len() + PAYLOAD_CRC_SIZE
+        );
+
+        // Frames which don't fit into one segment are split into ones which are not self-contained
+        let mut out = Vec::new();
+        write_segments(&oversized, false, 0, &mut out);
+        let first_header = (&out[..]).get_uint_le(UNCOMPRESSED_HEADER_SIZE);
+        assert_eq!(first_header, MAX_PAYLOAD_SIZE as u64);
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_segment_rejected() {
+        let mut out = Vec::new();
+        write_segments(&VOID_RESULT_FRAME, true, 0, &mut out);
+
+        let mut corrupted_header = out.clone();
+        corrupted_header[0] ^= 0x01;
+        let err = read_segment(&mut &corrupted_header[..], true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FrameError::SegmentHeaderChecksumMismatch(..)));
+
+        let mut corrupted_payload = out.clone();
+        corrupted_payload[COMPRESSED_HEADER_SIZE + HEADER_CRC_SIZE] ^= 0x01;
+        let err = read_segment(&mut &corrupted_payload[..], true)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            FrameError::SegmentPayloadChecksumMismatch(..)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_segment_reader_reads_frames() {
+        // Two frames in one self-contained segment, then a frame split into two segments
+        let mut out = Vec::new();
+        let mut two_frames = VOID_RESULT_FRAME.to_vec();
+        two_frames.extend_from_slice(&VOID_RESULT_FRAME);
+        write_segments(&two_frames, false, 0, &mut out);
+
+        let mut big_frame = VOID_RESULT_FRAME[..HEADER_SIZE].to_vec();
+        big_frame[5..9].copy_from_slice(&(MAX_PAYLOAD_SIZE as u32).to_be_bytes());
+        big_frame.resize(HEADER_SIZE + MAX_PAYLOAD_SIZE, 0xab);
+        write_segments(&big_frame, false, 0, &mut out);
+
+        let mut reader = SegmentReader::new(false);
+        let mut input = &out[..];
+        for _ in 0..2 {
+            let (params, opcode, body) = reader
+                .read_response_frame(&mut input, MAX_PAYLOAD_SIZE)
+                .await
+                .unwrap();
+            assert_eq!(params.version, 0x85);
+            assert_eq!(params.stream, 1);
+            assert_eq!(opcode, ResponseOpcode::Result);
+            assert_eq!(&body[..], &VOID_RESULT_FRAME[HEADER_SIZE..]);
+        }
+        let (_, _, body) = reader
+            .read_response_frame(&mut input, MAX_PAYLOAD_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(body.len(), MAX_PAYLOAD_SIZE);
+        assert!(input.is_empty());
+
+        // The limit applies to frames read from segments as well
+        let mut out = Vec::new();
+        write_segments(&big_frame, false, 0, &mut out);
+        let err = SegmentReader::new(false)
+            .read_response_frame(&mut &out[..], 4)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FrameError::FrameTooBig(MAX_PAYLOAD_SIZE, 4)));
+    }
+}
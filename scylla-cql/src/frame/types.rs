@@ -491,6 +491,14 @@ fn type_consistency() {
 }
 
 pub fn read_inet(buf: &mut &[u8]) -> Result<SocketAddr, ParseError> {
+    let ip_addr = read_inetaddr(buf)?;
+    let port = read_int(buf)?;
+
+    Ok(SocketAddr::new(ip_addr, port as u16))
+}
+
+/// Reads an address without a port (`[inetaddr]` in the protocol specification).
+pub fn read_inetaddr(buf: &mut &[u8]) -> Result<IpAddr, ParseError> {
     let len = buf.read_u8()?;
     let ip_addr = match len {
         4 => {
@@ -510,9 +518,8 @@ pub fn read_inet(buf: &mut &[u8]) -> Result<SocketAddr, ParseError> {
             )))
         }
     };
-    let port = read_int(buf)?;
 
-    Ok(SocketAddr::new(ip_addr, port as u16))
+    Ok(ip_addr)
 }
 
 pub fn write_inet(addr: SocketAddr, buf: &mut impl BufMut) {
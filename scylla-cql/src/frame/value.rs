@@ -1,7 +1,7 @@
 use crate::frame::frame_errors::ParseError;
 use crate::frame::types;
 use bigdecimal::BigDecimal;
-use bytes::BufMut;
+use bytes::{BufMut, Bytes};
 use chrono::prelude::*;
 use chrono::Duration;
 use num_bigint::BigInt;
@@ -10,6 +10,9 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryInto;
 use std::hash::BuildHasher;
 use std::net::IpAddr;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -29,14 +32,48 @@ pub trait Value {
 #[error("Value too big to be sent in a request - max 2GiB allowed")]
 pub struct ValueTooBig;
 
-/// Represents an unset value
+/// Represents an unset value\
+/// The database leaves a column bound to an unset value untouched, instead of
+/// writing a `NULL` (and creating a tombstone). Requires protocol v4, which is
+/// the only version the driver speaks.
 pub struct Unset;
 
-/// Represents an counter value
+/// Represents an counter value\
+/// Counters can be added and subtracted, e.g. to accumulate an update locally
+/// before sending it. Like counters in the database, they wrap around on overflow.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Counter(pub i64);
 
-/// Enum providing a way to represent a value that might be unset
+impl Add for Counter {
+    type Output = Counter;
+
+    fn add(self, rhs: Counter) -> Counter {
+        Counter(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Counter {
+    type Output = Counter;
+
+    fn sub(self, rhs: Counter) -> Counter {
+        Counter(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl AddAssign for Counter {
+    fn add_assign(&mut self, rhs: Counter) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Counter {
+    fn sub_assign(&mut self, rhs: Counter) {
+        *self = *self - rhs;
+    }
+}
+
+/// Enum providing a way to represent a value that might be unset,
+/// see [`Unset`] for details
 #[derive(Clone, Copy)]
 pub enum MaybeUnset<V: Value> {
     Unset,
@@ -58,6 +95,180 @@ pub struct Timestamp(pub Duration);
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Time(pub Duration);
 
+/// Wrapper used to explicitly send and receive values of the CQL `blob` type.\
+/// Unlike `Vec<u8>`, it can't be mistaken for a list of bytes.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct CqlBlob(pub Vec<u8>);
+
+impl From<Vec<u8>> for CqlBlob {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<CqlBlob> for Vec<u8> {
+    fn from(blob: CqlBlob) -> Self {
+        blob.0
+    }
+}
+
+/// Wrapper used to receive values as JSON text.\
+/// It can be read from the `[json]` column of `SELECT JSON`.
+/// Values are sent as JSON with [`JsonText`].
+#[cfg(feature = "serde")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Json<T>(pub T);
+
+/// JSON text encoded from a value implementing `serde::Serialize`, sent as CQL `text`.\
+/// It can be bound to `INSERT ... JSON ?`.
+/// The value is encoded in [`JsonText::new`], so that values which can't be
+/// represented as JSON are reported before a request is made.
+#[cfg(feature = "serde")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct JsonText(String);
+
+#[cfg(feature = "serde")]
+impl JsonText {
+    /// Encodes the value as JSON.
+    pub fn new<T: serde::Serialize + ?Sized>(value: &T) -> Result<Self, serde_json::Error> {
+        serde_json::to_string(value).map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Native CQL `timeuuid` - a version 1 UUID.\
+/// Timeuuids are ordered the same way the database orders them: by their timestamps first,
+/// and then by the remaining bytes, compared as signed bytes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CqlTimeuuid(Uuid);
+
+// Number of 100ns intervals between the UUID epoch (1582-10-15) and the unix epoch
+const UUID_EPOCH_OFFSET: u64 = 0x01B2_1DD2_1381_4000;
+
+// Timestamp of the last timeuuid generated by CqlTimeuuid::now()
+static LAST_TIMEUUID_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+// Clock sequence and node id used by CqlTimeuuid::now(), generated randomly once per process
+static TIMEUUID_CLOCK_SEQ_AND_NODE: AtomicU64 = AtomicU64::new(0);
+
+impl CqlTimeuuid {
+    /// Generates a new timeuuid based on the current system time.\
+    /// Timeuuids generated within the process are strictly increasing, even if
+    /// many of them are generated within the same 100ns interval.
+    /// The clock sequence and the node id are random.
+    pub fn now() -> Self {
+        let timestamp = Self::next_timestamp();
+        Self::from_parts(timestamp, Self::clock_seq_and_node())
+    }
+
+    /// Timestamp of the timeuuid - number of 100ns intervals since 1582-10-15.
+    pub fn timestamp(&self) -> u64 {
+        let bytes = self.0.as_bytes();
+        let time_low = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64;
+        let time_mid = u16::from_be_bytes([bytes[4], bytes[5]]) as u64;
+        let time_hi = (u16::from_be_bytes([bytes[6], bytes[7]]) & 0x0FFF) as u64;
+        (time_hi << 48) | (time_mid << 32) | time_low
+    }
+
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+
+    fn from_parts(timestamp: u64, clock_seq_and_node: u64) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&(timestamp as u32).to_be_bytes());
+        bytes[4..6].copy_from_slice(&((timestamp >> 32) as u16).to_be_bytes());
+        // Version 1 in the high nibble
+        bytes[6..8].copy_from_slice(&(((timestamp >> 48) as u16 & 0x0FFF) | 0x1000).to_be_bytes());
+        bytes[8..16].copy_from_slice(&clock_seq_and_node.to_be_bytes());
+        Self(Uuid::from_bytes(bytes))
+    }
+
+    fn next_timestamp() -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| (since_epoch.as_nanos() / 100) as u64)
+            .unwrap_or(0)
+            + UUID_EPOCH_OFFSET;
+        let mut last = LAST_TIMEUUID_TIMESTAMP.load(Ordering::Relaxed);
+        loop {
+            let next = std::cmp::max(now, last + 1);
+            match LAST_TIMEUUID_TIMESTAMP.compare_exchange_weak(
+                last,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return next,
+                Err(current) => last = current,
+            }
+        }
+    }
+
+    fn clock_seq_and_node() -> u64 {
+        let current = TIMEUUID_CLOCK_SEQ_AND_NODE.load(Ordering::Relaxed);
+        if current != 0 {
+            return current;
+        }
+
+        let mut bytes: [u8; 8] = rand::random();
+        // RFC 4122 variant
+        bytes[0] = (bytes[0] & 0x3F) | 0x80;
+        // Multicast bit marks a random node id
+        bytes[2] |= 0x01;
+        let generated = u64::from_be_bytes(bytes);
+
+        match TIMEUUID_CLOCK_SEQ_AND_NODE.compare_exchange(
+            0,
+            generated,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => generated,
+            Err(current) => current,
+        }
+    }
+}
+
+impl From<Uuid> for CqlTimeuuid {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl From<CqlTimeuuid> for Uuid {
+    fn from(timeuuid: CqlTimeuuid) -> Self {
+        timeuuid.0
+    }
+}
+
+impl Ord for CqlTimeuuid {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let signed_bytes = |timeuuid: &CqlTimeuuid| {
+            let mut bytes = [0i8; 8];
+            for (signed, byte) in bytes.iter_mut().zip(&timeuuid.0.as_bytes()[8..]) {
+                *signed = *byte as i8;
+            }
+            bytes
+        };
+        self.timestamp()
+            .cmp(&other.timestamp())
+            .then_with(|| signed_bytes(self).cmp(&signed_bytes(other)))
+    }
+}
+
+impl PartialOrd for CqlTimeuuid {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Keeps a buffer with serialized Values
 /// Allows adding new Values and iterating over serialized ones
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -87,6 +298,18 @@ pub enum SerializeValuesError {
     ParseError,
 }
 
+/// Names of values bound to a statement don't match names of its bind markers
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error(
+    "Named values don't match bind markers of the statement; missing values: {missing:?}, unknown names: {unknown:?}"
+)]
+pub struct NamedValuesMismatch {
+    /// Names of bind markers for which no value was provided
+    pub missing: Vec<String>,
+    /// Names of provided values that don't match any bind marker
+    pub unknown: Vec<String>,
+}
+
 pub type SerializedResult<'a> = Result<Cow<'a, SerializedValues>, SerializeValuesError>;
 
 /// Represents list of values to be sent in a query
@@ -232,6 +455,63 @@ impl SerializedValues {
             (name, serialized)
         })
     }
+
+    /// Converts named values into positional ones, placing each value at the positions
+    /// of the bind markers with the same name.\
+    /// Values without names are returned unchanged.\
+    /// Fails if no value was provided for some marker or if some value doesn't match any marker.
+    pub fn to_positional<'a>(
+        &self,
+        marker_names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<SerializedValues, NamedValuesMismatch> {
+        if !self.contains_names {
+            return Ok(self.clone());
+        }
+
+        let mut named_values: Vec<(&str, &[u8])> = Vec::with_capacity(self.values_num as usize);
+        let mut buf = &self.serialized_values[..];
+        for _ in 0..self.values_num {
+            // `unwrap()`s here are safe for the same reason as in `iter_name_value_pairs()`
+            let name = types::read_string(&mut buf).unwrap();
+            // Keep the value together with its length, so that it can be copied as is
+            let value_beg = buf;
+            types::read_bytes_opt(&mut buf).unwrap();
+            let value_len = value_beg.len() - buf.len();
+            named_values.push((name, &value_beg[..value_len]));
+        }
+
+        let mut used = vec![false; named_values.len()];
+        let mut missing = Vec::new();
+        let mut result = SerializedValues::with_capacity(self.size());
+        for marker_name in marker_names {
+            match named_values
+                .iter()
+                .position(|(name, _)| *name == marker_name)
+            {
+                Some(idx) => {
+                    used[idx] = true;
+                    result
+                        .serialized_values
+                        .extend_from_slice(named_values[idx].1);
+                    result.values_num += 1;
+                }
+                None => missing.push(marker_name.to_owned()),
+            }
+        }
+
+        let unknown: Vec<String> = named_values
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|((name, _), _)| (*name).to_owned())
+            .collect();
+
+        if !missing.is_empty() || !unknown.is_empty() {
+            return Err(NamedValuesMismatch { missing, unknown });
+        }
+
+        Ok(result)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -385,6 +665,32 @@ impl Value for BigDecimal {
     }
 }
 
+#[cfg(feature = "rust_decimal")]
+impl Value for rust_decimal::Decimal {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        let mantissa_bytes = self.mantissa().to_be_bytes();
+
+        // Varint is serialized as the shortest two's complement representation
+        let mut skipped = 0;
+        while skipped < mantissa_bytes.len() - 1 {
+            let (current, next) = (mantissa_bytes[skipped], mantissa_bytes[skipped + 1]);
+            let redundant =
+                (current == 0x00 && next & 0x80 == 0) || (current == 0xff && next & 0x80 != 0);
+            if !redundant {
+                break;
+            }
+            skipped += 1;
+        }
+        let serialized = &mantissa_bytes[skipped..];
+
+        buf.put_i32(serialized.len() as i32 + 4);
+        buf.put_i32(self.scale() as i32);
+        buf.extend_from_slice(serialized);
+
+        Ok(())
+    }
+}
+
 impl Value for NaiveDate {
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
         buf.put_i32(4);
@@ -426,6 +732,21 @@ impl Value for Time {
     }
 }
 
+impl Value for NaiveTime {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        // NaiveTime is able to represent a leap second, which doesn't fit in the CQL time range
+        let nanoseconds: i64 =
+            self.num_seconds_from_midnight() as i64 * 1_000_000_000 + self.nanosecond() as i64;
+        if !(0..=86399999999999).contains(&nanoseconds) {
+            return Err(ValueTooBig);
+        }
+
+        buf.put_i32(8);
+        buf.put_i64(nanoseconds);
+        Ok(())
+    }
+}
+
 impl Value for DateTime<Utc> {
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
         buf.put_i32(8);
@@ -434,6 +755,66 @@ impl Value for DateTime<Utc> {
     }
 }
 
+#[cfg(feature = "time")]
+impl Value for time::Date {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        buf.put_i32(4);
+        let unix_epoch = time::Date::from_calendar_date(1970, time::Month::January, 1).unwrap();
+
+        let days: u32 = ((*self - unix_epoch).whole_days() + (1 << 31))
+            .try_into() // convert to u32
+            .map_err(|_| ValueTooBig)?;
+
+        buf.put_u32(days);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Value for time::Time {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        let (hour, minute, second, nanosecond) = self.as_hms_nano();
+        let nanoseconds: i64 = (hour as i64 * 3600 + minute as i64 * 60 + second as i64)
+            * 1_000_000_000
+            + nanosecond as i64;
+
+        buf.put_i32(8);
+        buf.put_i64(nanoseconds);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Value for time::OffsetDateTime {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        // Milliseconds since unix epoch, rounded down like in DateTime<Utc>
+        let millis: i64 = self
+            .unix_timestamp_nanos()
+            .div_euclid(1_000_000)
+            .try_into()
+            .map_err(|_| ValueTooBig)?;
+
+        buf.put_i32(8);
+        buf.put_i64(millis);
+        Ok(())
+    }
+}
+
+// PrimitiveDateTime is treated as a UTC date and time
+#[cfg(feature = "time")]
+impl Value for time::PrimitiveDateTime {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        self.assume_utc().serialize(buf)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Value for JsonText {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        self.0.as_str().serialize(buf)
+    }
+}
+
 #[cfg(feature = "secret")]
 impl<V: Value + Zeroize> Value for Secret<V> {
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
@@ -480,6 +861,12 @@ impl Value for Uuid {
     }
 }
 
+impl Value for CqlTimeuuid {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        self.0.serialize(buf)
+    }
+}
+
 impl Value for BigInt {
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
         let serialized = self.to_signed_bytes_be();
@@ -521,6 +908,18 @@ impl Value for &[u8] {
     }
 }
 
+impl Value for Bytes {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        <&[u8] as Value>::serialize(&self.as_ref(), buf)
+    }
+}
+
+impl Value for CqlBlob {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        <&[u8] as Value>::serialize(&self.0.as_slice(), buf)
+    }
+}
+
 impl<const N: usize> Value for [u8; N] {
     fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
         let val_len: i32 = self.len().try_into().map_err(|_| ValueTooBig)?;
@@ -699,6 +1098,13 @@ impl<T: Value> Value for &[T] {
     }
 }
 
+// Note that [u8; N] is serialized as a blob, see above
+impl<T: Value, const N: usize> Value for [T; N] {
+    fn serialize(&self, buf: &mut Vec<u8>) -> Result<(), ValueTooBig> {
+        serialize_list_or_set(self.iter(), N, buf)
+    }
+}
+
 fn serialize_tuple<V: Value>(
     elem_iter: impl Iterator<Item = V>,
     buf: &mut Vec<u8>,
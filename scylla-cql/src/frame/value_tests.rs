@@ -1,11 +1,11 @@
 use crate::frame::value::BatchValuesIterator;
 
 use super::value::{
-    BatchValues, Date, MaybeUnset, SerializeValuesError, SerializedValues, Time, Timestamp, Unset,
-    Value, ValueList, ValueTooBig,
+    BatchValues, Counter, CqlBlob, CqlDuration, CqlTimeuuid, Date, MaybeUnset, NamedValuesMismatch,
+    SerializeValuesError, SerializedValues, Time, Timestamp, Unset, Value, ValueList, ValueTooBig,
 };
 use bytes::BufMut;
-use chrono::{Duration, NaiveDate};
+use chrono::{Duration, NaiveDate, NaiveTime};
 use std::{borrow::Cow, convert::TryInto};
 use uuid::Uuid;
 
@@ -29,6 +29,33 @@ fn basic_serialization() {
     assert_eq!(serialized("abc".to_string()), vec![0, 0, 0, 3, 97, 98, 99]);
 }
 
+#[test]
+fn small_int_types_boundary_serialization() {
+    assert_eq!(serialized(i8::MIN), vec![0, 0, 0, 1, 0x80]);
+    assert_eq!(serialized(i8::MAX), vec![0, 0, 0, 1, 0x7f]);
+    assert_eq!(serialized(-1_i8), vec![0, 0, 0, 1, 0xff]);
+
+    assert_eq!(serialized(i16::MIN), vec![0, 0, 0, 2, 0x80, 0x00]);
+    assert_eq!(serialized(i16::MAX), vec![0, 0, 0, 2, 0x7f, 0xff]);
+    assert_eq!(serialized(-1_i16), vec![0, 0, 0, 2, 0xff, 0xff]);
+}
+
+#[test]
+fn counter_arithmetic() {
+    let mut counter = Counter(5) + Counter(3) - Counter(10);
+    assert_eq!(counter, Counter(-2));
+
+    counter += Counter(4);
+    counter -= Counter(1);
+    assert_eq!(counter, Counter(1));
+
+    // Counters wrap around on overflow
+    assert_eq!(Counter(i64::MAX) + Counter(1), Counter(i64::MIN));
+    assert_eq!(Counter(i64::MIN) - Counter(1), Counter(i64::MAX));
+
+    assert_eq!(serialized(Counter(1)), serialized(1_i64));
+}
+
 #[test]
 fn u8_array_serialization() {
     let val = [1u8; 4];
@@ -41,6 +68,147 @@ fn u8_slice_serialization() {
     assert_eq!(serialized(val.as_slice()), vec![0, 0, 0, 4, 1, 1, 1, 1]);
 }
 
+#[test]
+fn bytes_serialization() {
+    let val = bytes::Bytes::from_static(&[1, 1, 1, 1]);
+    assert_eq!(serialized(val), vec![0, 0, 0, 4, 1, 1, 1, 1]);
+}
+
+#[test]
+fn blob_serialization() {
+    let val = CqlBlob(vec![0, 1, 0, 0xff, 0]);
+    assert_eq!(serialized(val), vec![0, 0, 0, 5, 0, 1, 0, 0xff, 0]);
+    assert_eq!(serialized(CqlBlob::default()), vec![0, 0, 0, 0]);
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn rust_decimal_serialization() {
+    use bigdecimal::BigDecimal;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    // Serialized the same way as the equivalent BigDecimal
+    let tests = [
+        "4.2",
+        "0",
+        "-1",
+        "127",
+        "128",
+        "-128",
+        "-129",
+        "997",
+        "-0.001",
+        "1.9999999999999999999999999999",
+        "79228162514264337593543950335",
+        "-79228162514264337593543950335",
+    ];
+    for test in tests {
+        assert_eq!(
+            serialized(Decimal::from_str(test).unwrap()),
+            serialized(BigDecimal::from_str(test).unwrap()),
+            "{}",
+            test
+        );
+    }
+
+    assert_eq!(
+        serialized(Decimal::new(-1, 3)),
+        vec![0, 0, 0, 5, 0, 0, 0, 3, 0xff]
+    );
+}
+
+#[test]
+fn array_and_slice_serialization() {
+    // Arrays and slices are serialized as lists, the same way as Vecs
+    let array: [i32; 4] = [1, 2, 3, 4];
+    assert_eq!(serialized(array), serialized(array.to_vec()));
+    assert_eq!(
+        serialized([7_i32]),
+        vec![0, 0, 0, 12, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 7]
+    );
+
+    let slice: &[&str] = &["a", "bc"];
+    assert_eq!(serialized(slice), serialized(slice.to_vec()));
+    assert_eq!(
+        serialized(slice),
+        vec![0, 0, 0, 15, 0, 0, 0, 2, 0, 0, 0, 1, b'a', 0, 0, 0, 2, b'b', b'c']
+    );
+
+    let empty_array: [i32; 0] = [];
+    assert_eq!(serialized(empty_array), vec![0, 0, 0, 4, 0, 0, 0, 0]);
+}
+
+#[test]
+fn set_serialization() {
+    use std::collections::{BTreeSet, HashSet};
+
+    // A set is serialized as the number of elements followed by the elements
+    let btree_set: BTreeSet<String> = ["b".to_string(), "a".to_string()].into_iter().collect();
+    assert_eq!(
+        serialized(btree_set),
+        vec![0, 0, 0, 14, 0, 0, 0, 2, 0, 0, 0, 1, b'a', 0, 0, 0, 1, b'b']
+    );
+
+    let hash_set: HashSet<i32> = [7].into_iter().collect();
+    assert_eq!(
+        serialized(hash_set),
+        vec![0, 0, 0, 12, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 7]
+    );
+
+    let empty_set: BTreeSet<i32> = BTreeSet::new();
+    assert_eq!(serialized(empty_set), vec![0, 0, 0, 4, 0, 0, 0, 0]);
+}
+
+#[test]
+fn map_serialization() {
+    use std::collections::{BTreeMap, HashMap};
+
+    // A map is serialized as the number of entries followed by keys and values
+    let hash_map: HashMap<String, i32> = [("a".to_string(), 7)].into_iter().collect();
+    assert_eq!(
+        serialized(hash_map),
+        vec![0, 0, 0, 17, 0, 0, 0, 1, 0, 0, 0, 1, b'a', 0, 0, 0, 4, 0, 0, 0, 7]
+    );
+
+    // Nested collections are serialized with their own length prefixes
+    let nested_map: BTreeMap<i32, Vec<String>> = [(1, vec!["x".to_string()]), (2, vec![])]
+        .into_iter()
+        .collect();
+    assert_eq!(
+        serialized(nested_map),
+        vec![
+            0, 0, 0, 41, // map size
+            0, 0, 0, 2, // number of entries
+            0, 0, 0, 4, 0, 0, 0, 1, // key 1
+            0, 0, 0, 9, 0, 0, 0, 1, 0, 0, 0, 1, b'x', // value ["x"]
+            0, 0, 0, 4, 0, 0, 0, 2, // key 2
+            0, 0, 0, 4, 0, 0, 0, 0, // value []
+        ]
+    );
+}
+
+#[test]
+fn ip_addr_serialization() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    // IPv4 addresses are serialized as 4 bytes
+    let ipv4 = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+    assert_eq!(serialized(ipv4), vec![0, 0, 0, 4, 127, 0, 0, 1]);
+
+    // IPv6 addresses are serialized as 16 bytes
+    let ipv6 = IpAddr::V6(Ipv6Addr::new(
+        0x2001, 0x0db8, 0, 0, 0, 0x8a2e, 0x0370, 0x7334,
+    ));
+    assert_eq!(
+        serialized(ipv6),
+        vec![
+            0, 0, 0, 16, 0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0x8a, 0x2e, 0x03, 0x70, 0x73,
+            0x34
+        ]
+    );
+}
+
 #[test]
 fn naive_date_serialization() {
     // 1970-01-31 is 2^31
@@ -97,6 +265,113 @@ fn time_serialization() {
     assert_eq!(long_time.serialize(&mut Vec::new()), Err(ValueTooBig));
 }
 
+#[test]
+fn naive_time_serialization() {
+    // NaiveTime is serialized as a number of nanoseconds since midnight
+
+    let max_time: i64 = 24 * 60 * 60 * 1_000_000_000 - 1;
+    let tests = [
+        (NaiveTime::from_hms_opt(0, 0, 0).unwrap(), 0),
+        (NaiveTime::from_hms_nano_opt(0, 0, 0, 1).unwrap(), 1),
+        (
+            NaiveTime::from_hms_nano_opt(1, 2, 3, 4).unwrap(),
+            3_723_000_000_004,
+        ),
+        (
+            NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+            max_time,
+        ),
+    ];
+
+    for (test_time, test_val) in tests.iter() {
+        let bytes: Vec<u8> = serialized(*test_time);
+
+        let mut expected_bytes: Vec<u8> = vec![0, 0, 0, 8];
+        expected_bytes.extend_from_slice(&test_val.to_be_bytes());
+
+        assert_eq!(bytes, expected_bytes);
+    }
+
+    // Leap seconds can't be represented as a CQL time value
+    let leap_second = NaiveTime::from_hms_nano_opt(23, 59, 59, 1_500_000_000).unwrap();
+    assert_eq!(leap_second.serialize(&mut Vec::new()), Err(ValueTooBig));
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_crate_serialization() {
+    use chrono::{DateTime, Utc};
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+    // Dates are serialized the same way as the equivalent NaiveDates
+    let dates = [
+        (1970, Month::January, 1),
+        (1969, Month::December, 31),
+        (2020, Month::March, 7),
+        (1337, Month::April, 5),
+        (-9999, Month::January, 1),
+        (9999, Month::December, 31),
+    ];
+    for (year, month, day) in dates {
+        let date = Date::from_calendar_date(year, month, day).unwrap();
+        let naive_date = NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap();
+        assert_eq!(serialized(date), serialized(naive_date));
+    }
+    assert_eq!(
+        serialized(Date::from_calendar_date(1970, Month::January, 1).unwrap()),
+        vec![0, 0, 0, 4, 0x80, 0, 0, 0]
+    );
+
+    // Times are serialized the same way as the equivalent NaiveTimes
+    let times = [
+        (0, 0, 0, 0),
+        (13, 37, 42, 123_456_789),
+        (23, 59, 59, 999_999_999),
+    ];
+    for (hour, minute, second, nano) in times {
+        let time = Time::from_hms_nano(hour, minute, second, nano).unwrap();
+        let naive_time =
+            NaiveTime::from_hms_nano_opt(hour as u32, minute as u32, second as u32, nano).unwrap();
+        assert_eq!(serialized(time), serialized(naive_time));
+    }
+
+    // Datetimes are serialized the same way as the equivalent DateTime<Utc>
+    for nanos in [0, 1_999_999, -1, -1_000_001, 1_234_567_890_123_456_789] {
+        let datetime = OffsetDateTime::from_unix_timestamp_nanos(nanos).unwrap();
+        let chrono_datetime = DateTime::<Utc>::from_timestamp(
+            nanos.div_euclid(1_000_000_000) as i64,
+            nanos.rem_euclid(1_000_000_000) as u32,
+        )
+        .unwrap();
+        assert_eq!(serialized(datetime), serialized(chrono_datetime));
+
+        let primitive_datetime = PrimitiveDateTime::new(datetime.date(), datetime.time());
+        assert_eq!(serialized(primitive_datetime), serialized(chrono_datetime));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_serialization() {
+    use super::value::JsonText;
+    use std::collections::BTreeMap;
+
+    // Sent as text containing the JSON representation
+    assert_eq!(
+        serialized(JsonText::new(&7).unwrap()),
+        vec![0, 0, 0, 1, b'7']
+    );
+    let json = JsonText::new(&BTreeMap::from([("a", vec![1, 2])])).unwrap();
+    assert_eq!(json.as_str(), r#"{"a":[1,2]}"#);
+    assert_eq!(
+        serialized(json),
+        [&[0, 0, 0, 11][..], br#"{"a":[1,2]}"#].concat()
+    );
+
+    // Only string keys are allowed in JSON objects
+    assert!(JsonText::new(&BTreeMap::from([(vec![1], 2)])).is_err());
+}
+
 #[test]
 fn timestamp_serialization() {
     // Timestamp is milliseconds since unix epoch represented as i64
@@ -136,6 +411,67 @@ fn datetime_serialization() {
     }
 }
 
+#[test]
+fn datetime_serialization_before_epoch_and_sub_millis() {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    // Datetimes before unix epoch are serialized as negative milliseconds
+    let before_epoch: DateTime<Utc> = Utc.with_ymd_and_hms(1969, 12, 31, 23, 59, 58).unwrap();
+    let mut expected_bytes: Vec<u8> = vec![0, 0, 0, 8];
+    expected_bytes.extend_from_slice(&(-2000_i64).to_be_bytes());
+    assert_eq!(serialized(before_epoch), expected_bytes);
+
+    // Sub-millisecond precision is truncated, not rounded
+    let tests = [
+        (Utc.timestamp_nanos(1_999_999), 1_i64),
+        (Utc.timestamp_nanos(1_000_001), 1),
+        (Utc.timestamp_nanos(999_999), 0),
+        // Truncation is done towards the previous millisecond, so it's consistent
+        // after the unix epoch and before it.
+        (Utc.timestamp_nanos(-1), -1),
+        (Utc.timestamp_nanos(-1_999_999), -2),
+    ];
+
+    for (test_datetime, expected_millis) in tests {
+        let mut expected_bytes: Vec<u8> = vec![0, 0, 0, 8];
+        expected_bytes.extend_from_slice(&expected_millis.to_be_bytes());
+        assert_eq!(serialized(test_datetime), expected_bytes);
+    }
+}
+
+#[test]
+fn cql_duration_serialization() {
+    // Each of the fields is encoded as a zig-zag encoded vint
+    let duration = CqlDuration {
+        months: 6,
+        days: 9,
+        nanoseconds: 21372137,
+    };
+    assert_eq!(
+        serialized(duration),
+        vec![0, 0, 0, 6, 0x0c, 0x12, 0xe2, 0x8c, 0x39, 0xd2]
+    );
+
+    // All fields are signed independently of each other
+    let mixed_signs = CqlDuration {
+        months: -1,
+        days: 1,
+        nanoseconds: -2,
+    };
+    assert_eq!(serialized(mixed_signs), vec![0, 0, 0, 3, 0x01, 0x02, 0x03]);
+
+    let extremes = CqlDuration {
+        months: i32::MIN,
+        days: i32::MAX,
+        nanoseconds: i64::MIN,
+    };
+    let mut expected_bytes: Vec<u8> = vec![0, 0, 0, 19];
+    expected_bytes.extend_from_slice(&[0xf0, 0xff, 0xff, 0xff, 0xff]);
+    expected_bytes.extend_from_slice(&[0xf0, 0xff, 0xff, 0xff, 0xfe]);
+    expected_bytes.extend_from_slice(&[0xff; 9]);
+    assert_eq!(serialized(extremes), expected_bytes);
+}
+
 #[test]
 fn timeuuid_serialization() {
     // A few random timeuuids generated manually
@@ -162,6 +498,97 @@ fn timeuuid_serialization() {
     }
 }
 
+#[test]
+fn generated_timeuuids_are_strictly_increasing() {
+    let since_uuid_epoch =
+        |duration: std::time::Duration| (duration.as_nanos() / 100) as u64 + 0x01B2_1DD2_1381_4000;
+    let now = || {
+        since_uuid_epoch(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap(),
+        )
+    };
+
+    let started = now();
+    let timeuuids: Vec<CqlTimeuuid> = (0..10_000).map(|_| CqlTimeuuid::now()).collect();
+
+    for timeuuid in &timeuuids {
+        assert_eq!(timeuuid.as_uuid().get_version_num(), 1);
+        assert_eq!(timeuuid.as_uuid().get_variant(), uuid::Variant::RFC4122);
+        assert!(timeuuid.timestamp() >= started);
+
+        let mut expected_serialized: Vec<u8> = vec![0, 0, 0, 16];
+        expected_serialized.extend_from_slice(timeuuid.as_uuid().as_bytes());
+        assert_eq!(serialized(*timeuuid), expected_serialized);
+    }
+    for pair in timeuuids.windows(2) {
+        assert!(pair[0] < pair[1], "{:?} >= {:?}", pair[0], pair[1]);
+        assert!(pair[0].timestamp() < pair[1].timestamp());
+    }
+}
+
+#[test]
+fn timeuuid_ordering() {
+    let timeuuid = |s: &str| CqlTimeuuid::from(Uuid::parse_str(s).unwrap());
+
+    assert_eq!(
+        timeuuid("8e14e760-7fa8-11eb-bc66-000000000001").timestamp(),
+        0x1eb_7fa8_8e14_e760
+    );
+
+    // Timestamps are compared first, regardless of the order of bytes
+    assert!(
+        timeuuid("ffffffff-0000-1000-8000-000000000000")
+            < timeuuid("00000000-0001-1000-8000-000000000000")
+    );
+    assert!(
+        timeuuid("00000000-0000-1fff-8000-000000000000")
+            > timeuuid("ffffffff-ffff-1000-8000-000000000000")
+    );
+
+    // The remaining bytes are compared as signed bytes
+    assert!(
+        timeuuid("00000000-0000-1000-8000-000000000000")
+            < timeuuid("00000000-0000-1000-7f00-000000000000")
+    );
+    assert!(
+        timeuuid("00000000-0000-1000-8000-000000000001")
+            < timeuuid("00000000-0000-1000-8000-000000000002")
+    );
+}
+
+#[test]
+fn user_type_serialization() {
+    use crate as scylla;
+    use crate::macros::IntoUserType;
+
+    #[derive(IntoUserType)]
+    struct Address {
+        street: String,
+        zip: i32,
+        note: Option<String>,
+    }
+
+    let address = Address {
+        street: "ab".to_string(),
+        zip: 7,
+        note: None,
+    };
+
+    // Fields are serialized one after another, in the order they are declared,
+    // each of them with its own length prefix
+    assert_eq!(
+        serialized(address),
+        vec![
+            0, 0, 0, 18, // total size
+            0, 0, 0, 2, b'a', b'b', // street
+            0, 0, 0, 4, 0, 0, 0, 7, // zip
+            255, 255, 255, 255, // note - null
+        ]
+    );
+}
+
 #[test]
 fn option_value() {
     assert_eq!(serialized(Some(32_i32)), vec![0, 0, 0, 4, 0, 0, 0, 32]);
@@ -277,6 +704,33 @@ fn serialized_values() {
     }
 }
 
+#[test]
+fn serialized_values_to_positional() {
+    let mut values = SerializedValues::new();
+    values.add_named_value("b", &2_i32).unwrap();
+    values.add_named_value("a", &None::<i32>).unwrap();
+
+    let positional = values.to_positional(["a", "b", "a"]).unwrap();
+    assert!(!positional.has_names());
+    assert_eq!(
+        positional.iter().collect::<Vec<_>>(),
+        vec![None, Some([0, 0, 0, 2].as_ref()), None]
+    );
+
+    assert_eq!(
+        values.to_positional(["a", "c"]),
+        Err(NamedValuesMismatch {
+            missing: vec!["c".to_string()],
+            unknown: vec!["b".to_string()],
+        })
+    );
+
+    // Values without names are left as they are
+    let mut unnamed = SerializedValues::new();
+    unnamed.add_value(&1_i32).unwrap();
+    assert_eq!(unnamed.to_positional(["a", "b"]).unwrap(), unnamed);
+}
+
 #[test]
 fn unit_value_list() {
     let serialized_unit: SerializedValues =
@@ -730,3 +1184,58 @@ fn check_batch_values_iterator_is_not_lending() {
     }
     f(((10,), (11,)))
 }
+
+#[test]
+fn derived_value_list() {
+    use crate as scylla;
+    use crate::macros::ValueList;
+
+    #[derive(ValueList)]
+    struct Borrowing<'a> {
+        a: i32,
+        b: &'a str,
+        c: Option<i64>,
+    }
+
+    let text = String::from("text");
+    let values = Borrowing {
+        a: 1,
+        b: &text,
+        c: None,
+    };
+
+    // Fields are serialized in the order of declaration, same as a tuple
+    assert_eq!(
+        values.serialized().unwrap().into_owned(),
+        (1_i32, "text", None::<i64>)
+            .serialized()
+            .unwrap()
+            .into_owned()
+    );
+}
+
+#[test]
+fn serialized_values_limits() {
+    let mut values = SerializedValues::new();
+    for i in 0..i16::MAX {
+        values.add_value(&(i as i32)).unwrap();
+    }
+    assert_eq!(values.len(), i16::MAX);
+    // Each value takes 4 bytes of length and 4 bytes of an i32
+    assert_eq!(values.size(), i16::MAX as usize * 8);
+
+    assert_eq!(
+        values.add_value(&0_i32),
+        Err(SerializeValuesError::TooManyValues)
+    );
+    // A failed addition doesn't change the list
+    assert_eq!(values.len(), i16::MAX);
+    assert_eq!(values.size(), i16::MAX as usize * 8);
+
+    let mut named_values = SerializedValues::new();
+    named_values.add_named_value("a", &1_i32).unwrap();
+    assert_eq!(
+        named_values.add_value(&2_i32),
+        Err(SerializeValuesError::MixingNamedAndNotNamedValues)
+    );
+}
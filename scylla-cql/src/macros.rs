@@ -10,7 +10,10 @@ pub use scylla_macros::FromUserType;
 /// Works only on simple structs without generics etc
 pub use scylla_macros::IntoUserType;
 
-/// #[derive(ValueList)] allows to pass struct as a list of values for a query
+/// #[derive(ValueList)] allows to pass struct as a list of values for a query\
+/// Fields are bound to the bind markers in the order of their declaration.
+/// Structs with lifetimes (e.g. borrowing `&'a str` fields) are supported, generic type
+/// parameters need a `Value` bound
 pub use scylla_macros::ValueList;
 
 // Reexports for derive(IntoUserType)
@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::DeriveInput;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, DeriveInput};
 
 /// #[derive(ValueList)] allows to parse a struct as a list of values,
 /// which can be fed to the query directly.
@@ -13,14 +13,19 @@ pub fn value_list_derive(tokens_input: TokenStream) -> TokenStream {
     let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
 
     let values_len = struct_fields.named.len();
-    let field_name = struct_fields.named.iter().map(|field| &field.ident);
+    // Spanned on fields, so that a field of a type that isn't a Value
+    // (e.g. a generic parameter without the bound) is pointed at by the compiler
+    let add_values_code = struct_fields.named.iter().map(|field| {
+        let field_name = &field.ident;
+        quote_spanned! {field.span() =>
+            result.add_value(&self.#field_name)?;
+        }
+    });
     let generated = quote! {
         impl #impl_generics #path::ValueList for #struct_name #ty_generics #where_clause {
             fn serialized(&self) -> #path::SerializedResult {
                 let mut result = #path::SerializedValues::with_capacity(#values_len);
-                #(
-                    result.add_value(&self.#field_name)?;
-                )*
+                #(#add_values_code)*
 
                 ::std::result::Result::Ok(::std::borrow::Cow::Owned(result))
             }
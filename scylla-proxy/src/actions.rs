@@ -37,6 +37,9 @@ pub enum Condition {
     /// True iff the frame has the given opcode (and is a response).
     ResponseOpcode(ResponseOpcode),
 
+    /// True iff the frame uses the given version of the protocol (ignoring the direction bit).
+    ProtocolVersion(u8),
+
     /// True iff the frame body contains the given byte slice, with case-sensitive comparison.
     BodyContainsCaseSensitive(Box<[u8]>),
 
@@ -54,6 +57,7 @@ pub enum Condition {
 pub(crate) struct EvaluationContext {
     pub(crate) connection_seq_no: usize,
     pub(crate) opcode: FrameOpcode,
+    pub(crate) protocol_version: u8,
     pub(crate) frame_body: Bytes,
 }
 
@@ -86,6 +90,8 @@ impl Condition {
                 FrameOpcode::Response(op2) => *op1 == op2,
             },
 
+            Condition::ProtocolVersion(version) => *version == ctx.protocol_version,
+
             Condition::BodyContainsCaseSensitive(pattern) => ctx
                 .frame_body
                 .windows(pattern.len())
@@ -724,9 +730,23 @@ fn condition_case_insensitive_matching() {
     let ctx = EvaluationContext {
         connection_seq_no: 42,
         opcode: FrameOpcode::Request(RequestOpcode::Options),
+        protocol_version: 4,
         frame_body: Bytes::from_static(b"\0\0x{0x223}Cassandra'sINEFFICIENCY\x12\x31"),
     };
 
     assert!(condition_matching.eval(&ctx));
     assert!(!condition_nonmatching.eval(&ctx));
 }
+
+#[test]
+fn condition_protocol_version_matching() {
+    let ctx = EvaluationContext {
+        connection_seq_no: 42,
+        opcode: FrameOpcode::Request(RequestOpcode::Options),
+        protocol_version: 5,
+        frame_body: Bytes::new(),
+    };
+
+    assert!(Condition::ProtocolVersion(5).eval(&ctx));
+    assert!(!Condition::ProtocolVersion(4).eval(&ctx));
+}
@@ -1069,6 +1069,7 @@ impl ProxyWorker {
                         let ctx = EvaluationContext {
                             connection_seq_no: connection_no,
                             opcode: FrameOpcode::Request(request.opcode),
+                            protocol_version: request.params.version & 0x7F,
                             frame_body: request.body.clone(),
                         };
                         let mut guard = request_rules.lock().unwrap();
@@ -1172,6 +1173,7 @@ impl ProxyWorker {
                         let ctx = EvaluationContext {
                             connection_seq_no: connection_no,
                             opcode: FrameOpcode::Response(response.opcode),
+                            protocol_version: response.params.version & 0x7F,
                             frame_body: response.body.clone(),
                         };
                         let mut guard = response_rules.lock().unwrap();
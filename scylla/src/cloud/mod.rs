@@ -60,3 +60,74 @@ pub(crate) fn set_ssl_config_for_scylla_cloud_host(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use openssl::ssl::{NameType, SslVerifyMode};
+    use uuid::Uuid;
+
+    use super::{set_ssl_config_for_scylla_cloud_host, CloudConfig};
+    use crate::transport::connection::ConnectionConfig;
+
+    fn connection_config_from_bundle() -> ConnectionConfig {
+        let cloud_config = CloudConfig::read_from_yaml("src/cloud/full_config.yaml").unwrap();
+        ConnectionConfig {
+            cloud_config: Some(Arc::new(cloud_config)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sni_derived_from_cloud_config() {
+        let proxy_address: SocketAddr = "127.0.1.1:9142".parse().unwrap();
+        let host_id = Uuid::new_v4();
+
+        // Connections to a node go through the SNI proxy, addressed with the node's host id
+        // in the domain of its datacenter
+        let mut connection_config = connection_config_from_bundle();
+        set_ssl_config_for_scylla_cloud_host(
+            Some(host_id),
+            Some("eu-west-2"),
+            proxy_address,
+            &mut connection_config,
+        )
+        .unwrap();
+        let ssl = connection_config.ssl_config.unwrap().new_ssl().unwrap();
+        assert_eq!(
+            ssl.servername(NameType::HOST_NAME),
+            Some(format!("{}.cql.cluster-id.scylla.com", host_id).as_str())
+        );
+        assert_eq!(ssl.verify_mode(), SslVerifyMode::PEER);
+
+        // Before the host id is known (e.g. for the control connection), the domain itself is used
+        let mut connection_config = connection_config_from_bundle();
+        set_ssl_config_for_scylla_cloud_host(
+            None,
+            Some("eu-west-1"),
+            proxy_address,
+            &mut connection_config,
+        )
+        .unwrap();
+        let ssl = connection_config.ssl_config.unwrap().new_ssl().unwrap();
+        assert_eq!(
+            ssl.servername(NameType::HOST_NAME),
+            Some("cql.my-cluster-id.scylla.com")
+        );
+        // This datacenter is configured with `insecureSkipTlsVerify: true`
+        assert_eq!(ssl.verify_mode(), SslVerifyMode::NONE);
+
+        // Nodes from datacenters absent from the config don't get TLS configured
+        let mut connection_config = connection_config_from_bundle();
+        set_ssl_config_for_scylla_cloud_host(
+            Some(host_id),
+            Some("unknown-dc"),
+            proxy_address,
+            &mut connection_config,
+        )
+        .unwrap();
+        assert!(connection_config.ssl_config.is_none());
+    }
+}
@@ -127,7 +127,7 @@ pub use frame::response::cql_to_rust::FromRow;
 
 pub use transport::caching_session::CachingSession;
 pub use transport::execution_profile::ExecutionProfile;
-pub use transport::query_result::QueryResult;
+pub use transport::query_result::{PagingState, QueryResult};
 pub use transport::session::{IntoTypedRows, Session, SessionConfig};
 pub use transport::session_builder::SessionBuilder;
 
@@ -137,7 +137,9 @@ pub use transport::session_builder::CloudSessionBuilder;
 pub use transport::execution_profile;
 pub use transport::host_filter;
 pub use transport::load_balancing;
+pub use transport::reconnection_policy;
 pub use transport::retry_policy;
 pub use transport::speculative_execution;
+pub use transport::timestamp_generator;
 
-pub use transport::metrics::Metrics;
+pub use transport::metrics::{Metrics, QueryErrorKind};
@@ -4,9 +4,14 @@ use std::convert::TryFrom;
 use std::num::NonZeroU16;
 use thiserror::Error;
 
+/// A token on the ring of the cluster.
+///
+/// Murmur3Partitioner and CDCPartitioner tokens are 64-bit signed integers,
+/// while RandomPartitioner tokens are 127-bit nonnegative integers.
+/// The value is wide enough to hold both.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub struct Token {
-    pub value: i64,
+    pub value: i128,
 }
 
 pub type Shard = u32;
@@ -1,5 +1,8 @@
+use bytes::Bytes;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
@@ -50,6 +53,7 @@ impl Batch {
     }
 
     /// Sets the consistency to be used when executing this batch.
+    /// Consistencies of the statements contained in the batch are not used.
     pub fn set_consistency(&mut self, c: Consistency) {
         self.config.consistency = Some(c);
     }
@@ -110,6 +114,31 @@ impl Batch {
         self.config.timestamp
     }
 
+    /// Sets the client-side timeout for this batch.
+    /// If not None, the driver will stop waiting for the request
+    /// to finish after `timeout` passed.
+    /// Otherwise, default session client timeout will be applied.
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.config.request_timeout = timeout
+    }
+
+    /// Gets client timeout associated with this batch
+    pub fn get_request_timeout(&self) -> Option<Duration> {
+        self.config.request_timeout
+    }
+
+    /// Sets the custom payload, which is sent to the server along with the batch.
+    /// It is not interpreted by the driver, but may be used e.g. by custom query handlers
+    /// on the server side.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Bytes>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload sent along with the batch.
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Bytes>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Set the retry policy for this batch, overriding the one from execution profile if not None.
     #[inline]
     pub fn set_retry_policy(&mut self, retry_policy: Option<Arc<dyn RetryPolicy>>) {
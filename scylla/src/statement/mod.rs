@@ -1,4 +1,6 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use bytes::Bytes;
 
 use crate::transport::execution_profile::ExecutionProfileHandle;
 use crate::{history::HistoryListener, retry_policy::RetryPolicy};
@@ -19,6 +21,8 @@ pub(crate) struct StatementConfig {
     pub(crate) tracing: bool,
     pub(crate) timestamp: Option<i64>,
     pub(crate) request_timeout: Option<Duration>,
+    pub(crate) custom_payload: Option<HashMap<String, Bytes>>,
+    pub(crate) keyspace: Option<String>,
 
     pub(crate) history_listener: Option<Arc<dyn HistoryListener>>,
 
@@ -1,23 +1,26 @@
+use arc_swap::ArcSwap;
 use bytes::{Bytes, BytesMut};
 use scylla_cql::errors::{BadQuery, QueryError};
 use smallvec::{smallvec, SmallVec};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
 
-use scylla_cql::frame::response::result::ColumnSpec;
+use scylla_cql::frame::response::result::{deser_cql_value, ColumnSpec, ColumnType};
 
 use super::StatementConfig;
-use crate::frame::response::result::PreparedMetadata;
+use crate::frame::response::result::{PartitionKeyIndex, PreparedMetadata, ResultMetadata};
 use crate::frame::types::{Consistency, SerialConsistency};
-use crate::frame::value::SerializedValues;
+use crate::frame::value::{NamedValuesMismatch, SerializedValues};
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
 use crate::routing::Token;
 use crate::transport::execution_profile::ExecutionProfileHandle;
-use crate::transport::partitioner::{Partitioner, PartitionerHasher, PartitionerName};
+use crate::transport::partitioner::{PartitionerHasher, PartitionerName};
 
 /// Represents a statement prepared on the server.
 #[derive(Debug)]
@@ -30,11 +33,15 @@ pub struct PreparedStatement {
     page_size: Option<i32>,
     partitioner_name: PartitionerName,
     is_confirmed_lwt: bool,
+    use_cached_result_metadata: bool,
+    check_value_types: bool,
 }
 
 #[derive(Debug)]
 struct PreparedStatementSharedData {
     metadata: PreparedMetadata,
+    // Replaced when the statement gets reprepared, e.g. after a schema change
+    result_metadata: ArcSwap<ResultMetadata>,
     statement: String,
 }
 
@@ -48,6 +55,8 @@ impl Clone for PreparedStatement {
             page_size: self.page_size,
             partitioner_name: self.partitioner_name.clone(),
             is_confirmed_lwt: self.is_confirmed_lwt,
+            use_cached_result_metadata: self.use_cached_result_metadata,
+            check_value_types: self.check_value_types,
         }
     }
 }
@@ -57,6 +66,7 @@ impl PreparedStatement {
         id: Bytes,
         is_lwt: bool,
         metadata: PreparedMetadata,
+        result_metadata: ResultMetadata,
         statement: String,
         page_size: Option<i32>,
         config: StatementConfig,
@@ -65,6 +75,7 @@ impl PreparedStatement {
             id,
             shared: Arc::new(PreparedStatementSharedData {
                 metadata,
+                result_metadata: ArcSwap::from_pointee(result_metadata),
                 statement,
             }),
             prepare_tracing_ids: Vec::new(),
@@ -72,6 +83,8 @@ impl PreparedStatement {
             config,
             partitioner_name: Default::default(),
             is_confirmed_lwt: is_lwt,
+            use_cached_result_metadata: false,
+            check_value_types: false,
         }
     }
 
@@ -111,6 +124,15 @@ impl PreparedStatement {
         !self.get_prepared_metadata().pk_indexes.is_empty()
     }
 
+    /// Returns the indexes of bound values which form the partition key, as received from the server.\
+    /// They are sorted by [`index`](PartitionKeyIndex::index), i.e. the position of the value
+    /// in the bound values, while [`sequence`](PartitionKeyIndex::sequence) is the position
+    /// of the column in the partition key.\
+    /// Empty if the statement doesn't bind the whole partition key (see [Self::is_token_aware()]).
+    pub fn partition_key_indexes(&self) -> &[PartitionKeyIndex] {
+        &self.get_prepared_metadata().pk_indexes
+    }
+
     /// Returns true if it is known that the prepared statement contains
     /// a Lightweight Transaction. If so, the optimisation can be performed:
     /// the query should be routed to the replicas in a predefined order
@@ -144,6 +166,60 @@ impl PreparedStatement {
         Ok(buf.freeze())
     }
 
+    /// Binds named values to the bind markers of this statement with the same names,
+    /// putting them in the positions expected by the database.\
+    /// Values without names are returned unchanged.
+    pub(crate) fn bind_named_values<'v>(
+        &self,
+        values: Cow<'v, SerializedValues>,
+    ) -> Result<Cow<'v, SerializedValues>, NamedValuesMismatch> {
+        if !values.has_names() {
+            return Ok(values);
+        }
+
+        let marker_names = self
+            .get_prepared_metadata()
+            .col_specs
+            .iter()
+            .map(|spec| spec.name.as_str());
+        Ok(Cow::Owned(values.to_positional(marker_names)?))
+    }
+
+    /// Checks the bound values against the bind markers,
+    /// so that invalid values are rejected before being sent to the database.\
+    /// There must be exactly one value for each marker and values bound to `ascii` markers
+    /// must contain ASCII characters only. If enabled with `set_check_value_types`, all values
+    /// are also checked to be valid values of their markers' types.
+    pub(crate) fn check_values(&self, values: &SerializedValues) -> Result<(), BadQuery> {
+        let col_specs = &self.get_prepared_metadata().col_specs;
+        if col_specs.len() != values.len() as usize {
+            return Err(BadQuery::WrongNumberOfValues {
+                expected: col_specs.len(),
+                provided: values.len() as usize,
+            });
+        }
+
+        for (col_spec, value) in col_specs.iter().zip(values.iter()) {
+            if let (ColumnType::Ascii, Some(bytes)) = (&col_spec.typ, value) {
+                if !bytes.is_ascii() {
+                    return Err(BadQuery::NonAsciiValue(col_spec.name.clone()));
+                }
+            }
+            if let (true, Some(mut bytes)) = (self.check_value_types, value) {
+                // Values of custom types can't be decoded by the driver, so they are not checked
+                if !matches!(col_spec.typ, ColumnType::Custom(_))
+                    && deser_cql_value(&col_spec.typ, &mut bytes).is_err()
+                {
+                    return Err(BadQuery::ValueTypeMismatch {
+                        name: col_spec.name.clone(),
+                        expected: col_spec.typ.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Determines which values consistute the partition key and puts them in order.
     ///
     /// This is a preparation step necessary for calculating token based on a prepared statement.
@@ -176,6 +252,9 @@ impl PreparedStatement {
                 TokenCalculationError::ValueTooLong(values_len) => {
                     QueryError::BadQuery(BadQuery::ValuesTooLongForKey(values_len, u16::MAX.into()))
                 }
+                TokenCalculationError::UnsupportedPartitioner(name) => {
+                    QueryError::BadQuery(BadQuery::UnsupportedPartitioner(name))
+                }
             })?;
 
         Ok(Some((partition_key, token)))
@@ -287,6 +366,18 @@ impl PreparedStatement {
         self.config.request_timeout
     }
 
+    /// Sets the custom payload, which is sent to the server along with the statement.
+    /// It is not interpreted by the driver, but may be used e.g. by custom query handlers
+    /// on the server side.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Bytes>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload sent along with the statement.
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Bytes>> {
+        self.config.custom_payload.as_ref()
+    }
+
     /// Sets the name of the partitioner used for this statement.
     pub(crate) fn set_partitioner_name(&mut self, partitioner_name: PartitionerName) {
         self.partitioner_name = partitioner_name;
@@ -297,6 +388,51 @@ impl PreparedStatement {
         &self.shared.metadata
     }
 
+    /// Access metadata about the result of this prepared statement as returned by the database
+    /// when the statement was (re)prepared.
+    pub(crate) fn get_result_metadata(&self) -> Arc<ResultMetadata> {
+        self.shared.result_metadata.load_full()
+    }
+
+    /// Replaces the cached result metadata, e.g. with the one received when repreparing.
+    /// The change is visible to all clones of this statement.
+    pub(crate) fn update_result_metadata(&self, result_metadata: Arc<ResultMetadata>) {
+        self.shared.result_metadata.store(result_metadata);
+    }
+
+    /// Enables or disables using the cached result metadata when executing this statement.
+    ///
+    /// If enabled, the driver asks the database to skip sending the result metadata
+    /// and uses the one received when the statement was prepared instead,
+    /// saving bandwidth and parsing effort on every execution.
+    /// Statements are reprepared when their table gets altered, which refreshes the cached metadata.
+    /// If the database returns rows with a different number of columns than the cached metadata
+    /// describes, the statement is executed again with the metadata, which then replaces the cached one.
+    /// A schema change keeping the number of columns may still go unnoticed. Disabled by default.
+    pub fn set_use_cached_result_metadata(&mut self, use_cached_metadata: bool) {
+        self.use_cached_result_metadata = use_cached_metadata;
+    }
+
+    /// Gets whether the cached result metadata is used when executing this statement.
+    pub fn get_use_cached_result_metadata(&self) -> bool {
+        self.use_cached_result_metadata
+    }
+
+    /// Enables or disables checking the types of bound values on the client side.
+    ///
+    /// If enabled, each value is checked to be a valid value of the type of its bind marker
+    /// before the statement is executed, e.g. binding a `String` to an `int` marker fails with
+    /// [`BadQuery::ValueTypeMismatch`] without a round-trip to the database.
+    /// The check has to decode every bound value, so it's disabled by default.
+    pub fn set_check_value_types(&mut self, check_value_types: bool) {
+        self.check_value_types = check_value_types;
+    }
+
+    /// Gets whether the types of bound values are checked on the client side.
+    pub fn get_check_value_types(&self) -> bool {
+        self.check_value_types
+    }
+
     /// Get the name of the partitioner used for this statement.
     pub(crate) fn get_partitioner_name(&self) -> &PartitionerName {
         &self.partitioner_name
@@ -346,6 +482,8 @@ pub enum PartitionKeyExtractionError {
 pub enum TokenCalculationError {
     #[error("Value bytes too long to create partition key, max 65 535 allowed! value.len(): {0}")]
     ValueTooLong(usize),
+    #[error("Partitioner {0} is unsupported for token awareness")]
+    UnsupportedPartitioner(String),
 }
 
 #[derive(Clone, Debug, Error, PartialEq, Eq, PartialOrd, Ord)]
@@ -444,7 +582,7 @@ impl<'ps> PartitionKey<'ps> {
         &self,
         partitioner_name: &PartitionerName,
     ) -> Result<Token, TokenCalculationError> {
-        let mut partitioner_hasher = partitioner_name.build_hasher();
+        let mut partitioner_hasher = partitioner_name.build_hasher()?;
         let mut writer = |chunk: &[u8]| partitioner_hasher.write(chunk);
 
         self.write_encoded_partition_key(&mut writer)?;
@@ -459,10 +597,12 @@ mod tests {
         response::result::{
             ColumnSpec, ColumnType, PartitionKeyIndex, PreparedMetadata, TableSpec,
         },
-        value::SerializedValues,
+        value::{SerializedValues, Unset, ValueList},
     };
 
-    use crate::prepared_statement::PartitionKey;
+    use crate::prepared_statement::{PartitionKey, PreparedStatement};
+    use crate::transport::errors::BadQuery;
+    use assert_matches::assert_matches;
 
     fn make_meta(
         cols: impl IntoIterator<Item = ColumnType>,
@@ -498,6 +638,18 @@ mod tests {
         }
     }
 
+    fn make_prepared(meta: PreparedMetadata, contents: &str) -> PreparedStatement {
+        PreparedStatement::new(
+            Default::default(),
+            false,
+            meta,
+            Default::default(),
+            contents.to_owned(),
+            None,
+            Default::default(),
+        )
+    }
+
     #[test]
     fn test_partition_key_multiple_columns_shuffled() {
         let meta = make_meta(
@@ -528,4 +680,127 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_non_ascii_value_rejected() {
+        let meta = make_meta([ColumnType::Int, ColumnType::Ascii, ColumnType::Text], [0]);
+        let prepared = make_prepared(meta, "INSERT INTO ks.t (a, b, c) VALUES (?, ?, ?)");
+
+        let values = (1, "ascii", "zażółć gęślą jaźń").serialized().unwrap();
+        prepared.check_values(&values).unwrap();
+
+        let values = (1, Option::<&str>::None, "text").serialized().unwrap();
+        prepared.check_values(&values).unwrap();
+
+        let values = (1, "zażółć", "text").serialized().unwrap();
+        assert_matches!(
+            prepared.check_values(&values),
+            Err(BadQuery::NonAsciiValue(name)) if name == "col_1"
+        );
+    }
+
+    #[test]
+    fn test_wrong_number_of_values_rejected() {
+        let meta = make_meta([ColumnType::Int, ColumnType::Text, ColumnType::Int], [0]);
+        let prepared = make_prepared(meta, "INSERT INTO ks.t (a, b, c) VALUES (?, ?, ?)");
+
+        let values = (1, "text").serialized().unwrap();
+        let err = prepared.check_values(&values).unwrap_err();
+        assert_matches!(
+            err,
+            BadQuery::WrongNumberOfValues {
+                expected: 3,
+                provided: 2
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Prepared statement has 3 bind markers, but 2 values were provided"
+        );
+
+        let values = (1, "text", 2, 3).serialized().unwrap();
+        assert_matches!(
+            prepared.check_values(&values),
+            Err(BadQuery::WrongNumberOfValues {
+                expected: 3,
+                provided: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_value_types_checked_when_enabled() {
+        let meta = make_meta(
+            [
+                ColumnType::Int,
+                ColumnType::Text,
+                ColumnType::List(Box::new(ColumnType::BigInt)),
+            ],
+            [0],
+        );
+        let mut prepared = make_prepared(meta, "INSERT INTO ks.t (a, b, c) VALUES (?, ?, ?)");
+
+        let valid = (1, "text", vec![1_i64, 2])
+            .serialized()
+            .unwrap()
+            .into_owned();
+        let string_for_int = ("1", "text", vec![1_i64, 2])
+            .serialized()
+            .unwrap()
+            .into_owned();
+        let wrong_list_elements = (1, "text", vec![1_i32, 2])
+            .serialized()
+            .unwrap()
+            .into_owned();
+        let nulls = (Option::<i32>::None, Option::<&str>::None, Unset)
+            .serialized()
+            .unwrap();
+
+        // Not checked by default
+        assert!(!prepared.get_check_value_types());
+        prepared.check_values(&string_for_int).unwrap();
+
+        prepared.set_check_value_types(true);
+        prepared.check_values(&valid).unwrap();
+        prepared.check_values(&nulls).unwrap();
+        let err = prepared.check_values(&string_for_int).unwrap_err();
+        assert_matches!(
+            &err,
+            BadQuery::ValueTypeMismatch { name, expected: ColumnType::Int } if name == "col_0"
+        );
+        assert_eq!(
+            err.to_string(),
+            "Value bound to marker col_0 is not a valid value of type Int"
+        );
+        assert_matches!(
+            prepared.check_values(&wrong_list_elements),
+            Err(BadQuery::ValueTypeMismatch { name, .. }) if name == "col_2"
+        );
+    }
+
+    #[test]
+    fn test_composite_partition_key() {
+        // Statement binding values for columns c, a, b of a table with PRIMARY KEY ((a, b), c)
+        let meta = make_meta([ColumnType::Int, ColumnType::Text, ColumnType::Int], [1, 2]);
+        let prepared = make_prepared(meta, "INSERT INTO ks.t (c, a, b) VALUES (?, ?, ?)");
+        let indexes: Vec<_> = prepared
+            .partition_key_indexes()
+            .iter()
+            .map(|pki| (pki.index, pki.sequence))
+            .collect();
+        assert_eq!(indexes, vec![(1, 0), (2, 1)]);
+        assert!(prepared.is_token_aware());
+
+        let mut values = SerializedValues::new();
+        values.add_value(&7i32).unwrap();
+        values.add_value(&"ab").unwrap();
+        values.add_value(&0x0102i32).unwrap();
+
+        // Each component is prefixed with its 2-byte length and followed by a zero byte
+        let routing_key = prepared.compute_partition_key(&values).unwrap();
+        assert_eq!(
+            routing_key.as_ref(),
+            &[0, 2, b'a', b'b', 0, 0, 4, 0, 0, 1, 2, 0]
+        );
+    }
 }
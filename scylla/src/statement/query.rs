@@ -3,6 +3,9 @@ use crate::frame::types::{Consistency, SerialConsistency};
 use crate::history::HistoryListener;
 use crate::retry_policy::RetryPolicy;
 use crate::transport::execution_profile::ExecutionProfileHandle;
+use crate::transport::query_result::PagingState;
+use bytes::Bytes;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -15,6 +18,7 @@ pub struct Query {
 
     pub contents: String,
     page_size: Option<i32>,
+    paging_state: Option<PagingState>,
 }
 
 impl Query {
@@ -23,13 +27,14 @@ impl Query {
         Self {
             contents: query_text.into(),
             page_size: None,
+            paging_state: None,
             config: Default::default(),
         }
     }
 
     /// Returns self with page size set to the given value
     pub fn with_page_size(mut self, page_size: i32) -> Self {
-        self.page_size = Some(page_size);
+        self.set_page_size(page_size);
         self
     }
 
@@ -49,6 +54,24 @@ impl Query {
         self.page_size
     }
 
+    /// Returns self with paging state set to the given value
+    pub fn with_paging_state(mut self, paging_state: PagingState) -> Self {
+        self.paging_state = Some(paging_state);
+        self
+    }
+
+    /// Sets the paging state for this CQL query.
+    /// If not None, `Session::query` and `Session::query_iter` will resume fetching
+    /// from the page following the one which returned this paging state.
+    pub fn set_paging_state(&mut self, paging_state: Option<PagingState>) {
+        self.paging_state = paging_state;
+    }
+
+    /// Returns the paging state for this CQL query.
+    pub fn get_paging_state(&self) -> Option<&PagingState> {
+        self.paging_state.as_ref()
+    }
+
     /// Sets the consistency to be used when executing this statement.
     pub fn set_consistency(&mut self, c: Consistency) {
         self.config.consistency = Some(c);
@@ -124,6 +147,32 @@ impl Query {
         self.config.request_timeout
     }
 
+    /// Sets the custom payload, which is sent to the server along with the query.
+    /// It is not interpreted by the driver, but may be used e.g. by custom query handlers
+    /// on the server side.
+    pub fn set_custom_payload(&mut self, custom_payload: Option<HashMap<String, Bytes>>) {
+        self.config.custom_payload = custom_payload;
+    }
+
+    /// Gets the custom payload sent along with the query.
+    pub fn get_custom_payload(&self) -> Option<&HashMap<String, Bytes>> {
+        self.config.custom_payload.as_ref()
+    }
+
+    /// Sets the keyspace in which this statement is executed, overriding the keyspace
+    /// chosen with `USE` on the connection. It applies also to the statement prepared from
+    /// this query. The name is case sensitive, like in a quoted CQL identifier.
+    /// Requires protocol v5 - on a connection using protocol v4 executing the statement fails
+    /// with [`BadQuery::PerRequestKeyspaceUnsupported`](crate::transport::errors::BadQuery::PerRequestKeyspaceUnsupported).
+    pub fn set_keyspace(&mut self, keyspace: Option<String>) {
+        self.config.keyspace = keyspace;
+    }
+
+    /// Gets the keyspace in which this statement is executed, if it was set.
+    pub fn get_keyspace(&self) -> Option<&str> {
+        self.config.keyspace.as_deref()
+    }
+
     /// Set the retry policy for this statement, overriding the one from execution profile if not None.
     #[inline]
     pub fn set_retry_policy(&mut self, retry_policy: Option<Arc<dyn RetryPolicy>>) {
@@ -152,6 +201,12 @@ impl Query {
         self.config.execution_profile_handle = profile_handle;
     }
 
+    /// Returns self associated with execution profile referred by the provided handle.
+    pub fn with_execution_profile_handle(mut self, profile_handle: ExecutionProfileHandle) -> Self {
+        self.config.execution_profile_handle = Some(profile_handle);
+        self
+    }
+
     /// Borrows the execution profile handle associated with this query.
     pub fn get_execution_profile_handle(&self) -> Option<&ExecutionProfileHandle> {
         self.config.execution_profile_handle.as_ref()
@@ -1,5 +1,7 @@
 use crate::authentication::{AuthError, AuthenticatorProvider, AuthenticatorSession};
+use crate::transport::errors::{DbError, NewSessionError};
 use crate::utils::test_utils::unique_keyspace_name;
+use assert_matches::assert_matches;
 use async_trait::async_trait;
 use bytes::{BufMut, BytesMut};
 use std::sync::Arc;
@@ -26,6 +28,28 @@ async fn authenticate_superuser() {
     println!("Ok.");
 }
 
+#[tokio::test]
+#[ignore]
+async fn authenticate_with_wrong_password() {
+    let uri = std::env::var("SCYLLA_URI").unwrap_or_else(|_| "127.0.0.1:9042".to_string());
+
+    println!("Connecting to {} with a wrong password ...", uri);
+
+    let err = crate::SessionBuilder::new()
+        .known_node(uri)
+        .user("cassandra", "wrong_password")
+        .build()
+        .await
+        .unwrap_err();
+
+    assert_matches!(
+        err,
+        NewSessionError::DbError(DbError::AuthenticationError, _)
+    );
+
+    println!("Ok.");
+}
+
 struct CustomAuthenticator;
 
 #[async_trait]
@@ -9,9 +9,10 @@ use crate::{QueryResult, Session};
 use bytes::Bytes;
 use dashmap::DashMap;
 use futures::future::try_join_all;
-use scylla_cql::frame::response::result::PreparedMetadata;
+use scylla_cql::frame::response::result::{PreparedMetadata, ResultMetadata};
 use std::collections::hash_map::RandomState;
 use std::hash::BuildHasher;
+use std::sync::Arc;
 
 /// Contains just the parts of a prepared statement that were returned
 /// from the database. All remaining parts (query string, page size,
@@ -22,10 +23,14 @@ struct RawPreparedStatementData {
     id: Bytes,
     is_confirmed_lwt: bool,
     metadata: PreparedMetadata,
+    result_metadata: Arc<ResultMetadata>,
     partitioner_name: PartitionerName,
 }
 
-/// Provides auto caching while executing queries
+/// Provides auto caching while executing queries\
+/// Statements are prepared on first use and the cached ones are reused afterwards.
+/// If the database reports a cached statement as unprepared (e.g. after a schema change),
+/// it is transparently prepared again and the request is retried.
 #[derive(Debug)]
 pub struct CachingSession<S = RandomState>
 where
@@ -36,7 +41,9 @@ where
     /// If a prepared statement is added while the limit is reached, the oldest prepared statement
     /// is removed from the cache
     max_capacity: usize,
-    cache: DashMap<String, RawPreparedStatementData, S>,
+    /// Statements are cached by their contents and the keyspace set in the query,
+    /// because the same contents may refer to tables of different keyspaces
+    cache: DashMap<(String, Option<String>), RawPreparedStatementData, S>,
 }
 
 impl<S> CachingSession<S>
@@ -163,13 +170,18 @@ where
         query: impl Into<Query>,
     ) -> Result<PreparedStatement, QueryError> {
         let query = query.into();
+        let cache_key = (
+            query.contents.clone(),
+            query.get_keyspace().map(ToOwned::to_owned),
+        );
 
-        if let Some(raw) = self.cache.get(&query.contents) {
+        if let Some(raw) = self.cache.get(&cache_key) {
             let page_size = query.get_page_size();
             let mut stmt = PreparedStatement::new(
                 raw.id.clone(),
                 raw.is_confirmed_lwt,
                 raw.metadata.clone(),
+                (*raw.result_metadata).clone(),
                 query.contents,
                 page_size,
                 query.config,
@@ -177,15 +189,14 @@ where
             stmt.set_partitioner_name(raw.partitioner_name.clone());
             Ok(stmt)
         } else {
-            let query_contents = query.contents.clone();
             let prepared = self.session.prepare(query).await?;
 
             if self.max_capacity == self.cache.len() {
                 // Cache is full, remove the first entry
-                // Don't hold a reference into the map (that's why the to_string() is called)
+                // Don't hold a reference into the map (that's why the key is cloned)
                 // This is because the documentation of the remove fn tells us that it may deadlock
                 // when holding some sort of reference into the map
-                let query = self.cache.iter().next().map(|c| c.key().to_string());
+                let query = self.cache.iter().next().map(|c| c.key().clone());
 
                 // Don't inline this: https://stackoverflow.com/questions/69873846/an-owned-value-is-still-references-somehow
                 if let Some(q) = query {
@@ -197,9 +208,10 @@ where
                 id: prepared.get_id().clone(),
                 is_confirmed_lwt: prepared.is_confirmed_lwt(),
                 metadata: prepared.get_prepared_metadata().clone(),
+                result_metadata: prepared.get_result_metadata(),
                 partitioner_name: prepared.get_partitioner_name().clone(),
             };
-            self.cache.insert(query_contents, raw);
+            self.cache.insert(cache_key, raw);
 
             Ok(prepared)
         }
@@ -302,11 +314,14 @@ mod tests {
         assert_eq!(2, session.cache.len());
 
         // This query should be in the cache
-        assert!(session.cache.get(last_query).is_some());
+        assert!(session.cache.get(&(last_query.to_owned(), None)).is_some());
 
         // Either the first or middle query should be removed
-        let first_query_removed = session.cache.get(first_query).is_none();
-        let middle_query_removed = session.cache.get(middle_query).is_none();
+        let first_query_removed = session.cache.get(&(first_query.to_owned(), None)).is_none();
+        let middle_query_removed = session
+            .cache
+            .get(&(middle_query.to_owned(), None))
+            .is_none();
 
         assert!(first_query_removed || middle_query_removed);
     }
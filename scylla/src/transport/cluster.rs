@@ -20,6 +20,7 @@ use itertools::Itertools;
 use scylla_cql::errors::{BadQuery, NewSessionError};
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::instrument::WithSubscriber;
@@ -29,7 +30,7 @@ use uuid::Uuid;
 use super::node::{KnownNode, NodeAddr};
 
 use super::locator::ReplicaLocator;
-use super::partitioner::calculate_token_for_partition_key;
+use super::partitioner::calculate_token_for_partition_key_with_partitioner_name;
 use super::topology::Strategy;
 
 /// Cluster manages up to date information and connections to database nodes.
@@ -42,7 +43,11 @@ pub(crate) struct Cluster {
     refresh_channel: tokio::sync::mpsc::Sender<RefreshRequest>,
     use_keyspace_channel: tokio::sync::mpsc::Sender<UseKeyspaceRequest>,
 
-    _worker_handle: RemoteHandle<()>,
+    // Used to subscribe to server events forwarded by `ClusterWorker`
+    server_events_broadcaster: tokio::sync::broadcast::Sender<Event>,
+
+    // Taken out and dropped on shutdown, which stops the worker
+    worker_handle: std::sync::Mutex<Option<RemoteHandle<()>>>,
 }
 
 /// Enables printing [Cluster] struct in a neat way, by skipping the rather useless
@@ -65,9 +70,10 @@ pub struct Datacenter {
 
 #[derive(Clone)]
 pub struct ClusterData {
-    pub(crate) known_peers: HashMap<Uuid, Arc<Node>>, // Invariant: nonempty after Cluster::new()
+    pub(crate) known_peers: HashMap<Uuid, Arc<Node>>, // Invariant: nonempty after Cluster::new(), until shutdown
     pub(crate) keyspaces: HashMap<String, Keyspace>,
     pub(crate) locator: ReplicaLocator,
+    pub(crate) partitioner: PartitionerName,
 }
 
 /// Enables printing [ClusterData] struct in a neat way, skipping the clutter involved by
@@ -111,6 +117,9 @@ struct ClusterWorker {
     // Channel used to receive server events
     server_events_channel: tokio::sync::mpsc::Receiver<Event>,
 
+    // Channel used to forward server events to the users subscribed to them
+    server_events_broadcaster: tokio::sync::broadcast::Sender<Event>,
+
     // Channel used to receive signals that control connection is broken
     control_connection_repair_channel: tokio::sync::broadcast::Receiver<()>,
 
@@ -145,10 +154,12 @@ impl Cluster {
         fetch_schema_metadata: bool,
         host_filter: Option<Arc<dyn HostFilter>>,
         cluster_metadata_refresh_interval: Duration,
+        control_connection_init_attempts: NonZeroU32,
     ) -> Result<Cluster, NewSessionError> {
         let (refresh_sender, refresh_receiver) = tokio::sync::mpsc::channel(32);
         let (use_keyspace_sender, use_keyspace_receiver) = tokio::sync::mpsc::channel(32);
         let (server_events_sender, server_events_receiver) = tokio::sync::mpsc::channel(32);
+        let (server_events_broadcaster, _) = tokio::sync::broadcast::channel(32);
         let (control_connection_repair_sender, control_connection_repair_receiver) =
             tokio::sync::broadcast::channel(32);
 
@@ -157,6 +168,7 @@ impl Cluster {
             control_connection_repair_sender,
             pool_config.connection_config.clone(),
             pool_config.keepalive_interval,
+            pool_config.reconnection_policy.clone(),
             server_events_sender,
             keyspaces_to_fetch,
             fetch_schema_metadata,
@@ -164,7 +176,27 @@ impl Cluster {
         )
         .await?;
 
-        let metadata = metadata_reader.read_metadata(true).await?;
+        // Each attempt tries all known nodes, so the session can be created
+        // even if some of the contact points are down
+        let mut reconnection_schedule = pool_config.reconnection_policy.new_schedule();
+        let mut attempts_left = control_connection_init_attempts.get();
+        let metadata = loop {
+            attempts_left -= 1;
+            match metadata_reader.read_metadata(true).await {
+                Ok(metadata) => break metadata,
+                Err(err) if attempts_left > 0 => {
+                    let delay = reconnection_schedule.next_delay();
+                    warn!(
+                        error = %err,
+                        "Failed to establish the control connection to any of the known nodes, \
+                        retrying in {:?}",
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
         let cluster_data = ClusterData::new(
             metadata,
             &pool_config,
@@ -173,6 +205,14 @@ impl Cluster {
             host_filter.as_deref(),
         )
         .await;
+        if let PartitionerName::Unsupported(name) = &cluster_data.partitioner {
+            warn!(
+                "The cluster uses partitioner {}, which is unsupported for token awareness. \
+                Calculating tokens of statements will fail, unless their tables specify \
+                a supported partitioner.",
+                name
+            );
+        }
         cluster_data.wait_until_all_pools_are_initialized().await;
         let cluster_data: Arc<ArcSwap<ClusterData>> =
             Arc::new(ArcSwap::from(Arc::new(cluster_data)));
@@ -185,6 +225,7 @@ impl Cluster {
 
             refresh_channel: refresh_receiver,
             server_events_channel: server_events_receiver,
+            server_events_broadcaster: server_events_broadcaster.clone(),
             control_connection_repair_channel: control_connection_repair_receiver,
 
             use_keyspace_channel: use_keyspace_receiver,
@@ -201,7 +242,8 @@ impl Cluster {
             data: cluster_data,
             refresh_channel: refresh_sender,
             use_keyspace_channel: use_keyspace_sender,
-            _worker_handle: worker_handle,
+            server_events_broadcaster,
+            worker_handle: std::sync::Mutex::new(Some(worker_handle)),
         };
 
         Ok(result)
@@ -211,6 +253,10 @@ impl Cluster {
         self.data.load_full()
     }
 
+    pub(crate) fn subscribe_to_server_events(&self) -> tokio::sync::broadcast::Receiver<Event> {
+        self.server_events_broadcaster.subscribe()
+    }
+
     pub(crate) async fn refresh_metadata(&self) -> Result<(), QueryError> {
         let (response_sender, response_receiver) = tokio::sync::oneshot::channel();
 
@@ -219,13 +265,13 @@ impl Cluster {
                 response_chan: response_sender,
             })
             .await
-            .expect("Bug in Cluster::refresh_metadata sending");
-        // Other end of this channel is in ClusterWorker, can't be dropped while we have &self to Cluster with _worker_handle
+            .map_err(|_| QueryError::SessionClosed)?;
+        // Other end of this channel is in ClusterWorker, it's dropped only after shutdown
 
         response_receiver
             .await
-            .expect("Bug in Cluster::refresh_metadata receiving")
-        // ClusterWorker always responds
+            .map_err(|_| QueryError::SessionClosed)?
+        // ClusterWorker always responds, unless it was stopped by shutdown
     }
 
     pub(crate) async fn use_keyspace(
@@ -240,10 +286,19 @@ impl Cluster {
                 response_chan: response_sender,
             })
             .await
-            .expect("Bug in Cluster::use_keyspace sending");
-        // Other end of this channel is in ClusterWorker, can't be dropped while we have &self to Cluster with _worker_handle
+            .map_err(|_| QueryError::SessionClosed)?;
+        // Other end of this channel is in ClusterWorker, it's dropped only after shutdown
+
+        response_receiver
+            .await
+            .map_err(|_| QueryError::SessionClosed)? // ClusterWorker always responds, unless it was stopped by shutdown
+    }
 
-        response_receiver.await.unwrap() // ClusterWorker always responds
+    /// Stops the worker, which closes the control connection, and drops the cluster data
+    /// to close connection pools. Pools still used by some requests are closed when they finish.
+    pub(crate) fn shutdown(&self) {
+        drop(self.worker_handle.lock().unwrap().take());
+        self.data.store(Arc::new(ClusterData::empty()));
     }
 }
 
@@ -260,6 +315,16 @@ impl ClusterData {
         }
     }
 
+    /// Cluster data without any nodes, used after the session is shut down.
+    fn empty() -> Self {
+        ClusterData {
+            known_peers: HashMap::new(),
+            keyspaces: HashMap::new(),
+            locator: ReplicaLocator::new(std::iter::empty(), std::iter::empty()),
+            partitioner: PartitionerName::default(),
+        }
+    }
+
     pub(crate) async fn wait_until_all_pools_are_initialized(&self) {
         for node in self.locator.unique_nodes_in_global_ring().iter() {
             node.wait_until_pool_initialized().await;
@@ -338,6 +403,12 @@ impl ClusterData {
 
         Self::update_rack_count(&mut datacenters);
 
+        let partitioner = metadata
+            .partitioner
+            .as_deref()
+            .map(PartitionerName::from_cluster_partitioner)
+            .unwrap_or_default();
+
         let keyspaces = metadata.keyspaces;
         let (locator, keyspaces) = tokio::task::spawn_blocking(move || {
             let keyspace_strategies = keyspaces.values().map(|ks| &ks.strategy);
@@ -351,6 +422,7 @@ impl ClusterData {
             known_peers: new_known_peers,
             keyspaces,
             locator,
+            partitioner,
         }
     }
 
@@ -398,14 +470,20 @@ impl ClusterData {
             .and_then(|k| k.tables.get(table))
             .and_then(|t| t.partitioner.as_deref())
             .and_then(PartitionerName::from_str)
-            .unwrap_or_default();
+            .unwrap_or_else(|| self.partitioner.clone());
 
-        calculate_token_for_partition_key(&partition_key.serialized().unwrap(), &partitioner)
-            .map_err(|err| match err {
-                TokenCalculationError::ValueTooLong(values_len) => {
-                    BadQuery::ValuesTooLongForKey(values_len, u16::MAX.into())
-                }
-            })
+        calculate_token_for_partition_key_with_partitioner_name(
+            &partition_key.serialized().unwrap(),
+            &partitioner,
+        )
+        .map_err(|err| match err {
+            TokenCalculationError::ValueTooLong(values_len) => {
+                BadQuery::ValuesTooLongForKey(values_len, u16::MAX.into())
+            }
+            TokenCalculationError::UnsupportedPartitioner(name) => {
+                BadQuery::UnsupportedPartitioner(name)
+            }
+        })
     }
 
     /// Access to replicas owning a given token
@@ -453,8 +531,11 @@ impl ClusterData {
     pub(crate) fn iter_working_connections(
         &self,
     ) -> Result<impl Iterator<Item = Arc<Connection>> + '_, QueryError> {
-        // The returned iterator is nonempty by nonemptiness invariant of `self.known_peers`.
-        assert!(!self.known_peers.is_empty());
+        // The returned iterator is nonempty by nonemptiness invariant of `self.known_peers`,
+        // which only stops holding after the session is shut down.
+        if self.known_peers.is_empty() {
+            return Err(QueryError::SessionClosed);
+        }
         let mut peers_iter = self.known_peers.values();
 
         // First we try to find the first working pool of connections.
@@ -463,7 +544,7 @@ impl ClusterData {
             .by_ref()
             .map(|node| node.get_working_connections())
             .find_or_first(Result::is_ok)
-            .expect("impossible: known_peers was asserted to be nonempty")?;
+            .expect("impossible: known_peers was checked to be nonempty")?;
 
         let remaining_pools_iter = peers_iter
             .map(|node| node.get_working_connections())
@@ -510,6 +591,8 @@ impl ClusterWorker {
                 recv_res = self.server_events_channel.recv() => {
                     if let Some(event) = recv_res {
                         debug!("Received server event: {:?}", event);
+                        // Sending fails only if nobody is subscribed, which is fine
+                        let _ = self.server_events_broadcaster.send(event.clone());
                         match event {
                             Event::TopologyChange(_) => (), // Refresh immediately
                             Event::StatusChange(status) => {
@@ -1,13 +1,14 @@
 use bytes::Bytes;
 use futures::{future::RemoteHandle, FutureExt};
 use scylla_cql::errors::TranslationError;
+use scylla_cql::frame::frame_errors::ParseError;
 use scylla_cql::frame::request::options::Options;
 use scylla_cql::frame::response::Error;
 use scylla_cql::frame::types::SerialConsistency;
 use socket2::{SockRef, TcpKeepalive};
 use tokio::io::{split, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
 use tokio::net::{TcpSocket, TcpStream};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, Notify};
 use tokio::time::Instant;
 use tracing::instrument::WithSubscriber;
 use tracing::{debug, error, trace, warn};
@@ -16,7 +17,7 @@ use uuid::Uuid;
 use std::borrow::Cow;
 #[cfg(feature = "ssl")]
 use std::pin::Pin;
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, AtomicU8};
 use std::time::Duration;
 #[cfg(feature = "ssl")]
 use tokio_openssl::SslStream;
@@ -35,9 +36,10 @@ use std::sync::Mutex as StdMutex;
 use std::{
     cmp::Ordering,
     net::{Ipv4Addr, Ipv6Addr},
+    num::{NonZeroU64, NonZeroUsize},
 };
 
-use super::errors::{BadKeyspaceName, DbError, QueryError};
+use super::errors::{BadKeyspaceName, BadQuery, DbError, QueryError};
 use super::iterator::RowIterator;
 use super::session::AddressTranslator;
 use super::topology::{PeerEndpoint, UntranslatedEndpoint, UntranslatedPeer};
@@ -50,10 +52,13 @@ use crate::frame::protocol_features::ProtocolFeatures;
 use crate::frame::{
     self,
     request::{self, batch, execute, query, register, SerializableRequest},
-    response::{event::Event, result, NonErrorResponse, Response, ResponseOpcode},
+    response::{
+        event::Event, result, result::ResultMetadata, NonErrorResponse, Response, ResponseOpcode,
+    },
+    segment::{self, SegmentReader},
     server_event_type::EventType,
     value::{BatchValues, ValueList},
-    FrameParams, SerializedRequest,
+    FrameParams, ProtocolVersion, SerializedRequest,
 };
 use crate::query::Query;
 use crate::routing::ShardInfo;
@@ -96,6 +101,8 @@ struct RouterHandle {
     // pushing values in a synchronous way (without an `.await`), which is
     // needed for pushing values in `Drop` implementations.
     orphan_notification_sender: mpsc::UnboundedSender<RequestId>,
+    // Version of the protocol in which requests are serialized.
+    protocol_version: ProtocolVersion,
 }
 
 impl RouterHandle {
@@ -108,15 +115,43 @@ impl RouterHandle {
         &self,
         request: &impl SerializableRequest,
         compression: Option<Compression>,
+        compression_threshold: usize,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Bytes>>,
+    ) -> Result<TaskResponse, QueryError> {
+        let serialized_request = SerializedRequest::make_with_version(
+            request,
+            self.protocol_version,
+            compression,
+            compression_threshold,
+            tracing,
+            custom_payload,
+        )?;
+        self.send_serialized_request(serialized_request).await
+    }
+
+    async fn send_serialized_request(
+        &self,
+        serialized_request: SerializedRequest,
+    ) -> Result<TaskResponse, QueryError> {
+        self.send_serialized_request_with_framing(serialized_request, None)
+            .await
+    }
+
+    // If `switch_to_segments` is set, the router starts sending requests in segments
+    // right after this request, and reading responses in segments right after its response.
+    async fn send_serialized_request_with_framing(
+        &self,
+        serialized_request: SerializedRequest,
+        switch_to_segments: Option<SegmentFraming>,
     ) -> Result<TaskResponse, QueryError> {
-        let serialized_request = SerializedRequest::make(request, compression, tracing)?;
         let request_id = self.allocate_request_id();
 
         let (response_sender, receiver) = oneshot::channel();
         let response_handler = ResponseHandler {
             response_sender,
             request_id,
+            switch_to_segments,
         };
 
         // Dropping `notifier` (before calling `notifier.disable()`) will send a notification to
@@ -157,6 +192,7 @@ pub(crate) struct ConnectionFeatures {
     shard_info: Option<ShardInfo>,
     shard_aware_port: Option<u16>,
     protocol_features: ProtocolFeatures,
+    supported_options: HashMap<String, Vec<String>>,
 }
 
 type RequestId = u64;
@@ -164,6 +200,14 @@ type RequestId = u64;
 struct ResponseHandler {
     response_sender: oneshot::Sender<Result<TaskResponse, QueryError>>,
     request_id: RequestId,
+    // Set only for the STARTUP request of a connection using protocol v5.
+    switch_to_segments: Option<SegmentFraming>,
+}
+
+// Format of the segments used by a connection since the handshake (protocol v5 only).
+#[derive(Clone, Copy)]
+struct SegmentFraming {
+    compressed: bool,
 }
 
 // Used to notify `Connection::orphaner` about `Connection::send_request`
@@ -214,6 +258,7 @@ pub(crate) struct QueryResponse {
     pub(crate) response: Response,
     pub(crate) tracing_id: Option<Uuid>,
     pub(crate) warnings: Vec<String>,
+    pub(crate) custom_payload: Option<HashMap<String, Bytes>>,
 }
 
 // A QueryResponse in which response can not be Response::Error
@@ -221,6 +266,7 @@ pub(crate) struct NonErrorQueryResponse {
     pub(crate) response: NonErrorResponse,
     pub(crate) tracing_id: Option<Uuid>,
     pub(crate) warnings: Vec<String>,
+    pub(crate) custom_payload: Option<HashMap<String, Bytes>>,
 }
 
 impl QueryResponse {
@@ -229,6 +275,7 @@ impl QueryResponse {
             response: self.response.into_non_error_response()?,
             tracing_id: self.tracing_id,
             warnings: self.warnings,
+            custom_payload: self.custom_payload,
         })
     }
 
@@ -271,6 +318,7 @@ impl NonErrorQueryResponse {
         Ok(QueryResult {
             rows,
             warnings: self.warnings,
+            custom_payload: self.custom_payload,
             tracing_id: self.tracing_id,
             paging_state,
             col_specs,
@@ -345,9 +393,72 @@ mod ssl_config {
     }
 }
 
+/// Determines how long the connection waits for more requests to send them together
+/// in a single write, when write coalescing is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteCoalescingDelay {
+    /// Reschedules the task writing to the socket once the queue of requests is empty,
+    /// which was empirically tested to inject a 1-300µs delay.
+    #[default]
+    SmallNondeterministic,
+    /// Waits up to the given number of milliseconds after the first request of a write.
+    /// It reduces the number of syscalls under high throughput even further,
+    /// at the cost of increasing the latency of each request by up to the delay.
+    Milliseconds(NonZeroU64),
+}
+
+// Write coalescing settings used by the connection writer.
+#[derive(Debug, Clone, Copy)]
+struct WriteCoalescing {
+    delay: WriteCoalescingDelay,
+    max_requests: Option<NonZeroUsize>,
+}
+
+/// Version of the protocol used by new connections, shared by all connections of a session.\
+/// Unless the version is pinned, it starts at the highest version supported by the driver
+/// and is lowered whenever a node rejects it, so that only the first connection
+/// to a cluster of older nodes has to be reopened.
+pub(crate) struct SharedProtocolVersion {
+    version: AtomicU8,
+    pinned: bool,
+}
+
+impl SharedProtocolVersion {
+    pub(crate) fn new(pinned: Option<ProtocolVersion>, compression: Option<Compression>) -> Self {
+        let version = match pinned {
+            Some(version) => version,
+            // Snappy isn't supported by protocol v5, so prefer keeping the requested compression
+            None if compression == Some(Compression::Snappy) => ProtocolVersion::V4,
+            None => ProtocolVersion::MAX,
+        };
+        Self {
+            version: AtomicU8::new(version.as_u8()),
+            pinned: pinned.is_some(),
+        }
+    }
+
+    pub(crate) fn get(&self) -> ProtocolVersion {
+        // Only supported versions are ever stored
+        ProtocolVersion::try_from(self.version.load(std::sync::atomic::Ordering::Relaxed)).unwrap()
+    }
+
+    pub(crate) fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    // Makes new connections use at most the given version. Does nothing if the version is pinned.
+    fn lower_to(&self, version: ProtocolVersion) {
+        if !self.pinned {
+            self.version
+                .fetch_min(version.as_u8(), std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ConnectionConfig {
     pub compression: Option<Compression>,
+    pub compression_threshold: usize,
     pub tcp_nodelay: bool,
     pub tcp_keepalive_interval: Option<Duration>,
     #[cfg(feature = "ssl")]
@@ -361,6 +472,11 @@ pub struct ConnectionConfig {
     pub authenticator: Option<Arc<dyn AuthenticatorProvider>>,
     pub address_translator: Option<Arc<dyn AddressTranslator>>,
     pub enable_write_coalescing: bool,
+    pub write_coalescing_delay: WriteCoalescingDelay,
+    pub write_coalescing_max_requests: Option<NonZeroUsize>,
+    pub batch_size_warn_threshold: Option<usize>,
+    pub max_frame_body_size: usize,
+    pub(crate) protocol_version: Arc<SharedProtocolVersion>,
 
     pub keepalive_interval: Option<Duration>,
     pub keepalive_timeout: Option<Duration>,
@@ -370,6 +486,7 @@ impl Default for ConnectionConfig {
     fn default() -> Self {
         Self {
             compression: None,
+            compression_threshold: 0,
             tcp_nodelay: true,
             tcp_keepalive_interval: None,
             event_sender: None,
@@ -382,6 +499,11 @@ impl Default for ConnectionConfig {
             #[cfg(feature = "cloud")]
             cloud_config: None,
             enable_write_coalescing: true,
+            write_coalescing_delay: Default::default(),
+            write_coalescing_max_requests: None,
+            batch_size_warn_threshold: None,
+            max_frame_body_size: frame::MAX_FRAME_BODY_SIZE,
+            protocol_version: Arc::new(SharedProtocolVersion::new(None, None)),
 
             // Note: this is different than SessionConfig default values.
             keepalive_interval: None,
@@ -410,25 +532,8 @@ impl ConnectionConfig {
 pub(crate) type ErrorReceiver = tokio::sync::oneshot::Receiver<QueryError>;
 
 impl Connection {
-    // Returns new connection and ErrorReceiver which can be used to wait for a fatal error
-    pub(crate) async fn new(
-        addr: SocketAddr,
-        source_port: Option<u16>,
-        config: ConnectionConfig,
-    ) -> Result<(Self, ErrorReceiver), QueryError> {
-        let stream_connector = match source_port {
-            Some(p) => {
-                tokio::time::timeout(config.connect_timeout, connect_with_source_port(addr, p))
-                    .await
-            }
-            None => tokio::time::timeout(config.connect_timeout, TcpStream::connect(addr)).await,
-        };
-        let stream = match stream_connector {
-            Ok(stream) => stream?,
-            Err(_) => {
-                return Err(QueryError::TimeoutError);
-            }
-        };
+    /// Applies socket options from the config to a freshly established TCP connection.
+    fn configure_socket(stream: &TcpStream, config: &ConnectionConfig) -> std::io::Result<()> {
         stream.set_nodelay(config.tcp_nodelay)?;
 
         if let Some(tcp_keepalive_interval) = config.tcp_keepalive_interval {
@@ -476,20 +581,46 @@ impl Connection {
                 tcp_keepalive = tcp_keepalive.with_retries(10);
             }
 
-            let sf = SockRef::from(&stream);
+            let sf = SockRef::from(stream);
             sf.set_tcp_keepalive(&tcp_keepalive)?;
         }
 
+        Ok(())
+    }
+
+    // Returns new connection and ErrorReceiver which can be used to wait for a fatal error
+    pub(crate) async fn new(
+        addr: SocketAddr,
+        source_port: Option<u16>,
+        config: ConnectionConfig,
+    ) -> Result<(Self, ErrorReceiver), QueryError> {
+        let stream_connector = match source_port {
+            Some(p) => {
+                tokio::time::timeout(config.connect_timeout, connect_with_source_port(addr, p))
+                    .await
+            }
+            None => tokio::time::timeout(config.connect_timeout, TcpStream::connect(addr)).await,
+        };
+        let stream = match stream_connector {
+            Ok(stream) => stream?,
+            Err(_) => {
+                return Err(QueryError::TimeoutError);
+            }
+        };
+        Self::configure_socket(&stream, &config)?;
+
         // TODO: What should be the size of the channel?
         let (sender, receiver) = mpsc::channel(1024);
         let (error_sender, error_receiver) = tokio::sync::oneshot::channel();
         // Unbounded because it allows for synchronous pushes
         let (orphan_notification_sender, orphan_notification_receiver) = mpsc::unbounded_channel();
 
+        let protocol_version = config.protocol_version.get();
         let router_handle = Arc::new(RouterHandle {
             submit_channel: sender,
             request_id_generator: AtomicU64::new(0),
             orphan_notification_sender,
+            protocol_version,
         });
 
         let _worker_handle = Self::run_router(
@@ -503,10 +634,13 @@ impl Connection {
         )
         .await?;
 
+        let mut features = ConnectionFeatures::default();
+        features.protocol_features.protocol_version = protocol_version;
+
         let connection = Connection {
             _worker_handle,
             config,
-            features: Default::default(),
+            features,
             connect_address: addr,
             router_handle,
         };
@@ -518,27 +652,47 @@ impl Connection {
         &self,
         options: HashMap<String, String>,
     ) -> Result<Response, QueryError> {
-        Ok(self
-            .send_request(&request::Startup { options }, false, false)
-            .await?
-            .response)
+        let serialized_request =
+            self.serialize_request(&request::Startup { options }, false, false, None)?;
+
+        // In protocol v5, everything sent after the STARTUP request and its response is framed
+        let switch_to_segments =
+            (self.get_protocol_version() == ProtocolVersion::V5).then_some(SegmentFraming {
+                compressed: self.config.compression == Some(Compression::Lz4),
+            });
+        let task_response = self
+            .router_handle
+            .send_serialized_request_with_framing(serialized_request, switch_to_segments)
+            .await?;
+
+        Ok(Self::parse_response(
+            task_response,
+            self.config.compression,
+            self.config.max_frame_body_size,
+            &self.features.protocol_features,
+        )?
+        .response)
     }
 
     pub(crate) async fn get_options(&self) -> Result<Response, QueryError> {
         Ok(self
-            .send_request(&request::Options {}, false, false)
+            .send_request(&request::Options {}, false, false, None)
             .await?
             .response)
     }
 
     pub(crate) async fn prepare(&self, query: &Query) -> Result<PreparedStatement, QueryError> {
+        self.check_keyspace_supported(query.get_keyspace())?;
+
         let query_response = self
             .send_request(
                 &request::Prepare {
                     query: &query.contents,
+                    keyspace: query.get_keyspace(),
                 },
                 true,
                 query.config.tracing,
+                query.config.custom_payload.as_ref(),
             )
             .await?;
 
@@ -550,6 +704,7 @@ impl Connection {
                     .protocol_features
                     .prepared_flags_contain_lwt_mark(p.prepared_metadata.flags as u32),
                 p.prepared_metadata,
+                p.result_metadata,
                 query.contents.clone(),
                 query.get_page_size(),
                 query.config.clone(),
@@ -572,7 +727,9 @@ impl Connection {
         query: impl Into<Query>,
         previous_prepared: &PreparedStatement,
     ) -> Result<(), QueryError> {
-        let reprepare_query: Query = query.into();
+        let mut reprepare_query: Query = query.into();
+        // The statement has to be prepared in the same keyspace to keep its id
+        reprepare_query.set_keyspace(previous_prepared.config.keyspace.clone());
         let reprepared = self.prepare(&reprepare_query).await?;
         // Reprepared statement should keep its id - it's the md5 sum
         // of statement contents
@@ -581,6 +738,9 @@ impl Connection {
                 "Prepared statement Id changed, md5 sum should stay the same",
             ))
         } else {
+            // The statement might have been invalidated by a schema change,
+            // so the result metadata has to be refreshed
+            previous_prepared.update_result_metadata(reprepared.get_result_metadata());
             Ok(())
         }
     }
@@ -589,7 +749,7 @@ impl Connection {
         &self,
         response: Option<Vec<u8>>,
     ) -> Result<QueryResponse, QueryError> {
-        self.send_request(&request::AuthResponse { response }, false, false)
+        self.send_request(&request::AuthResponse { response }, false, false, None)
             .await
     }
 
@@ -623,9 +783,16 @@ impl Connection {
         serial_consistency: Option<SerialConsistency>,
     ) -> Result<QueryResult, QueryError> {
         let query: Query = query.into();
-        self.query_with_consistency(&query, &values, consistency, serial_consistency, None)
-            .await?
-            .into_query_result()
+        self.query_with_consistency(
+            &query,
+            &values,
+            consistency,
+            serial_consistency,
+            query.get_timestamp(),
+            None,
+        )
+        .await?
+        .into_query_result()
     }
 
     pub(crate) async fn query(
@@ -642,6 +809,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             query.config.serial_consistency.flatten(),
+            query.get_timestamp(),
             paging_state,
         )
         .await
@@ -653,19 +821,24 @@ impl Connection {
         values: impl ValueList,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        timestamp: Option<i64>,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResponse, QueryError> {
         let serialized_values = values.serialized()?;
+        self.check_keyspace_supported(query.get_keyspace())?;
 
         let values_size = serialized_values.size();
         if values_size != 0 {
             let prepared = self.prepare(query).await?;
+            let bound_values = prepared.bind_named_values(serialized_values)?;
+            prepared.check_values(&bound_values)?;
             return self
                 .execute_with_consistency(
                     &prepared,
-                    values,
+                    &bound_values,
                     consistency,
                     serial_consistency,
+                    timestamp,
                     paging_state,
                 )
                 .await;
@@ -679,12 +852,19 @@ impl Connection {
                 values: serialized_values,
                 page_size: query.get_page_size(),
                 paging_state,
-                timestamp: query.get_timestamp(),
+                skip_metadata: false,
+                timestamp,
+                keyspace: query.get_keyspace().map(Cow::Borrowed),
             },
         };
 
-        self.send_request(&query_frame, true, query.config.tracing)
-            .await
+        self.send_request(
+            &query_frame,
+            true,
+            query.config.tracing,
+            query.config.custom_payload.as_ref(),
+        )
+        .await
     }
 
     pub(crate) async fn execute_with_consistency(
@@ -693,24 +873,37 @@ impl Connection {
         values: impl ValueList,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        timestamp: Option<i64>,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResponse, QueryError> {
         let serialized_values = values.serialized()?;
 
-        let execute_frame = execute::Execute {
+        // Only statements returning rows have result metadata worth caching
+        let cached_metadata = || {
+            if !prepared_statement.get_use_cached_result_metadata() {
+                return None;
+            }
+            let metadata = prepared_statement.get_result_metadata();
+            (!metadata.col_specs.is_empty()).then_some(metadata)
+        };
+
+        let mut execute_frame = execute::Execute {
             id: prepared_statement.get_id().to_owned(),
+            result_metadata_id: None,
             parameters: query::QueryParameters {
                 consistency,
                 serial_consistency,
                 values: serialized_values,
                 page_size: prepared_statement.get_page_size(),
-                timestamp: prepared_statement.get_timestamp(),
+                timestamp,
                 paging_state,
+                skip_metadata: false,
+                keyspace: None,
             },
         };
 
         let query_response = self
-            .send_request(&execute_frame, true, prepared_statement.config.tracing)
+            .send_execute(&mut execute_frame, prepared_statement, cached_metadata())
             .await?;
 
         match &query_response.response {
@@ -722,13 +915,83 @@ impl Connection {
                 // Repreparation of a statement is needed
                 self.reprepare(prepared_statement.get_statement(), prepared_statement)
                     .await?;
-                self.send_request(&execute_frame, true, prepared_statement.config.tracing)
+                self.send_execute(&mut execute_frame, prepared_statement, cached_metadata())
                     .await
             }
             _ => Ok(query_response),
         }
     }
 
+    // Sends the EXECUTE with `skip_metadata` set if there is cached result metadata to use.
+    // If the rows returned by the database don't match the cached metadata (e.g. the table
+    // was altered in the meantime), sends it again without `skip_metadata` and refreshes
+    // the metadata cached in the statement.
+    // In protocol v5 the database detects outdated metadata by its id itself,
+    // and returns the new metadata along with the rows.
+    async fn send_execute(
+        &self,
+        execute_frame: &mut execute::Execute<'_>,
+        prepared_statement: &PreparedStatement,
+        cached_metadata: Option<Arc<ResultMetadata>>,
+    ) -> Result<QueryResponse, QueryError> {
+        let tracing = prepared_statement.config.tracing;
+        let custom_payload = prepared_statement.config.custom_payload.as_ref();
+        // Taken anew, as repreparing the statement refreshes the metadata along with its id
+        execute_frame.result_metadata_id =
+            prepared_statement.get_result_metadata().metadata_id.clone();
+
+        let cached_metadata = match cached_metadata {
+            Some(cached_metadata) => cached_metadata,
+            None => {
+                execute_frame.parameters.skip_metadata = false;
+                return self
+                    .send_request(execute_frame, true, tracing, custom_payload)
+                    .await;
+            }
+        };
+
+        execute_frame.parameters.skip_metadata = true;
+        if let Some(query_response) = self
+            .send_request_with_cached_metadata(
+                execute_frame,
+                true,
+                tracing,
+                custom_payload,
+                &cached_metadata,
+            )
+            .await?
+        {
+            // The id is present only if the database reported that the metadata has changed
+            if let Response::Result(result::Result::Rows(rows)) = &query_response.response {
+                if rows.metadata.metadata_id.is_some() {
+                    Self::update_result_metadata(prepared_statement, &rows.metadata);
+                }
+            }
+            return Ok(query_response);
+        }
+
+        debug!(
+            "Connection::execute: Cached result metadata of statement with id {:?} is outdated - executing with metadata",
+            execute_frame.id
+        );
+        execute_frame.parameters.skip_metadata = false;
+        let query_response = self
+            .send_request(execute_frame, true, tracing, custom_payload)
+            .await?;
+
+        if let Response::Result(result::Result::Rows(rows)) = &query_response.response {
+            Self::update_result_metadata(prepared_statement, &rows.metadata);
+        }
+
+        Ok(query_response)
+    }
+
+    fn update_result_metadata(prepared_statement: &PreparedStatement, metadata: &ResultMetadata) {
+        let mut result_metadata = metadata.clone();
+        result_metadata.paging_state = None;
+        prepared_statement.update_result_metadata(Arc::new(result_metadata));
+    }
+
     /// Executes a query and fetches its results over multiple pages, using
     /// the asynchronous iterator interface.
     pub(crate) async fn query_iter(
@@ -766,6 +1029,7 @@ impl Connection {
                 .config
                 .determine_consistency(self.config.default_consistency),
             batch.config.serial_consistency.flatten(),
+            batch.get_timestamp(),
         )
         .await
     }
@@ -776,20 +1040,53 @@ impl Connection {
         values: impl BatchValues,
         consistency: Consistency,
         serial_consistency: Option<SerialConsistency>,
+        timestamp: Option<i64>,
     ) -> Result<QueryResult, QueryError> {
+        // Statements of a batch are executed in a single keyspace, and the keyspace
+        // of a prepared statement is already known to the database
+        let has_query_with_keyspace = batch.statements.iter().any(|s| match s {
+            BatchStatement::Query(q) => q.get_keyspace().is_some(),
+            BatchStatement::PreparedStatement(_) => false,
+        });
+        if has_query_with_keyspace {
+            return Err(QueryError::BadQuery(BadQuery::Other(
+                "Keyspace of an unprepared query in a batch can't be set, prepare the batch with Session::prepare_batch instead".to_string(),
+            )));
+        }
+
         let batch_frame = batch::Batch {
             statements: Cow::Borrowed(&batch.statements),
             values,
             batch_type: batch.get_type(),
             consistency,
             serial_consistency,
-            timestamp: batch.get_timestamp(),
+            timestamp,
+        };
+
+        let serialize_batch = || {
+            self.serialize_request(
+                &batch_frame,
+                true,
+                batch.config.tracing,
+                batch.config.custom_payload.as_ref(),
+            )
         };
 
+        let mut serialized_batch = serialize_batch()?;
+        if let Some(threshold) = self.config.batch_size_warn_threshold {
+            let batch_size = serialized_batch.get_request_size();
+            if batch_size > threshold {
+                warn!(
+                    "Batch of {} statements has a serialized size of {} bytes, exceeding the warning threshold of {} bytes",
+                    batch.statements.len(),
+                    batch_size,
+                    threshold
+                );
+            }
+        }
+
         loop {
-            let query_response = self
-                .send_request(&batch_frame, true, batch.config.tracing)
-                .await?;
+            let query_response = self.send_serialized_request(serialized_batch).await?;
 
             return match query_response.response {
                 Response::Error(err) => match err.error {
@@ -803,6 +1100,7 @@ impl Connection {
                         });
                         if let Some(p) = prepared_statement {
                             self.reprepare(p.get_statement(), p).await?;
+                            serialized_batch = serialize_batch()?;
                             continue;
                         } else {
                             return Err(QueryError::ProtocolError(
@@ -861,7 +1159,7 @@ impl Connection {
         };
 
         match self
-            .send_request(&register_frame, true, false)
+            .send_request(&register_frame, true, false, None)
             .await?
             .response
         {
@@ -891,21 +1189,87 @@ impl Connection {
         request: &impl SerializableRequest,
         compress: bool,
         tracing: bool,
+        custom_payload: Option<&HashMap<String, Bytes>>,
     ) -> Result<QueryResponse, QueryError> {
+        let serialized_request =
+            self.serialize_request(request, compress, tracing, custom_payload)?;
+        self.send_serialized_request(serialized_request).await
+    }
+
+    /// Sends a request whose `Rows` response may lack column metadata,
+    /// which is then taken from `cached_metadata`.
+    /// Returns `None` if the response lacks column metadata and `cached_metadata`
+    /// describes a different number of columns.
+    async fn send_request_with_cached_metadata(
+        &self,
+        request: &impl SerializableRequest,
+        compress: bool,
+        tracing: bool,
+        custom_payload: Option<&HashMap<String, Bytes>>,
+        cached_metadata: &ResultMetadata,
+    ) -> Result<Option<QueryResponse>, QueryError> {
+        let serialized_request =
+            self.serialize_request(request, compress, tracing, custom_payload)?;
+        let task_response = self
+            .router_handle
+            .send_serialized_request(serialized_request)
+            .await?;
+
+        match Self::parse_response_with_cached_metadata(
+            task_response,
+            self.config.compression,
+            self.config.max_frame_body_size,
+            &self.features.protocol_features,
+            Some(cached_metadata),
+        )? {
+            Ok(query_response) => Ok(Some(query_response)),
+            Err(ParseError::CachedMetadataMismatch(col_count, cached_col_count)) => {
+                debug!(
+                    "Result has {} columns, but the cached result metadata describes {}",
+                    col_count, cached_col_count
+                );
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn serialize_request(
+        &self,
+        request: &impl SerializableRequest,
+        compress: bool,
+        tracing: bool,
+        custom_payload: Option<&HashMap<String, Bytes>>,
+    ) -> Result<SerializedRequest, QueryError> {
         let compression = if compress {
             self.config.compression
         } else {
             None
         };
 
+        Ok(SerializedRequest::make_with_version(
+            request,
+            self.get_protocol_version(),
+            compression,
+            self.config.compression_threshold,
+            tracing,
+            custom_payload,
+        )?)
+    }
+
+    async fn send_serialized_request(
+        &self,
+        serialized_request: SerializedRequest,
+    ) -> Result<QueryResponse, QueryError> {
         let task_response = self
             .router_handle
-            .send_request(request, compression, tracing)
+            .send_serialized_request(serialized_request)
             .await?;
 
         Self::parse_response(
             task_response,
             self.config.compression,
+            self.config.max_frame_body_size,
             &self.features.protocol_features,
         )
     }
@@ -913,12 +1277,33 @@ impl Connection {
     fn parse_response(
         task_response: TaskResponse,
         compression: Option<Compression>,
+        max_body_size: usize,
         features: &ProtocolFeatures,
     ) -> Result<QueryResponse, QueryError> {
-        let body_with_ext = frame::parse_response_body_extensions(
+        Self::parse_response_with_cached_metadata(
+            task_response,
+            compression,
+            max_body_size,
+            features,
+            None,
+        )?
+        .map_err(Into::into)
+    }
+
+    // Errors from deserializing the response itself are returned separately,
+    // so that the caller can tell outdated cached metadata apart from other failures
+    fn parse_response_with_cached_metadata(
+        task_response: TaskResponse,
+        compression: Option<Compression>,
+        max_body_size: usize,
+        features: &ProtocolFeatures,
+        cached_metadata: Option<&ResultMetadata>,
+    ) -> Result<Result<QueryResponse, ParseError>, QueryError> {
+        let body_with_ext = frame::parse_response_body_extensions_with_max_body_size(
             task_response.params.flags,
             compression,
             task_response.body,
+            max_body_size,
         )?;
 
         for warn_description in &body_with_ext.warnings {
@@ -928,14 +1313,22 @@ impl Connection {
             );
         }
 
-        let response =
-            Response::deserialize(features, task_response.opcode, &mut &*body_with_ext.body)?;
+        let response = match Response::deserialize_with_cached_metadata(
+            features,
+            task_response.opcode,
+            &mut &*body_with_ext.body,
+            cached_metadata,
+        ) {
+            Ok(response) => response,
+            Err(err) => return Ok(Err(err)),
+        };
 
-        Ok(QueryResponse {
+        Ok(Ok(QueryResponse {
             response,
             warnings: body_with_ext.warnings,
             tracing_id: body_with_ext.trace_id,
-        })
+            custom_payload: body_with_ext.custom_payload,
+        }))
     }
 
     async fn run_router(
@@ -951,7 +1344,12 @@ impl Connection {
         if let Some(ssl_config) = &config.ssl_config {
             let ssl = ssl_config.new_ssl()?;
             let mut stream = SslStream::new(ssl, stream)?;
-            let _pin = Pin::new(&mut stream).connect().await;
+            // A failed handshake (e.g. an untrusted server certificate) must not be ignored,
+            // otherwise the connection would be used as if it was properly secured.
+            Pin::new(&mut stream)
+                .connect()
+                .await
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
 
             let (task, handle) = Self::router(
                 config,
@@ -1004,8 +1402,16 @@ impl Connection {
         // are carefully written in such a way that they do not hold the lock
         // across .await points. Therefore, it should not be too expensive.
         let handler_map = StdMutex::new(ResponseHandlerMap::new());
+        // Signalled by the reader whenever a response frees a stream id,
+        // so that the writer can wait for one when all of them are in use.
+        let stream_id_freed = Notify::new();
+
+        let write_coalescing = config.enable_write_coalescing.then_some(WriteCoalescing {
+            delay: config.write_coalescing_delay,
+            max_requests: config.write_coalescing_max_requests,
+        });
 
-        let enable_write_coalescing = config.enable_write_coalescing;
+        let compression_threshold = config.compression_threshold;
 
         let k = Self::keepaliver(
             router_handle,
@@ -1017,13 +1423,16 @@ impl Connection {
         let r = Self::reader(
             BufReader::with_capacity(8192, read_half),
             &handler_map,
+            &stream_id_freed,
             config,
         );
         let w = Self::writer(
             BufWriter::with_capacity(8192, write_half),
             &handler_map,
+            &stream_id_freed,
             receiver,
-            enable_write_coalescing,
+            write_coalescing,
+            compression_threshold,
         );
         let o = Self::orphaner(&handler_map, orphan_notification_receiver);
 
@@ -1050,10 +1459,26 @@ impl Connection {
     async fn reader(
         mut read_half: (impl AsyncRead + Unpin),
         handler_map: &StdMutex<ResponseHandlerMap>,
+        stream_id_freed: &Notify,
         config: ConnectionConfig,
     ) -> Result<(), QueryError> {
+        // Set once the response to STARTUP is read, if the connection uses protocol v5
+        let mut segment_reader: Option<SegmentReader> = None;
         loop {
-            let (params, opcode, body) = frame::read_response_frame(&mut read_half).await?;
+            let (params, opcode, body) = match &mut segment_reader {
+                Some(segment_reader) => {
+                    segment_reader
+                        .read_response_frame(&mut read_half, config.max_frame_body_size)
+                        .await?
+                }
+                None => {
+                    frame::read_response_frame_with_max_body_size(
+                        &mut read_half,
+                        config.max_frame_body_size,
+                    )
+                    .await?
+                }
+            };
             let response = TaskResponse {
                 params,
                 opcode,
@@ -1069,7 +1494,13 @@ impl Connection {
                 }
                 Ordering::Equal => {
                     if let Some(event_sender) = config.event_sender.as_ref() {
-                        Self::handle_event(response, config.compression, event_sender).await?;
+                        Self::handle_event(
+                            response,
+                            config.compression,
+                            config.max_frame_body_size,
+                            event_sender,
+                        )
+                        .await?;
                     }
                     continue;
                 }
@@ -1082,10 +1513,17 @@ impl Connection {
                 let mut handler_map_guard = handler_map.try_lock().unwrap();
                 handler_map_guard.lookup(params.stream)
             };
+            stream_id_freed.notify_one();
 
             use HandlerLookupResult::*;
             match handler_lookup_res {
                 Handler(handler) => {
+                    if let Some(framing) = handler.switch_to_segments {
+                        // An error response means that the connection isn't going to be used
+                        if matches!(opcode, ResponseOpcode::Ready | ResponseOpcode::Authenticate) {
+                            segment_reader = Some(SegmentReader::new(framing.compressed));
+                        }
+                    }
                     // Don't care if sending of the response fails. This must
                     // mean that the receiver side was impatient and is not
                     // waiting for the result anymore.
@@ -1110,21 +1548,34 @@ impl Connection {
         }
     }
 
-    fn alloc_stream_id(
+    // Allocates a stream id for the request. If all of them are in use,
+    // waits until a response frees one. Requests which were written, but not flushed yet
+    // are flushed first - otherwise their responses could never free anything.
+    async fn alloc_stream_id(
+        write_half: &mut (impl AsyncWrite + Unpin),
         handler_map: &StdMutex<ResponseHandlerMap>,
-        response_handler: ResponseHandler,
-    ) -> Option<i16> {
-        // We are guaranteed here that handler_map will not be locked
-        // by anybody else, so we can do try_lock().unwrap()
-        let mut handler_map_guard = handler_map.try_lock().unwrap();
-        match handler_map_guard.allocate(response_handler) {
-            Ok(stream_id) => Some(stream_id),
-            Err(response_handler) => {
-                error!("Could not allocate stream id");
-                let _ = response_handler
-                    .response_sender
-                    .send(Err(QueryError::UnableToAllocStreamId));
-                None
+        stream_id_freed: &Notify,
+        mut response_handler: ResponseHandler,
+    ) -> Result<i16, QueryError> {
+        let mut flushed = false;
+        loop {
+            let allocated = {
+                // We are guaranteed here that handler_map will not be locked
+                // by anybody else, so we can do try_lock().unwrap()
+                let mut handler_map_guard = handler_map.try_lock().unwrap();
+                handler_map_guard.allocate(response_handler)
+            };
+            match allocated {
+                Ok(stream_id) => return Ok(stream_id),
+                Err(handler) => {
+                    response_handler = handler;
+                    if !flushed {
+                        debug!("All stream ids are in use, waiting for one to be freed");
+                        write_half.flush().await?;
+                        flushed = true;
+                    }
+                    stream_id_freed.notified().await;
+                }
             }
         }
     }
@@ -1132,35 +1583,82 @@ impl Connection {
     async fn writer(
         mut write_half: (impl AsyncWrite + Unpin),
         handler_map: &StdMutex<ResponseHandlerMap>,
+        stream_id_freed: &Notify,
         mut task_receiver: mpsc::Receiver<Task>,
-        enable_write_coalescing: bool,
+        write_coalescing: Option<WriteCoalescing>,
+        compression_threshold: usize,
     ) -> Result<(), QueryError> {
+        let delay = write_coalescing.map(|coalescing| coalescing.delay);
+        let max_requests = write_coalescing.and_then(|coalescing| coalescing.max_requests);
+
+        // Set once STARTUP is written, if the connection uses protocol v5
+        let mut segment_framing: Option<SegmentFraming> = None;
+        let mut segments = Vec::new();
+
         // When the Connection object is dropped, the sender half
         // of the channel will be dropped, this task will return an error
         // and the whole worker will be stopped
         while let Some(mut task) = task_receiver.recv().await {
             let mut num_requests = 0;
             let mut total_sent = 0;
-            while let Some(stream_id) = Self::alloc_stream_id(handler_map, task.response_handler) {
+            let write_start = Instant::now();
+            loop {
+                let switch_to_segments = task.response_handler.switch_to_segments;
+                let stream_id = Self::alloc_stream_id(
+                    &mut write_half,
+                    handler_map,
+                    stream_id_freed,
+                    task.response_handler,
+                )
+                .await?;
                 let mut req = task.serialized_request;
                 req.set_stream(stream_id);
                 let req_data: &[u8] = req.get_data();
                 total_sent += req_data.len();
                 num_requests += 1;
-                write_half.write_all(req_data).await?;
+                match segment_framing {
+                    Some(framing) => {
+                        segments.clear();
+                        segment::write_segments(
+                            req_data,
+                            framing.compressed,
+                            compression_threshold,
+                            &mut segments,
+                        );
+                        write_half.write_all(&segments).await?;
+                    }
+                    None => write_half.write_all(req_data).await?,
+                }
+                if switch_to_segments.is_some() {
+                    segment_framing = switch_to_segments;
+                }
+                if matches!(max_requests, Some(max) if num_requests >= max.get()) {
+                    break;
+                }
                 task = match task_receiver.try_recv() {
                     Ok(t) => t,
-                    Err(_) if enable_write_coalescing => {
-                        // Yielding was empirically tested to inject a 1-300µs delay,
-                        // much better than tokio::time::sleep's 1ms granularity.
-                        // Also, yielding in a busy system let's the queue catch up with new items.
-                        tokio::task::yield_now().await;
-                        match task_receiver.try_recv() {
-                            Ok(t) => t,
-                            Err(_) => break,
+                    Err(_) => match delay {
+                        Some(WriteCoalescingDelay::SmallNondeterministic) => {
+                            // Yielding was empirically tested to inject a 1-300µs delay,
+                            // much better than tokio::time::sleep's 1ms granularity.
+                            // Also, yielding in a busy system let's the queue catch up with new items.
+                            tokio::task::yield_now().await;
+                            match task_receiver.try_recv() {
+                                Ok(t) => t,
+                                Err(_) => break,
+                            }
                         }
-                    }
-                    Err(_) => break,
+                        Some(WriteCoalescingDelay::Milliseconds(delay_ms)) => {
+                            // The delay is counted from the first request, so that a steady
+                            // trickle of requests can't postpone the flush indefinitely
+                            let deadline = write_start + Duration::from_millis(delay_ms.get());
+                            match tokio::time::timeout_at(deadline, task_receiver.recv()).await {
+                                Ok(Some(t)) => t,
+                                _ => break,
+                            }
+                        }
+                        None => break,
+                    },
                 }
             }
             trace!("Sending {} requests; {} bytes", num_requests, total_sent);
@@ -1217,7 +1715,7 @@ impl Connection {
     ) -> Result<(), QueryError> {
         async fn issue_keepalive_query(router_handle: &RouterHandle) -> Result<(), QueryError> {
             router_handle
-                .send_request(&Options, None, false)
+                .send_request(&Options, None, 0, false, None)
                 .await
                 .map(|_| ())
         }
@@ -1270,6 +1768,7 @@ impl Connection {
     async fn handle_event(
         task_response: TaskResponse,
         compression: Option<Compression>,
+        max_body_size: usize,
         event_sender: &mpsc::Sender<Event>,
     ) -> Result<(), QueryError> {
         // Protocol features are negotiated during connection handshake.
@@ -1283,7 +1782,8 @@ impl Connection {
         // future implementors.
         let features = ProtocolFeatures::default(); // TODO: Use the right features
 
-        let response = Self::parse_response(task_response, compression, &features)?.response;
+        let response =
+            Self::parse_response(task_response, compression, max_body_size, &features)?.response;
         let event = match response {
             Response::Event(e) => e,
             _ => {
@@ -1308,6 +1808,32 @@ impl Connection {
         self.features.shard_aware_port
     }
 
+    /// Options reported by the node in response to the OPTIONS request.
+    pub(crate) fn get_supported_options(&self) -> &HashMap<String, Vec<String>> {
+        &self.features.supported_options
+    }
+
+    /// Compression used on this connection, i.e. the configured one if the node supports it.
+    pub(crate) fn get_compression(&self) -> Option<Compression> {
+        self.config.compression
+    }
+
+    /// Version of the protocol used on this connection.
+    pub(crate) fn get_protocol_version(&self) -> ProtocolVersion {
+        self.router_handle.protocol_version
+    }
+
+    // The keyspace of a single request can be sent only since protocol v5
+    fn check_keyspace_supported(&self, keyspace: Option<&str>) -> Result<(), QueryError> {
+        let version = self.get_protocol_version();
+        if keyspace.is_some() && version < ProtocolVersion::V5 {
+            return Err(QueryError::BadQuery(
+                BadQuery::PerRequestKeyspaceUnsupported(version),
+            ));
+        }
+        Ok(())
+    }
+
     fn set_features(&mut self, features: ConnectionFeatures) {
         self.features = features;
     }
@@ -1379,27 +1905,69 @@ pub(crate) async fn open_named_connection(
     driver_name: Option<String>,
     driver_version: Option<String>,
 ) -> Result<(Connection, ErrorReceiver), QueryError> {
-    // TODO: shouldn't all this logic be in Connection::new?
-    let (mut connection, error_receiver) =
-        Connection::new(addr, source_port, config.clone()).await?;
+    // The timeout bounds the whole connection setup, i.e. not only establishing
+    // the TCP connection, but also the handshake (OPTIONS, STARTUP and authentication).
+    let connect_timeout = config.connect_timeout;
+    tokio::time::timeout(
+        connect_timeout,
+        open_named_connection_without_timeout(
+            addr,
+            source_port,
+            config,
+            driver_name,
+            driver_version,
+        ),
+    )
+    .await
+    .unwrap_or(Err(QueryError::TimeoutError))
+}
 
-    let options_result = connection.get_options().await?;
+async fn open_named_connection_without_timeout(
+    addr: SocketAddr,
+    source_port: Option<u16>,
+    config: ConnectionConfig,
+    driver_name: Option<String>,
+    driver_version: Option<String>,
+) -> Result<(Connection, ErrorReceiver), QueryError> {
+    // TODO: shouldn't all this logic be in Connection::new?
+    let (mut connection, error_receiver, mut supported) = loop {
+        let (connection, error_receiver) =
+            Connection::new(addr, source_port, config.clone()).await?;
+
+        match connection.get_options().await? {
+            Response::Supported(supported) => break (connection, error_receiver, supported),
+            Response::Error(Error {
+                error: DbError::ProtocolError,
+                reason,
+            }) if !config.protocol_version.is_pinned()
+                && connection.get_protocol_version().lower().is_some() =>
+            {
+                // The node doesn't support the version, so reconnect with the preceding one
+                let version = connection.get_protocol_version();
+                let lower_version = version.lower().unwrap();
+                debug!(
+                    "Node {} rejected protocol {} ({}), reconnecting with {}",
+                    addr, version, reason, lower_version
+                );
+                config.protocol_version.lower_to(lower_version);
+            }
+            Response::Error(Error { error, reason }) => {
+                return Err(QueryError::DbError(error, reason))
+            }
+            _ => {
+                return Err(QueryError::ProtocolError(
+                    "Wrong response to OPTIONS message was received",
+                ));
+            }
+        }
+    };
 
     let shard_aware_port_key = match config.is_ssl() {
         true => "SCYLLA_SHARD_AWARE_PORT_SSL",
         false => "SCYLLA_SHARD_AWARE_PORT",
     };
 
-    let mut supported = match options_result {
-        Response::Supported(supported) => supported,
-        Response::Error(Error { error, reason }) => return Err(QueryError::DbError(error, reason)),
-        _ => {
-            return Err(QueryError::ProtocolError(
-                "Wrong response to OPTIONS message was received",
-            ));
-        }
-    };
-
+    let supported_options = supported.options.clone();
     let shard_info = ShardInfo::try_from(&supported.options).ok();
     let supported_compression = supported.options.remove("COMPRESSION").unwrap_or_default();
     let shard_aware_port = supported
@@ -1410,7 +1978,8 @@ pub(crate) async fn open_named_connection(
         .next()
         .and_then(|p| p.parse::<u16>().ok());
 
-    let protocol_features = ProtocolFeatures::parse_from_supported(&supported.options);
+    let mut protocol_features = ProtocolFeatures::parse_from_supported(&supported.options);
+    protocol_features.protocol_version = connection.get_protocol_version();
 
     let mut options = HashMap::new();
     protocol_features.add_startup_options(&mut options);
@@ -1419,6 +1988,7 @@ pub(crate) async fn open_named_connection(
         shard_info,
         shard_aware_port,
         protocol_features,
+        supported_options,
     };
     connection.set_features(features);
 
@@ -1431,7 +2001,14 @@ pub(crate) async fn open_named_connection(
     }
     if let Some(compression) = &config.compression {
         let compression_str = compression.to_string();
-        if supported_compression.iter().any(|c| c == &compression_str) {
+        if *compression == Compression::Snappy
+            && connection.get_protocol_version() == ProtocolVersion::V5
+        {
+            warn!(
+                "Snappy compression isn't supported by protocol v5, falling back to no compression"
+            );
+            connection.config.compression = None;
+        } else if supported_compression.iter().any(|c| c == &compression_str) {
             // Compression is reported to be supported by the server,
             // request it from the server
             options.insert("COMPRESSION".to_string(), compression.to_string());
@@ -1763,32 +2340,68 @@ impl VerifiedKeyspaceName {
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
-    use scylla_cql::errors::QueryError;
+    use bytes::{BufMut, Bytes, BytesMut};
+    use scylla_cql::errors::{BadQuery, DbError, QueryError};
     use scylla_cql::frame::protocol_features::{
         LWT_OPTIMIZATION_META_BIT_MASK_KEY, SCYLLA_LWT_ADD_METADATA_MARK_EXTENSION,
     };
+    use scylla_cql::frame::request::options::Options;
     use scylla_cql::frame::types;
+    use scylla_cql::frame::{segment, ProtocolVersion, SerializedRequest};
     use scylla_proxy::{
         Condition, Node, Proxy, Reaction, RequestFrame, RequestOpcode, RequestReaction,
-        RequestRule, ResponseFrame, ShardAwareness,
+        RequestRule, ResponseFrame, ResponseOpcode, ShardAwareness,
     };
 
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+    use tokio::net::{TcpListener, TcpStream};
     use tokio::select;
-    use tokio::sync::mpsc;
+    use tokio::sync::{mpsc, oneshot, Notify};
 
-    use super::ConnectionConfig;
+    use super::{
+        Connection, ConnectionConfig, ResponseHandler, ResponseHandlerMap, SharedProtocolVersion,
+        Task, WriteCoalescing, WriteCoalescingDelay,
+    };
+    use crate::frame::response::{result, Response};
+    use crate::frame::types::Consistency;
     use crate::query::Query;
     use crate::transport::connection::open_connection;
     use crate::transport::node::ResolvedContactPoint;
     use crate::transport::topology::UntranslatedEndpoint;
+    use crate::transport::Compression;
     use crate::utils::test_utils::unique_keyspace_name;
     use crate::{IntoTypedRows, SessionBuilder};
     use futures::{StreamExt, TryStreamExt};
     use std::collections::HashMap;
     use std::net::SocketAddr;
+    use std::num::{NonZeroU64, NonZeroUsize};
     use std::sync::Arc;
+    use std::sync::Mutex as StdMutex;
     use std::time::Duration;
 
+    // Builds a response to the given request, with the body written by `write_body`
+    fn forged_response(
+        frame: RequestFrame,
+        opcode: ResponseOpcode,
+        write_body: impl FnOnce(&mut BytesMut),
+    ) -> ResponseFrame {
+        let mut body = BytesMut::new();
+        write_body(&mut body);
+        ResponseFrame {
+            params: frame.params.for_response(),
+            opcode,
+            body: body.freeze(),
+        }
+    }
+
+    // The proxy doesn't understand the segments used since the handshake in protocol v5
+    fn proxy_connection_config() -> ConnectionConfig {
+        ConnectionConfig {
+            protocol_version: Arc::new(SharedProtocolVersion::new(Some(ProtocolVersion::V4), None)),
+            ..Default::default()
+        }
+    }
+
     // Just like resolve_hostname in session.rs
     async fn resolve_hostname(hostname: &str) -> SocketAddr {
         match tokio::net::lookup_host(hostname).await {
@@ -2015,14 +2628,519 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_lwt_optimisation_mark_negotiation() {
-        const MASK: &str = "2137";
+    async fn socket_options_are_applied() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
 
-        let lwt_optimisation_entry = format!("{}={}", LWT_OPTIMIZATION_META_BIT_MASK_KEY, MASK);
+        for (tcp_nodelay, tcp_keepalive_interval) in
+            [(true, Some(Duration::from_secs(42))), (false, None)]
+        {
+            let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let config = ConnectionConfig {
+                tcp_nodelay,
+                tcp_keepalive_interval,
+                ..Default::default()
+            };
+            super::Connection::configure_socket(&stream, &config).unwrap();
+
+            let socket = socket2::SockRef::from(&stream);
+            assert_eq!(socket.nodelay().unwrap(), tcp_nodelay);
+            assert_eq!(
+                socket.keepalive().unwrap(),
+                tcp_keepalive_interval.is_some()
+            );
+            #[cfg(target_os = "linux")]
+            if let Some(interval) = tcp_keepalive_interval {
+                assert_eq!(socket.keepalive_time().unwrap(), interval);
+            }
+        }
+    }
+
+    #[test]
+    fn tcp_nodelay_is_enabled_by_default() {
+        assert!(ConnectionConfig::default().tcp_nodelay);
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(5000)]
+    async fn responses_in_unsupported_protocol_version_are_rejected() {
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        // The node responds in protocol v6, which the driver does not speak.
+        let rules = vec![RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Options),
+            RequestReaction::forge_response(Arc::new(move |frame: RequestFrame| {
+                let mut response =
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap();
+                response.params.version = 0x86;
+                response
+            })),
+        )];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let result = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            ConnectionConfig::default(),
+        )
+        .await;
+
+        let err = result.map(|_| ()).unwrap_err();
+        assert_matches!(&err, QueryError::InvalidMessage(msg) if msg.contains("version 6"));
+
+        let _ = proxy.finish().await;
+    }
 
+    #[tokio::test]
+    #[ntest::timeout(5000)]
+    async fn protocol_version_is_lowered_when_rejected_by_node() {
         let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
 
+        // The node only speaks protocol v4, just like Scylla
+        let rules = vec![
+            RequestRule(
+                Condition::not(Condition::ProtocolVersion(4)),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    let mut params = frame.params;
+                    params.version = 0x04;
+                    ResponseFrame::forged_error(
+                        params,
+                        DbError::ProtocolError,
+                        Some("Invalid or unsupported protocol version"),
+                    )
+                    .unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::Ready, |_| {})
+                })),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let endpoint = UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+            address: proxy_addr,
+            datacenter: None,
+        });
+
         let config = ConnectionConfig::default();
+        assert_eq!(config.protocol_version.get(), ProtocolVersion::V5);
+        let (conn, _error_receiver) = open_connection(endpoint.clone(), None, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(conn.get_protocol_version(), ProtocolVersion::V4);
+        // Further connections start with the lowered version
+        assert_eq!(config.protocol_version.get(), ProtocolVersion::V4);
+
+        // A pinned version is never lowered
+        let config = ConnectionConfig {
+            protocol_version: Arc::new(SharedProtocolVersion::new(Some(ProtocolVersion::V5), None)),
+            ..Default::default()
+        };
+        let result = open_connection(endpoint, None, config.clone()).await;
+        assert_matches!(
+            result.map(|_| ()),
+            Err(QueryError::DbError(DbError::ProtocolError, _))
+        );
+        assert_eq!(config.protocol_version.get(), ProtocolVersion::V5);
+
+        let _ = proxy.finish().await;
+    }
+
+    // Reads a request frame sent outside of segments, returning its stream and opcode
+    async fn read_unframed_request(conn: &mut TcpStream) -> (i16, u8) {
+        let mut header = [0u8; 9];
+        conn.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], 0x05);
+        let body_len = u32::from_be_bytes(header[5..9].try_into().unwrap()) as usize;
+        conn.read_exact(&mut vec![0u8; body_len]).await.unwrap();
+        (i16::from_be_bytes([header[2], header[3]]), header[4])
+    }
+
+    fn v5_response_frame(stream: i16, opcode: ResponseOpcode, body: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x85, 0x00];
+        frame.extend_from_slice(&stream.to_be_bytes());
+        frame.push(opcode as u8);
+        frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    // Responds to OPTIONS and STARTUP like a node speaking protocol v5,
+    // after which everything is sent in segments
+    async fn respond_to_v5_handshake(conn: &mut TcpStream) {
+        let (stream, opcode) = read_unframed_request(conn).await;
+        assert_eq!(opcode, RequestOpcode::Options as u8);
+        let mut supported = BytesMut::new();
+        let options: HashMap<String, Vec<String>> =
+            [("COMPRESSION".to_string(), vec!["lz4".to_string()])]
+                .into_iter()
+                .collect();
+        types::write_string_multimap(&options, &mut supported).unwrap();
+        let response = v5_response_frame(stream, ResponseOpcode::Supported, &supported);
+        conn.write_all(&response).await.unwrap();
+
+        let (stream, opcode) = read_unframed_request(conn).await;
+        assert_eq!(opcode, RequestOpcode::Startup as u8);
+        let response = v5_response_frame(stream, ResponseOpcode::Ready, &[]);
+        conn.write_all(&response).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(5000)]
+    async fn protocol_v5_requests_are_sent_in_segments() {
+        for compression in [None, Some(Compression::Lz4)] {
+            let listener = TcpListener::bind((scylla_proxy::get_exclusive_local_address(), 9042))
+                .await
+                .unwrap();
+            let addr = listener.local_addr().unwrap();
+            let compressed = compression.is_some();
+
+            let node = async {
+                let (mut conn, _) = listener.accept().await.unwrap();
+                respond_to_v5_handshake(&mut conn).await;
+
+                let payload = segment::read_segment(&mut conn, compressed).await.unwrap();
+                assert_eq!(payload[0], 0x05);
+                assert_eq!(payload[4], RequestOpcode::Query as u8);
+                let stream = i16::from_be_bytes([payload[2], payload[3]]);
+                // A Void result
+                let response = v5_response_frame(stream, ResponseOpcode::Result, &[0, 0, 0, 1]);
+                let mut segments = Vec::new();
+                segment::write_segments(&response, compressed, 0, &mut segments);
+                conn.write_all(&segments).await.unwrap();
+
+                conn
+            };
+
+            let connect = async {
+                let config = ConnectionConfig {
+                    compression,
+                    ..Default::default()
+                };
+                let (conn, _error_receiver) = open_connection(
+                    UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                        address: addr,
+                        datacenter: None,
+                    }),
+                    None,
+                    config,
+                )
+                .await
+                .unwrap();
+                assert_eq!(conn.get_protocol_version(), ProtocolVersion::V5);
+                assert_eq!(conn.get_compression(), compression);
+
+                let response = conn
+                    .query(&Query::new("INSERT INTO ks.t (a) VALUES (1)"), (), None)
+                    .await
+                    .unwrap();
+                assert_matches!(response.response, Response::Result(result::Result::Void));
+                conn
+            };
+
+            let (_node_conn, _driver_conn) = tokio::join!(node, connect);
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(5000)]
+    async fn query_keyspace_is_sent_in_protocol_v5() {
+        const KEYSPACES: [&str; 2] = ["ks1", "ks2"];
+        const QUERY: &str = "SELECT a FROM t";
+
+        let listener = TcpListener::bind((scylla_proxy::get_exclusive_local_address(), 9042))
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // The same query is sent with a different keyspace each time
+        let node = async {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            respond_to_v5_handshake(&mut conn).await;
+
+            for keyspace in KEYSPACES {
+                let payload = segment::read_segment(&mut conn, false).await.unwrap();
+                assert_eq!(payload[4], RequestOpcode::Query as u8);
+                let stream = i16::from_be_bytes([payload[2], payload[3]]);
+
+                let mut body = &payload[9..];
+                assert_eq!(types::read_long_string(&mut body).unwrap(), QUERY);
+                types::read_consistency(&mut body).unwrap();
+                let flags = types::read_int(&mut body).unwrap();
+                assert_eq!(flags, 0x80);
+                assert_eq!(types::read_string(&mut body).unwrap(), keyspace);
+                assert!(body.is_empty());
+
+                let response = v5_response_frame(stream, ResponseOpcode::Result, &[0, 0, 0, 1]);
+                let mut segments = Vec::new();
+                segment::write_segments(&response, false, 0, &mut segments);
+                conn.write_all(&segments).await.unwrap();
+            }
+
+            conn
+        };
+
+        let connect = async {
+            let (conn, _error_receiver) = open_connection(
+                UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                    address: addr,
+                    datacenter: None,
+                }),
+                None,
+                ConnectionConfig::default(),
+            )
+            .await
+            .unwrap();
+
+            for keyspace in KEYSPACES {
+                let mut query = Query::new(QUERY);
+                query.set_keyspace(Some(keyspace.to_string()));
+                let response = conn.query(&query, (), None).await.unwrap();
+                assert_matches!(response.response, Response::Result(result::Result::Void));
+            }
+            conn
+        };
+
+        let (_node_conn, _driver_conn) = tokio::join!(node, connect);
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(5000)]
+    async fn query_keyspace_is_rejected_in_protocol_v4() {
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_ready(frame.params)
+                })),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let (conn, _error_receiver) = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            proxy_connection_config(),
+        )
+        .await
+        .unwrap();
+
+        let mut query = Query::new("SELECT a FROM t");
+        query.set_keyspace(Some("ks".to_string()));
+
+        // Neither the query nor its preparation can carry the keyspace
+        assert_matches!(
+            conn.query(&query, (), None).await.map(|_| ()),
+            Err(QueryError::BadQuery(
+                BadQuery::PerRequestKeyspaceUnsupported(ProtocolVersion::V4)
+            ))
+        );
+        assert_matches!(
+            conn.prepare(&query).await.map(|_| ()),
+            Err(QueryError::BadQuery(
+                BadQuery::PerRequestKeyspaceUnsupported(ProtocolVersion::V4)
+            ))
+        );
+
+        let _ = proxy.finish().await;
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(5000)]
+    async fn connection_timeout_covers_handshake() {
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        // The node accepts the TCP connection and responds to OPTIONS, but never to STARTUP.
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(move |frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::drop_frame(),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+        let config = ConnectionConfig {
+            connect_timeout: CONNECT_TIMEOUT,
+            ..Default::default()
+        };
+
+        let started = std::time::Instant::now();
+        let result = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            config,
+        )
+        .await;
+        let elapsed = started.elapsed();
+
+        assert_matches!(result.map(|_| ()), Err(QueryError::TimeoutError));
+        assert!(elapsed >= CONNECT_TIMEOUT);
+        assert!(elapsed < CONNECT_TIMEOUT + Duration::from_secs(1));
+
+        let _ = proxy.finish().await;
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(30000)]
+    async fn requests_wait_for_free_stream_ids() {
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        // The node answers every query with a Void result, after a delay
+        // long enough for all the requests to be in flight at the same time.
+        const RESPONSE_DELAY: Duration = Duration::from_millis(500);
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::Ready, |_| {})
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Query),
+                RequestReaction::forge_response_with_delay(
+                    RESPONSE_DELAY,
+                    Arc::new(|frame: RequestFrame| {
+                        forged_response(frame, ResponseOpcode::Result, |body| {
+                            body.put_i32(0x0001) // Void
+                        })
+                    }),
+                ),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let (conn, _error_receiver) = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            proxy_connection_config(),
+        )
+        .await
+        .unwrap();
+
+        // More requests than there are stream ids on a connection
+        const REQUESTS: usize = i16::MAX as usize + 1 + 1000;
+        let query = Query::new("INSERT INTO ks.t (a) VALUES (1)");
+        let started = std::time::Instant::now();
+        let results = futures::future::join_all(
+            (0..REQUESTS).map(|_| conn.query_single_page(query.clone(), &[])),
+        )
+        .await;
+
+        // None of them failed, the ones over the limit waited for the first responses
+        for result in results {
+            result.unwrap();
+        }
+        assert!(started.elapsed() >= 2 * RESPONSE_DELAY);
+
+        let _ = proxy.finish().await;
+    }
+
+    #[tokio::test]
+    async fn test_lwt_optimisation_mark_negotiation() {
+        const MASK: &str = "2137";
+
+        let lwt_optimisation_entry = format!("{}={}", LWT_OPTIMIZATION_META_BIT_MASK_KEY, MASK);
+
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        let config = proxy_connection_config();
 
         let (startup_tx, mut startup_rx) = mpsc::unbounded_channel();
 
@@ -2092,6 +3210,381 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn execute_reprepares_statement_on_unprepared_error() {
+        const STATEMENT_ID: &[u8] = b"stmt_id";
+        const STATEMENT: &str = "INSERT INTO ks.t (a) VALUES (1)";
+
+        // RESULT of kind Prepared, with no bind markers and no result columns
+        let forge_prepared = || {
+            RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                forged_response(frame, ResponseOpcode::Result, |body| {
+                    body.put_i32(0x0004); // Prepared
+                    types::write_short_bytes(STATEMENT_ID, body).unwrap();
+                    // Prepared metadata: flags, columns count, partition key count
+                    body.put_i32(0);
+                    body.put_i32(0);
+                    body.put_i32(0);
+                    // Result metadata: flags (no metadata), columns count
+                    body.put_i32(0x0004);
+                    body.put_i32(0);
+                })
+            }))
+        };
+
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        let (prepare_tx, mut prepare_rx) = mpsc::unbounded_channel();
+        let (execute_tx, mut execute_rx) = mpsc::unbounded_channel();
+
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::Ready, |_| {})
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Prepare),
+                forge_prepared().with_feedback_when_performed(prepare_tx),
+            ),
+            // The node "forgets" the statement before the first execution
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Execute)
+                    .and(Condition::TrueForLimitedTimes(1)),
+                RequestReaction::forge_with_error(DbError::Unprepared {
+                    statement_id: Bytes::from_static(STATEMENT_ID),
+                })
+                .with_feedback_when_performed(execute_tx.clone()),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Execute),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::Result, |body| {
+                        body.put_i32(0x0001) // Void
+                    })
+                }))
+                .with_feedback_when_performed(execute_tx),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let (conn, _error_receiver) = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            proxy_connection_config(),
+        )
+        .await
+        .unwrap();
+
+        let prepared = conn.prepare(&Query::new(STATEMENT)).await.unwrap();
+        assert_eq!(prepared.get_id().as_ref(), STATEMENT_ID);
+        prepare_rx.recv().await.unwrap();
+
+        // The error is handled transparently
+        conn.execute_with_consistency(&prepared, (), Consistency::One, None, None, None)
+            .await
+            .unwrap()
+            .into_query_result()
+            .unwrap();
+
+        // The statement was prepared again between two executions
+        execute_rx.recv().await.unwrap();
+        let (reprepare, _shard) = prepare_rx.recv().await.unwrap();
+        assert_eq!(
+            types::read_long_string(&mut &*reprepare.body).unwrap(),
+            STATEMENT
+        );
+        execute_rx.recv().await.unwrap();
+        assert!(execute_rx.try_recv().is_err());
+
+        let _ = proxy.finish().await;
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn custom_payload_round_trip() {
+        const CUSTOM_PAYLOAD_FLAG: u8 = 0x04;
+
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        let (query_tx, mut query_rx) = mpsc::unbounded_channel();
+
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::Ready, |_| {})
+                })),
+            ),
+            // The node echoes the custom payload of the request in a Void result
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Query),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    let payload = if frame.params.flags & CUSTOM_PAYLOAD_FLAG != 0 {
+                        Some(types::read_bytes_map(&mut &*frame.body).unwrap())
+                    } else {
+                        None
+                    };
+                    let mut response = forged_response(frame, ResponseOpcode::Result, |body| {
+                        if let Some(payload) = &payload {
+                            types::write_bytes_map(payload, body).unwrap();
+                        }
+                        body.put_i32(0x0001) // Void
+                    });
+                    response.params.flags = if payload.is_some() {
+                        CUSTOM_PAYLOAD_FLAG
+                    } else {
+                        0
+                    };
+                    response
+                }))
+                .with_feedback_when_performed(query_tx),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let (conn, _error_receiver) = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            proxy_connection_config(),
+        )
+        .await
+        .unwrap();
+
+        let payload: HashMap<String, Bytes> = [
+            ("key".to_string(), Bytes::from_static(b"value")),
+            ("empty".to_string(), Bytes::new()),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut query = Query::new("INSERT INTO ks.t (a) VALUES (1)");
+        query.set_custom_payload(Some(payload.clone()));
+        let result = conn
+            .query(&query, (), None)
+            .await
+            .unwrap()
+            .into_query_result()
+            .unwrap();
+        assert_eq!(result.custom_payload(), Some(&payload));
+
+        let (request, _shard) = query_rx.recv().await.unwrap();
+        assert_ne!(request.params.flags & CUSTOM_PAYLOAD_FLAG, 0);
+
+        // Requests without a custom payload don't carry the flag
+        query.set_custom_payload(None);
+        let result = conn
+            .query(&query, (), None)
+            .await
+            .unwrap()
+            .into_query_result()
+            .unwrap();
+        assert_eq!(result.custom_payload(), None);
+
+        let (request, _shard) = query_rx.recv().await.unwrap();
+        assert_eq!(request.params.flags & CUSTOM_PAYLOAD_FLAG, 0);
+
+        let _ = proxy.finish().await;
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn custom_authenticator_drives_challenge_response_exchange() {
+        use crate::authentication::{AuthError, AuthenticatorProvider, AuthenticatorSession};
+        use async_trait::async_trait;
+        use std::sync::Mutex;
+
+        const AUTHENTICATOR_NAME: &str = "org.example.MockAuthenticator";
+
+        // Data passed by the driver to the authenticator during the exchange.
+        #[derive(Default)]
+        struct ReceivedTokens {
+            authenticator_name: Option<String>,
+            challenge: Option<Vec<u8>>,
+            success: Option<Vec<u8>>,
+        }
+
+        struct MockAuthenticatorSession(Arc<Mutex<ReceivedTokens>>);
+
+        #[async_trait]
+        impl AuthenticatorSession for MockAuthenticatorSession {
+            async fn evaluate_challenge(
+                &mut self,
+                token: Option<&[u8]>,
+            ) -> Result<Option<Vec<u8>>, AuthError> {
+                self.0.lock().unwrap().challenge = token.map(|t| t.to_vec());
+                Ok(Some(b"challenge_response".to_vec()))
+            }
+
+            async fn success(&mut self, token: Option<&[u8]>) -> Result<(), AuthError> {
+                self.0.lock().unwrap().success = token.map(|t| t.to_vec());
+                Ok(())
+            }
+        }
+
+        struct MockAuthenticatorProvider(Arc<Mutex<ReceivedTokens>>);
+
+        #[async_trait]
+        impl AuthenticatorProvider for MockAuthenticatorProvider {
+            async fn start_authentication_session(
+                &self,
+                authenticator_name: &str,
+            ) -> Result<(Option<Vec<u8>>, Box<dyn AuthenticatorSession>), AuthError> {
+                self.0.lock().unwrap().authenticator_name = Some(authenticator_name.to_owned());
+                Ok((
+                    Some(b"initial_response".to_vec()),
+                    Box::new(MockAuthenticatorSession(self.0.clone())),
+                ))
+            }
+        }
+
+        let proxy_addr = SocketAddr::new(scylla_proxy::get_exclusive_local_address(), 9042);
+
+        let (auth_response_tx, mut auth_response_rx) = mpsc::unbounded_channel();
+
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    ResponseFrame::forged_supported(frame.params, &HashMap::new()).unwrap()
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Startup),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::Authenticate, |body| {
+                        types::write_string(AUTHENTICATOR_NAME, body).unwrap()
+                    })
+                })),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::AuthResponse).and(
+                    Condition::BodyContainsCaseSensitive(Box::new(*b"challenge_response")),
+                ),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::AuthSuccess, |body| {
+                        types::write_bytes(b"success_token", body).unwrap()
+                    })
+                }))
+                .with_feedback_when_performed(auth_response_tx.clone()),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::AuthResponse),
+                RequestReaction::forge_response(Arc::new(|frame: RequestFrame| {
+                    forged_response(frame, ResponseOpcode::AuthChallenge, |body| {
+                        types::write_bytes(b"challenge_token", body).unwrap()
+                    })
+                }))
+                .with_feedback_when_performed(auth_response_tx),
+            ),
+        ];
+
+        let proxy = Proxy::builder()
+            .with_node(
+                Node::builder()
+                    .proxy_address(proxy_addr)
+                    .request_rules(rules)
+                    .build_dry_mode(),
+            )
+            .build()
+            .run()
+            .await
+            .unwrap();
+
+        let received_tokens = Arc::new(Mutex::new(ReceivedTokens::default()));
+        let config = ConnectionConfig {
+            authenticator: Some(Arc::new(MockAuthenticatorProvider(received_tokens.clone()))),
+            ..proxy_connection_config()
+        };
+
+        let (_conn, _error_receiver) = open_connection(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: proxy_addr,
+                datacenter: None,
+            }),
+            None,
+            config,
+        )
+        .await
+        .unwrap();
+
+        // Responses produced by the authenticator are sent to the server unchanged...
+        let read_auth_response = |frame: RequestFrame| {
+            types::read_bytes_opt(&mut &*frame.body)
+                .unwrap()
+                .map(|token| token.to_vec())
+        };
+        let (initial_response, _shard) = auth_response_rx.recv().await.unwrap();
+        assert_eq!(
+            read_auth_response(initial_response),
+            Some(b"initial_response".to_vec())
+        );
+        let (challenge_response, _shard) = auth_response_rx.recv().await.unwrap();
+        assert_eq!(
+            read_auth_response(challenge_response),
+            Some(b"challenge_response".to_vec())
+        );
+
+        let _ = proxy.finish().await;
+
+        // ...and the tokens sent by the server are passed to the authenticator.
+        let received_tokens = received_tokens.lock().unwrap();
+        assert_eq!(
+            received_tokens.authenticator_name.as_deref(),
+            Some(AUTHENTICATOR_NAME)
+        );
+        assert_eq!(
+            received_tokens.challenge.as_deref(),
+            Some(&b"challenge_token"[..])
+        );
+        assert_eq!(
+            received_tokens.success.as_deref(),
+            Some(&b"success_token"[..])
+        );
+    }
+
     #[tokio::test]
     #[ntest::timeout(20000)]
     #[cfg(not(scylla_cloud_tests))]
@@ -2108,7 +3601,7 @@ mod tests {
         let config = ConnectionConfig {
             keepalive_interval: Some(Duration::from_millis(500)),
             keepalive_timeout: Some(Duration::from_secs(1)),
-            ..Default::default()
+            ..proxy_connection_config()
         };
 
         let mut proxy = Proxy::builder()
@@ -2165,4 +3658,214 @@ mod tests {
 
         let _ = proxy.finish().await;
     }
+
+    #[cfg(feature = "ssl")]
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn connection_rejects_untrusted_server_certificate() {
+        use super::SslConfig;
+        use openssl::ssl::{
+            Ssl, SslAcceptor, SslContextBuilder, SslFiletype, SslMethod, SslVerifyMode,
+        };
+        use std::path::{Path, PathBuf};
+        use std::pin::Pin;
+        use tokio_openssl::SslStream;
+
+        let certs_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test/tls");
+
+        let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+        acceptor
+            .set_certificate_chain_file(certs_dir.join("db.crt"))
+            .unwrap();
+        acceptor
+            .set_private_key_file(certs_dir.join("db.key"), SslFiletype::PEM)
+            .unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // A fake server which only performs the TLS handshake and reports whether it succeeded.
+        let (handshake_tx, mut handshake_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let ssl = Ssl::new(acceptor.context()).unwrap();
+                let mut stream = SslStream::new(ssl, stream).unwrap();
+                let result = Pin::new(&mut stream).accept().await;
+                handshake_tx.send(result.is_ok()).unwrap();
+            }
+        });
+
+        let connect = |ca_file: Option<PathBuf>| async move {
+            let mut context_builder = SslContextBuilder::new(SslMethod::tls()).unwrap();
+            context_builder.set_verify(SslVerifyMode::PEER);
+            if let Some(ca_file) = ca_file {
+                context_builder.set_ca_file(ca_file).unwrap();
+            }
+            let config = ConnectionConfig {
+                ssl_config: Some(SslConfig::new_with_global_context(context_builder.build())),
+                ..ConnectionConfig::default()
+            };
+            open_connection(
+                UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                    address: addr,
+                    datacenter: None,
+                }),
+                None,
+                config,
+            )
+            .await
+        };
+
+        // The server certificate is signed by the test CA, which is not trusted by default.
+        assert!(connect(None).await.is_err());
+        assert!(!handshake_rx.recv().await.unwrap());
+
+        // With the test CA trusted the handshake succeeds. The connection still fails to open,
+        // because the fake server doesn't speak CQL.
+        assert!(connect(Some(certs_dir.join("ca.crt"))).await.is_err());
+        assert!(handshake_rx.recv().await.unwrap());
+    }
+
+    // Counts the writes reaching the socket, i.e. the `write` syscalls a real socket would make
+    struct WriteCountingSink {
+        writes: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl tokio::io::AsyncWrite for WriteCountingSink {
+        fn poll_write(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.writes
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    const NUM_CLIENTS: usize = 8;
+    const REQUESTS_PER_CLIENT: usize = 32;
+
+    // Runs the connection writer while a number of concurrent clients issue requests
+    // one after another, and returns the number of writes made to the socket
+    async fn count_socket_writes(write_coalescing: Option<WriteCoalescing>) -> usize {
+        let writes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let handler_map = StdMutex::new(ResponseHandlerMap::new());
+        let stream_id_freed = Notify::new();
+        let (task_sender, task_receiver) = mpsc::channel(1024);
+
+        let writer = Connection::writer(
+            BufWriter::with_capacity(
+                8192,
+                WriteCountingSink {
+                    writes: writes.clone(),
+                },
+            ),
+            &handler_map,
+            &stream_id_freed,
+            task_receiver,
+            write_coalescing,
+            0,
+        );
+        // Each client runs in its own task, like the application tasks using a session would
+        let clients: Vec<_> = (0..NUM_CLIENTS)
+            .map(|_| {
+                let task_sender = task_sender.clone();
+                tokio::spawn(async move {
+                    for _ in 0..REQUESTS_PER_CLIENT {
+                        let (response_sender, _response_receiver) = oneshot::channel();
+                        let task = Task {
+                            serialized_request: SerializedRequest::make(
+                                &Options, None, 0, false, None,
+                            )
+                            .unwrap(),
+                            response_handler: ResponseHandler {
+                                response_sender,
+                                request_id: 0,
+                                switch_to_segments: None,
+                            },
+                        };
+                        task_sender.send(task).await.unwrap();
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        // Dropping the last sender stops the writer
+        drop(task_sender);
+        let clients = async move {
+            for client in clients {
+                client.await.unwrap();
+            }
+        };
+
+        let (writer_result, ()) = tokio::join!(writer, clients);
+        writer_result.unwrap();
+        writes.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    #[tokio::test]
+    async fn write_coalescing_reduces_socket_writes() {
+        let coalescing = |delay, max_requests| {
+            Some(WriteCoalescing {
+                delay,
+                max_requests,
+            })
+        };
+
+        let without_coalescing = count_socket_writes(None).await;
+        let with_small_delay = count_socket_writes(coalescing(
+            WriteCoalescingDelay::SmallNondeterministic,
+            None,
+        ))
+        .await;
+        let ms_delay = WriteCoalescingDelay::Milliseconds(NonZeroU64::new(1).unwrap());
+        let with_ms_delay = count_socket_writes(coalescing(ms_delay, None)).await;
+        let max_requests = 16;
+        let with_max_requests =
+            count_socket_writes(coalescing(ms_delay, NonZeroUsize::new(max_requests))).await;
+
+        // Without coalescing, requests are written as soon as they are issued
+        assert!(
+            without_coalescing >= REQUESTS_PER_CLIENT,
+            "{} writes without coalescing",
+            without_coalescing
+        );
+        // With coalescing, the writer waits for the clients to issue more requests in the meantime
+        assert!(
+            with_small_delay < without_coalescing / 4,
+            "{} writes with coalescing, {} without",
+            with_small_delay,
+            without_coalescing
+        );
+        assert!(
+            with_ms_delay < without_coalescing / 4,
+            "{} writes with coalescing, {} without",
+            with_ms_delay,
+            without_coalescing
+        );
+        // No more than the limit of requests are written at once
+        assert!(
+            with_max_requests >= NUM_CLIENTS * REQUESTS_PER_CLIENT / max_requests,
+            "{} writes with at most {} requests each",
+            with_max_requests,
+            max_requests
+        );
+    }
 }
@@ -3,6 +3,9 @@ use crate::cloud::set_ssl_config_for_scylla_cloud_host;
 
 use crate::routing::{Shard, ShardCount, Sharder, Token};
 use crate::transport::errors::QueryError;
+use crate::transport::reconnection_policy::{
+    ExponentialBackoffReconnectionPolicy, ReconnectionPolicy, ReconnectionSchedule,
+};
 use crate::transport::{
     connection,
     connection::{Connection, ConnectionConfig, ErrorReceiver, VerifiedKeyspaceName},
@@ -23,6 +26,7 @@ use std::convert::TryInto;
 use std::io::ErrorKind;
 use std::num::NonZeroUsize;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock, Weak};
 use std::time::Duration;
 
@@ -60,6 +64,7 @@ pub(crate) struct PoolConfig {
     pub(crate) pool_size: PoolSize,
     pub(crate) can_use_shard_aware_port: bool,
     pub(crate) keepalive_interval: Option<Duration>,
+    pub(crate) reconnection_policy: Arc<dyn ReconnectionPolicy>,
 }
 
 impl Default for PoolConfig {
@@ -69,6 +74,7 @@ impl Default for PoolConfig {
             pool_size: Default::default(),
             can_use_shard_aware_port: true,
             keepalive_interval: None,
+            reconnection_policy: Arc::new(ExponentialBackoffReconnectionPolicy::default()),
         }
     }
 }
@@ -155,6 +161,7 @@ pub(crate) struct NodeConnectionPool {
     _refiller_handle: Arc<RemoteHandle<()>>,
     pool_updated_notify: Arc<Notify>,
     endpoint: Arc<RwLock<UntranslatedEndpoint>>,
+    target_connection_count: Arc<AtomicUsize>,
 }
 
 impl std::fmt::Debug for NodeConnectionPool {
@@ -211,6 +218,7 @@ impl NodeConnectionPool {
         );
 
         let conns = refiller.get_shared_connections();
+        let target_connection_count = refiller.get_target_connection_count();
         let (fut, refiller_handle) = refiller.run(use_keyspace_request_receiver).remote_handle();
         tokio::spawn(fut.with_current_subscriber());
 
@@ -220,6 +228,7 @@ impl NodeConnectionPool {
             _refiller_handle: Arc::new(refiller_handle),
             pool_updated_notify,
             endpoint: arced_endpoint,
+            target_connection_count,
         }
     }
 
@@ -348,6 +357,24 @@ impl NodeConnectionPool {
         })
     }
 
+    // Returns the number of connections that are currently open and usable.
+    pub(crate) fn live_connection_count(&self) -> usize {
+        match &**self.conns.load() {
+            MaybePoolConnections::Ready(PoolConnections::NotSharded(conns)) => conns.len(),
+            MaybePoolConnections::Ready(PoolConnections::Sharded { connections, .. }) => {
+                connections.iter().map(Vec::len).sum()
+            }
+            MaybePoolConnections::Initializing | MaybePoolConnections::Broken(_) => 0,
+        }
+    }
+
+    // Returns the number of connections the pool aims to keep open.
+    // Until the number of shards of the node is known, the node is assumed
+    // to have only one shard.
+    pub(crate) fn target_connection_count(&self) -> usize {
+        self.target_connection_count.load(Ordering::Relaxed)
+    }
+
     fn choose_random_connection_from_slice(v: &[Arc<Connection>]) -> Option<Arc<Connection>> {
         trace!(
             connections = v
@@ -393,39 +420,6 @@ impl NodeConnectionPool {
 
 const EXCESS_CONNECTION_BOUND_PER_SHARD_MULTIPLIER: usize = 10;
 
-// TODO: Make it configurable through a policy (issue #184)
-const MIN_FILL_BACKOFF: Duration = Duration::from_millis(50);
-const MAX_FILL_BACKOFF: Duration = Duration::from_secs(10);
-const FILL_BACKOFF_MULTIPLIER: u32 = 2;
-
-// A simple exponential strategy for pool fill backoffs.
-struct RefillDelayStrategy {
-    current_delay: Duration,
-}
-
-impl RefillDelayStrategy {
-    fn new() -> Self {
-        Self {
-            current_delay: MIN_FILL_BACKOFF,
-        }
-    }
-
-    fn get_delay(&self) -> Duration {
-        self.current_delay
-    }
-
-    fn on_successful_fill(&mut self) {
-        self.current_delay = MIN_FILL_BACKOFF;
-    }
-
-    fn on_fill_error(&mut self) {
-        self.current_delay = std::cmp::min(
-            MAX_FILL_BACKOFF,
-            self.current_delay * FILL_BACKOFF_MULTIPLIER,
-        );
-    }
-}
-
 struct PoolRefiller {
     // Following information identify the pool and do not change
     pool_config: PoolConfig,
@@ -441,11 +435,15 @@ struct PoolRefiller {
     shared_conns: Arc<ArcSwap<MaybePoolConnections>>,
     conns: Vec<Vec<Arc<Connection>>>,
 
+    // Updated together with `shared_conns`, depends on the pool size
+    // and the number of shards
+    target_connection_count: Arc<AtomicUsize>,
+
     // Set to true if there was an error since the last refill,
     // set to false when refilling starts.
     had_error_since_last_refill: bool,
 
-    refill_delay_strategy: RefillDelayStrategy,
+    reconnection_schedule: Box<dyn ReconnectionSchedule>,
 
     // Receives information about connections becoming ready, i.e. newly connected
     // or after its keyspace was correctly set.
@@ -498,6 +496,10 @@ impl PoolRefiller {
         // and assume that the node is a Cassandra node
         let conns = vec![Vec::new()];
         let shared_conns = Arc::new(ArcSwap::new(Arc::new(MaybePoolConnections::Initializing)));
+        let reconnection_schedule = pool_config.reconnection_policy.new_schedule();
+        let target_connection_count = Arc::new(AtomicUsize::new(
+            Self::compute_target_connection_count(pool_config.pool_size, 1),
+        ));
 
         Self {
             endpoint,
@@ -508,9 +510,10 @@ impl PoolRefiller {
 
             shared_conns,
             conns,
+            target_connection_count,
 
             had_error_since_last_refill: false,
-            refill_delay_strategy: RefillDelayStrategy::new(),
+            reconnection_schedule,
 
             ready_connections: FuturesUnordered::new(),
             connection_errors: FuturesUnordered::new(),
@@ -532,6 +535,17 @@ impl PoolRefiller {
         self.shared_conns.clone()
     }
 
+    pub(crate) fn get_target_connection_count(&self) -> Arc<AtomicUsize> {
+        self.target_connection_count.clone()
+    }
+
+    fn compute_target_connection_count(pool_size: PoolSize, nr_shards: usize) -> usize {
+        match pool_size {
+            PoolSize::PerHost(target) => target.get(),
+            PoolSize::PerShard(target) => target.get() * nr_shards,
+        }
+    }
+
     // The main loop of the pool refiller
     pub(crate) async fn run(
         mut self,
@@ -592,12 +606,10 @@ impl PoolRefiller {
 
             // Schedule refilling here
             if !refill_scheduled && self.need_filling() {
-                if self.had_error_since_last_refill {
-                    self.refill_delay_strategy.on_fill_error();
-                } else {
-                    self.refill_delay_strategy.on_successful_fill();
+                if !self.had_error_since_last_refill {
+                    self.reconnection_schedule.reset();
                 }
-                let delay = self.refill_delay_strategy.get_delay();
+                let delay = self.reconnection_schedule.next_delay();
                 debug!(
                     "[{}] Scheduling next refill in {} ms",
                     self.endpoint_description(),
@@ -1005,6 +1017,10 @@ impl PoolRefiller {
 
         // Make the connection list available
         self.shared_conns.store(new_conns);
+        self.target_connection_count.store(
+            Self::compute_target_connection_count(self.pool_config.pool_size, self.conns.len()),
+            Ordering::Relaxed,
+        );
 
         // Notify potential waiters
         self.pool_updated_notify.notify_waiters();
@@ -1257,12 +1273,20 @@ async fn open_connection_to_shard_aware_port(
 
 #[cfg(test)]
 mod tests {
-    use super::open_connection_to_shard_aware_port;
+    use super::{open_connection_to_shard_aware_port, NodeConnectionPool, PoolConfig, PoolSize};
     use crate::routing::{ShardCount, Sharder};
     use crate::transport::connection::ConnectionConfig;
     use crate::transport::node::ResolvedContactPoint;
+    use crate::transport::reconnection_policy::{ReconnectionPolicy, ReconnectionSchedule};
     use crate::transport::topology::UntranslatedEndpoint;
     use std::net::{SocketAddr, ToSocketAddrs};
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::broadcast;
+    use tokio::time::Instant;
 
     // Open many connections to a node
     // Port collision should occur
@@ -1314,4 +1338,94 @@ mod tests {
             res.unwrap();
         }
     }
+
+    // Returns a constant delay and counts how it is used by the pool
+    #[derive(Debug, Default)]
+    struct CountingReconnectionPolicy {
+        delay: Duration,
+        next_delay_calls: Arc<AtomicUsize>,
+        reset_calls: Arc<AtomicUsize>,
+    }
+
+    struct CountingReconnectionSchedule {
+        delay: Duration,
+        next_delay_calls: Arc<AtomicUsize>,
+        reset_calls: Arc<AtomicUsize>,
+    }
+
+    impl ReconnectionPolicy for CountingReconnectionPolicy {
+        fn new_schedule(&self) -> Box<dyn ReconnectionSchedule> {
+            Box::new(CountingReconnectionSchedule {
+                delay: self.delay,
+                next_delay_calls: self.next_delay_calls.clone(),
+                reset_calls: self.reset_calls.clone(),
+            })
+        }
+    }
+
+    impl ReconnectionSchedule for CountingReconnectionSchedule {
+        fn next_delay(&mut self) -> Duration {
+            self.next_delay_calls.fetch_add(1, Ordering::Relaxed);
+            self.delay
+        }
+
+        fn reset(&mut self) {
+            self.reset_calls.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[tokio::test]
+    #[ntest::timeout(20000)]
+    async fn pool_refills_according_to_reconnection_policy() {
+        const DELAY: Duration = Duration::from_millis(200);
+        const ATTEMPTS: usize = 4;
+
+        // A fake node which closes every connection right away,
+        // so that all attempts to fill the pool fail.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let attempt_times = Arc::new(Mutex::new(Vec::new()));
+        let attempt_times_clone = attempt_times.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                attempt_times_clone.lock().unwrap().push(Instant::now());
+                drop(stream);
+            }
+        });
+
+        let policy = Arc::new(CountingReconnectionPolicy {
+            delay: DELAY,
+            ..Default::default()
+        });
+        let pool_config = PoolConfig {
+            pool_size: PoolSize::PerHost(NonZeroUsize::new(1).unwrap()),
+            reconnection_policy: policy.clone(),
+            ..Default::default()
+        };
+        let (pool_empty_notifier, _) = broadcast::channel(1);
+        let _pool = NodeConnectionPool::new(
+            UntranslatedEndpoint::ContactPoint(ResolvedContactPoint {
+                address: addr,
+                datacenter: None,
+            }),
+            pool_config,
+            None,
+            pool_empty_notifier,
+        );
+
+        while attempt_times.lock().unwrap().len() < ATTEMPTS {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Each failed fill asks the schedule for the delay before the next one,
+        // and the schedule is never reset, as no fill succeeded.
+        assert!(policy.next_delay_calls.load(Ordering::Relaxed) >= ATTEMPTS - 1);
+        assert_eq!(policy.reset_calls.load(Ordering::Relaxed), 0);
+
+        let attempt_times = attempt_times.lock().unwrap();
+        for (prev, next) in attempt_times.iter().zip(attempt_times.iter().skip(1)) {
+            assert!(*next - *prev >= DELAY);
+        }
+    }
 }
@@ -75,6 +75,14 @@ async fn test_cql_list() {
     insert_and_select(&session, table_name, &list2, &list2).await;
     insert_and_select(&session, table_name, &list_empty, &list_empty_selected).await;
 
+    // Array
+    let list_array: [i32; 4] = [4, 3, 2, 1];
+    insert_and_select(&session, table_name, &list_array, &list_array.to_vec()).await;
+
+    // Slice
+    let list_slice: &[i32] = &[7, -7];
+    insert_and_select(&session, table_name, &list_slice, &list_slice.to_vec()).await;
+
     // CqlValue
     let list_cql_value: CqlValue =
         CqlValue::List(vec![CqlValue::Int(-1), CqlValue::Int(1), CqlValue::Int(0)]);
@@ -91,6 +99,22 @@ async fn test_cql_list() {
     .await;
 }
 
+#[tokio::test]
+async fn test_cql_list_of_text() {
+    let session: Session = connect().await;
+
+    let table_name: &str = "test_cql_list_of_text_tab";
+    create_table(&session, table_name, "list<text>").await;
+
+    let list_slice: &[&str] = &["ala", "ma", "kota"];
+    let list_expected: Vec<String> = list_slice.iter().map(|s| s.to_string()).collect();
+    insert_and_select(&session, table_name, &list_slice, &list_expected).await;
+
+    let list_array: [&str; 2] = ["scylla", "rust"];
+    let list_expected: Vec<String> = list_array.iter().map(|s| s.to_string()).collect();
+    insert_and_select(&session, table_name, &list_array, &list_expected).await;
+}
+
 #[tokio::test]
 async fn test_cql_set() {
     let session: Session = connect().await;
@@ -154,6 +178,26 @@ async fn test_cql_set() {
     .await;
 }
 
+#[tokio::test]
+async fn test_cql_text_set() {
+    let session: Session = connect().await;
+
+    let table_name: &str = "test_cql_text_set_tab";
+    create_table(&session, table_name, "set<text>").await;
+
+    // BTreeSet
+    let set_btreeset: BTreeSet<String> = vec!["zeta", "alpha", "", "beta"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    insert_and_select(&session, table_name, &set_btreeset, &set_btreeset).await;
+
+    // HashSet
+    let set_hashset: HashSet<String> = set_btreeset.iter().cloned().collect();
+    insert_and_select(&session, table_name, &set_hashset, &set_hashset).await;
+    insert_and_select(&session, table_name, &set_hashset, &set_btreeset).await;
+}
+
 #[tokio::test]
 async fn test_cql_map() {
     let session: Session = connect().await;
@@ -204,6 +248,118 @@ async fn test_cql_map() {
     .await;
 }
 
+#[tokio::test]
+async fn test_cql_text_map() {
+    let session: Session = connect().await;
+
+    let table_name: &str = "test_cql_text_map_tab";
+    create_table(&session, table_name, "map<text, int>").await;
+
+    // HashMap
+    let map_hashmap: HashMap<String, i32> = vec![("a", 1), ("b", -2), ("", 0)]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    insert_and_select(&session, table_name, &map_hashmap, &map_hashmap).await;
+
+    // BTreeMap
+    let map_btreemap: BTreeMap<String, i32> = map_hashmap.clone().into_iter().collect();
+    insert_and_select(&session, table_name, &map_btreemap, &map_btreemap).await;
+    insert_and_select(&session, table_name, &map_btreemap, &map_hashmap).await;
+}
+
+#[tokio::test]
+async fn test_cql_nested_map() {
+    let session: Session = connect().await;
+
+    let table_name: &str = "test_cql_nested_map_tab";
+    create_table(&session, table_name, "map<int, frozen<list<text>>>").await;
+
+    let map_btreemap: BTreeMap<i32, Vec<String>> = vec![
+        (1, vec!["x".to_string(), "y".to_string()]),
+        (2, vec!["z".to_string()]),
+    ]
+    .into_iter()
+    .collect();
+    insert_and_select(&session, table_name, &map_btreemap, &map_btreemap).await;
+
+    let map_hashmap: HashMap<i32, Vec<String>> = map_btreemap.clone().into_iter().collect();
+    insert_and_select(&session, table_name, &map_hashmap, &map_hashmap).await;
+}
+
+#[tokio::test]
+async fn test_cql_two_level_nested_collections() {
+    let session: Session = connect().await;
+
+    let map_of_lists: HashMap<String, Vec<i32>> = vec![
+        ("a".to_string(), vec![1, 2, 3]),
+        ("b".to_string(), vec![]),
+        ("c".to_string(), vec![-1, -1]),
+    ]
+    .into_iter()
+    .collect();
+
+    for (table_name, value_type) in [
+        ("test_cql_map_of_lists_tab", "map<text, frozen<list<int>>>"),
+        (
+            "test_cql_frozen_map_of_lists_tab",
+            "frozen<map<text, frozen<list<int>>>>",
+        ),
+    ] {
+        create_table(&session, table_name, value_type).await;
+        insert_and_select(&session, table_name, &map_of_lists, &map_of_lists).await;
+    }
+
+    let table_name: &str = "test_cql_list_of_sets_tab";
+    create_table(&session, table_name, "list<frozen<set<text>>>").await;
+    let list_of_sets: Vec<BTreeSet<String>> = vec![
+        vec!["x".to_string(), "y".to_string()].into_iter().collect(),
+        vec!["x".to_string()].into_iter().collect(),
+    ];
+    insert_and_select(&session, table_name, &list_of_sets, &list_of_sets).await;
+}
+
+#[tokio::test]
+async fn test_cql_three_level_nested_collections() {
+    let session: Session = connect().await;
+
+    let inner: BTreeMap<String, Vec<i64>> = vec![
+        ("one".to_string(), vec![1, 11]),
+        ("two".to_string(), vec![2]),
+        ("empty".to_string(), vec![]),
+    ]
+    .into_iter()
+    .collect();
+    let map_of_maps: BTreeMap<i32, BTreeMap<String, Vec<i64>>> =
+        vec![(1, inner.clone()), (2, inner.into_iter().take(1).collect())]
+            .into_iter()
+            .collect();
+
+    for (table_name, value_type) in [
+        (
+            "test_cql_map_of_maps_tab",
+            "map<int, frozen<map<text, frozen<list<bigint>>>>>",
+        ),
+        (
+            "test_cql_frozen_map_of_maps_tab",
+            "frozen<map<int, frozen<map<text, frozen<list<bigint>>>>>>",
+        ),
+    ] {
+        create_table(&session, table_name, value_type).await;
+        insert_and_select(&session, table_name, &map_of_maps, &map_of_maps).await;
+    }
+
+    let table_name: &str = "test_cql_nested_lists_tab";
+    create_table(
+        &session,
+        table_name,
+        "list<frozen<list<frozen<list<int>>>>>",
+    )
+    .await;
+    let nested_lists: Vec<Vec<Vec<i32>>> = vec![vec![vec![1, 2], vec![]], vec![vec![3]]];
+    insert_and_select(&session, table_name, &nested_lists, &nested_lists).await;
+}
+
 #[tokio::test]
 async fn test_cql_tuple() {
     let session: Session = connect().await;
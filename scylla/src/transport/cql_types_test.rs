@@ -1,18 +1,23 @@
 use crate as scylla;
-use crate::cql_to_rust::FromCqlVal;
+use crate::cql_to_rust::{FromCqlVal, FromCqlValError, FromRowError};
 use crate::frame::response::result::CqlValue;
 use crate::frame::value::Counter;
+use crate::frame::value::CqlDuration;
 use crate::frame::value::Value;
-use crate::frame::value::{Date, Time, Timestamp};
+use crate::frame::value::{CqlBlob, CqlTimeuuid, Date, MaybeUnset, Time, Timestamp};
 use crate::macros::{FromUserType, IntoUserType};
 use crate::test_utils::create_new_session_builder;
+use crate::transport::errors::{BadQuery, QueryError};
+use crate::transport::query_result::SingleRowTypedError;
 use crate::transport::session::IntoTypedRows;
 use crate::transport::session::Session;
 use crate::utils::test_utils::unique_keyspace_name;
+use assert_matches::assert_matches;
 use bigdecimal::BigDecimal;
-use chrono::{Duration, NaiveDate};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
 use num_bigint::BigInt;
 use std::cmp::PartialEq;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
@@ -104,6 +109,24 @@ where
     }
 }
 
+#[tokio::test]
+async fn test_tinyint() {
+    let min = i8::MIN.to_string();
+    let max = i8::MAX.to_string();
+    let tests = ["0", "1", "-1", "42", min.as_str(), max.as_str()];
+
+    run_tests::<i8>(&tests, "tinyint").await;
+}
+
+#[tokio::test]
+async fn test_smallint() {
+    let min = i16::MIN.to_string();
+    let max = i16::MAX.to_string();
+    let tests = ["0", "1", "-1", "1234", min.as_str(), max.as_str()];
+
+    run_tests::<i16>(&tests, "smallint").await;
+}
+
 #[tokio::test]
 async fn test_varint() {
     let tests = [
@@ -136,6 +159,50 @@ async fn test_decimal() {
     run_tests::<BigDecimal>(&tests, "decimal").await;
 }
 
+#[cfg(feature = "rust_decimal")]
+#[tokio::test]
+async fn test_rust_decimal() {
+    use rust_decimal::Decimal;
+
+    let tests = [
+        "4.2",
+        "0",
+        "1.9999999999999999999999999999",
+        "997",
+        "12345678901234567890.123456789",
+        "-12345678901234567890.123456789",
+    ];
+
+    run_tests::<Decimal>(&tests, "decimal").await;
+
+    // Values which don't fit in a Decimal can't be read as one
+    let session: Session = init_test("rust_decimal_tests", "decimal").await;
+    let too_large = [
+        "1.999999999999999999999999999999999999999",
+        "123456789012345678901234567890.1234567890",
+        "-123456789012345678901234567890.1234567890",
+    ];
+    for test in too_large {
+        session
+            .query(
+                format!(
+                    "INSERT INTO rust_decimal_tests (id, val) VALUES (0, {})",
+                    test
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let result = session
+            .query("SELECT val FROM rust_decimal_tests WHERE id = 0", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(Decimal,)>();
+        assert!(result.is_err(), "{} was read as {:?}", test, result);
+    }
+}
+
 #[tokio::test]
 async fn test_bool() {
     let tests = ["true", "false"];
@@ -143,6 +210,64 @@ async fn test_bool() {
     run_tests::<bool>(&tests, "boolean").await;
 }
 
+#[tokio::test]
+async fn test_ascii_and_text() {
+    let session: Session = init_test("ascii_and_text_tests", "ascii").await;
+    session
+        .query("ALTER TABLE ascii_and_text_tests ADD txt text", &[])
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+
+    let insert = session
+        .prepare("INSERT INTO ascii_and_text_tests (id, val, txt) VALUES (?, ?, ?)")
+        .await
+        .unwrap();
+    session
+        .execute(&insert, (0, "ascii", "zażółć gęślą jaźń"))
+        .await
+        .unwrap();
+
+    let (val, txt): (String, String) = session
+        .query(
+            "SELECT val, txt FROM ascii_and_text_tests WHERE id = 0",
+            &[],
+        )
+        .await
+        .unwrap()
+        .single_row_typed()
+        .unwrap();
+    assert_eq!(val, "ascii");
+    assert_eq!(txt, "zażółć gęślą jaźń");
+
+    // Named values are checked against the markers with the same names,
+    // regardless of the order they are given in (a BTreeMap yields "txt" before "val")
+    let named_values = BTreeMap::from([("val", "ascii"), ("txt", "zażółć gęślą jaźń")]);
+    session
+        .query(
+            "UPDATE ascii_and_text_tests SET val = :val, txt = :txt WHERE id = 0",
+            &named_values,
+        )
+        .await
+        .unwrap();
+
+    // Non-ASCII values bound to an ascii column are rejected before reaching the database
+    for result in [
+        session.execute(&insert, (1, "zażółć", "text")).await,
+        session
+            .query(
+                "INSERT INTO ascii_and_text_tests (id, val) VALUES (?, ?)",
+                (1, "zażółć"),
+            )
+            .await,
+    ] {
+        assert_matches!(
+            result,
+            Err(QueryError::BadQuery(BadQuery::NonAsciiValue(name))) if name == "val"
+        );
+    }
+}
+
 #[tokio::test]
 async fn test_float() {
     let max = f32::MAX.to_string();
@@ -194,6 +319,46 @@ async fn test_counter() {
     }
 }
 
+#[tokio::test]
+async fn test_counter_increment_decrement() {
+    let session: Session = init_test("counter_update_tests", "counter").await;
+
+    let increment = "UPDATE counter_update_tests SET val = val + ? WHERE id = 0";
+    let decrement = "UPDATE counter_update_tests SET val = val - ? WHERE id = 0";
+    let select = || async {
+        session
+            .query("SELECT val FROM counter_update_tests WHERE id = 0", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(Counter,)>()
+            .unwrap()
+            .0
+    };
+
+    session.query(increment, (Counter(10),)).await.unwrap();
+    assert_eq!(select().await, Counter(10));
+
+    session.query(decrement, (Counter(3),)).await.unwrap();
+    assert_eq!(select().await, Counter(7));
+
+    // Updates can be accumulated locally before sending them
+    let mut delta = Counter(0);
+    for _ in 0..5 {
+        delta += Counter(2);
+    }
+    delta -= Counter(1);
+    session.query(increment, (delta,)).await.unwrap();
+    assert_eq!(select().await, Counter(16));
+
+    // A non-counter column can't be read as a Counter
+    assert!(session
+        .query("SELECT id FROM counter_update_tests WHERE id = 0", &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(Counter,)>()
+        .is_err());
+}
+
 #[tokio::test]
 async fn test_naive_date() {
     let session: Session = init_test("naive_date", "date").await;
@@ -314,6 +479,231 @@ async fn test_naive_date() {
         .unwrap_err();
 }
 
+#[cfg(feature = "time")]
+#[tokio::test]
+async fn test_time_crate_date() {
+    use time::{Date, Month};
+
+    let session: Session = init_test("time_crate_date", "date").await;
+
+    let tests = [
+        // Basic test values
+        (
+            "0000-01-01",
+            Some(Date::from_calendar_date(0, Month::January, 1).unwrap()),
+        ),
+        (
+            "1970-01-01",
+            Some(Date::from_calendar_date(1970, Month::January, 1).unwrap()),
+        ),
+        (
+            "2020-03-07",
+            Some(Date::from_calendar_date(2020, Month::March, 7).unwrap()),
+        ),
+        (
+            "1337-04-05",
+            Some(Date::from_calendar_date(1337, Month::April, 5).unwrap()),
+        ),
+        (
+            "-0001-12-31",
+            Some(Date::from_calendar_date(-1, Month::December, 31).unwrap()),
+        ),
+        // min/max values allowed by Date
+        ("-9999-01-01", Some(Date::MIN)),
+        ("9999-12-31", Some(Date::MAX)),
+        // 1 less/more than min/max values allowed by Date
+        ("-10000-12-31", None),
+        ("10000-01-01", None),
+        // min value allowed by the database
+        ("-5877641-06-23", None),
+    ];
+
+    for (date_text, date) in tests.iter() {
+        session
+            .query(
+                format!(
+                    "INSERT INTO time_crate_date (id, val) VALUES (0, '{}')",
+                    date_text
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let read_date: Option<Date> = session
+            .query("SELECT val from time_crate_date", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(Date,)>()
+            .ok()
+            .map(|row| row.0);
+
+        assert_eq!(read_date, *date);
+
+        // If date is representable by Date try inserting it and reading again
+        if let Some(date) = date {
+            session
+                .query(
+                    "INSERT INTO time_crate_date (id, val) VALUES (0, ?)",
+                    (date,),
+                )
+                .await
+                .unwrap();
+
+            let (read_date,): (Date,) = session
+                .query("SELECT val from time_crate_date", &[])
+                .await
+                .unwrap()
+                .single_row_typed::<(Date,)>()
+                .unwrap();
+            assert_eq!(read_date, *date);
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+#[tokio::test]
+async fn test_time_crate_time() {
+    use time::Time;
+
+    let session: Session = init_test("time_crate_time", "time").await;
+
+    let tests = [
+        ("00:00:00", Time::MIDNIGHT),
+        ("01:01:01", Time::from_hms(1, 1, 1).unwrap()),
+        (
+            "13:37:42.123456789",
+            Time::from_hms_nano(13, 37, 42, 123_456_789).unwrap(),
+        ),
+        (
+            "23:59:59.999999999",
+            Time::from_hms_nano(23, 59, 59, 999_999_999).unwrap(),
+        ),
+    ];
+
+    for (time_text, time) in tests.iter() {
+        session
+            .query(
+                format!(
+                    "INSERT INTO time_crate_time (id, val) VALUES (0, '{}')",
+                    time_text
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let (read_time,): (Time,) = session
+            .query("SELECT val from time_crate_time", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(Time,)>()
+            .unwrap();
+        assert_eq!(read_time, *time);
+
+        session
+            .query(
+                "INSERT INTO time_crate_time (id, val) VALUES (0, ?)",
+                (time,),
+            )
+            .await
+            .unwrap();
+
+        let (read_time,): (Time,) = session
+            .query("SELECT val from time_crate_time", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(Time,)>()
+            .unwrap();
+        assert_eq!(read_time, *time);
+    }
+}
+
+#[cfg(feature = "time")]
+#[tokio::test]
+async fn test_time_crate_timestamp() {
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+    let session: Session = init_test("time_crate_timestamp", "timestamp").await;
+
+    let datetime = |year, month, day, hour, minute, second, milli| {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(year, month, day).unwrap(),
+            Time::from_hms_milli(hour, minute, second, milli).unwrap(),
+        )
+    };
+    let tests = [
+        (
+            "1970-01-01T00:00:00Z",
+            datetime(1970, Month::January, 1, 0, 0, 0, 0),
+        ),
+        (
+            "1969-12-31T23:59:59.999Z",
+            datetime(1969, Month::December, 31, 23, 59, 59, 999),
+        ),
+        (
+            "2022-12-30T23:59:59.123Z",
+            datetime(2022, Month::December, 30, 23, 59, 59, 123),
+        ),
+        (
+            "1337-04-05T12:00:00Z",
+            datetime(1337, Month::April, 5, 12, 0, 0, 0),
+        ),
+    ];
+
+    for (datetime_text, datetime) in tests.iter() {
+        session
+            .query(
+                format!(
+                    "INSERT INTO time_crate_timestamp (id, val) VALUES (0, '{}')",
+                    datetime_text
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let (read_datetime,): (PrimitiveDateTime,) = session
+            .query("SELECT val from time_crate_timestamp", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(PrimitiveDateTime,)>()
+            .unwrap();
+        assert_eq!(read_datetime, *datetime);
+
+        // Both types can be bound and read
+        session
+            .query(
+                "INSERT INTO time_crate_timestamp (id, val) VALUES (0, ?)",
+                (datetime,),
+            )
+            .await
+            .unwrap();
+        let (read_datetime,): (OffsetDateTime,) = session
+            .query("SELECT val from time_crate_timestamp", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(OffsetDateTime,)>()
+            .unwrap();
+        assert_eq!(read_datetime, datetime.assume_utc());
+
+        session
+            .query(
+                "INSERT INTO time_crate_timestamp (id, val) VALUES (0, ?)",
+                (read_datetime,),
+            )
+            .await
+            .unwrap();
+        let (read_datetime,): (PrimitiveDateTime,) = session
+            .query("SELECT val from time_crate_timestamp", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(PrimitiveDateTime,)>()
+            .unwrap();
+        assert_eq!(read_datetime, *datetime);
+    }
+}
+
 #[tokio::test]
 async fn test_date() {
     // Tests value::Date which allows to insert dates outside NaiveDate range
@@ -452,6 +842,73 @@ async fn test_time() {
     }
 }
 
+#[tokio::test]
+async fn test_naive_time() {
+    let session: Session = init_test("naive_time_tests", "time").await;
+
+    let tests = [
+        ("00:00:00", NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+        ("01:01:01", NaiveTime::from_hms_opt(1, 1, 1).unwrap()),
+        (
+            "00:00:00.000000001",
+            NaiveTime::from_hms_nano_opt(0, 0, 0, 1).unwrap(),
+        ),
+        (
+            "23:59:59.999999999",
+            NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+        ),
+    ];
+
+    for (time_str, naive_time) in &tests {
+        // Insert time as a string and verify that it matches
+        session
+            .query(
+                format!(
+                    "INSERT INTO naive_time_tests (id, val) VALUES (0, '{}')",
+                    time_str
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let (read_time,): (NaiveTime,) = session
+            .query("SELECT val from naive_time_tests", &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(NaiveTime,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_time, *naive_time);
+
+        // Insert time as a bound NaiveTime value and verify that it matches
+        session
+            .query(
+                "INSERT INTO naive_time_tests (id, val) VALUES (0, ?)",
+                (naive_time,),
+            )
+            .await
+            .unwrap();
+
+        let (read_time,): (NaiveTime,) = session
+            .query("SELECT val from naive_time_tests", &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(NaiveTime,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_time, *naive_time);
+    }
+}
+
 #[tokio::test]
 async fn test_timestamp() {
     let session: Session = init_test("timestamp_tests", "timestamp").await;
@@ -481,53 +938,242 @@ async fn test_timestamp() {
         //("2011-02-03T04:05:00.000+0000", Duration::milliseconds(1299038700000)),
     ];
 
-    for (timestamp_str, timestamp_duration) in &tests {
-        // Insert timestamp as a string and verify that it matches
+    for (timestamp_str, timestamp_duration) in &tests {
+        // Insert timestamp as a string and verify that it matches
+        session
+            .query(
+                format!(
+                    "INSERT INTO timestamp_tests (id, val) VALUES (0, '{}')",
+                    timestamp_str
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let (read_timestamp,): (Duration,) = session
+            .query("SELECT val from timestamp_tests", &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(Duration,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_timestamp, *timestamp_duration);
+
+        // Insert timestamp as a bound Timestamp value and verify that it matches
+        session
+            .query(
+                "INSERT INTO timestamp_tests (id, val) VALUES (0, ?)",
+                (Timestamp(*timestamp_duration),),
+            )
+            .await
+            .unwrap();
+
+        let (read_timestamp,): (Duration,) = session
+            .query("SELECT val from timestamp_tests", &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(Duration,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_timestamp, *timestamp_duration);
+    }
+}
+
+#[tokio::test]
+async fn test_datetime_utc() {
+    let session: Session = init_test("datetime_utc_tests", "timestamp").await;
+
+    let tests = [
+        ("0", Utc.timestamp_millis_opt(0).unwrap()),
+        ("1000", Utc.timestamp_millis_opt(1000).unwrap()),
+        ("-1", Utc.timestamp_millis_opt(-1).unwrap()),
+        (
+            "1690909211123",
+            Utc.with_ymd_and_hms(2023, 8, 1, 17, 0, 11).unwrap() + Duration::milliseconds(123),
+        ),
+        (
+            "-14182940000",
+            Utc.with_ymd_and_hms(1969, 7, 20, 20, 17, 40).unwrap(),
+        ),
+    ];
+
+    for (timestamp_str, datetime) in &tests {
+        // Insert timestamp as a string and verify that it matches
+        session
+            .query(
+                format!(
+                    "INSERT INTO datetime_utc_tests (id, val) VALUES (0, {})",
+                    timestamp_str
+                ),
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let (read_datetime,): (DateTime<Utc>,) = session
+            .query("SELECT val from datetime_utc_tests", &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(DateTime<Utc>,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_datetime, *datetime);
+
+        // Insert timestamp as a bound DateTime<Utc> value and verify that it matches
+        session
+            .query(
+                "INSERT INTO datetime_utc_tests (id, val) VALUES (0, ?)",
+                (datetime,),
+            )
+            .await
+            .unwrap();
+
+        let (read_datetime,): (DateTime<Utc>,) = session
+            .query("SELECT val from datetime_utc_tests", &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(DateTime<Utc>,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_datetime, *datetime);
+    }
+
+    // Sub-millisecond precision is truncated when sending a DateTime<Utc>
+    let precise_datetime = Utc.timestamp_nanos(1_999_999);
+    session
+        .query(
+            "INSERT INTO datetime_utc_tests (id, val) VALUES (0, ?)",
+            (precise_datetime,),
+        )
+        .await
+        .unwrap();
+
+    let (read_datetime,): (DateTime<Utc>,) = session
+        .query("SELECT val from datetime_utc_tests", &[])
+        .await
+        .unwrap()
+        .rows
+        .unwrap()
+        .into_typed::<(DateTime<Utc>,)>()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(read_datetime, Utc.timestamp_millis_opt(1).unwrap());
+}
+
+#[tokio::test]
+async fn test_cql_duration() {
+    let session: Session = init_test("duration_tests", "duration").await;
+
+    // Scylla requires all the fields of a duration to have the same sign
+    let tests = [
+        (
+            "1mo2d3ns",
+            CqlDuration {
+                months: 1,
+                days: 2,
+                nanoseconds: 3,
+            },
+        ),
+        (
+            "-1mo2d3ns",
+            CqlDuration {
+                months: -1,
+                days: -2,
+                nanoseconds: -3,
+            },
+        ),
+        (
+            "1y1h",
+            CqlDuration {
+                months: 12,
+                days: 0,
+                nanoseconds: 3_600_000_000_000,
+            },
+        ),
+        (
+            "-3w",
+            CqlDuration {
+                months: 0,
+                days: -21,
+                nanoseconds: 0,
+            },
+        ),
+        (
+            "0s",
+            CqlDuration {
+                months: 0,
+                days: 0,
+                nanoseconds: 0,
+            },
+        ),
+    ];
+
+    for (duration_str, duration) in &tests {
+        // Insert duration as a string and verify that it matches
         session
             .query(
                 format!(
-                    "INSERT INTO timestamp_tests (id, val) VALUES (0, '{}')",
-                    timestamp_str
+                    "INSERT INTO duration_tests (id, val) VALUES (0, {})",
+                    duration_str
                 ),
                 &[],
             )
             .await
             .unwrap();
 
-        let (read_timestamp,): (Duration,) = session
-            .query("SELECT val from timestamp_tests", &[])
+        let (read_duration,): (CqlDuration,) = session
+            .query("SELECT val from duration_tests", &[])
             .await
             .unwrap()
             .rows
             .unwrap()
-            .into_typed::<(Duration,)>()
+            .into_typed::<(CqlDuration,)>()
             .next()
             .unwrap()
             .unwrap();
 
-        assert_eq!(read_timestamp, *timestamp_duration);
+        assert_eq!(read_duration, *duration);
 
-        // Insert timestamp as a bound Timestamp value and verify that it matches
+        // Insert duration as a bound CqlDuration value and verify that it matches
         session
             .query(
-                "INSERT INTO timestamp_tests (id, val) VALUES (0, ?)",
-                (Timestamp(*timestamp_duration),),
+                "INSERT INTO duration_tests (id, val) VALUES (0, ?)",
+                (duration,),
             )
             .await
             .unwrap();
 
-        let (read_timestamp,): (Duration,) = session
-            .query("SELECT val from timestamp_tests", &[])
+        let (read_duration,): (CqlDuration,) = session
+            .query("SELECT val from duration_tests", &[])
             .await
             .unwrap()
             .rows
             .unwrap()
-            .into_typed::<(Duration,)>()
+            .into_typed::<(CqlDuration,)>()
             .next()
             .unwrap()
             .unwrap();
 
-        assert_eq!(read_timestamp, *timestamp_duration);
+        assert_eq!(read_duration, *duration);
     }
 }
 
@@ -606,6 +1252,80 @@ async fn test_timeuuid() {
 
         assert_eq!(read_timeuuid.as_bytes(), timeuuid_bytes);
     }
+
+    // Timeuuids are distinguishable from uuids when read as CqlValue
+    let (read_value,): (CqlValue,) = session
+        .query("SELECT val from timeuuid_tests", &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(CqlValue,)>()
+        .unwrap();
+    assert_eq!(read_value, CqlValue::Timeuuid(Uuid::from_bytes(tests[2].1)));
+}
+
+#[tokio::test]
+async fn test_generated_timeuuid_ordering() {
+    let session: Session = init_test("timeuuid_generation_tests", "int").await;
+    session
+        .query(
+            "CREATE TABLE IF NOT EXISTS timeuuid_ordering_tests (p int, c timeuuid, PRIMARY KEY (p, c))",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let timeuuids: Vec<CqlTimeuuid> = (0..100).map(|_| CqlTimeuuid::now()).collect();
+
+    let prepared = session
+        .prepare("INSERT INTO timeuuid_ordering_tests (p, c) VALUES (0, ?)")
+        .await
+        .unwrap();
+    // Insert in the reversed order, so that the database has to order them
+    for timeuuid in timeuuids.iter().rev() {
+        session.execute(&prepared, (timeuuid,)).await.unwrap();
+    }
+
+    let read_timeuuids: Vec<CqlTimeuuid> = session
+        .query("SELECT c FROM timeuuid_ordering_tests WHERE p = 0", &[])
+        .await
+        .unwrap()
+        .rows_typed::<(CqlTimeuuid,)>()
+        .unwrap()
+        .map(|row| row.unwrap().0)
+        .collect();
+
+    // The database orders the timeuuids in the order of generation
+    assert_eq!(read_timeuuids, timeuuids);
+}
+
+#[tokio::test]
+async fn test_uuid() {
+    run_tests::<Uuid>(
+        &[
+            "00000000-0000-0000-0000-000000000000",
+            "8e14e760-7fa8-11eb-bc66-000000000001",
+            "f47ac10b-58cc-4372-a567-0e02b2c3d479",
+            "ffffffff-ffff-ffff-ffff-ffffffffffff",
+        ],
+        "uuid",
+    )
+    .await;
+
+    let session: Session = init_test("uuid_tests", "uuid").await;
+    let uuid = Uuid::parse_str("f47ac10b-58cc-4372-a567-0e02b2c3d479").unwrap();
+    session
+        .query("INSERT INTO uuid_tests (id, val) VALUES (0, ?)", (uuid,))
+        .await
+        .unwrap();
+
+    let (read_value,): (CqlValue,) = session
+        .query("SELECT val from uuid_tests", &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(CqlValue,)>()
+        .unwrap();
+    assert_eq!(read_value, CqlValue::Uuid(uuid));
+    assert_eq!(read_value.as_timeuuid(), None);
 }
 
 #[tokio::test]
@@ -769,6 +1489,40 @@ async fn test_blob() {
     }
 }
 
+#[tokio::test]
+async fn test_cql_blob() {
+    let session: Session = init_test("cql_blob_tests", "blob").await;
+
+    // Arbitrary bytes, including embedded nulls
+    let tests = [
+        vec![],
+        vec![0x00],
+        vec![0x00, 0x00, 0x00],
+        vec![0x11, 0x00, 0x22, 0x00],
+        (0..=255).collect::<Vec<u8>>(),
+    ];
+
+    for bytes in tests {
+        let blob = CqlBlob(bytes);
+        session
+            .query(
+                "INSERT INTO cql_blob_tests (id, val) VALUES (0, ?)",
+                (&blob,),
+            )
+            .await
+            .unwrap();
+
+        let (read_blob,): (CqlBlob,) = session
+            .query("SELECT val from cql_blob_tests WHERE id = 0", &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(CqlBlob,)>()
+            .unwrap();
+
+        assert_eq!(read_blob, blob);
+    }
+}
+
 #[tokio::test]
 async fn test_udt_after_schema_update() {
     let table_name = "udt_tests";
@@ -911,6 +1665,125 @@ async fn test_udt_after_schema_update() {
     );
 }
 
+#[tokio::test]
+async fn test_udt_address() {
+    let table_name = "udt_address_tests";
+    let type_name = "address";
+
+    let session: Session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session
+        .query(
+            format!(
+                "CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = \
+            {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+
+    session
+        .query(
+            format!(
+                "CREATE TYPE IF NOT EXISTS {} (street text, zip int, note text)",
+                type_name
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (id int PRIMARY KEY, val frozen<{}>)",
+                table_name, type_name
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    #[derive(IntoUserType, FromUserType, Debug, PartialEq)]
+    struct Address {
+        street: String,
+        zip: i32,
+        note: Option<String>,
+    }
+
+    let tests = [
+        Address {
+            street: "Main St".to_string(),
+            zip: 12345,
+            note: Some("ring twice".to_string()),
+        },
+        Address {
+            street: "".to_string(),
+            zip: -1,
+            note: None,
+        },
+    ];
+
+    for address in &tests {
+        session
+            .query(
+                format!("INSERT INTO {} (id, val) VALUES (0, ?)", table_name),
+                (address,),
+            )
+            .await
+            .unwrap();
+
+        let (read_address,): (Address,) = session
+            .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
+            .await
+            .unwrap()
+            .rows
+            .unwrap()
+            .into_typed::<(Address,)>()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(read_address, *address);
+    }
+
+    // Field not set in the inserted value is read as None
+    session
+        .query(
+            format!(
+                "INSERT INTO {} (id, val) VALUES (0, {{street: 'Elm St', zip: 54321}})",
+                table_name
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let (read_address,): (Address,) = session
+        .query(format!("SELECT val from {} WHERE id = 0", table_name), &[])
+        .await
+        .unwrap()
+        .rows
+        .unwrap()
+        .into_typed::<(Address,)>()
+        .next()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        read_address,
+        Address {
+            street: "Elm St".to_string(),
+            zip: 54321,
+            note: None,
+        }
+    );
+}
+
 #[tokio::test]
 async fn test_empty() {
     let session: Session = init_test("empty_tests", "int").await;
@@ -949,3 +1822,196 @@ async fn test_empty() {
 
     assert_eq!(empty, CqlValue::Empty);
 }
+
+#[tokio::test]
+async fn test_empty_text_and_blob() {
+    let session: Session = init_test("empty_text_blob_tests", "text").await;
+    session
+        .query("ALTER TABLE empty_text_blob_tests ADD b blob", &[])
+        .await
+        .unwrap();
+
+    // Empty values, as literals and as bound values
+    session
+        .query(
+            "INSERT INTO empty_text_blob_tests (id, val, b) VALUES (0, '', 0x)",
+            &[],
+        )
+        .await
+        .unwrap();
+    session
+        .query(
+            "INSERT INTO empty_text_blob_tests (id, val, b) VALUES (1, ?, ?)",
+            ("", Vec::<u8>::new()),
+        )
+        .await
+        .unwrap();
+    // Nulls
+    session
+        .query(
+            "INSERT INTO empty_text_blob_tests (id, val, b) VALUES (2, ?, ?)",
+            (None::<&str>, None::<Vec<u8>>),
+        )
+        .await
+        .unwrap();
+
+    let select = |id: i32| {
+        let session = &session;
+        async move {
+            session
+                .query(
+                    "SELECT val, b FROM empty_text_blob_tests WHERE id = ?",
+                    (id,),
+                )
+                .await
+                .unwrap()
+                .single_row()
+                .unwrap()
+        }
+    };
+
+    for id in [0, 1] {
+        // Empty text and blob are regular values, distinct from nulls
+        let row = select(id).await;
+        assert_eq!(row.columns[0], Some(CqlValue::Text(String::new())));
+        assert_eq!(row.columns[1], Some(CqlValue::Blob(Vec::new())));
+
+        let (text, blob) = row
+            .into_typed::<(Option<String>, Option<Vec<u8>>)>()
+            .unwrap();
+        assert_eq!(text, Some(String::new()));
+        assert_eq!(blob, Some(Vec::new()));
+    }
+
+    let row = select(2).await;
+    assert_eq!(row.columns, vec![None, None]);
+    assert_eq!(
+        row.into_typed::<(Option<String>, Option<Vec<u8>>)>(),
+        Ok((None, None))
+    );
+    assert!(select(2).await.into_typed::<(String, Vec<u8>)>().is_err());
+}
+
+// Checks that `None` is bound as NULL and `MaybeUnset::Unset` leaves the column unchanged,
+// and that NULL can be read only into an Option
+async fn run_null_tests<T>(table_name: &str, type_name: &str, value: T)
+where
+    T: Value + FromCqlVal<CqlValue> + Debug + Clone + PartialEq,
+{
+    let session: Session = init_test(table_name, type_name).await;
+
+    let select = |id: i32| {
+        let session = &session;
+        async move {
+            session
+                .query(
+                    format!("SELECT val FROM {} WHERE id = ?", table_name),
+                    (id,),
+                )
+                .await
+                .unwrap()
+        }
+    };
+
+    let insert = format!("INSERT INTO {} (id, val) VALUES (?, ?)", table_name);
+    session
+        .query(insert.clone(), (0, Some(value.clone())))
+        .await
+        .unwrap();
+    session.query(insert.clone(), (1, None::<T>)).await.unwrap();
+    // Unset doesn't overwrite the value inserted before
+    session
+        .query(insert, (0, MaybeUnset::<T>::Unset))
+        .await
+        .unwrap();
+
+    let (val,) = select(0).await.single_row_typed::<(Option<T>,)>().unwrap();
+    assert_eq!(val, Some(value));
+
+    let (val,) = select(1).await.single_row_typed::<(Option<T>,)>().unwrap();
+    assert_eq!(val, None);
+
+    assert_matches!(
+        select(1).await.single_row_typed::<(T,)>(),
+        Err(SingleRowTypedError::FromRowError(FromRowError::BadCqlVal {
+            err: FromCqlValError::ValIsNull,
+            column: 0
+        }))
+    );
+}
+
+#[tokio::test]
+async fn test_null() {
+    run_null_tests("null_int_tests", "int", 42_i32).await;
+    run_null_tests("null_bigint_tests", "bigint", -42_i64).await;
+    run_null_tests("null_boolean_tests", "boolean", true).await;
+    run_null_tests("null_double_tests", "double", 0.5_f64).await;
+    run_null_tests("null_text_tests", "text", "text".to_string()).await;
+    run_null_tests(
+        "null_uuid_tests",
+        "uuid",
+        Uuid::parse_str("8e14e760-7fa8-11eb-bc66-000000000001").unwrap(),
+    )
+    .await;
+    run_null_tests("null_list_tests", "list<int>", vec![1_i32, 2, 3]).await;
+}
+
+#[tokio::test]
+async fn test_tuple() {
+    let session: Session = init_test("tuple_tests", "tuple<int, text>").await;
+
+    session
+        .query(
+            "INSERT INTO tuple_tests (id, val) VALUES (0, (1, 'one'))",
+            (),
+        )
+        .await
+        .unwrap();
+    session
+        .query(
+            "INSERT INTO tuple_tests (id, val) VALUES (1, ?)",
+            ((2_i32, "two"),),
+        )
+        .await
+        .unwrap();
+    // Elements of a tuple can be null
+    session
+        .query(
+            "INSERT INTO tuple_tests (id, val) VALUES (2, ?)",
+            ((3_i32, None::<&str>),),
+        )
+        .await
+        .unwrap();
+
+    let (val,) = session
+        .query("SELECT val FROM tuple_tests WHERE id = 0", ())
+        .await
+        .unwrap()
+        .single_row_typed::<((i32, String),)>()
+        .unwrap();
+    assert_eq!(val, (1, "one".to_string()));
+
+    let (val,) = session
+        .query("SELECT val FROM tuple_tests WHERE id = 1", ())
+        .await
+        .unwrap()
+        .single_row_typed::<((i32, String),)>()
+        .unwrap();
+    assert_eq!(val, (2, "two".to_string()));
+
+    // A null element can be read only into an Option
+    let (val,) = session
+        .query("SELECT val FROM tuple_tests WHERE id = 2", ())
+        .await
+        .unwrap()
+        .single_row_typed::<((i32, Option<String>),)>()
+        .unwrap();
+    assert_eq!(val, (3, None));
+
+    let result = session
+        .query("SELECT val FROM tuple_tests WHERE id = 2", ())
+        .await
+        .unwrap()
+        .single_row_typed::<((i32, String),)>();
+    assert!(result.is_err());
+}
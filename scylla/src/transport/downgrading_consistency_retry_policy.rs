@@ -392,6 +392,105 @@ mod tests {
         }
     }
 
+    // Explicit downgrade decisions for a keyspace with RF=3 and some replicas down
+    #[test]
+    fn downgrading_consistency_rf3_replicas_down() {
+        let unavailable = |consistency: Consistency, required: i32, alive: i32| {
+            QueryError::DbError(
+                DbError::Unavailable {
+                    consistency,
+                    required,
+                    alive,
+                },
+                String::new(),
+            )
+        };
+        let read_timeout = |consistency: Consistency, required: i32, received: i32| {
+            QueryError::DbError(
+                DbError::ReadTimeout {
+                    consistency,
+                    received,
+                    required,
+                    data_present: false,
+                },
+                String::new(),
+            )
+        };
+
+        let cases = [
+            // One replica down
+            (
+                Consistency::All,
+                unavailable(Consistency::All, 3, 2),
+                RetryDecision::RetrySameNode(Some(Consistency::Two)),
+            ),
+            (
+                Consistency::All,
+                read_timeout(Consistency::All, 3, 2),
+                RetryDecision::RetrySameNode(Some(Consistency::Two)),
+            ),
+            // Two replicas down
+            (
+                Consistency::All,
+                unavailable(Consistency::All, 3, 1),
+                RetryDecision::RetrySameNode(Some(Consistency::One)),
+            ),
+            (
+                Consistency::Quorum,
+                unavailable(Consistency::Quorum, 2, 1),
+                RetryDecision::RetrySameNode(Some(Consistency::One)),
+            ),
+            (
+                Consistency::Quorum,
+                read_timeout(Consistency::Quorum, 2, 1),
+                RetryDecision::RetrySameNode(Some(Consistency::One)),
+            ),
+            // All replicas down - never downgrade below ONE
+            (
+                Consistency::Quorum,
+                unavailable(Consistency::Quorum, 2, 0),
+                RetryDecision::DontRetry,
+            ),
+            (
+                Consistency::Quorum,
+                read_timeout(Consistency::Quorum, 2, 0),
+                RetryDecision::DontRetry,
+            ),
+        ];
+
+        for (cl, error, expected_decision) in cases {
+            for is_idempotent in [false, true] {
+                let mut policy = DowngradingConsistencyRetryPolicy::new().new_session();
+                assert_eq!(
+                    policy.decide_should_retry(make_query_info_with_cl(&error, is_idempotent, cl)),
+                    expected_decision
+                );
+            }
+        }
+
+        // Serial reads are not downgraded
+        for cl in [Consistency::Serial, Consistency::LocalSerial] {
+            let mut policy = DowngradingConsistencyRetryPolicy::new().new_session();
+            assert_eq!(
+                policy.decide_should_retry(make_query_info_with_cl(
+                    &unavailable(cl, 2, 1),
+                    false,
+                    cl
+                )),
+                RetryDecision::RetryNextNode(None)
+            );
+            let mut policy = DowngradingConsistencyRetryPolicy::new().new_session();
+            assert_eq!(
+                policy.decide_should_retry(make_query_info_with_cl(
+                    &read_timeout(cl, 2, 1),
+                    false,
+                    cl
+                )),
+                RetryDecision::DontRetry
+            );
+        }
+    }
+
     // On ReadTimeout we retry one time if there were enough responses and the data was present no matter the idempotence
     #[test]
     fn downgrading_consistency_read_timeout() {
@@ -58,7 +58,9 @@ use uuid::Uuid;
 const DEFAULT_ITER_PAGE_SIZE: i32 = 5000;
 
 /// Iterator over rows returned by paged queries\
-/// Allows to easily access rows without worrying about handling multiple pages
+/// Allows to easily access rows without worrying about handling multiple pages.\
+/// Pages are fetched in the background, but only a bounded number of them is
+/// buffered - next pages are requested only as the consumer polls the rows
 pub struct RowIterator {
     current_row_idx: usize,
     current_page: Rows,
@@ -150,6 +152,7 @@ impl RowIterator {
         let routing_info = RoutingInfo {
             consistency,
             serial_consistency,
+            is_idempotent: query.config.is_idempotent,
             ..Default::default()
         };
 
@@ -175,6 +178,7 @@ impl RowIterator {
                         values_ref,
                         consistency,
                         serial_consistency,
+                        query_ref.get_timestamp(),
                         paging_state,
                     )
                     .await
@@ -196,7 +200,7 @@ impl RowIterator {
                 retry_session,
                 execution_profile,
                 metrics,
-                paging_state: None,
+                paging_state: query.get_paging_state().cloned(),
                 history_listener: query.config.history_listener.clone(),
                 current_query_id: None,
                 current_attempt_id: None,
@@ -258,6 +262,7 @@ impl RowIterator {
                 token,
                 keyspace: config.prepared.get_keyspace_name(),
                 is_confirmed_lwt: config.prepared.is_confirmed_lwt(),
+                is_idempotent: config.prepared.config.is_idempotent,
             };
 
             let choose_connection = |node: Arc<Node>| async move {
@@ -276,6 +281,7 @@ impl RowIterator {
                         values_ref,
                         consistency,
                         serial_consistency,
+                        prepared_ref.get_timestamp(),
                         paging_state,
                     )
                     .await
@@ -355,6 +361,7 @@ impl RowIterator {
                         &values,
                         consistency,
                         serial_consistency,
+                        query.get_timestamp(),
                         paging_state,
                     )
                 },
@@ -699,7 +706,7 @@ where
                 Ok(ControlFlow::Continue(()))
             }
             Err(err) => {
-                self.metrics.inc_failed_paged_queries();
+                self.metrics.inc_failed_paged_queries(&err);
                 self.execution_profile
                     .load_balancing_policy
                     .on_query_failure(&self.statement_info, elapsed, node, &err);
@@ -718,8 +725,8 @@ where
                 Ok(ControlFlow::Break(proof))
             }
             Ok(_) => {
-                self.metrics.inc_failed_paged_queries();
                 let err = QueryError::ProtocolError("Unexpected response to next page query");
+                self.metrics.inc_failed_paged_queries(&err);
                 self.execution_profile
                     .load_balancing_policy
                     .on_query_failure(&self.statement_info, elapsed, node, &err);
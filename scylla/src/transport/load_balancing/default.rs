@@ -74,6 +74,8 @@ pub struct DefaultPolicy {
     preferences: NodeLocationPreference,
     is_token_aware: bool,
     permit_dc_failover: bool,
+    permit_dc_failover_for_non_idempotent: bool,
+    max_remote_nodes: Option<usize>,
     pick_predicate: Box<dyn Fn(&NodeRef) -> bool + Send + Sync>,
     latency_awareness: Option<LatencyAwareness>,
     fixed_shuffle_seed: Option<u64>,
@@ -85,6 +87,11 @@ impl fmt::Debug for DefaultPolicy {
             .field("preferences", &self.preferences)
             .field("is_token_aware", &self.is_token_aware)
             .field("permit_dc_failover", &self.permit_dc_failover)
+            .field(
+                "permit_dc_failover_for_non_idempotent",
+                &self.permit_dc_failover_for_non_idempotent,
+            )
+            .field("max_remote_nodes", &self.max_remote_nodes)
             .field("latency_awareness", &self.latency_awareness)
             .field("fixed_shuffle_seed", &self.fixed_shuffle_seed)
             .finish_non_exhaustive()
@@ -150,7 +157,7 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
 
             // If preferred datacenter is not specified, or if datacenter failover is possible, loosen restriction about locality.
             if self.preferences.datacenter().is_none()
-                || self.is_datacenter_failover_possible(&routing_info)
+                || self.is_picking_remote_node_possible(&routing_info)
             {
                 // Try to pick some alive random replica.
                 let picked = self.pick_replica(
@@ -191,7 +198,7 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
 
         let all_nodes = cluster.replica_locator().unique_nodes_in_global_ring();
         // If a datacenter failover is possible, loosen restriction about locality.
-        if self.is_datacenter_failover_possible(&routing_info) {
+        if self.is_picking_remote_node_possible(&routing_info) {
             let picked = Self::pick_node(all_nodes, &self.pick_predicate);
             if let Some(alive_maybe_remote) = picked {
                 return Some(alive_maybe_remote);
@@ -206,7 +213,7 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
         }
 
         // If a datacenter failover is possible, loosen restriction about locality.
-        if self.is_datacenter_failover_possible(&routing_info) {
+        if self.is_picking_remote_node_possible(&routing_info) {
             let picked = Self::pick_node(all_nodes, |node| node.is_enabled());
             if let Some(down_but_enabled_maybe_remote_node) = picked {
                 return Some(down_but_enabled_maybe_remote_node);
@@ -334,9 +341,9 @@ or refrain from preferring datacenters (which may ban all other datacenters, if
             .unique();
 
         if let Some(latency_awareness) = self.latency_awareness.as_ref() {
-            Box::new(latency_awareness.wrap(plan))
+            Box::new(self.limit_remote_nodes(latency_awareness.wrap(plan)))
         } else {
-            Box::new(plan)
+            Box::new(self.limit_remote_nodes(plan))
         }
     }
 
@@ -627,6 +634,36 @@ impl DefaultPolicy {
         self.preferences.datacenter().is_some()
             && self.permit_dc_failover
             && !routing_info.local_consistency
+            && (self.permit_dc_failover_for_non_idempotent || routing_info.is_idempotent)
+            && self.max_remote_nodes != Some(0)
+    }
+
+    // When the number of remote nodes is limited, remote nodes are returned only
+    // by the fallback plan, which keeps track of how many of them were already used.
+    fn is_picking_remote_node_possible(&self, routing_info: &ProcessedRoutingInfo) -> bool {
+        self.is_datacenter_failover_possible(routing_info) && self.max_remote_nodes.is_none()
+    }
+
+    fn limit_remote_nodes<'a>(
+        &'a self,
+        plan: impl Iterator<Item = NodeRef<'a>>,
+    ) -> impl Iterator<Item = NodeRef<'a>> {
+        let preferred_datacenter = self.preferences.datacenter();
+        let mut remote_nodes_left = self.max_remote_nodes;
+
+        plan.filter(
+            move |node| match (preferred_datacenter, &mut remote_nodes_left) {
+                (Some(dc), Some(left)) if node.datacenter.as_deref() != Some(dc) => {
+                    if *left == 0 {
+                        false
+                    } else {
+                        *left -= 1;
+                        true
+                    }
+                }
+                _ => true,
+            },
+        )
     }
 }
 
@@ -636,6 +673,8 @@ impl Default for DefaultPolicy {
             preferences: NodeLocationPreference::Any,
             is_token_aware: true,
             permit_dc_failover: false,
+            permit_dc_failover_for_non_idempotent: true,
+            max_remote_nodes: None,
             pick_predicate: Box::new(Self::is_alive),
             latency_awareness: None,
             fixed_shuffle_seed: None,
@@ -662,6 +701,8 @@ pub struct DefaultPolicyBuilder {
     preferences: NodeLocationPreference,
     is_token_aware: bool,
     permit_dc_failover: bool,
+    permit_dc_failover_for_non_idempotent: bool,
+    max_remote_nodes: Option<usize>,
     latency_awareness: Option<LatencyAwarenessBuilder>,
     enable_replica_shuffle: bool,
 }
@@ -673,6 +714,8 @@ impl DefaultPolicyBuilder {
             preferences: NodeLocationPreference::Any,
             is_token_aware: true,
             permit_dc_failover: false,
+            permit_dc_failover_for_non_idempotent: true,
+            max_remote_nodes: None,
             latency_awareness: None,
             enable_replica_shuffle: true,
         }
@@ -693,6 +736,8 @@ impl DefaultPolicyBuilder {
             preferences: self.preferences,
             is_token_aware: self.is_token_aware,
             permit_dc_failover: self.permit_dc_failover,
+            permit_dc_failover_for_non_idempotent: self.permit_dc_failover_for_non_idempotent,
+            max_remote_nodes: self.max_remote_nodes,
             pick_predicate,
             latency_awareness,
             fixed_shuffle_seed: (!self.enable_replica_shuffle).then(rand::random),
@@ -788,6 +833,30 @@ impl DefaultPolicyBuilder {
         self
     }
 
+    /// Sets whether datacenter failover is permitted for statements
+    /// which are not marked as idempotent.
+    ///
+    /// Sending a non-idempotent statement to a remote datacenter after
+    /// a local attempt failed may result in applying it twice. When this flag
+    /// is unset, such statements are only sent to local nodes, even if
+    /// `permit_dc_failover` is set. Idempotent statements are not affected.
+    ///
+    /// This option is enabled by default.
+    pub fn permit_dc_failover_for_non_idempotent(mut self, permit: bool) -> Self {
+        self.permit_dc_failover_for_non_idempotent = permit;
+        self
+    }
+
+    /// Limits the number of nodes from non-preferred datacenters that can be
+    /// included in a single load balancing plan when datacenter failover happens.
+    ///
+    /// Has no effect if no datacenter is preferred or datacenter failover
+    /// is not permitted. By default, the number of remote nodes is not limited.
+    pub fn max_remote_nodes(mut self, max_remote_nodes: usize) -> Self {
+        self.max_remote_nodes = Some(max_remote_nodes);
+        self
+    }
+
     /// Latency awareness is a mechanism that penalises nodes whose measured
     /// recent average latency classifies it as falling behind the others.
     ///
@@ -832,6 +901,8 @@ struct ProcessedRoutingInfo<'a> {
 
     // True if one of LOCAL_ONE, LOCAL_QUORUM, LOCAL_SERIAL was requested
     local_consistency: bool,
+
+    is_idempotent: bool,
 }
 
 impl<'a> ProcessedRoutingInfo<'a> {
@@ -846,6 +917,7 @@ impl<'a> ProcessedRoutingInfo<'a> {
         Self {
             token_with_strategy: TokenWithStrategy::new(query, cluster),
             local_consistency,
+            is_idempotent: query.is_idempotent,
         }
     }
 }
@@ -867,11 +939,13 @@ impl<'a> TokenWithStrategy<'a> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use scylla_cql::{frame::types::SerialConsistency, Consistency};
 
     use self::framework::{
-        get_plan_and_collect_node_identifiers, mock_cluster_data_for_token_unaware_tests,
-        ExpectedGroups, ExpectedGroupsBuilder,
+        get_plan_and_collect_node_identifiers, mock_cluster_data_for_rack_aware_tests,
+        mock_cluster_data_for_token_unaware_tests, ExpectedGroups, ExpectedGroupsBuilder,
     };
     use crate::{
         load_balancing::{
@@ -1103,7 +1177,7 @@ mod tests {
                     rack: None,
                     address: id_to_invalid_addr(*id),
                     tokens: vec![Token {
-                        value: *id as i64 * 100,
+                        value: *id as i128 * 100,
                     }],
                     host_id: Uuid::new_v4(),
                 })
@@ -1112,6 +1186,41 @@ mod tests {
             let info = Metadata {
                 peers,
                 keyspaces: HashMap::new(),
+                partitioner: None,
+            };
+
+            ClusterData::new(info, &Default::default(), &HashMap::new(), &None, None).await
+        }
+
+        // creates ClusterData with info about 8 nodes: nodes 1-3 live in rack r1 and nodes 4-6
+        // live in rack r2 of the "eu" datacenter, nodes 7 and 8 live in the "us" datacenter
+        pub(crate) async fn mock_cluster_data_for_rack_aware_tests() -> ClusterData {
+            let peers = [
+                ("eu", "r1", 1),
+                ("eu", "r2", 4),
+                ("eu", "r1", 2),
+                ("us", "r1", 7),
+                ("eu", "r2", 5),
+                ("eu", "r1", 3),
+                ("us", "r2", 8),
+                ("eu", "r2", 6),
+            ]
+            .iter()
+            .map(|(dc, rack, id)| Peer {
+                datacenter: Some(dc.to_string()),
+                rack: Some(rack.to_string()),
+                address: id_to_invalid_addr(*id),
+                tokens: vec![Token {
+                    value: *id as i128 * 100,
+                }],
+                host_id: Uuid::new_v4(),
+            })
+            .collect::<Vec<_>>();
+
+            let info = Metadata {
+                peers,
+                keyspaces: HashMap::new(),
+                partitioner: None,
             };
 
             ClusterData::new(info, &Default::default(), &HashMap::new(), &None, None).await
@@ -1133,6 +1242,7 @@ mod tests {
         is_confirmed_lwt: false,
         consistency: Consistency::Quorum,
         serial_consistency: Some(SerialConsistency::Serial),
+        is_idempotent: false,
     };
 
     pub(super) async fn test_default_policy_with_given_cluster_and_routing_info(
@@ -1197,6 +1307,121 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_default_policy_with_two_racks_in_local_dc() {
+        let cluster = mock_cluster_data_for_rack_aware_tests().await;
+
+        let policy = DefaultPolicy {
+            preferences: NodeLocationPreference::DatacenterAndRack(
+                "eu".to_owned(),
+                "r2".to_owned(),
+            ),
+            permit_dc_failover: true,
+            ..Default::default()
+        };
+        let expected_groups = ExpectedGroupsBuilder::new()
+            .group([4, 5, 6]) // local rack nodes
+            .group([1, 2, 3]) // local DC nodes
+            .group([7, 8]) // remote nodes
+            .build();
+
+        test_default_policy_with_given_cluster_and_routing_info(
+            &policy,
+            &cluster,
+            &EMPTY_ROUTING_INFO,
+            &expected_groups,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_with_dc_failover_for_non_idempotent_statements() {
+        let local_dc = "eu".to_string();
+        let cluster = mock_cluster_data_for_token_unaware_tests().await;
+        let policy = DefaultPolicy {
+            preferences: NodeLocationPreference::Datacenter(local_dc),
+            permit_dc_failover: true,
+            permit_dc_failover_for_non_idempotent: false,
+            ..Default::default()
+        };
+
+        let non_idempotent_routing_info = EMPTY_ROUTING_INFO;
+        let expected_groups = ExpectedGroupsBuilder::new()
+            .group([1, 2, 3]) // pick + fallback local nodes
+            .build(); // failover is forbidden for non-idempotent statements
+        test_default_policy_with_given_cluster_and_routing_info(
+            &policy,
+            &cluster,
+            &non_idempotent_routing_info,
+            &expected_groups,
+        )
+        .await;
+
+        let idempotent_routing_info = RoutingInfo {
+            is_idempotent: true,
+            ..EMPTY_ROUTING_INFO
+        };
+        let expected_groups = ExpectedGroupsBuilder::new()
+            .group([1, 2, 3]) // pick + fallback local nodes
+            .group([4, 5]) // fallback remote nodes
+            .build();
+        test_default_policy_with_given_cluster_and_routing_info(
+            &policy,
+            &cluster,
+            &idempotent_routing_info,
+            &expected_groups,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_with_limited_remote_nodes() {
+        let local_dc = "eu".to_string();
+        let cluster = mock_cluster_data_for_token_unaware_tests().await;
+
+        let policy_with_no_remote_nodes = DefaultPolicy {
+            preferences: NodeLocationPreference::Datacenter(local_dc.clone()),
+            permit_dc_failover: true,
+            max_remote_nodes: Some(0),
+            ..Default::default()
+        };
+        let expected_groups = ExpectedGroupsBuilder::new()
+            .group([1, 2, 3]) // pick + fallback local nodes
+            .build();
+        test_default_policy_with_given_cluster_and_routing_info(
+            &policy_with_no_remote_nodes,
+            &cluster,
+            &EMPTY_ROUTING_INFO,
+            &expected_groups,
+        )
+        .await;
+
+        let policy_with_one_remote_node = DefaultPolicy {
+            preferences: NodeLocationPreference::Datacenter(local_dc),
+            permit_dc_failover: true,
+            max_remote_nodes: Some(1),
+            ..Default::default()
+        };
+        let mut used_remote_nodes = HashSet::new();
+        for _ in 0..256 {
+            let plan = get_plan_and_collect_node_identifiers(
+                &policy_with_one_remote_node,
+                &EMPTY_ROUTING_INFO,
+                &cluster,
+            );
+            // Local nodes first, then exactly one of the remote nodes
+            assert_eq!(plan.len(), 4);
+            assert_eq!(
+                plan[..3].iter().copied().collect::<HashSet<_>>(),
+                [1, 2, 3].into()
+            );
+            assert!([4, 5].contains(&plan[3]));
+            used_remote_nodes.insert(plan[3]);
+        }
+        // The remote node is still round-robined
+        assert_eq!(used_remote_nodes, [4, 5].into());
+    }
+
     #[tokio::test]
     async fn test_default_policy_with_token_aware_statements() {
         use crate::transport::locator::test::{A, B, C, D, E, F, G};
@@ -2379,6 +2604,7 @@ mod latency_awareness {
                 | QueryError::DbError(DbError::Unavailable { .. }, _)
                 | QueryError::DbError(DbError::Unprepared { .. }, _)
                 | QueryError::TranslationError(_)
+                | QueryError::SessionClosed
                 | QueryError::DbError(DbError::Overloaded { .. }, _)
                 | QueryError::DbError(DbError::RateLimitReached { .. }, _) => false,
 
@@ -2722,7 +2948,7 @@ mod latency_awareness {
         use crate::{
             load_balancing::{
                 default::tests::test_default_policy_with_given_cluster_and_routing_info,
-                RoutingInfo,
+                LoadBalancingPolicy, RoutingInfo,
             },
             routing::Token,
             transport::{
@@ -2786,6 +3012,8 @@ mod latency_awareness {
             DefaultPolicy {
                 preferences: NodeLocationPreference::Datacenter("eu".to_owned()),
                 permit_dc_failover: true,
+                permit_dc_failover_for_non_idempotent: true,
+                max_remote_nodes: None,
                 is_token_aware: true,
                 pick_predicate,
                 latency_awareness: Some(latency_awareness),
@@ -3111,6 +3339,90 @@ mod latency_awareness {
             .await;
         }
 
+        #[tokio::test]
+        async fn latency_aware_default_policy_penalises_and_restores_nodes_based_on_reported_latencies(
+        ) {
+            // A tiny scale makes the average follow the most recent measurement closely.
+            let (policy, updater) = latency_aware_policy_with_explicit_updater_customised(|b| {
+                b.minimum_measurements(3).scale(Duration::from_nanos(1))
+            });
+            let cluster = tests::mock_cluster_data_for_token_unaware_tests().await;
+
+            async fn report_latencies(
+                policy: &DefaultPolicy,
+                cluster: &ClusterData,
+                id: u16,
+                latency: Duration,
+                times: usize,
+            ) {
+                let node = cluster
+                    .known_peers
+                    .values()
+                    .find(|node| node.address == id_to_invalid_addr(id))
+                    .unwrap();
+                for _ in 0..times {
+                    // Make sure consecutive measurements are taken at distinct instants.
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    policy.on_query_success(&EMPTY_ROUTING_INFO, latency, node);
+                }
+            }
+
+            let fast = Duration::from_millis(10);
+            let slow = Duration::from_millis(100);
+
+            // Not enough measurements of the slow node to penalise it.
+            for id in 2..=5 {
+                report_latencies(&policy, &cluster, id, fast, 3).await;
+            }
+            report_latencies(&policy, &cluster, 1, slow, 2).await;
+            updater.tick().await;
+
+            let expected_groups = ExpectedGroupsBuilder::new()
+                .group([1, 2, 3]) // pick + fallback local nodes
+                .group([4, 5]) // fallback remote nodes
+                .build();
+            test_default_policy_with_given_cluster_and_routing_info(
+                &policy,
+                &cluster,
+                &EMPTY_ROUTING_INFO,
+                &expected_groups,
+            )
+            .await;
+
+            // Enough measurements collected, so the slow node is moved to the end of the plan.
+            report_latencies(&policy, &cluster, 1, slow, 1).await;
+            updater.tick().await;
+
+            let expected_groups = ExpectedGroupsBuilder::new()
+                .group([2, 3]) // pick + fallback local nodes
+                .group([4, 5]) // fallback remote nodes
+                .group([1]) // local node that was penalised due to high latency
+                .build();
+            test_default_policy_with_given_cluster_and_routing_info(
+                &policy,
+                &cluster,
+                &EMPTY_ROUTING_INFO,
+                &expected_groups,
+            )
+            .await;
+
+            // The node recovers and rejoins the plan at its usual position.
+            report_latencies(&policy, &cluster, 1, fast, 1).await;
+            updater.tick().await;
+
+            let expected_groups = ExpectedGroupsBuilder::new()
+                .group([1, 2, 3]) // pick + fallback local nodes
+                .group([4, 5]) // fallback remote nodes
+                .build();
+            test_default_policy_with_given_cluster_and_routing_info(
+                &policy,
+                &cluster,
+                &EMPTY_ROUTING_INFO,
+                &expected_groups,
+            )
+            .await;
+        }
+
         #[tokio::test]
         async fn latency_aware_default_policy_stops_penalising_after_min_average_increases_enough_only_after_update_rate_elapses(
         ) {
@@ -10,8 +10,10 @@ use std::time::Duration;
 
 mod default;
 mod plan;
+mod single_target;
 pub use default::{DefaultPolicy, DefaultPolicyBuilder, LatencyAwarenessBuilder};
 pub use plan::Plan;
+pub use single_target::SingleTargetLoadBalancingPolicy;
 
 /// Represents info about statement that can be used by load balancing policies.
 #[derive(Default, Clone, Debug)]
@@ -33,6 +35,10 @@ pub struct RoutingInfo<'a> {
     /// If false, the query should be routed normally.
     /// Note: this a Scylla-specific optimisation. Therefore, the flag will be always false for Cassandra.
     pub is_confirmed_lwt: bool,
+
+    /// Whether the statement was marked as idempotent. Policies may refuse to send
+    /// non-idempotent statements to some nodes, e.g. the ones in remote datacenters.
+    pub is_idempotent: bool,
 }
 
 /// The fallback list of nodes in the query plan.
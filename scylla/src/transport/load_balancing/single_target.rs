@@ -0,0 +1,94 @@
+use super::{FallbackPlan, LoadBalancingPolicy, NodeRef, RoutingInfo};
+use crate::transport::ClusterData;
+use uuid::Uuid;
+
+/// A policy which sends every query to one specific node, regardless of the query's routing
+/// information.
+///
+/// It is meant to be set on the execution profile of particular statements, e.g. to read
+/// data back from the coordinator which received the write. There is no other node in the
+/// query plan, so if the target is not a part of the cluster, the query fails.
+#[derive(Debug, Clone)]
+pub struct SingleTargetLoadBalancingPolicy {
+    host_id: Uuid,
+}
+
+impl SingleTargetLoadBalancingPolicy {
+    /// Creates a policy targeting the node with the given host id.
+    pub fn new(host_id: Uuid) -> Self {
+        Self { host_id }
+    }
+
+    /// Returns the host id of the targeted node.
+    pub fn host_id(&self) -> Uuid {
+        self.host_id
+    }
+
+    fn target<'a>(&self, cluster: &'a ClusterData) -> Option<NodeRef<'a>> {
+        cluster.known_peers.get(&self.host_id)
+    }
+}
+
+impl LoadBalancingPolicy for SingleTargetLoadBalancingPolicy {
+    fn pick<'a>(
+        &'a self,
+        _query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> Option<NodeRef<'a>> {
+        self.target(cluster)
+    }
+
+    fn fallback<'a>(
+        &'a self,
+        _query: &'a RoutingInfo,
+        cluster: &'a ClusterData,
+    ) -> FallbackPlan<'a> {
+        Box::new(self.target(cluster).into_iter())
+    }
+
+    fn name(&self) -> String {
+        "SingleTargetLoadBalancingPolicy".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use uuid::Uuid;
+
+    use super::SingleTargetLoadBalancingPolicy;
+    use crate::load_balancing::{Plan, RoutingInfo};
+    use crate::routing::Token;
+    use crate::transport::locator::test::{mock_metadata_for_token_aware_tests, KEYSPACE_NTS_RF_3};
+    use crate::transport::ClusterData;
+
+    #[tokio::test]
+    async fn test_single_target_plan() {
+        let cluster = ClusterData::new(
+            mock_metadata_for_token_aware_tests(),
+            &Default::default(),
+            &HashMap::new(),
+            &None,
+            None,
+        )
+        .await;
+        let routing_info = RoutingInfo {
+            token: Some(Token { value: 160 }),
+            keyspace: Some(KEYSPACE_NTS_RF_3),
+            ..Default::default()
+        };
+
+        // The plan consists of the target only, whatever the replicas of the token are
+        for target in cluster.get_nodes_info() {
+            let policy = SingleTargetLoadBalancingPolicy::new(target.host_id);
+            let plan: Vec<_> = Plan::new(&policy, &routing_info, &cluster).collect();
+            assert_eq!(plan.len(), 1);
+            assert_eq!(plan[0].host_id, target.host_id);
+        }
+
+        // Unknown node
+        let policy = SingleTargetLoadBalancingPolicy::new(Uuid::new_v4());
+        assert_eq!(Plan::new(&policy, &routing_info, &cluster).count(), 0);
+    }
+}
@@ -1,6 +1,9 @@
 use histogram::Histogram;
+use scylla_cql::errors::QueryError;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use strum::EnumCount;
+use strum_macros::EnumCount;
 
 const ORDER_TYPE: Ordering = Ordering::Relaxed;
 
@@ -28,6 +31,45 @@ impl std::fmt::Display for MetricsError<'_> {
     }
 }
 
+/// Kinds of errors which are counted separately by [Metrics].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumCount)]
+#[non_exhaustive]
+pub enum QueryErrorKind {
+    /// An error returned by the database
+    Database,
+    /// The query was rejected by the driver before being sent
+    BadQuery,
+    /// A connection failed while executing the query
+    Io,
+    /// Unexpected or malformed response was received
+    Protocol,
+    /// The query has timed out
+    Timeout,
+    /// No stream ids were available for the query
+    StreamIds,
+    /// Address translation failed
+    AddressTranslation,
+    /// The session was closed before the query was executed
+    SessionClosed,
+}
+
+impl QueryErrorKind {
+    fn of(error: &QueryError) -> Self {
+        match error {
+            QueryError::DbError(_, _) => Self::Database,
+            QueryError::BadQuery(_) => Self::BadQuery,
+            QueryError::IoError(_) => Self::Io,
+            QueryError::ProtocolError(_) | QueryError::InvalidMessage(_) => Self::Protocol,
+            QueryError::TimeoutError | QueryError::RequestTimeout(_) => Self::Timeout,
+            QueryError::TooManyOrphanedStreamIds(_) | QueryError::UnableToAllocStreamId => {
+                Self::StreamIds
+            }
+            QueryError::TranslationError(_) => Self::AddressTranslation,
+            QueryError::SessionClosed => Self::SessionClosed,
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Metrics {
     errors_num: AtomicU64,
@@ -35,6 +77,7 @@ pub struct Metrics {
     errors_iter_num: AtomicU64,
     queries_iter_num: AtomicU64,
     retries_num: AtomicU64,
+    errors_by_kind: [AtomicU64; QueryErrorKind::COUNT],
     histogram: Arc<Mutex<Histogram>>,
 }
 
@@ -46,13 +89,15 @@ impl Metrics {
             errors_iter_num: AtomicU64::new(0),
             queries_iter_num: AtomicU64::new(0),
             retries_num: AtomicU64::new(0),
+            errors_by_kind: Default::default(),
             histogram: Arc::new(Mutex::new(Histogram::new())),
         }
     }
 
     /// Increments counter for errors that occurred in nonpaged queries.
-    pub(crate) fn inc_failed_nonpaged_queries(&self) {
+    pub(crate) fn inc_failed_nonpaged_queries(&self, error: &QueryError) {
         self.errors_num.fetch_add(1, ORDER_TYPE);
+        self.inc_errors_by_kind(error);
     }
 
     /// Increments counter for nonpaged queries.
@@ -61,8 +106,13 @@ impl Metrics {
     }
 
     /// Increments counter for errors that occurred in paged queries.
-    pub(crate) fn inc_failed_paged_queries(&self) {
+    pub(crate) fn inc_failed_paged_queries(&self, error: &QueryError) {
         self.errors_iter_num.fetch_add(1, ORDER_TYPE);
+        self.inc_errors_by_kind(error);
+    }
+
+    fn inc_errors_by_kind(&self, error: &QueryError) {
+        self.errors_by_kind[QueryErrorKind::of(error) as usize].fetch_add(1, ORDER_TYPE);
     }
 
     /// Increments counter for page queries in paged queries.
@@ -108,6 +158,11 @@ impl Metrics {
         self.errors_num.load(ORDER_TYPE)
     }
 
+    /// Returns counter for errors of the given kind, occurred in both nonpaged and paged queries
+    pub fn get_errors_num_by_kind(&self, kind: QueryErrorKind) -> u64 {
+        self.errors_by_kind[kind as usize].load(ORDER_TYPE)
+    }
+
     /// Returns counter for nonpaged queries
     pub fn get_queries_num(&self) -> u64 {
         self.queries_num.load(ORDER_TYPE)
@@ -128,3 +183,49 @@ impl Metrics {
         self.retries_num.load(ORDER_TYPE)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Metrics, QueryErrorKind};
+    use scylla_cql::errors::{BadQuery, DbError, QueryError};
+
+    #[test]
+    fn errors_counted_by_kind() {
+        let metrics = Metrics::new();
+        metrics.inc_failed_nonpaged_queries(&QueryError::DbError(
+            DbError::SyntaxError,
+            "bad syntax".to_string(),
+        ));
+        metrics.inc_failed_nonpaged_queries(&QueryError::TimeoutError);
+        metrics.inc_failed_paged_queries(&QueryError::RequestTimeout("timeout".to_string()));
+        metrics.inc_failed_paged_queries(&QueryError::BadQuery(BadQuery::Other(
+            "bad query".to_string(),
+        )));
+        metrics.inc_failed_nonpaged_queries(&QueryError::SessionClosed);
+
+        assert_eq!(metrics.get_errors_num(), 3);
+        assert_eq!(metrics.get_errors_iter_num(), 2);
+        assert_eq!(metrics.get_errors_num_by_kind(QueryErrorKind::Database), 1);
+        assert_eq!(metrics.get_errors_num_by_kind(QueryErrorKind::Timeout), 2);
+        assert_eq!(metrics.get_errors_num_by_kind(QueryErrorKind::BadQuery), 1);
+        assert_eq!(
+            metrics.get_errors_num_by_kind(QueryErrorKind::SessionClosed),
+            1
+        );
+        assert_eq!(metrics.get_errors_num_by_kind(QueryErrorKind::Io), 0);
+    }
+
+    #[test]
+    fn latency_percentiles() {
+        let metrics = Metrics::new();
+        for latency in 1..=100 {
+            metrics.log_query_latency(latency).unwrap();
+        }
+
+        let p50 = metrics.get_latency_percentile_ms(50.0).unwrap();
+        let p99 = metrics.get_latency_percentile_ms(99.0).unwrap();
+        assert!(p50 > 0);
+        assert!(p99 >= p50);
+        assert!(metrics.get_latency_avg_ms().unwrap() > 0);
+    }
+}
@@ -12,13 +12,15 @@ pub(crate) mod metrics;
 mod node;
 pub mod partitioner;
 pub mod query_result;
+pub mod reconnection_policy;
 pub mod retry_policy;
 pub mod session;
 pub mod session_builder;
 pub mod speculative_execution;
+pub mod timestamp_generator;
 pub mod topology;
 
-pub use crate::frame::{Authenticator, Compression};
+pub use crate::frame::{Authenticator, Compression, ProtocolVersion};
 pub use execution_profile::ExecutionProfile;
 pub use scylla_cql::errors;
 
@@ -35,4 +37,4 @@ mod cql_types_test;
 mod cql_value_test;
 
 pub use cluster::ClusterData;
-pub use node::{KnownNode, Node, NodeAddr, NodeRef};
+pub use node::{KnownNode, Node, NodeAddr, NodePoolStatus, NodeRef, NodeSupportedOptions};
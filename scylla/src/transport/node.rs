@@ -8,7 +8,9 @@ use crate::transport::connection::Connection;
 use crate::transport::connection::VerifiedKeyspaceName;
 use crate::transport::connection_pool::{NodeConnectionPool, PoolConfig};
 use crate::transport::errors::QueryError;
+use crate::transport::{Compression, ProtocolVersion};
 
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::io;
 use std::net::IpAddr;
@@ -82,6 +84,42 @@ pub struct Node {
     down_marker: AtomicBool,
 }
 
+/// A snapshot of the state of a node's connection pool, returned by [`Node::pool_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NodePoolStatus {
+    /// The number of connections the pool aims to keep open to the node,
+    /// as configured with [`PoolSize`](crate::transport::session::PoolSize).
+    /// Until the number of shards of the node is known, the node is assumed
+    /// to have only one shard.
+    pub target_connections: usize,
+
+    /// The number of connections to the node that are currently open and usable.
+    pub live_connections: usize,
+
+    /// Whether the node is considered up, i.e. it was not reported as down
+    /// by the cluster and there is at least one live connection to it.
+    pub is_up: bool,
+}
+
+/// What the driver learned about a node when connecting to it, returned by [`Node::supported_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NodeSupportedOptions {
+    /// Options sent by the node in the SUPPORTED response, e.g. `COMPRESSION`,
+    /// or Scylla-specific ones such as `SCYLLA_NR_SHARDS` and `SCYLLA_SHARD_AWARE_PORT`.
+    /// The shard-related options are missing if the node is not sharded (e.g. it's Cassandra),
+    /// in which case shard awareness cannot be used.
+    pub options: HashMap<String, Vec<String>>,
+
+    /// The compression used on connections to the node. `None` if either no compression
+    /// is configured or the node doesn't support the configured one.
+    pub compression: Option<Compression>,
+
+    /// The version of the protocol used on connections to the node.
+    pub protocol_version: ProtocolVersion,
+}
+
 /// A way that Nodes are often passed and accessed in the driver's code.
 pub type NodeRef<'a> = &'a Arc<Node>;
 
@@ -177,6 +215,35 @@ impl Node {
         self.pool.is_some()
     }
 
+    /// Returns the current status of the connection pool to this node.
+    ///
+    /// The status reflects connections breaking and being reopened by the pool
+    /// in the background, so consecutive calls can return different results.
+    /// Returns `None` if the node is disabled by the host filter.
+    pub fn pool_status(&self) -> Option<NodePoolStatus> {
+        let pool = self.pool.as_ref()?;
+        let live_connections = pool.live_connection_count();
+
+        Some(NodePoolStatus {
+            target_connections: pool.target_connection_count(),
+            live_connections,
+            is_up: !self.is_down() && live_connections > 0,
+        })
+    }
+
+    /// Returns the options the node reported when one of the connections to it was opened.
+    /// Returns `None` if there is no open connection to the node, e.g. when it's disabled
+    /// by the host filter or down.
+    pub fn supported_options(&self) -> Option<NodeSupportedOptions> {
+        let connection = self.pool.as_ref()?.random_connection().ok()?;
+
+        Some(NodeSupportedOptions {
+            options: connection.get_supported_options().clone(),
+            compression: connection.get_compression(),
+            protocol_version: connection.get_protocol_version(),
+        })
+    }
+
     pub(crate) fn change_down_marker(&self, is_down: bool) {
         self.down_marker.store(is_down, Ordering::Relaxed);
     }
@@ -1,4 +1,5 @@
 use bytes::Buf;
+use md5::{Digest, Md5};
 use std::num::Wrapping;
 
 use crate::{
@@ -11,6 +12,10 @@ pub(crate) enum PartitionerName {
     #[default]
     Murmur3,
     CDC,
+    Random,
+    // A partitioner which the driver can't compute tokens for,
+    // e.g. ByteOrderedPartitioner or a custom one. Holds the partitioner's name.
+    Unsupported(String),
 }
 
 impl PartitionerName {
@@ -19,21 +24,32 @@ impl PartitionerName {
             Some(PartitionerName::Murmur3)
         } else if name.ends_with("CDCPartitioner") {
             Some(PartitionerName::CDC)
+        } else if name.ends_with("RandomPartitioner") {
+            Some(PartitionerName::Random)
         } else {
             None
         }
     }
-}
 
-impl Partitioner for PartitionerName {
-    type Hasher = PartitionerHasherAny;
+    // Resolves the name of the partitioner used by the cluster, as read from system.local.
+    // Unlike `from_str`, it doesn't fall back to any partitioner if the name is not recognized,
+    // so that tokens are never silently computed with a wrong partitioner.
+    pub(crate) fn from_cluster_partitioner(name: &str) -> Self {
+        Self::from_str(name).unwrap_or_else(|| PartitionerName::Unsupported(name.to_owned()))
+    }
 
-    fn build_hasher(&self) -> Self::Hasher {
+    pub(crate) fn build_hasher(&self) -> Result<PartitionerHasherAny, TokenCalculationError> {
         match self {
-            PartitionerName::Murmur3 => {
-                PartitionerHasherAny::Murmur3(Murmur3Partitioner.build_hasher())
+            PartitionerName::Murmur3 => Ok(PartitionerHasherAny::Murmur3(
+                Murmur3Partitioner.build_hasher(),
+            )),
+            PartitionerName::CDC => Ok(PartitionerHasherAny::CDC(CDCPartitioner.build_hasher())),
+            PartitionerName::Random => Ok(PartitionerHasherAny::Random(
+                RandomPartitioner.build_hasher(),
+            )),
+            PartitionerName::Unsupported(name) => {
+                Err(TokenCalculationError::UnsupportedPartitioner(name.clone()))
             }
-            PartitionerName::CDC => PartitionerHasherAny::CDC(CDCPartitioner.build_hasher()),
         }
     }
 }
@@ -42,6 +58,7 @@ impl Partitioner for PartitionerName {
 pub(crate) enum PartitionerHasherAny {
     Murmur3(Murmur3PartitionerHasher),
     CDC(CDCPartitionerHasher),
+    Random(RandomPartitionerHasher),
 }
 
 impl PartitionerHasher for PartitionerHasherAny {
@@ -49,6 +66,7 @@ impl PartitionerHasher for PartitionerHasherAny {
         match self {
             PartitionerHasherAny::Murmur3(h) => h.write(pk_part),
             PartitionerHasherAny::CDC(h) => h.write(pk_part),
+            PartitionerHasherAny::Random(h) => h.write(pk_part),
         }
     }
 
@@ -56,6 +74,7 @@ impl PartitionerHasher for PartitionerHasherAny {
         match self {
             PartitionerHasherAny::Murmur3(h) => h.finish(),
             PartitionerHasherAny::CDC(h) => h.finish(),
+            PartitionerHasherAny::Random(h) => h.finish(),
         }
     }
 }
@@ -257,7 +276,19 @@ impl PartitionerHasher for Murmur3PartitionerHasher {
         h2 += h1;
 
         Token {
-            value: (((h2.0 as i128) << 64) | h1.0 as i128) as i64,
+            value: Self::normalize((((h2.0 as i128) << 64) | h1.0 as i128) as i64).into(),
+        }
+    }
+}
+
+impl Murmur3PartitionerHasher {
+    // Like in Cassandra and Scylla, the minimum token is reserved
+    // and a hash equal to it is mapped to the maximum token.
+    fn normalize(value: i64) -> i64 {
+        if value == i64::MIN {
+            i64::MAX
+        } else {
+            value
         }
     }
 }
@@ -305,7 +336,7 @@ impl PartitionerHasher for CDCPartitionerHasher {
                 // If the buffer is full, we can compute and fix the token.
                 if *len == Self::BUF_CAPACITY {
                     let token = Token {
-                        value: (&mut &buf[..]).get_i64(),
+                        value: (&mut &buf[..]).get_i64().into(),
                     };
                     self.state = CDCPartitionerHasherState::Computed(token);
                 }
@@ -316,12 +347,48 @@ impl PartitionerHasher for CDCPartitionerHasher {
 
     fn finish(&self) -> Token {
         match self.state {
-            CDCPartitionerHasherState::Feeding { .. } => Token { value: i64::MIN },
+            CDCPartitionerHasherState::Feeding { .. } => Token {
+                value: i64::MIN.into(),
+            },
             CDCPartitionerHasherState::Computed(token) => token,
         }
     }
 }
 
+/// Computes tokens the same way as Cassandra's `RandomPartitioner`, i.e. as the absolute value
+/// of the MD5 digest of the key, interpreted as a signed 128-bit big-endian integer.
+pub struct RandomPartitioner;
+
+impl Partitioner for RandomPartitioner {
+    type Hasher = RandomPartitionerHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        Self::Hasher { md5: Md5::new() }
+    }
+}
+
+pub struct RandomPartitionerHasher {
+    md5: Md5,
+}
+
+impl PartitionerHasher for RandomPartitionerHasher {
+    fn write(&mut self, pk_part: &[u8]) {
+        self.md5.update(pk_part);
+    }
+
+    fn finish(&self) -> Token {
+        let digest: [u8; 16] = self.md5.clone().finalize().into();
+        // The absolute value of the only digest outside of the token range, -2^127, is 2^127,
+        // which is the maximum token in Cassandra. Finding a key with such a digest is infeasible,
+        // so it is mapped to the largest value which fits in the token instead.
+        Token {
+            value: i128::from_be_bytes(digest)
+                .checked_abs()
+                .unwrap_or(i128::MAX),
+        }
+    }
+}
+
 /// Calculates the token for given partitioner and serialized partition key.
 ///
 /// The ordinary way to calculate token is based on a PreparedStatement
@@ -340,7 +407,24 @@ pub fn calculate_token_for_partition_key<P: Partitioner>(
     partitioner: &P,
 ) -> Result<Token, TokenCalculationError> {
     let mut partitioner_hasher = partitioner.build_hasher();
+    write_partition_key(serialized_partition_key_values, &mut partitioner_hasher)?;
+    Ok(partitioner_hasher.finish())
+}
+
+// Calculates the token for the given partitioner name, failing for unsupported partitioners.
+pub(crate) fn calculate_token_for_partition_key_with_partitioner_name(
+    serialized_partition_key_values: &SerializedValues,
+    partitioner_name: &PartitionerName,
+) -> Result<Token, TokenCalculationError> {
+    let mut partitioner_hasher = partitioner_name.build_hasher()?;
+    write_partition_key(serialized_partition_key_values, &mut partitioner_hasher)?;
+    Ok(partitioner_hasher.finish())
+}
 
+fn write_partition_key(
+    serialized_partition_key_values: &SerializedValues,
+    partitioner_hasher: &mut impl PartitionerHasher,
+) -> Result<(), TokenCalculationError> {
     if serialized_partition_key_values.len() == 1 {
         let val = serialized_partition_key_values.iter().next().unwrap();
         if let Some(val) = val {
@@ -358,7 +442,7 @@ pub fn calculate_token_for_partition_key<P: Partitioner>(
         }
     }
 
-    Ok(partitioner_hasher.finish())
+    Ok(())
 }
 
 #[cfg(test)]
@@ -368,11 +452,15 @@ mod tests {
 
     use crate::transport::partitioner::PartitionerHasher;
 
-    use super::{CDCPartitioner, Murmur3Partitioner, Partitioner};
+    use super::{
+        CDCPartitioner, Murmur3Partitioner, Murmur3PartitionerHasher, Partitioner, PartitionerName,
+        RandomPartitioner,
+    };
+    use crate::prepared_statement::TokenCalculationError;
 
     fn assert_correct_murmur3_hash(pk: &'static str, expected_hash: i64) {
         let hash = Murmur3Partitioner.hash_one(pk.as_bytes()).value;
-        assert_eq!(hash, expected_hash);
+        assert_eq!(hash, expected_hash.into());
     }
 
     #[test]
@@ -387,9 +475,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_murmur3_min_token_is_normalized() {
+        assert_eq!(Murmur3PartitionerHasher::normalize(i64::MIN), i64::MAX);
+        assert_eq!(Murmur3PartitionerHasher::normalize(i64::MAX), i64::MAX);
+        assert_eq!(Murmur3PartitionerHasher::normalize(0), 0);
+        assert_eq!(
+            Murmur3PartitionerHasher::normalize(i64::MIN + 1),
+            i64::MIN + 1
+        );
+    }
+
+    #[test]
+    fn test_random_partitioner() {
+        // Inputs of the MD5 test suite from RFC 1321, with tokens being the absolute values
+        // of the digests listed there, e.g. 0xd41d8cd98f00b204e9800998ecf8427e for "".
+        for s in [
+            ("", 58332598431525814501020785164969033090),
+            ("a", 16955237001963240173058271559858726497),
+            ("abc", 148866708576779697295343134153845407886),
+            ("message digest", 8746880611504415408002903419237867056),
+            (
+                "abcdefghijklmnopqrstuvwxyz",
+                79770152281850154784480278109750828741,
+            ),
+            (
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                61867928966441335897835061917515670113,
+            ),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                116878371745249285768420430739153598074,
+            ),
+        ] {
+            assert_eq!(RandomPartitioner.hash_one(s.0.as_bytes()).value, s.1);
+        }
+    }
+
+    #[test]
+    fn test_partitioner_name_from_str() {
+        assert_eq!(
+            PartitionerName::from_str("org.apache.cassandra.dht.Murmur3Partitioner"),
+            Some(PartitionerName::Murmur3)
+        );
+        assert_eq!(
+            PartitionerName::from_str("com.scylladb.dht.CDCPartitioner"),
+            Some(PartitionerName::CDC)
+        );
+        assert_eq!(
+            PartitionerName::from_str("org.apache.cassandra.dht.RandomPartitioner"),
+            Some(PartitionerName::Random)
+        );
+        assert_eq!(
+            PartitionerName::from_str("org.apache.cassandra.dht.ByteOrderedPartitioner"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cluster_partitioner_resolution() {
+        assert_eq!(
+            PartitionerName::from_cluster_partitioner(
+                "org.apache.cassandra.dht.Murmur3Partitioner"
+            ),
+            PartitionerName::Murmur3
+        );
+
+        let random =
+            PartitionerName::from_cluster_partitioner("org.apache.cassandra.dht.RandomPartitioner");
+        assert_eq!(random, PartitionerName::Random);
+        let mut hasher = random.build_hasher().unwrap();
+        hasher.write(b"abc");
+        assert_eq!(hasher.finish(), RandomPartitioner.hash_one(b"abc"));
+
+        let byte_ordered = "org.apache.cassandra.dht.ByteOrderedPartitioner";
+        let partitioner = PartitionerName::from_cluster_partitioner(byte_ordered);
+        assert_eq!(
+            partitioner,
+            PartitionerName::Unsupported(byte_ordered.to_owned())
+        );
+
+        let err = partitioner.build_hasher().err().unwrap();
+        assert_eq!(
+            err,
+            TokenCalculationError::UnsupportedPartitioner(byte_ordered.to_owned())
+        );
+        assert_eq!(
+            err.to_string(),
+            "Partitioner org.apache.cassandra.dht.ByteOrderedPartitioner is unsupported for token awareness"
+        );
+    }
+
     fn assert_correct_cdc_hash(pk: &'static str, expected_hash: i64) {
         let hash = CDCPartitioner.hash_one(pk.as_bytes()).value;
-        assert_eq!(hash, expected_hash);
+        assert_eq!(hash, expected_hash.into());
     }
 
     #[test]
@@ -447,6 +626,7 @@ mod tests {
         for input in inputs {
             check_for_partitioner(Murmur3Partitioner, &mut randgen, input);
             check_for_partitioner(CDCPartitioner, &mut randgen, input);
+            check_for_partitioner(RandomPartitioner, &mut randgen, input);
         }
     }
 
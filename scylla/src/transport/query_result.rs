@@ -1,11 +1,20 @@
 use crate::frame::response::cql_to_rust::{FromRow, FromRowError};
-use crate::frame::response::result::ColumnSpec;
 use crate::frame::response::result::Row;
+use crate::frame::response::result::{ColumnSpec, ColumnType};
+#[cfg(feature = "serde")]
+use crate::frame::value::Json;
 use crate::transport::session::{IntoTypedRows, TypedRowIter};
 use bytes::Bytes;
+use std::collections::HashMap;
 use thiserror::Error;
 use uuid::Uuid;
 
+/// Opaque paging state returned by the server.\
+/// It can be stored (e.g. base64-encoded on the client side) and passed back with
+/// [`Query::set_paging_state`](crate::query::Query::set_paging_state) to resume fetching
+/// from the page that follows it.
+pub type PagingState = Bytes;
+
 /// Result of a single query\
 /// Contains all rows returned by the database and some more information
 #[non_exhaustive]
@@ -17,10 +26,12 @@ pub struct QueryResult {
     pub rows: Option<Vec<Row>>,
     /// Warnings returned by the database
     pub warnings: Vec<String>,
+    /// Custom payload returned by the database, if any
+    pub custom_payload: Option<HashMap<String, Bytes>>,
     /// CQL Tracing uuid - can only be Some if tracing is enabled for this query
     pub tracing_id: Option<Uuid>,
     /// Paging state returned from the server
-    pub paging_state: Option<Bytes>,
+    pub paging_state: Option<PagingState>,
     /// Column specification returned from the server
     pub col_specs: Vec<ColumnSpec>,
     /// The original size of the serialized rows in request
@@ -37,6 +48,12 @@ impl QueryResult {
         }
     }
 
+    /// Returns `true` if the response contained rows, i.e. the query was of a type that returns rows.\
+    /// An empty `SELECT` result is still `rows`, while an `INSERT` result is not.
+    pub fn is_rows(&self) -> bool {
+        self.rows.is_some()
+    }
+
     /// Returns the received rows when present.\
     /// If `QueryResult.rows` is `None`, which means that this query is not supposed to return rows (e.g `INSERT`), returns an error.\
     /// Can return an empty `Vec`.
@@ -127,6 +144,33 @@ impl QueryResult {
         Ok(self.single_row()?.into_typed::<RowT>()?)
     }
 
+    /// Returns rows of a `SELECT JSON` query, with the JSON text of each row deserialized
+    /// into the given type.\
+    /// Fails if the result doesn't consist of the single `[json]` text column returned
+    /// by `SELECT JSON`.
+    #[cfg(feature = "serde")]
+    pub fn json_rows<T: serde::de::DeserializeOwned>(self) -> Result<Vec<T>, JsonRowsError> {
+        let is_json = matches!(
+            self.col_specs.as_slice(),
+            [spec] if spec.name == "[json]" && spec.typ == ColumnType::Text
+        );
+        let rows: Vec<Row> = self.rows()?;
+        if !is_json {
+            return Err(JsonRowsError::NotJson);
+        }
+
+        rows.into_typed::<(Json<T>,)>()
+            .map(|row| Ok(row?.0 .0))
+            .collect()
+    }
+
+    /// Returns specifications of the received columns, in the order in which they appear in rows.\
+    /// Each specification contains the keyspace and table name, the column name and its type.\
+    /// Empty for results of queries which don't return rows.
+    pub fn column_specs(&self) -> &[ColumnSpec] {
+        &self.col_specs
+    }
+
     /// Returns a column specification for a column with given name, or None if not found
     pub fn get_column_spec<'a>(&'a self, name: &str) -> Option<(usize, &'a ColumnSpec)> {
         self.col_specs
@@ -134,6 +178,37 @@ impl QueryResult {
             .enumerate()
             .find(|(_id, spec)| spec.name == name)
     }
+
+    /// Returns the value of the `[applied]` column of a lightweight transaction result.\
+    /// When the transaction was not applied (`Some(false)`), the remaining columns
+    /// contain the existing row that caused the condition to fail.\
+    /// Returns `None` for results of non-conditional queries.
+    pub fn applied(&self) -> Option<bool> {
+        match self.col_specs.first() {
+            Some(spec) if spec.name == "[applied]" && spec.typ == ColumnType::Boolean => {}
+            _ => return None,
+        }
+
+        self.rows
+            .as_ref()?
+            .first()?
+            .columns
+            .first()?
+            .as_ref()?
+            .as_boolean()
+    }
+
+    /// Returns warnings sent by the database along with the response,
+    /// e.g. about a batch being too large or a query reading too many tombstones.\
+    /// Each of them is also logged by the driver at `WARN` level.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Returns the custom payload sent by the database along with the response, if any.
+    pub fn custom_payload(&self) -> Option<&HashMap<String, Bytes>> {
+        self.custom_payload.as_ref()
+    }
 }
 
 /// [`QueryResult::rows()`](QueryResult::rows) or a similar function called on a bad QueryResult.\
@@ -240,6 +315,25 @@ pub enum SingleRowTypedError {
     FromRowError(#[from] FromRowError),
 }
 
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum JsonRowsError {
+    /// [`QueryResult::json_rows()`](QueryResult::json_rows) called on a bad QueryResult.\
+    /// Expected `QueryResult.rows` to be `Some`, but it was `None`.\
+    /// `QueryResult.rows` is `Some` for queries that can return rows (e.g `SELECT`).\
+    /// It is `None` for queries that can't return rows (e.g `INSERT`).
+    #[error(transparent)]
+    RowsExpected(#[from] RowsExpectedError),
+
+    /// The result has other columns than the single `[json]` column returned by `SELECT JSON`
+    #[error("Expected a single [json] text column, as returned by SELECT JSON")]
+    NotJson,
+
+    /// Deserializing the JSON text as the given type failed
+    #[error(transparent)]
+    FromRowError(#[from] FromRowError),
+}
+
 impl From<FirstRowError> for FirstRowTypedError {
     fn from(err: FirstRowError) -> FirstRowTypedError {
         match err {
@@ -261,7 +355,8 @@ impl From<SingleRowError> for SingleRowTypedError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::frame::response::result::{ColumnSpec, ColumnType, CqlValue, Row, TableSpec};
+    use crate::frame::response::result::{CqlValue, Row, TableSpec};
+    use scylla_macros::FromRow;
     use std::convert::TryInto;
 
     // Returns specified number of rows, each one containing one int32 value.
@@ -304,6 +399,7 @@ mod tests {
         QueryResult {
             rows: None,
             warnings: vec![],
+            custom_payload: None,
             tracing_id: None,
             paging_state: None,
             col_specs: vec![column_spec],
@@ -335,6 +431,14 @@ mod tests {
         assert_eq!(make_rows_query_result(3).rows_num(), Ok(3));
     }
 
+    #[test]
+    fn is_rows_test() {
+        assert!(!make_not_rows_query_result().is_rows());
+        assert!(make_rows_query_result(0).is_rows());
+        assert!(make_rows_query_result(1).is_rows());
+        assert!(make_rows_query_result(2).is_rows());
+    }
+
     #[test]
     fn rows_test() {
         assert_eq!(make_not_rows_query_result().rows(), Err(RowsExpectedError));
@@ -535,6 +639,63 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn first_row_typed_derived_struct_test() {
+        #[derive(FromRow, Debug, PartialEq)]
+        #[scylla_crate = "crate"]
+        struct Person {
+            id: i32,
+            name: Option<String>,
+        }
+
+        let make_people_query_result = || {
+            let mut res = make_rows_query_result(0);
+            res.rows = Some(vec![
+                Row {
+                    columns: vec![
+                        Some(CqlValue::Int(1)),
+                        Some(CqlValue::Text("Alice".to_string())),
+                    ],
+                },
+                Row {
+                    columns: vec![Some(CqlValue::Int(2)), None],
+                },
+            ]);
+            res
+        };
+        let alice = Person {
+            id: 1,
+            name: Some("Alice".to_string()),
+        };
+
+        assert_eq!(
+            make_people_query_result().first_row_typed::<Person>(),
+            Ok(Person {
+                id: 1,
+                name: Some("Alice".to_string()),
+            })
+        );
+        assert_eq!(
+            make_people_query_result().maybe_first_row_typed::<Person>(),
+            Ok(Some(alice))
+        );
+
+        assert_eq!(
+            make_rows_query_result(0).first_row_typed::<Person>(),
+            Err(FirstRowTypedError::RowsEmpty)
+        );
+        assert_eq!(
+            make_rows_query_result(0).maybe_first_row_typed::<Person>(),
+            Ok(None)
+        );
+
+        // Rows with a different shape fail to convert
+        assert!(matches!(
+            make_rows_query_result(1).first_row_typed::<Person>(),
+            Err(FirstRowTypedError::FromRowError(_))
+        ));
+    }
+
     #[test]
     fn single_row_test() {
         assert_eq!(
@@ -587,4 +748,95 @@ mod tests {
             Err(SingleRowTypedError::FromRowError(_))
         ));
     }
+
+    #[test]
+    fn applied_test() {
+        assert_eq!(make_not_rows_query_result().applied(), None);
+        assert_eq!(make_rows_query_result(1).applied(), None);
+
+        let make_lwt_result = |applied: bool| {
+            let mut res = make_rows_query_result(0);
+            res.col_specs.insert(
+                0,
+                ColumnSpec {
+                    table_spec: res.col_specs[0].table_spec.clone(),
+                    name: "[applied]".to_string(),
+                    typ: ColumnType::Boolean,
+                },
+            );
+            res.rows = Some(vec![Row {
+                columns: vec![Some(CqlValue::Boolean(applied)), Some(CqlValue::Int(0))],
+            }]);
+            res
+        };
+
+        assert_eq!(make_lwt_result(true).applied(), Some(true));
+        assert_eq!(make_lwt_result(false).applied(), Some(false));
+    }
+
+    #[test]
+    fn column_specs_test() {
+        let rows_result = make_rows_query_result(2);
+        let specs = rows_result.column_specs();
+        assert_eq!(specs.len(), 1);
+        assert_eq!(specs[0].table_spec.ks_name, "some_keyspace");
+        assert_eq!(specs[0].table_spec.table_name, "some_table");
+        assert_eq!(specs[0].name, "column0");
+        assert_eq!(specs[0].typ, ColumnType::Int);
+    }
+
+    #[test]
+    fn warnings_test() {
+        assert!(make_not_rows_query_result().warnings().is_empty());
+
+        let mut res = make_not_rows_query_result();
+        res.warnings = vec!["Batch is too large".to_string()];
+        assert_eq!(res.warnings(), ["Batch is too large".to_string()]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_rows_test() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let make_json_result = |name: &str, rows: &[&str]| {
+            let mut res = make_not_rows_query_result();
+            res.col_specs[0].name = name.to_string();
+            res.col_specs[0].typ = ColumnType::Text;
+            res.rows = Some(
+                rows.iter()
+                    .map(|json| Row {
+                        columns: vec![Some(CqlValue::Text(json.to_string()))],
+                    })
+                    .collect(),
+            );
+            res
+        };
+
+        let points: Vec<Point> =
+            make_json_result("[json]", &[r#"{"x": 1, "y": 2}"#, r#"{"x": 3, "y": 4}"#])
+                .json_rows()
+                .unwrap();
+        assert_eq!(points, vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }]);
+
+        assert_eq!(
+            make_not_rows_query_result().json_rows::<Point>(),
+            Err(JsonRowsError::RowsExpected(RowsExpectedError))
+        );
+        assert_eq!(
+            make_string_rows_query_result(1).json_rows::<Point>(),
+            Err(JsonRowsError::NotJson)
+        );
+        assert_eq!(
+            make_json_result("[json]", &[r#"{"x": 1}"#]).json_rows::<Point>(),
+            Err(JsonRowsError::FromRowError(FromRowError::BadCqlVal {
+                err: crate::frame::response::cql_to_rust::FromCqlValError::BadVal,
+                column: 0
+            }))
+        );
+    }
 }
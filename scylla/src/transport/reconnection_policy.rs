@@ -0,0 +1,147 @@
+use rand::{thread_rng, Rng};
+use std::time::Duration;
+
+/// Decides how long the driver waits before trying to open connections to a node again
+/// after previous attempts failed.
+pub trait ReconnectionPolicy: std::fmt::Debug + Send + Sync {
+    /// Called for each connection pool to create a schedule of its reconnection attempts.
+    fn new_schedule(&self) -> Box<dyn ReconnectionSchedule>;
+}
+
+/// Used throughout the lifetime of a single connection pool to compute
+/// delays between consecutive attempts to fill the pool.
+pub trait ReconnectionSchedule: Send + Sync {
+    /// Returns the delay before the next attempt to fill the pool.
+    /// Called each time the pool is not full and a fill has to be scheduled.
+    fn next_delay(&mut self) -> Duration;
+
+    /// Called after the pool was filled without errors,
+    /// so that the schedule starts over with its initial delay.
+    fn reset(&mut self);
+}
+
+/// A reconnection policy whose delay starts at `base_delay` and doubles after each
+/// failed attempt, until it reaches `max_delay`. Each delay is randomly shortened
+/// by up to 15%, so that many pools which failed at the same time don't reconnect
+/// all at once.
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoffReconnectionPolicy {
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl ExponentialBackoffReconnectionPolicy {
+    /// Creates a new policy with given initial and maximal delays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base_delay` is zero or greater than `max_delay`.
+    pub fn new(base_delay: Duration, max_delay: Duration) -> Self {
+        assert!(
+            !base_delay.is_zero(),
+            "base_delay of the reconnection policy must be positive"
+        );
+        assert!(
+            base_delay <= max_delay,
+            "base_delay of the reconnection policy must not be greater than max_delay"
+        );
+        Self {
+            base_delay,
+            max_delay,
+        }
+    }
+}
+
+impl Default for ExponentialBackoffReconnectionPolicy {
+    /// Base delay of 50 milliseconds, maximal delay of 10 seconds.
+    fn default() -> Self {
+        Self::new(Duration::from_millis(50), Duration::from_secs(10))
+    }
+}
+
+impl ReconnectionPolicy for ExponentialBackoffReconnectionPolicy {
+    fn new_schedule(&self) -> Box<dyn ReconnectionSchedule> {
+        Box::new(ExponentialBackoffReconnectionSchedule {
+            base_delay: self.base_delay,
+            max_delay: self.max_delay,
+            current_delay: self.base_delay,
+        })
+    }
+}
+
+struct ExponentialBackoffReconnectionSchedule {
+    base_delay: Duration,
+    max_delay: Duration,
+    current_delay: Duration,
+}
+
+const MAX_JITTER_FRACTION: f64 = 0.15;
+
+impl ReconnectionSchedule for ExponentialBackoffReconnectionSchedule {
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.current_delay;
+        self.current_delay = std::cmp::min(self.max_delay, self.current_delay * 2);
+
+        let jitter = thread_rng().gen_range(0.0..=MAX_JITTER_FRACTION);
+        delay.mul_f64(1.0 - jitter)
+    }
+
+    fn reset(&mut self) {
+        self.current_delay = self.base_delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExponentialBackoffReconnectionPolicy, ReconnectionPolicy, MAX_JITTER_FRACTION};
+    use std::time::Duration;
+
+    fn assert_jittered(delay: Duration, expected: Duration) {
+        assert!(delay <= expected, "{:?} > {:?}", delay, expected);
+        assert!(
+            delay >= expected.mul_f64(1.0 - MAX_JITTER_FRACTION),
+            "{:?} is too short, expected around {:?}",
+            delay,
+            expected
+        );
+    }
+
+    #[test]
+    fn exponential_backoff_grows_and_caps() {
+        let policy = ExponentialBackoffReconnectionPolicy::new(
+            Duration::from_millis(100),
+            Duration::from_millis(1000),
+        );
+        let mut schedule = policy.new_schedule();
+
+        for expected_ms in [100, 200, 400, 800, 1000, 1000, 1000] {
+            assert_jittered(schedule.next_delay(), Duration::from_millis(expected_ms));
+        }
+    }
+
+    #[test]
+    fn exponential_backoff_reset() {
+        let policy = ExponentialBackoffReconnectionPolicy::new(
+            Duration::from_millis(100),
+            Duration::from_millis(1000),
+        );
+        let mut schedule = policy.new_schedule();
+
+        for _ in 0..5 {
+            schedule.next_delay();
+        }
+        schedule.reset();
+        assert_jittered(schedule.next_delay(), Duration::from_millis(100));
+        assert_jittered(schedule.next_delay(), Duration::from_millis(200));
+
+        // Schedules of the same policy are independent
+        let mut other_schedule = policy.new_schedule();
+        assert_jittered(other_schedule.next_delay(), Duration::from_millis(100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn exponential_backoff_rejects_base_greater_than_max() {
+        ExponentialBackoffReconnectionPolicy::new(Duration::from_secs(2), Duration::from_secs(1));
+    }
+}
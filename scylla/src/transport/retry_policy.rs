@@ -345,6 +345,59 @@ mod tests {
         );
     }
 
+    // After reset the session should behave like a new one, e.g. when fetching the next page
+    #[test]
+    fn default_reset() {
+        let unavailable = QueryError::DbError(
+            DbError::Unavailable {
+                consistency: Consistency::Two,
+                required: 2,
+                alive: 1,
+            },
+            String::new(),
+        );
+        let read_timeout = QueryError::DbError(
+            DbError::ReadTimeout {
+                consistency: Consistency::Two,
+                received: 2,
+                required: 2,
+                data_present: false,
+            },
+            String::new(),
+        );
+        let write_timeout = QueryError::DbError(
+            DbError::WriteTimeout {
+                consistency: Consistency::Two,
+                received: 1,
+                required: 2,
+                write_type: WriteType::BatchLog,
+            },
+            String::new(),
+        );
+
+        let mut session = DefaultRetryPolicy::new().new_session();
+        for (error, retry_decision) in [
+            (&unavailable, RetryDecision::RetryNextNode(None)),
+            (&read_timeout, RetryDecision::RetrySameNode(None)),
+            (&write_timeout, RetryDecision::RetrySameNode(None)),
+        ] {
+            assert_eq!(
+                session.decide_should_retry(make_query_info(error, true)),
+                retry_decision
+            );
+            assert_eq!(
+                session.decide_should_retry(make_query_info(error, true)),
+                RetryDecision::DontRetry
+            );
+
+            session.reset();
+            assert_eq!(
+                session.decide_should_retry(make_query_info(error, true)),
+                retry_decision
+            );
+        }
+    }
+
     // On Unavailable error we retry one time no matter the idempotence
     #[test]
     fn default_unavailable() {
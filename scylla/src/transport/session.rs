@@ -12,30 +12,36 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use futures::future::join_all;
 use futures::future::try_join_all;
+use futures::Stream;
 use itertools::{Either, Itertools};
 pub use scylla_cql::errors::TranslationError;
 use scylla_cql::frame::response::result::{deser_cql_value, ColumnSpec, Rows};
 use scylla_cql::frame::response::NonErrorResponse;
+use scylla_cql::frame::{ProtocolVersion, MAX_FRAME_BODY_SIZE};
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::future::Future;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::{Semaphore, SemaphorePermit};
 use tokio::time::timeout;
-use tracing::{debug, trace, trace_span, Instrument};
+use tracing::{debug, trace, trace_span, warn, Instrument};
 use uuid::Uuid;
 
 use super::connection::NonErrorQueryResponse;
 use super::connection::QueryResponse;
 #[cfg(feature = "ssl")]
 use super::connection::SslConfig;
-use super::errors::{NewSessionError, QueryError};
+use super::errors::{BadQuery, NewSessionError, QueryError};
 use super::execution_profile::{ExecutionProfile, ExecutionProfileHandle, ExecutionProfileInner};
 #[cfg(feature = "cloud")]
 use super::node::CloudEndpoint;
@@ -45,6 +51,7 @@ use super::topology::UntranslatedPeer;
 use super::NodeRef;
 use crate::cql_to_rust::FromRow;
 use crate::frame::response::cql_to_rust::FromRowError;
+use crate::frame::response::event::Event;
 use crate::frame::response::result;
 use crate::frame::value::{
     BatchValues, BatchValuesFirstSerialized, BatchValuesIterator, ValueList,
@@ -55,7 +62,9 @@ use crate::routing::Token;
 use crate::statement::Consistency;
 use crate::tracing::{TracingEvent, TracingInfo};
 use crate::transport::cluster::{Cluster, ClusterData, ClusterNeatDebug};
-use crate::transport::connection::{Connection, ConnectionConfig, VerifiedKeyspaceName};
+use crate::transport::connection::{
+    Connection, ConnectionConfig, SharedProtocolVersion, VerifiedKeyspaceName,
+};
 use crate::transport::connection_pool::PoolConfig;
 use crate::transport::host_filter::HostFilter;
 use crate::transport::iterator::{PreparedIteratorConfig, RowIterator};
@@ -63,14 +72,19 @@ use crate::transport::load_balancing::{self, RoutingInfo};
 use crate::transport::metrics::Metrics;
 use crate::transport::node::Node;
 use crate::transport::query_result::QueryResult;
+use crate::transport::reconnection_policy::{
+    ExponentialBackoffReconnectionPolicy, ReconnectionPolicy,
+};
 use crate::transport::retry_policy::{QueryInfo, RetryDecision, RetrySession};
 use crate::transport::speculative_execution;
+use crate::transport::timestamp_generator::{MonotonicTimestampGenerator, TimestampGenerator};
 use crate::transport::Compression;
 use crate::{
     batch::{Batch, BatchStatement},
     statement::StatementConfig,
 };
 
+pub use crate::transport::connection::WriteCoalescingDelay;
 pub use crate::transport::connection_pool::PoolSize;
 
 use crate::authentication::AuthenticatorProvider;
@@ -138,6 +152,7 @@ impl AddressTranslator for HashMap<&'static str, &'static str> {
 pub struct Session {
     cluster: Cluster,
     default_execution_profile_handle: ExecutionProfileHandle,
+    execution_profiles: HashMap<String, ExecutionProfileHandle>,
     schema_agreement_interval: Duration,
     metrics: Arc<Metrics>,
     schema_agreement_timeout: Duration,
@@ -147,6 +162,11 @@ pub struct Session {
     tracing_info_fetch_attempts: NonZeroU32,
     tracing_info_fetch_interval: Duration,
     tracing_info_fetch_consistency: Consistency,
+    default_page_size: Option<i32>,
+    timestamp_generator: Option<Arc<dyn TimestampGenerator>>,
+    in_flight_requests: InFlightRequests,
+    request_slots: Option<Semaphore>,
+    protocol_version: Arc<SharedProtocolVersion>,
 }
 
 /// This implementation deliberately omits some details from Cluster in order
@@ -159,6 +179,7 @@ impl std::fmt::Debug for Session {
                 "default_execution_profile_handle",
                 &self.default_execution_profile_handle,
             )
+            .field("execution_profiles", &self.execution_profiles)
             .field("schema_agreement_interval", &self.schema_agreement_interval)
             .field("metrics", &self.metrics)
             .field(
@@ -183,11 +204,20 @@ pub struct SessionConfig {
     /// Preferred compression algorithm to use on connections.
     /// If it's not supported by database server Session will fall back to no compression.
     pub compression: Option<Compression>,
+
+    /// Minimum size in bytes of a request body for it to be compressed.
+    /// Smaller requests are sent uncompressed, as compressing them isn't worth the overhead.
+    pub compression_threshold: usize,
+
     pub tcp_nodelay: bool,
     pub tcp_keepalive_interval: Option<Duration>,
 
     pub default_execution_profile_handle: ExecutionProfileHandle,
 
+    /// Execution profiles registered under a name, which can be later retrieved
+    /// from the session with [`Session::get_execution_profile_handle`].
+    pub execution_profiles: HashMap<String, ExecutionProfileHandle>,
+
     pub used_keyspace: Option<String>,
     pub keyspace_case_sensitive: bool,
 
@@ -203,6 +233,18 @@ pub struct SessionConfig {
     /// The default is `PerShard(1)`, which is the recommended setting for Scylla clusters.
     pub connection_pool_size: PoolSize,
 
+    /// Decides how long pools wait before trying to open connections again after a failure.
+    /// The default is [`ExponentialBackoffReconnectionPolicy`] with a base delay of 50 milliseconds
+    /// and a maximal delay of 10 seconds.
+    pub reconnection_policy: Arc<dyn ReconnectionPolicy>,
+
+    /// Generates client-side timestamps for requests which don't have a timestamp set explicitly.
+    /// A timestamp is generated once per request, so all of its retries and speculative
+    /// executions are sent with the same timestamp.
+    /// If `None`, timestamps are assigned by the database.
+    /// The default is [`MonotonicTimestampGenerator`].
+    pub timestamp_generator: Option<Arc<dyn TimestampGenerator>>,
+
     /// If true, prevents the driver from connecting to the shard-aware port, even if the node supports it.
     /// Generally, this options is best left as default (false).
     pub disallow_shard_aware_port: bool,
@@ -264,6 +306,28 @@ pub struct SessionConfig {
     /// this option.
     pub enable_write_coalescing: bool,
 
+    /// How long the driver waits for more requests to send them together
+    /// when write coalescing is enabled. See [`WriteCoalescingDelay`].
+    pub write_coalescing_delay: WriteCoalescingDelay,
+
+    /// The maximal number of requests sent together in a single write
+    /// when write coalescing is enabled. There is no limit by default.
+    pub write_coalescing_max_requests: Option<NonZeroUsize>,
+
+    /// If set, the driver logs a warning when the serialized size of a batch request
+    /// (in bytes) exceeds this threshold. It's meant to mirror the server's
+    /// `batch_size_warn_threshold`, so that oversized batches are noticed on the client side.
+    pub batch_size_warn_threshold: Option<usize>,
+
+    /// The largest frame body (in bytes) the driver accepts from the server.
+    /// A frame announcing a longer body breaks the connection before anything
+    /// is allocated for it. Defaults to the protocol's limit of 256 MiB.
+    pub max_frame_body_size: usize,
+
+    /// The version of the CQL protocol to use. If `None`, the driver negotiates
+    /// the highest version supported both by itself and by the nodes.
+    pub protocol_version: Option<ProtocolVersion>,
+
     /// Number of attempts to fetch [`TracingInfo`]
     /// in [`Session::get_tracing_info`]. Tracing info
     /// might not be available immediately on queried node - that's why
@@ -280,11 +344,27 @@ pub struct SessionConfig {
     /// in [`Session::get_tracing_info`].
     pub tracing_info_fetch_consistency: Consistency,
 
+    /// Page size used by statements which don't have their own page size set.
+    /// If `None`, such statements are not paged, except for [`Session::query_iter`]
+    /// and [`Session::execute_iter`], which use a page size of 5000.
+    /// Must be positive, otherwise creating the session fails.
+    pub default_page_size: Option<i32>,
+
     /// Interval between refreshing cluster metadata. This
     /// can be configured according to the traffic pattern
     /// for e.g: if they do not want unexpected traffic
     /// or they expect the topology to change frequently.
     pub cluster_metadata_refresh_interval: Duration,
+
+    /// Number of rounds of trying to open the control connection to any of the known nodes
+    /// when creating the session. Delays between the rounds are determined by
+    /// [`Self::reconnection_policy`]. The default is 1.
+    pub control_connection_init_attempts: NonZeroU32,
+
+    /// Maximum number of requests executed concurrently with `query`, `execute` and `batch`.
+    /// Requests over the limit wait until one of the running requests completes.
+    /// If `None`, the number of requests is not limited.
+    pub max_concurrent_requests: Option<NonZeroUsize>,
 }
 
 impl SessionConfig {
@@ -302,11 +382,13 @@ impl SessionConfig {
         SessionConfig {
             known_nodes: Vec::new(),
             compression: None,
+            compression_threshold: 0,
             tcp_nodelay: true,
             tcp_keepalive_interval: None,
             schema_agreement_interval: Duration::from_millis(200),
             default_execution_profile_handle: ExecutionProfile::new_from_inner(Default::default())
                 .into_handle(),
+            execution_profiles: HashMap::new(),
             used_keyspace: None,
             keyspace_case_sensitive: false,
             #[cfg(feature = "ssl")]
@@ -314,6 +396,8 @@ impl SessionConfig {
             authenticator: None,
             connect_timeout: Duration::from_secs(5),
             connection_pool_size: Default::default(),
+            reconnection_policy: Arc::new(ExponentialBackoffReconnectionPolicy::default()),
+            timestamp_generator: Some(Arc::new(MonotonicTimestampGenerator::new())),
             disallow_shard_aware_port: false,
             keyspaces_to_fetch: Vec::new(),
             fetch_schema_metadata: true,
@@ -327,10 +411,18 @@ impl SessionConfig {
             #[cfg(feature = "cloud")]
             cloud_config: None,
             enable_write_coalescing: true,
+            write_coalescing_delay: Default::default(),
+            write_coalescing_max_requests: None,
+            batch_size_warn_threshold: None,
+            max_frame_body_size: MAX_FRAME_BODY_SIZE,
+            protocol_version: None,
             tracing_info_fetch_attempts: NonZeroU32::new(5).unwrap(),
             tracing_info_fetch_interval: Duration::from_millis(3),
             tracing_info_fetch_consistency: Consistency::One,
+            default_page_size: None,
             cluster_metadata_refresh_interval: Duration::from_secs(60),
+            control_connection_init_attempts: NonZeroU32::new(1).unwrap(),
+            max_concurrent_requests: None,
         }
     }
 
@@ -492,8 +584,18 @@ impl Session {
             return Err(NewSessionError::EmptyKnownNodesList);
         }
 
+        if let Some(page_size) = config.default_page_size.filter(|page_size| *page_size <= 0) {
+            return Err(NewSessionError::InvalidPageSize(page_size));
+        }
+
+        let protocol_version = Arc::new(SharedProtocolVersion::new(
+            config.protocol_version,
+            config.compression,
+        ));
+
         let connection_config = ConnectionConfig {
             compression: config.compression,
+            compression_threshold: config.compression_threshold,
             tcp_nodelay: config.tcp_nodelay,
             tcp_keepalive_interval: config.tcp_keepalive_interval,
             #[cfg(feature = "ssl")]
@@ -506,6 +608,11 @@ impl Session {
             #[cfg(feature = "cloud")]
             cloud_config: config.cloud_config,
             enable_write_coalescing: config.enable_write_coalescing,
+            write_coalescing_delay: config.write_coalescing_delay,
+            write_coalescing_max_requests: config.write_coalescing_max_requests,
+            batch_size_warn_threshold: config.batch_size_warn_threshold,
+            max_frame_body_size: config.max_frame_body_size,
+            protocol_version: protocol_version.clone(),
             keepalive_interval: config.keepalive_interval,
             keepalive_timeout: config.keepalive_timeout,
         };
@@ -515,6 +622,7 @@ impl Session {
             pool_size: config.connection_pool_size,
             can_use_shard_aware_port: !config.disallow_shard_aware_port,
             keepalive_interval: config.keepalive_interval,
+            reconnection_policy: config.reconnection_policy,
         };
 
         let cluster = Cluster::new(
@@ -524,6 +632,7 @@ impl Session {
             config.fetch_schema_metadata,
             config.host_filter,
             config.cluster_metadata_refresh_interval,
+            config.control_connection_init_attempts,
         )
         .await?;
 
@@ -532,6 +641,7 @@ impl Session {
         let session = Session {
             cluster,
             default_execution_profile_handle,
+            execution_profiles: config.execution_profiles,
             schema_agreement_interval: config.schema_agreement_interval,
             metrics: Arc::new(Metrics::new()),
             schema_agreement_timeout: config.schema_agreement_timeout,
@@ -542,6 +652,13 @@ impl Session {
             tracing_info_fetch_attempts: config.tracing_info_fetch_attempts,
             tracing_info_fetch_interval: config.tracing_info_fetch_interval,
             tracing_info_fetch_consistency: config.tracing_info_fetch_consistency,
+            default_page_size: config.default_page_size,
+            timestamp_generator: config.timestamp_generator,
+            in_flight_requests: InFlightRequests::default(),
+            request_slots: config
+                .max_concurrent_requests
+                .map(|limit| Semaphore::new(limit.get())),
+            protocol_version,
         };
 
         if let Some(keyspace_name) = config.used_keyspace {
@@ -604,7 +721,9 @@ impl Session {
         query: impl Into<Query>,
         values: impl ValueList,
     ) -> Result<QueryResult, QueryError> {
-        self.query_paged(query, values, None).await
+        let query: Query = query.into();
+        let paging_state = query.get_paging_state().cloned();
+        self.query_paged(query, values, paging_state).await
     }
 
     /// Queries the database with a custom paging state.
@@ -612,14 +731,18 @@ impl Session {
     ///
     /// * `query` - query to be performed
     /// * `values` - values bound to the query
-    /// * `paging_state` - previously received paging state or None
+    /// * `paging_state` - previously received paging state or None,
+    ///   the paging state set on the query itself is ignored
     pub async fn query_paged(
         &self,
         query: impl Into<Query>,
         values: impl ValueList,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResult, QueryError> {
-        let query: Query = query.into();
+        let _in_flight_guard = self.in_flight_requests.start()?;
+        let _request_slot = self.acquire_request_slot().await;
+        let mut query: Query = query.into();
+        self.apply_default_page_size(&mut query);
         let serialized_values = values.serialized()?;
 
         let execution_profile = query
@@ -636,9 +759,12 @@ impl Session {
                 .config
                 .serial_consistency
                 .unwrap_or(execution_profile.serial_consistency),
+            is_idempotent: query.config.is_idempotent,
             ..Default::default()
         };
 
+        let timestamp = self.get_timestamp(query.get_timestamp());
+
         let span = RequestSpan::new_query(&query.contents, serialized_values.size());
         let run_query_result = self
             .run_query(
@@ -664,6 +790,7 @@ impl Session {
                                 values_ref,
                                 consistency,
                                 serial_consistency,
+                                timestamp,
                                 paging_state_ref.clone(),
                             )
                             .await
@@ -680,6 +807,7 @@ impl Session {
                 response: NonErrorResponse::Result(result::Result::Void),
                 tracing_id: None,
                 warnings: Vec::new(),
+                custom_payload: None,
             },
             RunQueryResult::Completed(response) => response,
         };
@@ -765,7 +893,12 @@ impl Session {
         query: impl Into<Query>,
         values: impl ValueList,
     ) -> Result<RowIterator, QueryError> {
-        let query: Query = query.into();
+        // Iterators are not waited for on shutdown, they are only rejected on a closed session
+        self.in_flight_requests.check_open()?;
+        let mut query: Query = query.into();
+        self.apply_default_page_size(&mut query);
+        // All pages are fetched with the same timestamp
+        query.set_timestamp(self.get_timestamp(query.get_timestamp()));
         let serialized_values = values.serialized()?;
 
         let execution_profile = query
@@ -819,7 +952,9 @@ impl Session {
     /// # }
     /// ```
     pub async fn prepare(&self, query: impl Into<Query>) -> Result<PreparedStatement, QueryError> {
-        let query = query.into();
+        let _in_flight_guard = self.in_flight_requests.start()?;
+        let mut query = query.into();
+        self.apply_default_page_size(&mut query);
         let query_ref = &query;
 
         let cluster_data = self.get_cluster_data();
@@ -852,15 +987,47 @@ impl Session {
                 .extend(statement.prepare_tracing_ids);
         }
 
+        let cluster_data = self.cluster.get_data();
         prepared.set_partitioner_name(
-            self.extract_partitioner_name(&prepared, &self.cluster.get_data())
+            self.extract_partitioner_name(&prepared, &cluster_data)
                 .and_then(PartitionerName::from_str)
-                .unwrap_or_default(),
+                .unwrap_or_else(|| cluster_data.partitioner.clone()),
         );
 
         Ok(prepared)
     }
 
+    /// Waits for a free slot if the number of concurrent requests is limited.
+    /// The slot is released when the returned permit is dropped.
+    async fn acquire_request_slot(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.request_slots {
+            Some(slots) => Some(
+                slots
+                    .acquire()
+                    .await
+                    .expect("The request slots semaphore is never closed"),
+            ),
+            None => None,
+        }
+    }
+
+    /// Sets the session's default page size on a query that doesn't have its own page size.
+    fn apply_default_page_size(&self, query: &mut Query) {
+        if let (None, Some(page_size)) = (query.get_page_size(), self.default_page_size) {
+            query.set_page_size(page_size);
+        }
+    }
+
+    /// Returns the timestamp to send along with a statement. A timestamp set on the statement
+    /// takes precedence over the generated one.
+    fn get_timestamp(&self, statement_timestamp: Option<i64>) -> Option<i64> {
+        statement_timestamp.or_else(|| {
+            self.timestamp_generator
+                .as_ref()
+                .map(|generator| generator.next_timestamp())
+        })
+    }
+
     fn extract_partitioner_name<'a>(
         &self,
         prepared: &PreparedStatement,
@@ -932,9 +1099,13 @@ impl Session {
         values: impl ValueList,
         paging_state: Option<Bytes>,
     ) -> Result<QueryResult, QueryError> {
-        let serialized_values = values.serialized()?;
+        let _in_flight_guard = self.in_flight_requests.start()?;
+        let _request_slot = self.acquire_request_slot().await;
+        let serialized_values = prepared.bind_named_values(values.serialized()?)?;
+        prepared.check_values(&serialized_values)?;
         let values_ref = &serialized_values;
         let paging_state_ref = &paging_state;
+        let timestamp = self.get_timestamp(prepared.get_timestamp());
 
         let (partition_key, token) = prepared
             .extract_partition_key_and_calculate_token(
@@ -960,6 +1131,7 @@ impl Session {
             token,
             keyspace: prepared.get_keyspace_name(),
             is_confirmed_lwt: prepared.is_confirmed_lwt(),
+            is_idempotent: prepared.config.is_idempotent,
         };
 
         let span = RequestSpan::new_prepared(
@@ -1003,6 +1175,7 @@ impl Session {
                                 values_ref,
                                 consistency,
                                 serial_consistency,
+                                timestamp,
                                 paging_state_ref.clone(),
                             )
                             .await
@@ -1019,6 +1192,7 @@ impl Session {
                 response: NonErrorResponse::Result(result::Result::Void),
                 tracing_id: None,
                 warnings: Vec::new(),
+                custom_payload: None,
             },
             RunQueryResult::Completed(response) => response,
         };
@@ -1077,8 +1251,13 @@ impl Session {
         prepared: impl Into<PreparedStatement>,
         values: impl ValueList,
     ) -> Result<RowIterator, QueryError> {
-        let prepared = prepared.into();
-        let serialized_values = values.serialized()?;
+        // Iterators are not waited for on shutdown, they are only rejected on a closed session
+        self.in_flight_requests.check_open()?;
+        let mut prepared: PreparedStatement = prepared.into();
+        // All pages are fetched with the same timestamp
+        prepared.set_timestamp(self.get_timestamp(prepared.get_timestamp()));
+        let serialized_values = prepared.bind_named_values(values.serialized()?)?;
+        prepared.check_values(&serialized_values)?;
 
         let execution_profile = prepared
             .get_execution_profile_handle()
@@ -1140,6 +1319,16 @@ impl Session {
         batch: &Batch,
         values: impl BatchValues,
     ) -> Result<QueryResult, QueryError> {
+        let _in_flight_guard = self.in_flight_requests.start()?;
+        let _request_slot = self.acquire_request_slot().await;
+        // The protocol encodes the number of statements as a short
+        let n_statements = batch.statements.len();
+        if n_statements > u16::MAX as usize {
+            return Err(QueryError::BadQuery(
+                BadQuery::TooManyQueriesInBatchStatement(n_statements),
+            ));
+        }
+
         // Shard-awareness behavior for batch will be to pick shard based on first batch statement's shard
         // If users batch statements by shard, they will be rewarded with full shard awareness
 
@@ -1170,11 +1359,13 @@ impl Session {
                     token: ps.calculate_token(first_serialized_value)?,
                     keyspace: ps.get_keyspace_name(),
                     is_confirmed_lwt: false,
+                    is_idempotent: batch.config.is_idempotent,
                 }
             }
             _ => RoutingInfo {
                 consistency,
                 serial_consistency,
+                is_idempotent: batch.config.is_idempotent,
                 ..Default::default()
             },
         };
@@ -1185,6 +1376,8 @@ impl Session {
         let values = BatchValuesFirstSerialized::new(&values, first_serialized_value);
         let values_ref = &values;
 
+        let timestamp = self.get_timestamp(batch.get_timestamp());
+
         let span = RequestSpan::new_batch();
 
         let run_query_result = self
@@ -1214,6 +1407,7 @@ impl Session {
                                 values_ref,
                                 consistency,
                                 serial_consistency,
+                                timestamp,
                             )
                             .await
                     }
@@ -1349,6 +1543,15 @@ impl Session {
         self.metrics.clone()
     }
 
+    /// Returns the version of the CQL native protocol used to communicate with the cluster.\
+    /// Unless pinned with [`SessionBuilder::protocol_version`](crate::SessionBuilder::protocol_version),
+    /// the version is the highest one supported by all nodes the driver has connected to.
+    /// Options reported by each node can be read with
+    /// [`Node::supported_options`](crate::transport::Node::supported_options).
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version.get()
+    }
+
     /// Access cluster data collected by the driver\
     /// Driver collects various information about network topology or schema.
     /// They can be read using this method
@@ -1356,6 +1559,29 @@ impl Session {
         self.cluster.get_data()
     }
 
+    /// Returns a stream of events pushed by the cluster to the driver:
+    /// topology changes, node status changes and schema changes.\
+    /// Only events received after this call are returned. If the stream is not polled
+    /// often enough, the oldest events are skipped in order to catch up.\
+    /// The stream ends when the session is dropped.
+    pub fn event_stream(&self) -> impl Stream<Item = Event> + Send + Unpin + 'static {
+        let receiver = self.cluster.subscribe_to_server_events();
+        Box::pin(futures::stream::unfold(
+            receiver,
+            |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => return Some((event, receiver)),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Event stream lagged behind, skipped {} events", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            },
+        ))
+    }
+
     /// Get [`TracingInfo`] of a traced query performed earlier
     ///
     /// See [the book](https://rust-driver.docs.scylladb.com/stable/tracing/tracing.html)
@@ -1714,7 +1940,7 @@ impl Session {
                             last_error = %e,
                             "Query failed"
                         );
-                        self.metrics.inc_failed_nonpaged_queries();
+                        self.metrics.inc_failed_nonpaged_queries(&e);
                         execution_profile.load_balancing_policy.on_query_failure(
                             context.query_info,
                             elapsed,
@@ -1774,14 +2000,21 @@ impl Session {
     }
 
     pub async fn await_schema_agreement(&self) -> Result<Uuid, QueryError> {
-        timeout(
-            self.schema_agreement_timeout,
-            self.await_schema_agreement_indefinitely(),
-        )
-        .await
-        .unwrap_or(Err(QueryError::RequestTimeout(
-            "schema agreement not reached in time".to_owned(),
-        )))
+        self.await_timed_schema_agreement(self.schema_agreement_timeout)
+            .await
+    }
+
+    /// Awaits schema agreement just like [`await_schema_agreement`](Session::await_schema_agreement),
+    /// but with the given timeout instead of the one configured in [`SessionConfig`].
+    pub async fn await_timed_schema_agreement(
+        &self,
+        timeout_duration: Duration,
+    ) -> Result<Uuid, QueryError> {
+        timeout(timeout_duration, self.await_schema_agreement_indefinitely())
+            .await
+            .unwrap_or(Err(QueryError::RequestTimeout(
+                "schema agreement not reached in time".to_owned(),
+            )))
     }
 
     pub async fn check_schema_agreement(&self) -> Result<Option<Uuid>, QueryError> {
@@ -1801,6 +2034,117 @@ impl Session {
     pub fn get_default_execution_profile_handle(&self) -> &ExecutionProfileHandle {
         &self.default_execution_profile_handle
     }
+
+    /// Retrieves the handle to execution profile registered in this session under the given name,
+    /// see [`SessionBuilder::execution_profile_handle`](crate::SessionBuilder::execution_profile_handle).
+    /// Returns `None` if no profile was registered with this name.
+    pub fn get_execution_profile_handle(&self, name: &str) -> Option<&ExecutionProfileHandle> {
+        self.execution_profiles.get(name)
+    }
+
+    /// Shuts the session down.
+    ///
+    /// New requests are rejected with [`QueryError::SessionClosed`] right away,
+    /// while requests which are already being executed are given `drain_timeout` to complete.
+    /// Afterwards, the background tasks which keep track of the cluster are stopped
+    /// and all connections are closed. Connections used by requests which
+    /// didn't complete in time are closed once these requests finish.\
+    /// Row iterators which were already created are not waited for, they fail
+    /// to fetch further pages once the connections are closed.
+    ///
+    /// Calling this method again has no effect.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use scylla::Session;
+    /// # use std::error::Error;
+    /// # async fn check_only_compiles(session: &Session) -> Result<(), Box<dyn Error>> {
+    /// use std::time::Duration;
+    ///
+    /// session.shutdown(Duration::from_secs(5)).await;
+    /// assert!(session.query("SELECT * FROM ks.tab", &[]).await.is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, drain_timeout: Duration) {
+        if !self.in_flight_requests.close() {
+            return;
+        }
+        if timeout(drain_timeout, self.in_flight_requests.wait_until_drained())
+            .await
+            .is_err()
+        {
+            warn!(
+                "Not all in-flight requests completed within {:?} of session shutdown",
+                drain_timeout
+            );
+        }
+        self.cluster.shutdown();
+    }
+
+    /// Returns true if the session was shut down with [`Session::shutdown`].
+    pub fn is_closed(&self) -> bool {
+        self.in_flight_requests.is_closed.load(Ordering::SeqCst)
+    }
+}
+
+/// Keeps track of requests being executed by a [`Session`],
+/// so that [`Session::shutdown`] can wait for them to complete.
+#[derive(Default)]
+struct InFlightRequests {
+    is_closed: AtomicBool,
+    count: AtomicUsize,
+    drained: tokio::sync::Notify,
+}
+
+impl InFlightRequests {
+    /// Registers a new request, fails if the session is closed.
+    /// The request is considered complete when the returned guard is dropped.
+    fn start(&self) -> Result<InFlightRequestGuard<'_>, QueryError> {
+        // Incrementing before checking the flag guarantees that either the request
+        // sees the session closed, or the shutdown sees the request in flight.
+        self.count.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightRequestGuard(self);
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(QueryError::SessionClosed);
+        }
+        Ok(guard)
+    }
+
+    /// Fails if the session is closed, without registering a request.
+    fn check_open(&self) -> Result<(), QueryError> {
+        if self.is_closed.load(Ordering::SeqCst) {
+            return Err(QueryError::SessionClosed);
+        }
+        Ok(())
+    }
+
+    /// Rejects new requests. Returns false if already closed.
+    fn close(&self) -> bool {
+        !self.is_closed.swap(true, Ordering::SeqCst)
+    }
+
+    async fn wait_until_drained(&self) {
+        loop {
+            // `notify_waiters` wakes the `Notified` futures created before it was called,
+            // regardless of whether they were polled yet
+            let drained = self.drained.notified();
+            if self.count.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            drained.await;
+        }
+    }
+}
+
+struct InFlightRequestGuard<'a>(&'a InFlightRequests);
+
+impl Drop for InFlightRequestGuard<'_> {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.drained.notify_waiters();
+        }
+    }
 }
 
 // run_query, execute_query, etc have a template type called ResT.
@@ -1877,6 +2221,13 @@ impl<'a> ExecuteQueryContext<'a> {
     }
 }
 
+/// Identifies requests in the logs, unique within the process.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 pub(crate) struct RequestSpan {
     span: tracing::Span,
     speculative_executions: AtomicUsize,
@@ -1889,6 +2240,7 @@ impl RequestSpan {
         let span = trace_span!(
             "Request",
             kind = "unprepared",
+            request_id = next_request_id(),
             contents = contents,
             //
             request_size = request_size,
@@ -1915,6 +2267,7 @@ impl RequestSpan {
         let span = trace_span!(
             "Request",
             kind = "prepared",
+            request_id = next_request_id(),
             partition_key = Empty,
             token = Empty,
             //
@@ -1950,6 +2303,7 @@ impl RequestSpan {
         let span = trace_span!(
             "Request",
             kind = "batch",
+            request_id = next_request_id(),
             //
             request_size = Empty,
             result_size = Empty,
@@ -2,8 +2,8 @@
 
 use super::errors::NewSessionError;
 use super::execution_profile::ExecutionProfileHandle;
-use super::session::{AddressTranslator, Session, SessionConfig};
-use super::Compression;
+use super::session::{AddressTranslator, Session, SessionConfig, WriteCoalescingDelay};
+use super::{Compression, ProtocolVersion};
 
 #[cfg(feature = "cloud")]
 use crate::cloud::{CloudConfig, CloudConfigError};
@@ -13,10 +13,13 @@ use crate::ExecutionProfile;
 use crate::statement::Consistency;
 use crate::transport::connection_pool::PoolSize;
 use crate::transport::host_filter::HostFilter;
+use crate::transport::reconnection_policy::ReconnectionPolicy;
+use crate::transport::timestamp_generator::TimestampGenerator;
+use scylla_cql::frame::MAX_FRAME_BODY_SIZE;
 use std::borrow::Borrow;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 #[cfg(feature = "cloud")]
 use std::path::Path;
 use std::sync::Arc;
@@ -388,6 +391,30 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Set the minimum size in bytes of a request body for it to be compressed.
+    /// Smaller requests are sent uncompressed, which avoids the compression overhead on tiny frames.
+    /// The threshold has no effect if compression is not used.
+    /// The default is 0, which means that all requests are compressed.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::Compression;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .compression(Some(Compression::Snappy))
+    ///     .compression_threshold(512)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compression_threshold(mut self, compression_threshold: usize) -> Self {
+        self.config.compression_threshold = compression_threshold;
+        self
+    }
+
     /// Set the delay for schema agreement check. How often driver should ask if schema is in agreement
     /// The default is 200 milliseconds.
     ///
@@ -436,6 +463,45 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Register an execution profile handle under the given name.
+    /// The handle can be later retrieved with [`Session::get_execution_profile_handle`]
+    /// and assigned to statements, so that each workload is configured in one place.
+    /// Registering another handle under the same name replaces the previous one.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{query::Query, statement::Consistency, ExecutionProfile, Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let oltp = ExecutionProfile::builder()
+    ///     .consistency(Consistency::LocalQuorum)
+    ///     .build();
+    /// let olap = ExecutionProfile::builder()
+    ///     .consistency(Consistency::One)
+    ///     .request_timeout(None)
+    ///     .build();
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .execution_profile_handle("oltp", oltp.into_handle())
+    ///     .execution_profile_handle("olap", olap.into_handle())
+    ///     .build()
+    ///     .await?;
+    ///
+    /// let olap_handle = session.get_execution_profile_handle("olap").unwrap().clone();
+    /// let query = Query::new("SELECT * FROM ks.tab").with_execution_profile_handle(olap_handle);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execution_profile_handle(
+        mut self,
+        name: impl Into<String>,
+        profile_handle: ExecutionProfileHandle,
+    ) -> Self {
+        self.config
+            .execution_profiles
+            .insert(name.into(), profile_handle);
+        self
+    }
+
     /// Set the nodelay TCP flag.
     /// The default is true.
     ///
@@ -530,6 +596,8 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
 
     /// Changes connection timeout
     /// The default is 5 seconds.
+    /// The timeout covers both establishing the TCP connection and the CQL handshake
+    /// (including authentication), so that an unresponsive node fails fast.
     /// If it's higher than underlying os's default connection timeout it won't effect.
     ///
     /// # Example
@@ -575,6 +643,60 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Sets the policy which decides how long connection pools wait before
+    /// trying to open connections again after a failure.
+    /// The default is [`ExponentialBackoffReconnectionPolicy`](crate::reconnection_policy::ExponentialBackoffReconnectionPolicy)
+    /// with a base delay of 50 milliseconds and a maximal delay of 10 seconds.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use scylla::reconnection_policy::ExponentialBackoffReconnectionPolicy;
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .reconnection_policy(Arc::new(ExponentialBackoffReconnectionPolicy::new(
+    ///         Duration::from_millis(100),
+    ///         Duration::from_secs(30),
+    ///     )))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reconnection_policy(mut self, policy: Arc<dyn ReconnectionPolicy>) -> Self {
+        self.config.reconnection_policy = policy;
+        self
+    }
+
+    /// Sets the generator of client-side timestamps, which are attached to requests
+    /// that don't have a timestamp set with `set_timestamp`.
+    /// A timestamp given in the CQL string (`USING TIMESTAMP`) still takes precedence.\
+    /// The default is [`MonotonicTimestampGenerator`](crate::timestamp_generator::MonotonicTimestampGenerator).
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::sync::Arc;
+    /// use scylla::timestamp_generator::MonotonicTimestampGenerator;
+    ///
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .timestamp_generator(Arc::new(MonotonicTimestampGenerator::new()))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn timestamp_generator(mut self, generator: Arc<dyn TimestampGenerator>) -> Self {
+        self.config.timestamp_generator = Some(generator);
+        self
+    }
+
     /// If true, prevents the driver from connecting to the shard-aware port, even if the node supports it.
     ///
     /// _This is a Scylla-specific option_. It has no effect on Cassandra clusters.
@@ -809,6 +931,32 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Set the number of rounds of trying to open the control connection when creating the session.
+    /// The default is 1.
+    ///
+    /// Each round tries all the known nodes, so a single unreachable contact point
+    /// doesn't prevent the session from being created. If all of them fail, the driver
+    /// waits for a delay determined by the [reconnection policy](Self::reconnection_policy)
+    /// and starts another round, until the attempts are exhausted.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::num::NonZeroU32;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_nodes(&["127.0.0.1:9042", "127.0.0.2:9042"])
+    ///     .control_connection_init_attempts(NonZeroU32::new(3).unwrap())
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn control_connection_init_attempts(mut self, attempts: NonZeroU32) -> Self {
+        self.config.control_connection_init_attempts = attempts;
+        self
+    }
+
     /// Set the number of attempts to fetch [TracingInfo](crate::tracing::TracingInfo)
     /// in [`Session::get_tracing_info`].
     /// The default is 5 attempts.
@@ -880,6 +1028,31 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Set the page size used by queries and prepared statements which don't have
+    /// their own page size set. A page size set on a statement takes precedence.
+    /// By default, such statements are not paged, except for the `*_iter` methods,
+    /// which fetch pages of 5000 rows.
+    ///
+    /// The page size must be positive, otherwise [`build()`](SessionBuilder::build)
+    /// fails with [`NewSessionError::InvalidPageSize`].
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .default_page_size(1000)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_page_size(mut self, page_size: i32) -> Self {
+        self.config.default_page_size = Some(page_size);
+        self
+    }
+
     /// If true, the driver will inject a small delay before flushing data
     /// to the socket - by rescheduling the task that writes data to the socket.
     /// This gives the task an opportunity to collect more write requests
@@ -911,6 +1084,162 @@ impl<K: SessionBuilderKind> GenericSessionBuilder<K> {
         self
     }
 
+    /// Sets how long the driver waits for more requests to send them together
+    /// when write coalescing is enabled.
+    ///
+    /// By default, the task writing to the socket is only rescheduled once the queue
+    /// of requests is empty, which takes 1-300µs. Waiting up to a number of milliseconds
+    /// instead reduces the number of syscalls even further under high throughput,
+    /// at the cost of increasing the latency of each request by up to the delay.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::session::WriteCoalescingDelay;
+    /// # use std::num::NonZeroU64;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .write_coalescing_delay(WriteCoalescingDelay::Milliseconds(
+    ///         NonZeroU64::new(1).unwrap(),
+    ///     ))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_coalescing_delay(mut self, delay: WriteCoalescingDelay) -> Self {
+        self.config.write_coalescing_delay = delay;
+        self
+    }
+
+    /// Limits the number of requests sent together in a single write
+    /// when write coalescing is enabled. Once the limit is reached, the requests are
+    /// written to the socket without waiting for more, even if the delay hasn't passed yet.
+    ///
+    /// There is no limit by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::num::NonZeroUsize;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .write_coalescing_max_requests(NonZeroUsize::new(64))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_coalescing_max_requests(mut self, max_requests: Option<NonZeroUsize>) -> Self {
+        self.config.write_coalescing_max_requests = max_requests;
+        self
+    }
+
+    /// Set the size threshold (in bytes) above which executing a batch logs a warning.
+    /// It's meant to mirror the server's `batch_size_warn_threshold`,
+    /// so that oversized batches are noticed before they cause trouble on the server side.
+    ///
+    /// No threshold is set by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .batch_size_warn_threshold(Some(5 * 1024))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn batch_size_warn_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.config.batch_size_warn_threshold = threshold;
+        self
+    }
+
+    /// Set the largest frame body (in bytes) accepted from the server.
+    /// If the server announces a longer frame, the connection is closed with an error
+    /// instead of allocating a buffer for the body.
+    ///
+    /// The default is the protocol's limit of 256 MiB, which is also the largest accepted value.
+    ///
+    /// # Panics
+    /// Panics if the size is 0 or larger than [`MAX_FRAME_BODY_SIZE`].
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .max_frame_body_size(16 * 1024 * 1024)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_frame_body_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "max frame body size must be larger than 0");
+        assert!(
+            size <= MAX_FRAME_BODY_SIZE,
+            "max frame body size must not exceed the protocol limit of 256 MiB"
+        );
+        self.config.max_frame_body_size = size;
+        self
+    }
+
+    /// Pin the version of the CQL protocol used to communicate with the cluster.
+    /// By default the driver negotiates the version: it tries the highest version it supports
+    /// and falls back to older ones when a node rejects it. Pinning the version
+    /// disables the fallback - connecting to nodes which don't support it fails.
+    /// It's mostly useful for testing.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use scylla::transport::ProtocolVersion;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .protocol_version(ProtocolVersion::V4)
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn protocol_version(mut self, version: ProtocolVersion) -> Self {
+        self.config.protocol_version = Some(version);
+        self
+    }
+
+    /// Limit the number of requests executed concurrently with `query`, `execute` and `batch`.
+    /// Requests over the limit wait until one of the running requests completes,
+    /// instead of piling up on the connections. Requests fetching pages with
+    /// `query_iter` and `execute_iter` are not limited.
+    ///
+    /// The number of requests is not limited by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use scylla::{Session, SessionBuilder};
+    /// # use std::num::NonZeroUsize;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let session: Session = SessionBuilder::new()
+    ///     .known_node("127.0.0.1:9042")
+    ///     .max_concurrent_requests(NonZeroUsize::new(1024))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn max_concurrent_requests(mut self, limit: Option<NonZeroUsize>) -> Self {
+        self.config.max_concurrent_requests = limit;
+        self
+    }
+
     /// Set the interval at which the driver refreshes the cluster metadata which contains information
     /// about the cluster topology as well as the cluster schema.
     ///
@@ -949,10 +1278,15 @@ mod tests {
     use scylla_cql::Consistency;
 
     use super::SessionBuilder;
+    use crate::transport::errors::NewSessionError;
     use crate::transport::execution_profile::{defaults, ExecutionProfile};
     use crate::transport::node::KnownNode;
+    use crate::transport::session::WriteCoalescingDelay;
+    use crate::transport::timestamp_generator::TimestampGenerator;
     use crate::transport::Compression;
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::num::{NonZeroU64, NonZeroUsize};
+    use std::sync::Arc;
     use std::time::Duration;
 
     #[test]
@@ -1034,6 +1368,40 @@ mod tests {
         assert_eq!(builder.config.compression, None);
     }
 
+    #[test]
+    fn compression_threshold() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.compression_threshold, 0);
+
+        builder = builder.compression_threshold(1024);
+        assert_eq!(builder.config.compression_threshold, 1024);
+    }
+
+    #[test]
+    fn timestamp_generator() {
+        #[derive(Debug)]
+        struct ConstantTimestampGenerator;
+
+        impl TimestampGenerator for ConstantTimestampGenerator {
+            fn next_timestamp(&self) -> i64 {
+                42
+            }
+        }
+
+        let mut builder = SessionBuilder::new();
+        assert!(builder.config.timestamp_generator.is_some());
+
+        builder = builder.timestamp_generator(Arc::new(ConstantTimestampGenerator));
+        assert_eq!(
+            builder
+                .config
+                .timestamp_generator
+                .as_ref()
+                .map(|generator| generator.next_timestamp()),
+            Some(42)
+        );
+    }
+
     #[test]
     fn tcp_nodelay() {
         let mut builder = SessionBuilder::new();
@@ -1148,6 +1516,142 @@ mod tests {
         );
     }
 
+    #[test]
+    fn named_execution_profiles() {
+        let builder = SessionBuilder::new();
+        assert!(builder.config.execution_profiles.is_empty());
+
+        let profile = |consistency| {
+            ExecutionProfile::builder()
+                .consistency(consistency)
+                .build()
+                .into_handle()
+        };
+        let builder = builder
+            .execution_profile_handle("oltp", profile(Consistency::LocalQuorum))
+            .execution_profile_handle("olap", profile(Consistency::One))
+            .execution_profile_handle("olap", profile(Consistency::Two));
+
+        let profiles = &builder.config.execution_profiles;
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(
+            profiles["oltp"].access().consistency,
+            Consistency::LocalQuorum
+        );
+        assert_eq!(profiles["olap"].access().consistency, Consistency::Two);
+    }
+
+    #[test]
+    fn write_coalescing_delay() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(
+            builder.config.write_coalescing_delay,
+            WriteCoalescingDelay::SmallNondeterministic
+        );
+
+        let delay = WriteCoalescingDelay::Milliseconds(NonZeroU64::new(2).unwrap());
+        builder = builder.write_coalescing_delay(delay);
+        assert_eq!(builder.config.write_coalescing_delay, delay);
+    }
+
+    #[test]
+    fn write_coalescing_max_requests() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.write_coalescing_max_requests, None);
+
+        builder = builder.write_coalescing_max_requests(NonZeroUsize::new(64));
+        assert_eq!(
+            builder.config.write_coalescing_max_requests,
+            NonZeroUsize::new(64)
+        );
+    }
+
+    #[test]
+    fn batch_size_warn_threshold() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.batch_size_warn_threshold, None);
+
+        builder = builder.batch_size_warn_threshold(Some(5 * 1024));
+        assert_eq!(builder.config.batch_size_warn_threshold, Some(5 * 1024));
+
+        builder = builder.batch_size_warn_threshold(None);
+        assert_eq!(builder.config.batch_size_warn_threshold, None);
+    }
+
+    #[test]
+    fn max_frame_body_size() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.max_frame_body_size, 256 * 1024 * 1024);
+
+        builder = builder.max_frame_body_size(1024);
+        assert_eq!(builder.config.max_frame_body_size, 1024);
+    }
+
+    #[test]
+    #[should_panic(expected = "max frame body size must be larger than 0")]
+    fn max_frame_body_size_zero() {
+        SessionBuilder::new().max_frame_body_size(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "max frame body size must not exceed the protocol limit")]
+    fn max_frame_body_size_above_protocol_limit() {
+        SessionBuilder::new().max_frame_body_size(256 * 1024 * 1024 + 1);
+    }
+
+    #[test]
+    #[cfg(feature = "cloud")]
+    fn cloud_session_builder_reads_config() {
+        let builder = super::CloudSessionBuilder::new("src/cloud/full_config.yaml").unwrap();
+        assert!(builder.config.cloud_config.is_some());
+
+        // Default consistencies come from the config's parameters
+        let profile = builder.config.default_execution_profile_handle.access();
+        assert_eq!(profile.consistency, Consistency::LocalQuorum);
+        assert_eq!(profile.serial_consistency, Some(SerialConsistency::Serial));
+
+        assert!(super::CloudSessionBuilder::new("src/cloud/no_such_config.yaml").is_err());
+    }
+
+    #[test]
+    fn max_concurrent_requests() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.max_concurrent_requests, None);
+
+        builder = builder.max_concurrent_requests(NonZeroUsize::new(16));
+        assert_eq!(
+            builder.config.max_concurrent_requests,
+            NonZeroUsize::new(16)
+        );
+
+        builder = builder.max_concurrent_requests(None);
+        assert_eq!(builder.config.max_concurrent_requests, None);
+    }
+
+    #[test]
+    fn default_page_size() {
+        let mut builder = SessionBuilder::new();
+        assert_eq!(builder.config.default_page_size, None);
+
+        builder = builder.default_page_size(10);
+        assert_eq!(builder.config.default_page_size, Some(10));
+    }
+
+    #[tokio::test]
+    async fn default_page_size_not_positive() {
+        for page_size in [0, -1] {
+            let result = SessionBuilder::new()
+                .known_node("127.0.0.1:9042")
+                .default_page_size(page_size)
+                .build()
+                .await;
+            assert!(matches!(
+                result,
+                Err(NewSessionError::InvalidPageSize(size)) if size == page_size
+            ));
+        }
+    }
+
     #[test]
     fn cluster_metadata_refresh_interval() {
         let builder = SessionBuilder::new();
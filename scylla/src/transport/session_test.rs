@@ -1,38 +1,46 @@
 use crate as scylla;
-use crate::batch::{Batch, BatchStatement};
-use crate::frame::response::result::Row;
+use crate::batch::{Batch, BatchStatement, BatchType};
+use crate::frame::response::event::{Event, SchemaChangeEvent, SchemaChangeType};
+use crate::frame::response::result::{ColumnType, CqlValue, Row};
 use crate::frame::value::ValueList;
 use crate::prepared_statement::PreparedStatement;
 use crate::query::Query;
 use crate::retry_policy::{QueryInfo, RetryDecision, RetryPolicy, RetrySession};
 use crate::routing::Token;
-use crate::statement::Consistency;
+use crate::statement::{Consistency, SerialConsistency};
 use crate::tracing::TracingInfo;
 use crate::transport::cluster::Datacenter;
+use crate::transport::connection_pool::PoolSize;
 use crate::transport::errors::{BadKeyspaceName, BadQuery, DbError, QueryError};
+use crate::transport::node::Node;
 use crate::transport::partitioner::{
     calculate_token_for_partition_key, Murmur3Partitioner, Partitioner, PartitionerName,
 };
+use crate::transport::timestamp_generator::TimestampGenerator;
 use crate::transport::topology::Strategy::NetworkTopologyStrategy;
 use crate::transport::topology::{
-    CollectionType, ColumnKind, CqlType, NativeType, UserDefinedType,
+    ClusteringOrder, CollectionType, ColumnKind, CqlType, NativeType, UserDefinedType,
 };
+use crate::transport::{Compression, ProtocolVersion};
 use crate::utils::test_utils::{
     create_new_session_builder, supports_feature, unique_keyspace_name,
 };
 use crate::CachingSession;
 use crate::ExecutionProfile;
+use crate::QueryErrorKind;
 use crate::QueryResult;
 use crate::{IntoTypedRows, Session, SessionBuilder};
 use assert_matches::assert_matches;
 use bytes::Bytes;
 use futures::{FutureExt, StreamExt, TryStreamExt};
 use itertools::Itertools;
-use scylla_cql::frame::value::Value;
+use scylla_cql::frame::value::{MaybeUnset, SerializedValues, Unset, Value};
 use std::collections::BTreeSet;
 use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use uuid::Uuid;
 
@@ -158,6 +166,205 @@ async fn test_unprepared_statement() {
     assert_eq!(results_from_manual_paging, rs);
 }
 
+#[tokio::test]
+async fn test_query_with_paging_state() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    for b in 0..10 {
+        session
+            .query(format!("INSERT INTO {}.t (a, b) VALUES (0, ?)", ks), (b,))
+            .await
+            .unwrap();
+    }
+
+    let query = Query::new(format!("SELECT b FROM {}.t WHERE a = 0", ks)).with_page_size(6);
+    let page1 = session.query(query.clone(), &[]).await.unwrap();
+    let paging_state = page1.paging_state.clone().unwrap();
+    let rows1: Vec<i32> = page1
+        .rows_typed::<(i32,)>()
+        .unwrap()
+        .map(|r| r.unwrap().0)
+        .collect();
+    assert_eq!(rows1, (0..6).collect::<Vec<_>>());
+
+    // The paging state is opaque, but it can be stored and used later
+    let stored = paging_state.to_vec();
+    let query = query.with_paging_state(Bytes::from(stored));
+    assert_eq!(query.get_paging_state(), Some(&paging_state));
+
+    let page2 = session.query(query.clone(), &[]).await.unwrap();
+    let rows2: Vec<i32> = page2
+        .rows_typed::<(i32,)>()
+        .unwrap()
+        .map(|r| r.unwrap().0)
+        .collect();
+    assert_eq!(rows2, (6..10).collect::<Vec<_>>());
+
+    // query_iter resumes from the paging state as well
+    let rows_iter: Vec<i32> = session
+        .query_iter(query, &[])
+        .await
+        .unwrap()
+        .into_typed::<(i32,)>()
+        .map_ok(|(b,)| b)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows_iter, rows2);
+}
+
+#[tokio::test]
+async fn test_default_page_size() {
+    let session = create_new_session_builder()
+        .default_page_size(10)
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (0, ?)", ks))
+        .await
+        .unwrap();
+    for b in 0..100 {
+        session.execute(&insert, (b,)).await.unwrap();
+    }
+
+    let select = format!("SELECT b FROM {}.t WHERE a = 0", ks);
+
+    // Statements without their own page size use the default one
+    let page = session.query(select.as_str(), &[]).await.unwrap();
+    assert!(page.paging_state.is_some());
+    assert!(page.rows_num().unwrap() <= 10);
+
+    let prepared = session.prepare(select.as_str()).await.unwrap();
+    assert_eq!(prepared.get_page_size(), Some(10));
+    let page = session.execute(&prepared, &[]).await.unwrap();
+    assert!(page.paging_state.is_some());
+    assert!(page.rows_num().unwrap() <= 10);
+
+    // The page size of a statement takes precedence
+    let query = Query::new(select.as_str()).with_page_size(50);
+    let page = session.query(query, &[]).await.unwrap();
+    assert!(page.paging_state.is_some());
+    assert_eq!(page.rows_num().unwrap(), 50);
+
+    // Iterators fetch all pages
+    let rows_num = session
+        .query_iter(select.as_str(), &[])
+        .await
+        .unwrap()
+        .count()
+        .await;
+    assert_eq!(rows_num, 100);
+}
+
+#[tokio::test]
+async fn test_shutdown() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let prepared = session
+        .prepare("SELECT host_id FROM system.local")
+        .await
+        .unwrap();
+    session
+        .query("SELECT host_id FROM system.local", &[])
+        .await
+        .unwrap();
+    assert!(!session.is_closed());
+
+    session.shutdown(Duration::from_secs(5)).await;
+    assert!(session.is_closed());
+    assert!(session.get_cluster_data().get_nodes_info().is_empty());
+
+    assert_matches!(
+        session.query("SELECT host_id FROM system.local", &[]).await,
+        Err(QueryError::SessionClosed)
+    );
+    assert_matches!(
+        session.execute(&prepared, &[]).await,
+        Err(QueryError::SessionClosed)
+    );
+    assert_matches!(
+        session.prepare("SELECT host_id FROM system.local").await,
+        Err(QueryError::SessionClosed)
+    );
+    assert_matches!(
+        session
+            .query_iter("SELECT host_id FROM system.local", &[])
+            .await
+            .map(|_| ()),
+        Err(QueryError::SessionClosed)
+    );
+    assert_matches!(
+        session.refresh_metadata().await,
+        Err(QueryError::SessionClosed)
+    );
+    assert_matches!(
+        session.check_schema_agreement().await,
+        Err(QueryError::SessionClosed)
+    );
+
+    // Shutting down again has no effect
+    session.shutdown(Duration::from_secs(5)).await;
+    assert!(session.is_closed());
+}
+
+#[tokio::test]
+async fn test_max_concurrent_requests() {
+    let session = Arc::new(
+        create_new_session_builder()
+            .max_concurrent_requests(NonZeroUsize::new(4))
+            .build()
+            .await
+            .unwrap(),
+    );
+    let prepared = session
+        .prepare("SELECT host_id FROM system.local")
+        .await
+        .unwrap();
+
+    // Far more requests than slots, they all wait for their turn instead of failing
+    let requests = (0..256)
+        .map(|i| {
+            let session = session.clone();
+            let prepared = prepared.clone();
+            tokio::spawn(async move {
+                if i % 2 == 0 {
+                    session.query("SELECT host_id FROM system.local", &[]).await
+                } else {
+                    session.execute(&prepared, &[]).await
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for request in requests {
+        request.await.unwrap().unwrap();
+    }
+}
+
 #[tokio::test]
 async fn test_prepared_statement() {
     let session = create_new_session_builder().build().await.unwrap();
@@ -229,7 +436,8 @@ async fn test_prepared_statement() {
                 .as_ref()
                 .unwrap()
                 .as_bigint()
-                .unwrap(),
+                .unwrap()
+                .into(),
         };
         let prepared_token = Murmur3Partitioner.hash_one(
             &prepared_statement
@@ -255,8 +463,15 @@ async fn test_prepared_statement() {
                 .as_ref()
                 .unwrap()
                 .as_bigint()
-                .unwrap(),
+                .unwrap()
+                .into(),
         };
+        let pk_indexes: Vec<u16> = prepared_complex_pk_statement
+            .partition_key_indexes()
+            .iter()
+            .map(|pki| pki.index)
+            .collect();
+        assert_eq!(pk_indexes, vec![0, 1, 2]);
         let prepared_token = Murmur3Partitioner.hash_one(
             &prepared_complex_pk_statement
                 .compute_partition_key(&serialized_values)
@@ -477,158 +692,860 @@ async fn test_batch() {
         .collect();
 
     assert_eq!(results, vec![(4, 20, &String::from("foobar"))]);
+
+    // Values count must match the number of statements in the batch
+    session
+        .batch(&batch, Vec::<(i32, i32, &str)>::new())
+        .await
+        .unwrap_err();
+    session
+        .batch(&batch, ((5_i32, 1_i32, "a"), (5_i32, 2_i32, "b")))
+        .await
+        .unwrap_err();
 }
 
 #[tokio::test]
-async fn test_token_calculation() {
+async fn test_batch_too_many_statements() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    let n_statements = 70000;
+    let mut batch: Batch = Batch::default();
+    for _ in 0..n_statements {
+        batch.append_statement("SELECT host_id FROM system.local");
+    }
+
+    // The batch is rejected before it is sent
+    let err = session
+        .batch(&batch, vec![(); n_statements])
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        QueryError::BadQuery(BadQuery::TooManyQueriesInBatchStatement(70000))
+    );
+}
+
+#[tokio::test]
+async fn test_named_values_reordering_and_mismatch() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
     session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
         .query(
-            format!("CREATE TABLE IF NOT EXISTS {}.t3 (a text primary key)", ks),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_named (pk int, ck int, v text, primary key (pk, ck))",
+                ks
+            ),
             &[],
         )
         .await
         .unwrap();
 
-    // Refresh metadata as `ClusterData::compute_token` use them
-    session.await_schema_agreement().await.unwrap();
-    session.refresh_metadata().await.unwrap();
-
-    let prepared_statement = session
-        .prepare(format!("INSERT INTO {}.t3 (a) VALUES (?)", ks))
+    let insert = session
+        .prepare(format!(
+            "INSERT INTO {}.t_named (pk, ck, v) VALUES (:pk, :ck, :v)",
+            ks
+        ))
         .await
         .unwrap();
 
-    // Try calculating tokens for different sizes of the key
-    for i in 1..50usize {
-        eprintln!("Trying key size {}", i);
-        let mut s = String::new();
-        for _ in 0..i {
-            s.push('a');
-        }
-        let values = (&s,);
-        let serialized_values = values.serialized().unwrap().into_owned();
-        session.execute(&prepared_statement, &values).await.unwrap();
+    // Values are provided in a different order than the markers in the statement
+    let mut values: BTreeMap<&str, CqlValue> = BTreeMap::new();
+    values.insert("v", CqlValue::Text("abc".to_string()));
+    values.insert("ck", CqlValue::Int(2));
+    values.insert("pk", CqlValue::Int(1));
+    session.execute(&insert, &values).await.unwrap();
 
-        let rs = session
-            .query(
-                format!("SELECT token(a) FROM {}.t3 WHERE a = ?", ks),
-                &values,
-            )
-            .await
-            .unwrap()
-            .rows
-            .unwrap();
-        let token = Token {
-            value: rs.first().unwrap().columns[0]
-                .as_ref()
-                .unwrap()
-                .as_bigint()
-                .unwrap(),
-        };
-        let prepared_token = Murmur3Partitioner.hash_one(
-            &prepared_statement
-                .compute_partition_key(&serialized_values)
-                .unwrap(),
-        );
-        assert_eq!(token, prepared_token);
-        let cluster_data_token = session
-            .get_cluster_data()
-            .compute_token(&ks, "t3", &serialized_values)
-            .unwrap();
-        assert_eq!(token, cluster_data_token);
+    let select = session
+        .prepare(format!(
+            "SELECT pk, ck, v FROM {}.t_named WHERE ck = :ck AND pk = :pk",
+            ks
+        ))
+        .await
+        .unwrap();
+    let mut key: HashMap<&str, i32> = HashMap::new();
+    key.insert("pk", 1);
+    key.insert("ck", 2);
+    let row = session
+        .execute(&select, &key)
+        .await
+        .unwrap()
+        .single_row_typed::<(i32, i32, String)>()
+        .unwrap();
+    assert_eq!(row, (1, 2, "abc".to_string()));
+
+    // Missing and unknown names are reported
+    let mut bad_key: HashMap<&str, i32> = HashMap::new();
+    bad_key.insert("pk", 1);
+    bad_key.insert("c", 2);
+    let err = session.execute(&select, &bad_key).await.unwrap_err();
+    match err {
+        QueryError::BadQuery(BadQuery::NamedValuesMismatch(mismatch)) => {
+            assert_eq!(mismatch.missing, vec!["ck".to_string()]);
+            assert_eq!(mismatch.unknown, vec!["c".to_string()]);
+        }
+        other => panic!("Unexpected error: {:?}", other),
     }
 }
 
 #[tokio::test]
-async fn test_token_awareness() {
+async fn test_unset_values_preserve_columns() {
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
     session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
     session
         .query(
-            format!("CREATE TABLE IF NOT EXISTS {}.t (a text primary key)", ks),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_unset (pk int primary key, a int, b text)",
+                ks
+            ),
             &[],
         )
         .await
         .unwrap();
 
-    let mut prepared_statement = session
-        .prepare(format!("INSERT INTO {}.t (a) VALUES (?)", ks))
+    let insert = session
+        .prepare(format!(
+            "INSERT INTO {}.t_unset (pk, a, b) VALUES (?, ?, ?)",
+            ks
+        ))
+        .await
+        .unwrap();
+    session
+        .execute(&insert, (1_i32, 10_i32, "abc"))
         .await
         .unwrap();
-    prepared_statement.set_tracing(true);
-
-    // The default policy should be token aware
-    for size in 1..50usize {
-        let key = vec!['a'; size].into_iter().collect::<String>();
-        let values = (&key,);
-
-        // Execute a query and observe tracing info
-        let res = session.execute(&prepared_statement, values).await.unwrap();
-        let tracing_info = session
-            .get_tracing_info(res.tracing_id.as_ref().unwrap())
-            .await
-            .unwrap();
-
-        // Verify that only one node was involved
-        assert_eq!(tracing_info.nodes().len(), 1);
 
-        // Do the same with execute_iter (it now works with writes)
-        let iter = session
-            .execute_iter(prepared_statement.clone(), values)
-            .await
-            .unwrap();
-        let tracing_id = iter.get_tracing_ids()[0];
-        let tracing_info = session.get_tracing_info(&tracing_id).await.unwrap();
+    // Unset columns are left untouched, while the set ones are overwritten
+    session
+        .execute(&insert, (1_i32, MaybeUnset::Set(20_i32), Unset))
+        .await
+        .unwrap();
+    let row = session
+        .query(format!("SELECT a, b FROM {}.t_unset WHERE pk = 1", ks), &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(i32, String)>()
+        .unwrap();
+    assert_eq!(row, (20, "abc".to_string()));
 
-        // Again, verify that only one node was involved
-        assert_eq!(tracing_info.nodes().len(), 1);
-    }
+    session
+        .execute(&insert, (1_i32, MaybeUnset::<i32>::Unset, "def"))
+        .await
+        .unwrap();
+    let row = session
+        .query(format!("SELECT a, b FROM {}.t_unset WHERE pk = 1", ks), &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(i32, String)>()
+        .unwrap();
+    assert_eq!(row, (20, "def".to_string()));
 }
 
 #[tokio::test]
-async fn test_use_keyspace() {
+async fn test_derive_from_row() {
+    #[derive(scylla::FromRow, Debug, PartialEq)]
+    struct Person {
+        id: i32,
+        name: String,
+        email: Option<String>,
+        scores: Vec<i32>,
+    }
+
     let session = create_new_session_builder().build().await.unwrap();
     let ks = unique_keyspace_name();
 
     session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
-
     session
         .query(
-            format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_from_row (id int primary key, name text, email text, scores list<int>)",
+                ks
+            ),
             &[],
         )
         .await
         .unwrap();
 
+    let insert = session
+        .prepare(format!(
+            "INSERT INTO {}.t_from_row (id, name, email, scores) VALUES (?, ?, ?, ?)",
+            ks
+        ))
+        .await
+        .unwrap();
     session
-        .query(format!("INSERT INTO {}.tab (a) VALUES ('test1')", ks), &[])
+        .execute(
+            &insert,
+            (1_i32, "Alice", Some("alice@example.com"), vec![1_i32, 2]),
+        )
         .await
         .unwrap();
-
-    session.use_keyspace(ks.clone(), false).await.unwrap();
-
     session
-        .query("INSERT INTO tab (a) VALUES ('test2')", &[])
+        .execute(&insert, (2_i32, "Bob", None::<&str>, vec![3_i32]))
         .await
         .unwrap();
 
-    let mut rows: Vec<String> = session
-        .query("SELECT * FROM tab", &[])
+    let mut people: Vec<Person> = session
+        .query(
+            format!("SELECT id, name, email, scores FROM {}.t_from_row", ks),
+            &[],
+        )
         .await
         .unwrap()
         .rows
         .unwrap()
-        .into_typed::<(String,)>()
-        .map(|res| res.unwrap().0)
+        .into_typed::<Person>()
+        .map(Result::unwrap)
         .collect();
+    people.sort_by_key(|person| person.id);
 
-    rows.sort();
+    assert_eq!(
+        people,
+        vec![
+            Person {
+                id: 1,
+                name: "Alice".to_string(),
+                email: Some("alice@example.com".to_string()),
+                scores: vec![1, 2],
+            },
+            Person {
+                id: 2,
+                name: "Bob".to_string(),
+                email: None,
+                scores: vec![3],
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_derive_value_list_with_lifetime() {
+    #[derive(scylla::ValueList)]
+    struct NewUser<'a> {
+        id: i32,
+        name: &'a str,
+        email: Option<&'a str>,
+    }
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_value_list (id int primary key, name text, email text)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let name = String::from("Alice");
+    let user = NewUser {
+        id: 1,
+        name: &name,
+        email: None,
+    };
+    let insert = session
+        .prepare(format!(
+            "INSERT INTO {}.t_value_list (id, name, email) VALUES (?, ?, ?)",
+            ks
+        ))
+        .await
+        .unwrap();
+    session.execute(&insert, &user).await.unwrap();
+
+    let row = session
+        .query(
+            format!("SELECT id, name, email FROM {}.t_value_list", ks),
+            &[],
+        )
+        .await
+        .unwrap()
+        .single_row_typed::<(i32, String, Option<String>)>()
+        .unwrap();
+    assert_eq!(row, (1, "Alice".to_string(), None));
+}
+
+#[tokio::test]
+async fn test_dynamic_serialized_values() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+
+    // The number of columns is known only at runtime
+    let columns_num = 5;
+    let columns: Vec<String> = (0..columns_num).map(|i| format!("c{}", i)).collect();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_dynamic (pk int primary key, {})",
+                ks,
+                columns.iter().map(|c| format!("{} int", c)).join(", ")
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let insert_str = format!(
+        "INSERT INTO {}.t_dynamic (pk, {}) VALUES (?, {})",
+        ks,
+        columns.join(", "),
+        columns.iter().map(|_| "?").join(", ")
+    );
+
+    let mut values = SerializedValues::new();
+    values.add_value(&0_i32).unwrap();
+    for i in 0..columns_num {
+        values.add_value(&(i * 10)).unwrap();
+    }
+    session.query(insert_str.clone(), &values).await.unwrap();
+
+    // The same values can be passed to a prepared statement
+    let prepared = session.prepare(insert_str).await.unwrap();
+    let mut values = SerializedValues::new();
+    values.add_value(&1_i32).unwrap();
+    for i in 0..columns_num {
+        values.add_value(&(i * 100)).unwrap();
+    }
+    session.execute(&prepared, values).await.unwrap();
+
+    let mut rows: Vec<Vec<i32>> = session
+        .query(
+            format!("SELECT pk, {} FROM {}.t_dynamic", columns.join(", "), ks),
+            &[],
+        )
+        .await
+        .unwrap()
+        .rows
+        .unwrap()
+        .into_iter()
+        .map(|row| {
+            row.columns
+                .into_iter()
+                .map(|col| col.unwrap().as_int().unwrap())
+                .collect()
+        })
+        .collect();
+    rows.sort();
+
+    assert_eq!(
+        rows,
+        vec![vec![0, 0, 10, 20, 30, 40], vec![1, 0, 100, 200, 300, 400]]
+    );
+}
+
+#[tokio::test]
+async fn test_large_tuple_round_trip() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_large_tuple (pk int primary key, \
+                c1 text, c2 bigint, c3 boolean, c4 double, c5 float, c6 smallint, \
+                c7 tinyint, c8 blob, c9 ascii, c10 int, c11 text)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    type LargeTuple = (
+        i32,
+        String,
+        i64,
+        bool,
+        f64,
+        f32,
+        i16,
+        i8,
+        Vec<u8>,
+        String,
+        Option<i32>,
+        Option<String>,
+    );
+    let tuple: LargeTuple = (
+        0,
+        "text".to_string(),
+        1,
+        true,
+        2.5,
+        3.5,
+        4,
+        5,
+        vec![6, 7],
+        "ascii".to_string(),
+        Some(8),
+        None,
+    );
+
+    let insert_str = format!(
+        "INSERT INTO {}.t_large_tuple (pk, c1, c2, c3, c4, c5, c6, c7, c8, c9, c10, c11) \
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        ks
+    );
+    session.query(insert_str.clone(), &tuple).await.unwrap();
+
+    let prepared = session.prepare(insert_str).await.unwrap();
+    let mut tuple2 = tuple.clone();
+    tuple2.0 = 1;
+    session.execute(&prepared, &tuple2).await.unwrap();
+
+    let mut rows: Vec<LargeTuple> = session
+        .query(
+            format!(
+                "SELECT pk, c1, c2, c3, c4, c5, c6, c7, c8, c9, c10, c11 FROM {}.t_large_tuple",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap()
+        .rows_typed::<LargeTuple>()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    rows.sort_by_key(|row| row.0);
+
+    assert_eq!(rows, vec![tuple, tuple2]);
+}
+
+#[tokio::test]
+async fn test_result_column_specs() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_col_specs (id int primary key, val list<text>)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // Metadata is present even if no rows were returned
+    let result = session
+        .query(format!("SELECT id, val FROM {}.t_col_specs", ks), &[])
+        .await
+        .unwrap();
+
+    let specs = result.column_specs();
+    assert_eq!(
+        specs
+            .iter()
+            .map(|spec| (spec.name.as_str(), spec.typ.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("id", ColumnType::Int),
+            ("val", ColumnType::List(Box::new(ColumnType::Text))),
+        ]
+    );
+    for spec in specs {
+        assert_eq!(spec.table_spec.ks_name, ks);
+        assert_eq!(spec.table_spec.table_name, "t_col_specs");
+    }
+}
+
+#[tokio::test]
+async fn test_metrics() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_metrics (a int primary key)",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let metrics = session.get_metrics();
+    let queries_before = metrics.get_queries_num();
+    let errors_before = metrics.get_errors_num();
+    let db_errors_before = metrics.get_errors_num_by_kind(QueryErrorKind::Database);
+
+    const QUERIES_NUM: u64 = 100;
+    for i in 0..QUERIES_NUM as i32 {
+        session
+            .query(format!("INSERT INTO {}.t_metrics (a) VALUES (?)", ks), (i,))
+            .await
+            .unwrap();
+    }
+    assert_eq!(metrics.get_queries_num() - queries_before, QUERIES_NUM);
+    assert_eq!(metrics.get_errors_num(), errors_before);
+
+    let p50 = metrics.get_latency_percentile_ms(50.0).unwrap();
+    let p99 = metrics.get_latency_percentile_ms(99.0).unwrap();
+    assert!(p99 >= p50);
+    metrics.get_latency_avg_ms().unwrap();
+
+    // A syntax error is not retried, so it is counted exactly once
+    session
+        .query(format!("INSERT INTO {}.t_metrics (a) VALUES", ks), &[])
+        .await
+        .unwrap_err();
+    assert_eq!(metrics.get_errors_num() - errors_before, 1);
+    assert_eq!(
+        metrics.get_errors_num_by_kind(QueryErrorKind::Database) - db_errors_before,
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_query_emits_span_with_chosen_node() {
+    use std::sync::Mutex;
+    use tracing::instrument::WithSubscriber;
+    use tracing::span::{Attributes, Id};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+
+    // (span name, parent span name, recorded fields)
+    type CapturedSpans = Arc<Mutex<Vec<(String, Option<String>, HashMap<String, String>)>>>;
+
+    struct FieldsVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl tracing::field::Visit for FieldsVisitor<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_owned(), format!("{:?}", value));
+        }
+    }
+
+    struct CapturingLayer(CapturedSpans);
+
+    impl<S> tracing_subscriber::Layer<S> for CapturingLayer
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut fields = HashMap::new();
+            attrs.record(&mut FieldsVisitor(&mut fields));
+            let parent = ctx
+                .span(id)
+                .and_then(|span| span.parent())
+                .map(|parent| parent.name().to_owned());
+            self.0
+                .lock()
+                .unwrap()
+                .push((attrs.metadata().name().to_owned(), parent, fields));
+        }
+    }
+
+    let session = create_new_session_builder().build().await.unwrap();
+
+    let captured: CapturedSpans = Default::default();
+    let subscriber = tracing_subscriber::registry().with(CapturingLayer(captured.clone()));
+    session
+        .query("SELECT host_id FROM system.local", &[])
+        .with_subscriber(subscriber)
+        .await
+        .unwrap();
+
+    let captured = captured.lock().unwrap();
+    let (_, _, request_fields) = captured
+        .iter()
+        .find(|(name, _, _)| name == "Request")
+        .expect("No request span emitted");
+    assert_eq!(request_fields.get("kind").unwrap(), "\"unprepared\"");
+    assert!(request_fields.contains_key("request_id"));
+
+    let node_addresses: Vec<String> = session
+        .get_cluster_data()
+        .get_nodes_info()
+        .iter()
+        .map(|node| node.address.to_string())
+        .collect();
+    let (_, parent, execution_fields) = captured
+        .iter()
+        .find(|(name, _, _)| name == "Executing query")
+        .expect("No query execution span emitted");
+    assert_eq!(parent.as_deref(), Some("Request"));
+    assert!(node_addresses.contains(execution_fields.get("node").unwrap()));
+}
+
+#[tokio::test]
+async fn test_large_batch_warning() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_large_batch (a int, b int, c text, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let prepared_statement = session
+        .prepare(format!(
+            "INSERT INTO {}.t_large_batch (a, b, c) VALUES (?, ?, ?)",
+            ks
+        ))
+        .await
+        .unwrap();
+
+    // A small batch doesn't cause any warnings
+    let mut small_batch: Batch = Default::default();
+    small_batch.append_statement(prepared_statement.clone());
+    let small_result = session
+        .batch(&small_batch, ((0_i32, 0_i32, "abc"),))
+        .await
+        .unwrap();
+    assert!(small_result.warnings().is_empty());
+
+    // ~200 KiB exceeds the default batch size warning threshold (128 KiB),
+    // but stays below the threshold at which the batch is rejected
+    let statements_num = 20;
+    let big_text = "a".repeat(10 * 1024);
+    let mut large_batch: Batch = Default::default();
+    for _ in 0..statements_num {
+        large_batch.append_statement(prepared_statement.clone());
+    }
+    let values: Vec<(i32, i32, &str)> = (0..statements_num)
+        .map(|b| (0_i32, b, big_text.as_str()))
+        .collect();
+
+    let large_result = session.batch(&large_batch, values).await.unwrap();
+    assert!(
+        large_result
+            .warnings()
+            .iter()
+            .any(|warning| warning.to_lowercase().contains("batch")),
+        "Expected a large batch warning, got: {:?}",
+        large_result.warnings()
+    );
+}
+
+#[tokio::test]
+async fn test_token_calculation() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!("CREATE TABLE IF NOT EXISTS {}.t3 (a text primary key)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // Refresh metadata as `ClusterData::compute_token` use them
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    let prepared_statement = session
+        .prepare(format!("INSERT INTO {}.t3 (a) VALUES (?)", ks))
+        .await
+        .unwrap();
+
+    // Try calculating tokens for different sizes of the key
+    for i in 1..50usize {
+        eprintln!("Trying key size {}", i);
+        let mut s = String::new();
+        for _ in 0..i {
+            s.push('a');
+        }
+        let values = (&s,);
+        let serialized_values = values.serialized().unwrap().into_owned();
+        session.execute(&prepared_statement, &values).await.unwrap();
+
+        let rs = session
+            .query(
+                format!("SELECT token(a) FROM {}.t3 WHERE a = ?", ks),
+                &values,
+            )
+            .await
+            .unwrap()
+            .rows
+            .unwrap();
+        let token = Token {
+            value: rs.first().unwrap().columns[0]
+                .as_ref()
+                .unwrap()
+                .as_bigint()
+                .unwrap()
+                .into(),
+        };
+        let prepared_token = Murmur3Partitioner.hash_one(
+            &prepared_statement
+                .compute_partition_key(&serialized_values)
+                .unwrap(),
+        );
+        assert_eq!(token, prepared_token);
+        let cluster_data_token = session
+            .get_cluster_data()
+            .compute_token(&ks, "t3", &serialized_values)
+            .unwrap();
+        assert_eq!(token, cluster_data_token);
+    }
+}
+
+#[tokio::test]
+async fn test_token_awareness() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!("CREATE TABLE IF NOT EXISTS {}.t (a text primary key)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let mut prepared_statement = session
+        .prepare(format!("INSERT INTO {}.t (a) VALUES (?)", ks))
+        .await
+        .unwrap();
+    prepared_statement.set_tracing(true);
+
+    // Refresh metadata as `ClusterData::get_endpoints` use them
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    // The default policy should be token aware
+    for size in 1..50usize {
+        let key = vec!['a'; size].into_iter().collect::<String>();
+        let values = (&key,);
+
+        // Execute a query and observe tracing info
+        let res = session.execute(&prepared_statement, values).await.unwrap();
+        let tracing_info = session
+            .get_tracing_info(res.tracing_id.as_ref().unwrap())
+            .await
+            .unwrap();
+
+        // Verify that only one node was involved
+        assert_eq!(tracing_info.nodes().len(), 1);
+
+        // Verify that the query was sent to the only replica of the key
+        let replicas = session
+            .get_cluster_data()
+            .get_endpoints(&ks, "t", values)
+            .unwrap();
+        assert_eq!(replicas.len(), 1);
+        assert_eq!(tracing_info.coordinator, Some(replicas[0].address.ip()));
+
+        // Do the same with execute_iter (it now works with writes)
+        let iter = session
+            .execute_iter(prepared_statement.clone(), values)
+            .await
+            .unwrap();
+        let tracing_id = iter.get_tracing_ids()[0];
+        let tracing_info = session.get_tracing_info(&tracing_id).await.unwrap();
+
+        // Again, verify that only one node was involved
+        assert_eq!(tracing_info.nodes().len(), 1);
+    }
+}
+
+#[tokio::test]
+async fn test_cluster_data_introspection() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    // The coordinator describes itself in system.local
+    let (host_id, datacenter, rack) = session
+        .query("SELECT host_id, data_center, rack FROM system.local", &[])
+        .await
+        .unwrap()
+        .single_row_typed::<(Uuid, Option<String>, Option<String>)>()
+        .unwrap();
+    let datacenter = datacenter.unwrap();
+
+    let ks = unique_keyspace_name();
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', '{}' : 1}}", ks, datacenter), &[]).await.unwrap();
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    let cluster_data = session.get_cluster_data();
+    let node = cluster_data
+        .get_nodes_info()
+        .iter()
+        .find(|node| node.host_id == host_id)
+        .expect("The coordinator is not present in cluster data");
+    assert_eq!(node.datacenter.as_deref(), Some(datacenter.as_str()));
+    assert_eq!(node.rack, rack);
+
+    assert!(cluster_data
+        .get_datacenters_info()
+        .get(&datacenter)
+        .unwrap()
+        .nodes
+        .iter()
+        .any(|dc_node| dc_node.host_id == host_id));
+
+    let keyspace = cluster_data.get_keyspace_info().get(&ks).unwrap();
+    assert_eq!(
+        keyspace.strategy,
+        NetworkTopologyStrategy {
+            datacenter_repfactors: [(datacenter, 1)].into_iter().collect()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_use_keyspace() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+
+    session
+        .query(
+            format!("CREATE TABLE IF NOT EXISTS {}.tab (a text primary key)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    session
+        .query(format!("INSERT INTO {}.tab (a) VALUES ('test1')", ks), &[])
+        .await
+        .unwrap();
+
+    session.use_keyspace(ks.clone(), false).await.unwrap();
+
+    session
+        .query("INSERT INTO tab (a) VALUES ('test2')", &[])
+        .await
+        .unwrap();
+
+    let mut rows: Vec<String> = session
+        .query("SELECT * FROM tab", &[])
+        .await
+        .unwrap()
+        .rows
+        .unwrap()
+        .into_typed::<(String,)>()
+        .map(|res| res.unwrap().0)
+        .collect();
+
+    rows.sort();
 
     assert_eq!(rows, vec!["test1".to_string(), "test2".to_string()]);
 
@@ -970,7 +1887,7 @@ async fn test_tracing_prepare(session: &Session, ks: String) {
 async fn test_get_tracing_info(session: &Session, ks: String) {
     // A query with tracing enabled has a tracing uuid in result
     let mut traced_query: Query = Query::new(format!("SELECT * FROM {}.tab", ks));
-    traced_query.config.tracing = true;
+    traced_query.set_tracing(true);
 
     let traced_query_result: QueryResult = session.query(traced_query, &[]).await.unwrap();
     let tracing_id: Uuid = traced_query_result.tracing_id.unwrap();
@@ -979,6 +1896,14 @@ async fn test_get_tracing_info(session: &Session, ks: String) {
     let tracing_info: TracingInfo = session.get_tracing_info(&tracing_id).await.unwrap();
     assert!(!tracing_info.events.is_empty());
     assert!(!tracing_info.nodes().is_empty());
+
+    // The coordinator which handled the query and the details of its events are filled in
+    assert!(tracing_info.coordinator.is_some());
+    assert!(tracing_info.duration.is_some());
+    assert!(tracing_info
+        .events
+        .iter()
+        .all(|event| event.activity.is_some() && event.source.is_some()));
 }
 
 async fn test_tracing_query_iter(session: &Session, ks: String) {
@@ -1113,8 +2038,59 @@ async fn test_await_schema_agreement() {
 
 #[tokio::test]
 async fn test_await_timed_schema_agreement() {
+    let session = create_new_session_builder()
+        .auto_await_schema_agreement(false)
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(format!("CREATE TABLE {}.t (a int PRIMARY KEY)", ks), &[])
+        .await
+        .unwrap();
+
+    let schema_version = session
+        .await_timed_schema_agreement(Duration::from_secs(30))
+        .await
+        .unwrap();
+    assert_eq!(
+        session.check_schema_agreement().await.unwrap(),
+        Some(schema_version)
+    );
+
+    // Agreement is checked only after the first interval elapses, so a zero timeout always expires
+    assert_matches!(
+        session.await_timed_schema_agreement(Duration::ZERO).await,
+        Err(QueryError::RequestTimeout(_))
+    );
+}
+
+#[tokio::test]
+async fn test_event_stream() {
     let session = create_new_session_builder().build().await.unwrap();
-    session.await_schema_agreement().await.unwrap();
+    let mut events = session.event_stream();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while let Some(event) = events.next().await {
+            if let Event::SchemaChange(SchemaChangeEvent::KeyspaceChange {
+                change_type: SchemaChangeType::Created,
+                keyspace_name,
+            }) = event
+            {
+                if keyspace_name == ks {
+                    return;
+                }
+            }
+        }
+        panic!("Event stream ended before the keyspace creation was reported");
+    })
+    .await
+    .expect("Timed out waiting for the keyspace creation event");
 }
 
 #[tokio::test]
@@ -1163,68 +2139,327 @@ async fn test_timestamp() {
         .execute(&prepared_statement, ("prepared query", "higher timestamp"))
         .await
         .unwrap();
-
-    prepared_statement.set_timestamp(Some(42));
+
+    prepared_statement.set_timestamp(Some(42));
+    session
+        .execute(&prepared_statement, ("prepared query", "lower timestamp"))
+        .await
+        .unwrap();
+
+    // test batch statement timestamps
+
+    let mut batch: Batch = Default::default();
+    batch.append_statement(regular_query);
+    batch.append_statement(prepared_statement);
+
+    batch.set_timestamp(Some(420));
+    session
+        .batch(
+            &batch,
+            (
+                ("first query in batch", "higher timestamp"),
+                ("second query in batch", "higher timestamp"),
+            ),
+        )
+        .await
+        .unwrap();
+
+    batch.set_timestamp(Some(42));
+    session
+        .batch(
+            &batch,
+            (
+                ("first query in batch", "lower timestamp"),
+                ("second query in batch", "lower timestamp"),
+            ),
+        )
+        .await
+        .unwrap();
+
+    let mut results = session
+        .query(
+            format!("SELECT a, b, WRITETIME(b) FROM {}.t_timestamp", ks),
+            &[],
+        )
+        .await
+        .unwrap()
+        .rows
+        .unwrap()
+        .into_typed::<(String, String, i64)>()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+    results.sort();
+
+    let expected_results = [
+        ("first query in batch", "higher timestamp", 420),
+        ("prepared query", "higher timestamp", 420),
+        ("regular query", "higher timestamp", 420),
+        ("second query in batch", "higher timestamp", 420),
+    ]
+    .iter()
+    .map(|(x, y, t)| (x.to_string(), y.to_string(), *t))
+    .collect::<Vec<_>>();
+
+    assert_eq!(results, expected_results);
+}
+
+#[tokio::test]
+async fn test_batch_timestamp_shared_by_statements() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query("CREATE TABLE tab (a int PRIMARY KEY, b int)", &[])
+        .await
+        .unwrap();
+
+    let prepared = session
+        .prepare("INSERT INTO tab (a, b) VALUES (?, ?)")
+        .await
+        .unwrap();
+    let mut batch: Batch = Default::default();
+    batch.append_statement("INSERT INTO tab (a, b) VALUES (?, ?)");
+    batch.append_statement(prepared);
+    batch.append_statement("UPDATE tab SET b = ? WHERE a = ?");
+    // USING TIMESTAMP in the statement takes precedence over the batch timestamp
+    batch.append_statement("INSERT INTO tab (a, b) VALUES (?, ?) USING TIMESTAMP 100");
+
+    batch.set_timestamp(Some(12345));
+    session
+        .batch(&batch, ((1, 1), (2, 2), (3, 3), (4, 4)))
+        .await
+        .unwrap();
+
+    let mut results: Vec<(i32, i64)> = session
+        .query("SELECT a, WRITETIME(b) FROM tab", &[])
+        .await
+        .unwrap()
+        .rows_typed()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    results.sort();
+
+    assert_eq!(results, vec![(1, 12345), (2, 12345), (3, 12345), (4, 100)]);
+}
+
+#[cfg(feature = "serde")]
+#[tokio::test]
+async fn test_json_round_trip() {
+    use crate::frame::value::JsonText;
+    use crate::transport::query_result::JsonRowsError;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Person {
+        id: i32,
+        name: String,
+        nicknames: Vec<String>,
+        email: Option<String>,
+    }
+
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query(
+            "CREATE TABLE person (id int PRIMARY KEY, name text, nicknames list<text>, email text)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let person = Person {
+        id: 1,
+        name: "Jane".to_string(),
+        nicknames: vec!["J".to_string(), "Janie".to_string()],
+        email: None,
+    };
+    let prepared = session.prepare("INSERT INTO person JSON ?").await.unwrap();
+    session
+        .execute(&prepared, (JsonText::new(&person).unwrap(),))
+        .await
+        .unwrap();
+
+    let read: Vec<Person> = session
+        .query("SELECT JSON * FROM person WHERE id = 1", &[])
+        .await
+        .unwrap()
+        .json_rows()
+        .unwrap();
+    assert_eq!(read, vec![person]);
+
+    // Other results aren't mistaken for SELECT JSON ones
+    let err = session
+        .query("SELECT name FROM person", &[])
+        .await
+        .unwrap()
+        .json_rows::<Person>()
+        .unwrap_err();
+    assert_eq!(err, JsonRowsError::NotJson);
+}
+
+#[tokio::test]
+async fn test_wrong_number_of_bound_values() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query("CREATE TABLE tab (a int PRIMARY KEY, b int, c int)", &[])
+        .await
+        .unwrap();
+
+    let prepared = session
+        .prepare("INSERT INTO tab (a, b, c) VALUES (?, ?, ?)")
+        .await
+        .unwrap();
+
+    // The values are rejected by the driver, before the request is sent
+    let err = session.execute(&prepared, (1, 2)).await.unwrap_err();
+    assert_matches!(
+        err,
+        QueryError::BadQuery(BadQuery::WrongNumberOfValues {
+            expected: 3,
+            provided: 2
+        })
+    );
+    let err = session
+        .query("INSERT INTO tab (a, b, c) VALUES (?, ?, ?)", (1, 2))
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        QueryError::BadQuery(BadQuery::WrongNumberOfValues {
+            expected: 3,
+            provided: 2
+        })
+    );
+
+    session.execute(&prepared, (1, 2, 3)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_type_mismatched_bind_rejected() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query("CREATE TABLE tab (a int PRIMARY KEY, b int)", &[])
+        .await
+        .unwrap();
+
+    let mut prepared = session
+        .prepare("INSERT INTO tab (a, b) VALUES (?, ?)")
+        .await
+        .unwrap();
+    prepared.set_check_value_types(true);
+
+    let err = session
+        .execute(&prepared, (1, "two".to_string()))
+        .await
+        .unwrap_err();
+    assert_matches!(
+        err,
+        QueryError::BadQuery(BadQuery::ValueTypeMismatch { name, expected: ColumnType::Int }) if name == "b"
+    );
+
+    session.execute(&prepared, (1, 2)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timestamp_generator() {
+    #[derive(Debug)]
+    struct ConstantTimestampGenerator;
+    impl TimestampGenerator for ConstantTimestampGenerator {
+        fn next_timestamp(&self) -> i64 {
+            12345
+        }
+    }
+
+    let session = create_new_session_builder()
+        .timestamp_generator(Arc::new(ConstantTimestampGenerator))
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t_timestamp_generator (a text, b text, primary key (a))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let insert_str = format!(
+        "INSERT INTO {}.t_timestamp_generator (a, b) VALUES (?, ?)",
+        ks
+    );
+
+    // The generated timestamp is used when there is no explicit one
+    session
+        .query(insert_str.clone(), ("generated", "b"))
+        .await
+        .unwrap();
+    let prepared = session.prepare(insert_str.clone()).await.unwrap();
     session
-        .execute(&prepared_statement, ("prepared query", "lower timestamp"))
+        .execute(&prepared, ("generated prepared", "b"))
         .await
         .unwrap();
 
-    // test batch statement timestamps
-
-    let mut batch: Batch = Default::default();
-    batch.append_statement(regular_query);
-    batch.append_statement(prepared_statement);
-
-    batch.set_timestamp(Some(420));
+    // A timestamp set on the statement takes precedence
+    let mut explicit_query = Query::new(insert_str);
+    explicit_query.set_timestamp(Some(42));
     session
-        .batch(
-            &batch,
-            (
-                ("first query in batch", "higher timestamp"),
-                ("second query in batch", "higher timestamp"),
-            ),
-        )
+        .query(explicit_query, ("explicit", "b"))
         .await
         .unwrap();
 
-    batch.set_timestamp(Some(42));
+    // And so does a timestamp given in the CQL string
     session
-        .batch(
-            &batch,
-            (
-                ("first query in batch", "lower timestamp"),
-                ("second query in batch", "lower timestamp"),
+        .query(
+            format!(
+                "INSERT INTO {}.t_timestamp_generator (a, b) VALUES (?, ?) USING TIMESTAMP 420",
+                ks
             ),
+            ("using timestamp", "b"),
         )
         .await
         .unwrap();
 
     let mut results = session
         .query(
-            format!("SELECT a, b, WRITETIME(b) FROM {}.t_timestamp", ks),
+            format!("SELECT a, WRITETIME(b) FROM {}.t_timestamp_generator", ks),
             &[],
         )
         .await
         .unwrap()
-        .rows
+        .rows_typed::<(String, i64)>()
         .unwrap()
-        .into_typed::<(String, String, i64)>()
         .map(Result::unwrap)
         .collect::<Vec<_>>();
     results.sort();
 
-    let expected_results = [
-        ("first query in batch", "higher timestamp", 420),
-        ("prepared query", "higher timestamp", 420),
-        ("regular query", "higher timestamp", 420),
-        ("second query in batch", "higher timestamp", 420),
-    ]
-    .iter()
-    .map(|(x, y, t)| (x.to_string(), y.to_string(), *t))
-    .collect::<Vec<_>>();
-
-    assert_eq!(results, expected_results);
+    assert_eq!(
+        results,
+        vec![
+            ("explicit".to_string(), 42),
+            ("generated".to_string(), 12345),
+            ("generated prepared".to_string(), 12345),
+            ("using timestamp".to_string(), 420),
+        ]
+    );
 }
 
 #[ignore = "works on remote Scylla instances only (local ones are too fast)"]
@@ -1678,6 +2913,90 @@ async fn test_primary_key_ordering_in_metadata() {
     assert_eq!(table.clustering_key, vec!["b", "a"]);
 }
 
+#[tokio::test]
+async fn test_composite_key_and_column_types_in_metadata() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session
+        .query(format!("CREATE KEYSPACE {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+        .await
+        .unwrap();
+    session.query(format!("USE {}", ks), &[]).await.unwrap();
+
+    session
+        .query("CREATE TYPE IF NOT EXISTS point (x int, y int)", &[])
+        .await
+        .unwrap();
+    session
+        .query(
+            "CREATE TABLE IF NOT EXISTS t (
+                    p1 text,
+                    p2 int,
+                    c1 timestamp,
+                    c2 uuid,
+                    s int STATIC,
+                    v frozen<point>,
+                    PRIMARY KEY ((p1, p2), c1, c2)
+                  ) WITH CLUSTERING ORDER BY (c1 DESC, c2 ASC)",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    session.await_schema_agreement().await.unwrap();
+    session.refresh_metadata().await.unwrap();
+
+    let cluster_data = session.get_cluster_data();
+    let keyspace = &cluster_data.get_keyspace_info()[&ks];
+    let table = &keyspace.tables["t"];
+
+    assert_eq!(table.partition_key, vec!["p1", "p2"]);
+    assert_eq!(table.clustering_key, vec!["c1", "c2"]);
+
+    let expected_columns = [
+        ("p1", NativeType::Text, ColumnKind::PartitionKey, None),
+        ("p2", NativeType::Int, ColumnKind::PartitionKey, None),
+        (
+            "c1",
+            NativeType::Timestamp,
+            ColumnKind::Clustering,
+            Some(ClusteringOrder::Desc),
+        ),
+        (
+            "c2",
+            NativeType::Uuid,
+            ColumnKind::Clustering,
+            Some(ClusteringOrder::Asc),
+        ),
+        ("s", NativeType::Int, ColumnKind::Static, None),
+    ];
+    for (name, type_, kind, clustering_order) in expected_columns {
+        let column = &table.columns[name];
+        assert_eq!(column.type_, CqlType::Native(type_), "column {}", name);
+        assert_eq!(column.kind, kind, "column {}", name);
+        assert_eq!(column.clustering_order, clustering_order, "column {}", name);
+    }
+
+    let point = keyspace.user_defined_types["point"].clone();
+    assert_eq!(
+        point.field_types,
+        vec![
+            ("x".to_string(), CqlType::Native(NativeType::Int)),
+            ("y".to_string(), CqlType::Native(NativeType::Int)),
+        ]
+    );
+    let v = &table.columns["v"];
+    assert_eq!(v.kind, ColumnKind::Regular);
+    assert_eq!(
+        v.type_,
+        CqlType::UserDefinedType {
+            frozen: true,
+            definition: Ok(point)
+        }
+    );
+}
+
 #[tokio::test]
 async fn test_table_partitioner_in_metadata() {
     if option_env!("CDC") == Some("disabled") {
@@ -1898,6 +3217,44 @@ async fn test_prepared_partitioner() {
     );
 }
 
+#[tokio::test]
+async fn test_cluster_partitioner_is_detected() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    // A default Scylla cluster uses Murmur3Partitioner
+    assert_eq!(
+        session.get_cluster_data().partitioner,
+        PartitionerName::Murmur3
+    );
+
+    let ks = unique_keyspace_name();
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(format!("CREATE TABLE {}.t (a int primary key)", ks), &[])
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+
+    // The cluster's partitioner is used for tokens of tables without a partitioner of their own
+    let prepared = session
+        .prepare(format!("INSERT INTO {}.t (a) VALUES (?)", ks))
+        .await
+        .unwrap();
+    assert_eq!(prepared.get_partitioner_name(), &PartitionerName::Murmur3);
+
+    let token = prepared
+        .calculate_token(&(42,).serialized().unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        token,
+        session
+            .get_cluster_data()
+            .compute_token(&ks, "t", (42,))
+            .unwrap()
+    );
+}
+
 async fn rename(session: &Session, rename_str: &str) {
     session
         .query(format!("ALTER TABLE tab RENAME {}", rename_str), ())
@@ -2523,6 +3880,125 @@ async fn test_batch_lwts_for_cassandra(session: &Session, batch: &Batch, batch_r
     assert_eq!(prepared_batch_res_rows, expected_prepared_batch_res_rows);
 }
 
+#[tokio::test]
+async fn test_lwt_with_local_serial_consistency() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query("CREATE TABLE tab (p int PRIMARY KEY, v int)", ())
+        .await
+        .unwrap();
+    session
+        .query("INSERT INTO tab (p, v) VALUES (0, 1)", ())
+        .await
+        .unwrap();
+
+    let mut query = Query::new("UPDATE tab SET v = ? WHERE p = 0 IF v = 1");
+    query.set_serial_consistency(Some(SerialConsistency::LocalSerial));
+
+    // The condition holds, so the update is applied
+    let res = session.query(query.clone(), (2,)).await.unwrap();
+    assert_eq!(res.col_specs[0].name, "[applied]");
+    let row = res.first_row().unwrap();
+    assert_eq!(row.columns[0].as_ref().unwrap().as_boolean(), Some(true));
+
+    // The condition doesn't hold anymore, the current value is returned
+    let res = session.query(query, (3,)).await.unwrap();
+    let (v_idx, _) = res.get_column_spec("v").unwrap();
+    let row = res.first_row().unwrap();
+    assert_eq!(row.columns[0].as_ref().unwrap().as_boolean(), Some(false));
+    assert_eq!(row.columns[v_idx].as_ref().unwrap().as_int(), Some(2));
+}
+
+#[tokio::test]
+async fn test_batch_lwt_with_local_serial_consistency() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query(
+            "CREATE TABLE tab (p int, c int, v int, PRIMARY KEY (p, c))",
+            (),
+        )
+        .await
+        .unwrap();
+    session
+        .query("INSERT INTO tab (p, c, v) VALUES (0, 0, 1)", ())
+        .await
+        .unwrap();
+
+    let mut batch = Batch::new(BatchType::Logged);
+    batch.append_statement("UPDATE tab SET v = ? WHERE p = 0 AND c = 0 IF v = 1");
+    batch.append_statement("INSERT INTO tab (p, c, v) VALUES (0, 1, ?)");
+    batch.set_consistency(Consistency::Quorum);
+    batch.set_serial_consistency(Some(SerialConsistency::LocalSerial));
+    assert_eq!(
+        batch.get_serial_consistency(),
+        Some(SerialConsistency::LocalSerial)
+    );
+
+    // The condition holds, so the whole batch is applied
+    let res = session.batch(&batch, ((2,), (3,))).await.unwrap();
+    assert_eq!(res.col_specs[0].name, "[applied]");
+    assert_eq!(res.applied(), Some(true));
+
+    // The condition doesn't hold anymore, so nothing is applied
+    let res = session.batch(&batch, ((4,), (5,))).await.unwrap();
+    assert_eq!(res.applied(), Some(false));
+
+    let rows: Vec<(i32, i32)> = session
+        .query("SELECT c, v FROM tab WHERE p = 0", ())
+        .await
+        .unwrap()
+        .rows_typed()
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(rows, vec![(0, 2), (1, 3)]);
+}
+
+#[tokio::test]
+async fn test_lwt_applied() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query("CREATE TABLE tab (p int PRIMARY KEY, v int)", ())
+        .await
+        .unwrap();
+
+    let insert = session
+        .prepare("INSERT INTO tab (p, v) VALUES (?, ?) IF NOT EXISTS")
+        .await
+        .unwrap();
+
+    let res = session.execute(&insert, (0, 1)).await.unwrap();
+    assert_eq!(res.applied(), Some(true));
+
+    // The row already exists, so it is returned along with `[applied] = false`
+    let res = session.execute(&insert, (0, 2)).await.unwrap();
+    assert_eq!(res.applied(), Some(false));
+    let (v_idx, _) = res.get_column_spec("v").unwrap();
+    let row = res.first_row().unwrap();
+    assert_eq!(row.columns[v_idx].as_ref().unwrap().as_int(), Some(1));
+
+    // Non-conditional statements don't have the `[applied]` column
+    let res = session
+        .query("INSERT INTO tab (p, v) VALUES (1, 1)", ())
+        .await
+        .unwrap();
+    assert_eq!(res.applied(), None);
+    let res = session.query("SELECT p, v FROM tab", ()).await.unwrap();
+    assert_eq!(res.applied(), None);
+}
+
 #[tokio::test]
 async fn test_keyspaces_to_fetch() {
     let ks1 = unique_keyspace_name();
@@ -2620,57 +4096,160 @@ async fn test_iter_works_when_retry_policy_returns_ignore_write_error() {
         .query_iter("INSERT INTO t (pk v) VALUES (1, 2)", ())
         .await
         .unwrap();
-
-    assert!(retried_flag.load(Ordering::Relaxed));
-    while iter.try_next().await.unwrap().is_some() {}
-
-    retried_flag.store(false, Ordering::Relaxed);
-    // Try the same with execute_iter()
-    let p = session
-        .prepare("INSERT INTO t (pk, v) VALUES (?, ?)")
+
+    assert!(retried_flag.load(Ordering::Relaxed));
+    while iter.try_next().await.unwrap().is_some() {}
+
+    retried_flag.store(false, Ordering::Relaxed);
+    // Try the same with execute_iter()
+    let p = session
+        .prepare("INSERT INTO t (pk, v) VALUES (?, ?)")
+        .await
+        .unwrap();
+    let mut iter = session.execute_iter(p, (1, 2)).await.unwrap();
+
+    assert!(retried_flag.load(Ordering::Relaxed));
+    while iter.try_next().await.unwrap().is_some() {}
+}
+
+#[tokio::test]
+async fn test_iter_methods_with_modification_statements() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, c text, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let mut query = Query::from(format!(
+        "INSERT INTO {}.t (a, b, c) VALUES (1, 2, 'abc')",
+        ks
+    ));
+    query.set_tracing(true);
+    let mut row_iterator = session.query_iter(query, &[]).await.unwrap();
+    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
+    assert!(!row_iterator.get_tracing_ids().is_empty());
+
+    let prepared_statement = session
+        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks))
+        .await
+        .unwrap();
+    let mut row_iterator = session
+        .execute_iter(prepared_statement, (2, 3, "cba"))
+        .await
+        .unwrap();
+    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
+}
+
+#[tokio::test]
+async fn test_iter_methods_with_small_page_size() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, primary key (a, b))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // All rows share a partition, so they are returned in clustering order
+    const ROWS_NUM: i32 = 3000;
+    let insert = session
+        .prepare(format!("INSERT INTO {}.t (a, b) VALUES (0, ?)", ks))
+        .await
+        .unwrap();
+    futures::future::try_join_all((0..ROWS_NUM).map(|b| session.execute(&insert, (b,))))
+        .await
+        .unwrap();
+
+    let expected: Vec<i32> = (0..ROWS_NUM).collect();
+
+    let query = Query::new(format!("SELECT b FROM {}.t WHERE a = 0", ks)).with_page_size(7);
+    let rows: Vec<i32> = session
+        .query_iter(query, &[])
+        .await
+        .unwrap()
+        .into_typed::<(i32,)>()
+        .map_ok(|(b,)| b)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows, expected);
+
+    let mut prepared = session
+        .prepare(format!("SELECT b FROM {}.t WHERE a = 0", ks))
+        .await
+        .unwrap();
+    prepared.set_page_size(7);
+    let rows: Vec<i32> = session
+        .execute_iter(prepared, &[])
+        .await
+        .unwrap()
+        .into_typed::<(i32,)>()
+        .map_ok(|(b,)| b)
+        .try_collect()
         .await
         .unwrap();
-    let mut iter = session.execute_iter(p, (1, 2)).await.unwrap();
-
-    assert!(retried_flag.load(Ordering::Relaxed));
-    while iter.try_next().await.unwrap().is_some() {}
+    assert_eq!(rows, expected);
 }
 
 #[tokio::test]
-async fn test_iter_methods_with_modification_statements() {
-    let session = create_new_session_builder().build().await.unwrap();
+async fn test_per_statement_consistency() {
     let ks = unique_keyspace_name();
+    let session = create_new_session_builder().build().await.unwrap();
 
-    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    // Replication factor is larger than the number of nodes in the test cluster,
+    // so some of the replicas are always unavailable
     session
         .query(
             format!(
-                "CREATE TABLE IF NOT EXISTS {}.t (a int, b int, c text, primary key (a, b))",
+                "CREATE KEYSPACE {} WITH REPLICATION = \
+                {{'class': 'SimpleStrategy', 'replication_factor': 10}}",
                 ks
             ),
-            &[],
+            (),
         )
         .await
         .unwrap();
-
-    let mut query = Query::from(format!(
-        "INSERT INTO {}.t (a, b, c) VALUES (1, 2, 'abc')",
-        ks
-    ));
-    query.set_tracing(true);
-    let mut row_iterator = session.query_iter(query, &[]).await.unwrap();
-    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
-    assert!(!row_iterator.get_tracing_ids().is_empty());
-
-    let prepared_statement = session
-        .prepare(format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks))
+    session
+        .query(format!("CREATE TABLE {}.tab (p int PRIMARY KEY)", ks), ())
         .await
         .unwrap();
-    let mut row_iterator = session
-        .execute_iter(prepared_statement, (2, 3, "cba"))
+
+    let mut query = Query::new(format!("INSERT INTO {}.tab (p) VALUES (1)", ks));
+    query.set_consistency(Consistency::All);
+    assert_matches!(
+        session.query(query.clone(), ()).await,
+        Err(QueryError::DbError(DbError::Unavailable { .. }, _))
+    );
+    query.set_consistency(Consistency::One);
+    session.query(query, ()).await.unwrap();
+
+    let mut prepared = session
+        .prepare(format!("INSERT INTO {}.tab (p) VALUES (?)", ks))
         .await
         .unwrap();
-    row_iterator.next().await.ok_or(()).unwrap_err(); // assert empty
+    prepared.set_consistency(Consistency::All);
+    assert_matches!(
+        session.execute(&prepared, (2,)).await,
+        Err(QueryError::DbError(DbError::Unavailable { .. }, _))
+    );
+    prepared.set_consistency(Consistency::One);
+    session.execute(&prepared, (2,)).await.unwrap();
 }
 
 #[tokio::test]
@@ -2857,3 +4436,390 @@ async fn test_manual_primary_key_computation() {
         .await;
     }
 }
+
+async fn large_blob_round_trip_with_compression(compression: Compression) {
+    let session = create_new_session_builder()
+        .compression(Some(compression))
+        .build()
+        .await
+        .unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session.use_keyspace(ks, false).await.unwrap();
+    session
+        .query("CREATE TABLE t (p int PRIMARY KEY, v blob)", ())
+        .await
+        .unwrap();
+
+    // 1 MiB of data which compresses well, but not trivially
+    let blob: Vec<u8> = (0..1024 * 1024_u32)
+        .map(|i| (i % 251) as u8 ^ (i / 4096) as u8)
+        .collect();
+
+    let insert = session
+        .prepare("INSERT INTO t (p, v) VALUES (0, ?)")
+        .await
+        .unwrap();
+    session.execute(&insert, (&blob,)).await.unwrap();
+
+    let (read_blob,): (Vec<u8>,) = session
+        .query("SELECT v FROM t WHERE p = 0", ())
+        .await
+        .unwrap()
+        .single_row_typed()
+        .unwrap();
+    assert_eq!(read_blob, blob);
+}
+
+#[tokio::test]
+async fn test_lz4_compression_large_blob() {
+    large_blob_round_trip_with_compression(Compression::Lz4).await;
+}
+
+#[tokio::test]
+async fn test_snappy_compression_large_blob() {
+    large_blob_round_trip_with_compression(Compression::Snappy).await;
+}
+
+// Waits until the node's pool has exactly `expected` working connections
+async fn wait_for_connection_count(node: &Node, expected: usize) {
+    let wait = async {
+        loop {
+            let count = node
+                .get_working_connections()
+                .map_or(0, |connections| connections.len());
+            if count == expected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(30), wait)
+        .await
+        .unwrap_or_else(|_| panic!("Pool of {} did not reach size {}", node.address, expected));
+}
+
+#[tokio::test]
+async fn test_pool_size() {
+    let session = create_new_session_builder()
+        .pool_size(PoolSize::PerHost(NonZeroUsize::new(3).unwrap()))
+        .build()
+        .await
+        .unwrap();
+    for node in session.get_cluster_data().get_nodes_info() {
+        wait_for_connection_count(node, 3).await;
+    }
+
+    let session = create_new_session_builder()
+        .pool_size(PoolSize::PerShard(NonZeroUsize::new(2).unwrap()))
+        .build()
+        .await
+        .unwrap();
+    for node in session.get_cluster_data().get_nodes_info() {
+        // Cassandra nodes are not sharded, they are treated as having a single shard
+        let shard_count = node
+            .sharder()
+            .map_or(1, |sharder| sharder.nr_shards.get() as usize);
+        wait_for_connection_count(node, 2 * shard_count).await;
+    }
+}
+
+#[tokio::test]
+async fn test_prepared_statement_cached_result_metadata() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b text, c bigint, primary key (a))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    for a in 0..10 {
+        session
+            .query(
+                format!("INSERT INTO {}.t (a, b, c) VALUES (?, ?, ?)", ks),
+                (a, a.to_string(), a as i64 * 100),
+            )
+            .await
+            .unwrap();
+    }
+
+    let mut prepared = session
+        .prepare(format!("SELECT * FROM {}.t", ks))
+        .await
+        .unwrap();
+    assert!(!prepared.get_use_cached_result_metadata());
+    prepared.set_use_cached_result_metadata(true);
+    prepared.set_page_size(3);
+
+    let expected: BTreeSet<(i32, String, i64)> = (0..10)
+        .map(|a| (a, a.to_string(), a as i64 * 100))
+        .collect();
+
+    // Repeated executions keep producing correctly typed rows
+    for _ in 0..3 {
+        let result = session.execute(&prepared, &[]).await.unwrap();
+        let specs: Vec<&str> = result.col_specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(specs, vec!["a", "b", "c"]);
+        assert_eq!(result.rows.as_ref().unwrap().len(), 3);
+
+        let rows: BTreeSet<(i32, String, i64)> = session
+            .execute_iter(prepared.clone(), &[])
+            .await
+            .unwrap()
+            .into_typed::<(i32, String, i64)>()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows, expected);
+    }
+
+    // Altering the table invalidates the statement; the metadata is refreshed when repreparing
+    session
+        .query(format!("ALTER TABLE {}.t ADD d int", ks), &[])
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+
+    let rows: BTreeSet<(i32, String, i64, Option<i32>)> = session
+        .execute_iter(prepared.clone(), &[])
+        .await
+        .unwrap()
+        .into_typed::<(i32, String, i64, Option<i32>)>()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        expected
+            .into_iter()
+            .map(|(a, b, c)| (a, b, c, None))
+            .collect()
+    );
+}
+
+#[tokio::test]
+async fn test_prepared_statement_outdated_cached_result_metadata() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let ks = unique_keyspace_name();
+
+    session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+    session
+        .query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}.t (a int, b text, primary key (a))",
+                ks
+            ),
+            &[],
+        )
+        .await
+        .unwrap();
+    session
+        .query(
+            format!("INSERT INTO {}.t (a, b) VALUES (1, 'one')", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let mut prepared = session
+        .prepare(format!("SELECT * FROM {}.t", ks))
+        .await
+        .unwrap();
+    prepared.set_use_cached_result_metadata(true);
+
+    let rows = session
+        .execute(&prepared, &[])
+        .await
+        .unwrap()
+        .rows_typed::<(i32, String)>()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(rows, vec![(1, "one".to_string())]);
+    let outdated_metadata = prepared.get_result_metadata();
+
+    // The table changes between executions
+    session
+        .query(format!("ALTER TABLE {}.t ADD c int", ks), &[])
+        .await
+        .unwrap();
+    session.await_schema_agreement().await.unwrap();
+    session
+        .query(
+            format!("INSERT INTO {}.t (a, b, c) VALUES (2, 'two', 2)", ks),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let expected = vec![
+        (1, "one".to_string(), None),
+        (2, "two".to_string(), Some(2)),
+    ];
+    let execute_sorted = || async {
+        let mut rows = session
+            .execute(&prepared, &[])
+            .await
+            .unwrap()
+            .rows_typed::<(i32, String, Option<i32>)>()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        rows.sort();
+        rows
+    };
+
+    // Depending on the database, the statement either gets reprepared
+    // or the mismatching cached metadata is noticed
+    assert_eq!(execute_sorted().await, expected);
+    assert_eq!(prepared.get_result_metadata().col_specs.len(), 3);
+
+    // The statement is not reprepared anymore, so the outdated metadata is only noticed
+    // when the returned rows don't match it
+    prepared.update_result_metadata(outdated_metadata);
+    assert_eq!(execute_sorted().await, expected);
+    assert_eq!(prepared.get_result_metadata().col_specs.len(), 3);
+}
+
+#[tokio::test]
+async fn test_protocol_version_and_supported_options() {
+    let session = create_new_session_builder().build().await.unwrap();
+
+    // Scylla speaks protocol v4, while Cassandra 4.0 and newer speak v5
+    let cluster_data = session.get_cluster_data();
+    let is_scylla = cluster_data
+        .get_nodes_info()
+        .iter()
+        .all(|node| node.sharder().is_some());
+    if !is_scylla {
+        assert_eq!(session.protocol_version(), ProtocolVersion::V5);
+    }
+
+    for node in cluster_data.get_nodes_info() {
+        let supported = node.supported_options().unwrap();
+        assert!(supported.options.contains_key("CQL_VERSION"));
+        assert_eq!(supported.compression, None);
+        assert_eq!(supported.protocol_version, session.protocol_version());
+
+        if let Some(sharder) = node.sharder() {
+            assert_eq!(
+                supported.options["SCYLLA_NR_SHARDS"],
+                vec![sharder.nr_shards.to_string()]
+            );
+        }
+    }
+
+    let session = create_new_session_builder()
+        .compression(Some(Compression::Lz4))
+        .build()
+        .await
+        .unwrap();
+    for node in session.get_cluster_data().get_nodes_info() {
+        let supported = node.supported_options().unwrap();
+        assert!(supported.options["COMPRESSION"].contains(&"lz4".to_string()));
+        assert_eq!(supported.compression, Some(Compression::Lz4));
+    }
+
+    // A pinned version is used even if the nodes support a newer one
+    let session = create_new_session_builder()
+        .protocol_version(ProtocolVersion::V4)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(session.protocol_version(), ProtocolVersion::V4);
+    session
+        .query("SELECT * FROM system.local", &[])
+        .await
+        .unwrap();
+    for node in session.get_cluster_data().get_nodes_info() {
+        assert_eq!(
+            node.supported_options().unwrap().protocol_version,
+            ProtocolVersion::V4
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_per_request_keyspace() {
+    let session = create_new_session_builder().build().await.unwrap();
+    let keyspaces = [unique_keyspace_name(), unique_keyspace_name()];
+
+    // The same table exists in both keyspaces, with different contents
+    for (i, ks) in keyspaces.iter().enumerate() {
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[]).await.unwrap();
+        session
+            .query(
+                format!("CREATE TABLE IF NOT EXISTS {}.t (a int primary key)", ks),
+                &[],
+            )
+            .await
+            .unwrap();
+        session
+            .query(format!("INSERT INTO {}.t (a) VALUES (?)", ks), (i as i32,))
+            .await
+            .unwrap();
+    }
+    session.await_schema_agreement().await.unwrap();
+
+    let query_in = |ks: &str| {
+        let mut query = Query::new("SELECT a FROM t");
+        query.set_keyspace(Some(ks.to_owned()));
+        query
+    };
+
+    if session.protocol_version() < ProtocolVersion::V5 {
+        assert_matches!(
+            session.query(query_in(&keyspaces[0]), &[]).await,
+            Err(QueryError::BadQuery(
+                BadQuery::PerRequestKeyspaceUnsupported(ProtocolVersion::V4)
+            ))
+        );
+        return;
+    }
+
+    let caching_session: CachingSession = CachingSession::from(session, 2);
+    for (i, ks) in keyspaces.iter().enumerate() {
+        let expected = (i as i32,);
+
+        let row = caching_session
+            .get_session()
+            .query(query_in(ks), &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(i32,)>()
+            .unwrap();
+        assert_eq!(row, expected);
+
+        // A statement prepared from the query is executed in its keyspace
+        let prepared = caching_session
+            .get_session()
+            .prepare(query_in(ks))
+            .await
+            .unwrap();
+        let row = caching_session
+            .get_session()
+            .execute(&prepared, &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(i32,)>()
+            .unwrap();
+        assert_eq!(row, expected);
+
+        // The same contents are cached separately for each keyspace
+        let row = caching_session
+            .execute(query_in(ks), &[])
+            .await
+            .unwrap()
+            .single_row_typed::<(i32,)>()
+            .unwrap();
+        assert_eq!(row, expected);
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Generates client-side timestamps which are sent along with requests
+/// that don't have a timestamp set explicitly.\
+/// A timestamp given in the CQL string (`USING TIMESTAMP`) still takes precedence.
+pub trait TimestampGenerator: std::fmt::Debug + Send + Sync {
+    /// Returns the next timestamp, in microseconds since the unix epoch.
+    fn next_timestamp(&self) -> i64;
+}
+
+/// A timestamp generator based on the system clock which guarantees that
+/// consecutive timestamps are strictly increasing, even if the clock goes back
+/// or many timestamps are generated within the same microsecond.
+#[derive(Debug, Default)]
+pub struct MonotonicTimestampGenerator {
+    last: AtomicI64,
+}
+
+impl MonotonicTimestampGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn now_micros() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_micros() as i64)
+            .unwrap_or(0)
+    }
+}
+
+impl TimestampGenerator for MonotonicTimestampGenerator {
+    fn next_timestamp(&self) -> i64 {
+        let now = Self::now_micros();
+        let mut last = self.last.load(Ordering::Relaxed);
+        loop {
+            let next = std::cmp::max(now, last + 1);
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return next,
+                Err(current) => last = current,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MonotonicTimestampGenerator, TimestampGenerator};
+    use std::sync::Arc;
+
+    #[test]
+    fn monotonic_timestamps_strictly_increase() {
+        let generator = MonotonicTimestampGenerator::new();
+        let mut previous = generator.next_timestamp();
+        for _ in 0..10_000 {
+            let next = generator.next_timestamp();
+            assert!(next > previous, "{} <= {}", next, previous);
+            previous = next;
+        }
+    }
+
+    #[test]
+    fn monotonic_timestamps_unique_across_threads() {
+        let generator = Arc::new(MonotonicTimestampGenerator::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let generator = generator.clone();
+                std::thread::spawn(move || {
+                    (0..1000)
+                        .map(|_| generator.next_timestamp())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut timestamps: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        let generated_num = timestamps.len();
+        timestamps.sort_unstable();
+        timestamps.dedup();
+        assert_eq!(timestamps.len(), generated_num);
+    }
+}
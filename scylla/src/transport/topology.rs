@@ -1,11 +1,14 @@
 use crate::frame::response::event::Event;
 use crate::routing::Token;
 use crate::statement::query::Query;
+use crate::statement::Consistency;
 use crate::transport::connection::{Connection, ConnectionConfig};
 use crate::transport::connection_pool::{NodeConnectionPool, PoolConfig, PoolSize};
 use crate::transport::errors::{DbError, QueryError};
 use crate::transport::host_filter::HostFilter;
 use crate::transport::node::resolve_contact_points;
+use crate::transport::reconnection_policy::ReconnectionPolicy;
+use crate::transport::session::IntoTypedRows;
 use crate::utils::parse::{ParseErrorCause, ParseResult, ParserState};
 
 use futures::future::{self, FutureExt};
@@ -29,7 +32,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use strum_macros::EnumString;
 use tokio::sync::{broadcast, mpsc};
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 use uuid::Uuid;
 
 use super::node::{KnownNode, NodeAddr, ResolvedContactPoint};
@@ -38,6 +41,7 @@ use super::node::{KnownNode, NodeAddr, ResolvedContactPoint};
 pub(crate) struct MetadataReader {
     connection_config: ConnectionConfig,
     keepalive_interval: Option<Duration>,
+    reconnection_policy: Arc<dyn ReconnectionPolicy>,
 
     control_connection_endpoint: UntranslatedEndpoint,
     control_connection: NodeConnectionPool,
@@ -61,6 +65,8 @@ pub(crate) struct MetadataReader {
 pub(crate) struct Metadata {
     pub(crate) peers: Vec<Peer>,
     pub(crate) keyspaces: HashMap<String, Keyspace>,
+    // Name of the partitioner used by the cluster, as reported in system.local
+    pub(crate) partitioner: Option<String>,
 }
 
 #[non_exhaustive] // <- so that we can add more fields in a backwards-compatible way
@@ -176,6 +182,8 @@ pub struct MaterializedView {
 pub struct Column {
     pub type_: CqlType,
     pub kind: ColumnKind,
+    /// Order in which rows are sorted by this column. Set only for clustering key columns.
+    pub clustering_order: Option<ClusteringOrder>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -325,6 +333,13 @@ pub enum ColumnKind {
     PartitionKey,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ClusteringOrder {
+    Asc,
+    Desc,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(clippy::enum_variant_names)]
 pub enum Strategy {
@@ -382,7 +397,7 @@ impl Metadata {
                 Peer {
                     address: endpoint.address(),
                     tokens: vec![Token {
-                        value: token as i64,
+                        value: (token as i64).into(),
                     }],
                     datacenter: None,
                     rack: None,
@@ -394,6 +409,7 @@ impl Metadata {
         Metadata {
             peers,
             keyspaces: HashMap::new(),
+            partitioner: None,
         }
     }
 }
@@ -406,6 +422,7 @@ impl MetadataReader {
         control_connection_repair_requester: broadcast::Sender<()>,
         mut connection_config: ConnectionConfig,
         keepalive_interval: Option<Duration>,
+        reconnection_policy: Arc<dyn ReconnectionPolicy>,
         server_event_sender: mpsc::Sender<Event>,
         keyspaces_to_fetch: Vec<String>,
         fetch_schema: bool,
@@ -436,6 +453,7 @@ impl MetadataReader {
             control_connection_endpoint.clone(),
             connection_config.clone(),
             keepalive_interval,
+            reconnection_policy.clone(),
             control_connection_repair_requester.clone(),
         );
 
@@ -443,6 +461,7 @@ impl MetadataReader {
             control_connection_endpoint,
             control_connection,
             keepalive_interval,
+            reconnection_policy,
             connection_config,
             known_peers: initial_peers
                 .into_iter()
@@ -522,6 +541,15 @@ impl MetadataReader {
 
         match &result {
             Ok(metadata) => {
+                if self.control_connection_endpoint.address()
+                    != address_of_failed_control_connection
+                {
+                    info!(
+                        previous_address = %address_of_failed_control_connection,
+                        new_address = %self.control_connection_endpoint.address(),
+                        "Control connection moved to another node"
+                    );
+                }
                 self.update_known_peers(metadata);
                 self.handle_unaccepted_host_in_control_connection(metadata);
                 debug!("Fetched new metadata");
@@ -563,6 +591,7 @@ impl MetadataReader {
                 self.control_connection_endpoint.clone(),
                 self.connection_config.clone(),
                 self.keepalive_interval,
+                self.reconnection_policy.clone(),
                 self.control_connection_repair_requester.clone(),
             );
 
@@ -663,6 +692,7 @@ impl MetadataReader {
                         self.control_connection_endpoint.clone(),
                         self.connection_config.clone(),
                         self.keepalive_interval,
+                        self.reconnection_policy.clone(),
                         self.control_connection_repair_requester.clone(),
                     );
                 }
@@ -674,11 +704,13 @@ impl MetadataReader {
         endpoint: UntranslatedEndpoint,
         connection_config: ConnectionConfig,
         keepalive_interval: Option<Duration>,
+        reconnection_policy: Arc<dyn ReconnectionPolicy>,
         refresh_requester: broadcast::Sender<()>,
     ) -> NodeConnectionPool {
         let pool_config = PoolConfig {
             connection_config,
             keepalive_interval,
+            reconnection_policy,
 
             // We want to have only one connection to receive events from
             pool_size: PoolSize::PerHost(NonZeroUsize::new(1).unwrap()),
@@ -692,6 +724,11 @@ impl MetadataReader {
     }
 }
 
+// Consistency of the queries fetching metadata.
+// All of them read node-local system tables, so there is no point in waiting for other replicas,
+// which could make the queries fail when some nodes are unreachable.
+const METADATA_QUERY_CONSISTENCY: Consistency = Consistency::One;
+
 async fn query_metadata(
     conn: &Arc<Connection>,
     connect_port: u16,
@@ -700,8 +737,10 @@ async fn query_metadata(
 ) -> Result<Metadata, QueryError> {
     let peers_query = query_peers(conn, connect_port);
     let keyspaces_query = query_keyspaces(conn, keyspace_to_fetch, fetch_schema);
+    let partitioner_query = query_partitioner(conn);
 
-    let (peers, keyspaces) = tokio::try_join!(peers_query, keyspaces_query)?;
+    let (peers, keyspaces, partitioner) =
+        tokio::try_join!(peers_query, keyspaces_query, partitioner_query)?;
 
     // There must be at least one peer
     if peers.is_empty() {
@@ -717,7 +756,11 @@ async fn query_metadata(
         ));
     }
 
-    Ok(Metadata { peers, keyspaces })
+    Ok(Metadata {
+        peers,
+        keyspaces,
+        partitioner,
+    })
 }
 
 #[derive(FromRow)]
@@ -749,6 +792,7 @@ async fn query_peers(conn: &Arc<Connection>, connect_port: u16) -> Result<Vec<Pe
     let mut peers_query =
         Query::new("select host_id, rpc_address, data_center, rack, tokens from system.peers");
     peers_query.set_page_size(1024);
+    peers_query.set_consistency(METADATA_QUERY_CONSISTENCY);
     let peers_query_stream = conn
         .clone()
         .query_iter(peers_query, &[])
@@ -759,6 +803,7 @@ async fn query_peers(conn: &Arc<Connection>, connect_port: u16) -> Result<Vec<Pe
     let mut local_query =
         Query::new("select host_id, rpc_address, data_center, rack, tokens from system.local");
     local_query.set_page_size(1024);
+    local_query.set_consistency(METADATA_QUERY_CONSISTENCY);
     let local_query_stream = conn
         .clone()
         .query_iter(local_query, &[])
@@ -773,10 +818,7 @@ async fn query_peers(conn: &Arc<Connection>, connect_port: u16) -> Result<Vec<Pe
 
     let translated_peers_futures = untranslated_rows.map(|row_result| async {
         let (source, raw_row) = row_result?;
-        let row = raw_row.into_typed().map_err(|_| {
-            QueryError::ProtocolError("system.peers or system.local has invalid column type")
-        })?;
-        create_peer_from_row(source, row, local_address).await
+        create_peer_from_raw_row(source, raw_row, local_address).await
     });
 
     let peers = translated_peers_futures
@@ -786,6 +828,26 @@ async fn query_peers(conn: &Arc<Connection>, connect_port: u16) -> Result<Vec<Pe
     Ok(peers.into_iter().flatten().collect())
 }
 
+// A row which can't be parsed only makes the node it describes skipped,
+// so that a single malformed entry doesn't prevent the rest of the topology from being loaded.
+async fn create_peer_from_raw_row(
+    source: NodeInfoSource,
+    raw_row: Row,
+    local_address: SocketAddr,
+) -> Result<Option<Peer>, QueryError> {
+    match raw_row.into_typed::<NodeInfoRow>() {
+        Ok(row) => create_peer_from_row(source, row, local_address).await,
+        Err(err) => {
+            warn!(
+                "{} row has invalid column types ({}); skipping node.",
+                source.describe(),
+                err
+            );
+            Ok(None)
+        }
+    }
+}
+
 async fn create_peer_from_row(
     source: NodeInfoSource,
     row: NodeInfoRow,
@@ -836,11 +898,10 @@ async fn create_peer_from_row(
         Err(e) => {
             // FIXME: we could allow the users to provide custom partitioning information
             // in order for it to work with non-standard token sizes.
-            // Also, we could implement support for Cassandra's other standard partitioners
-            // like RandomPartitioner or ByteOrderedPartitioner.
-            trace!("Couldn't parse tokens as 64-bit integers: {}, proceeding with a dummy token. If you're using a partitioner with different token size, consider migrating to murmur3", e);
+            // Also, we could implement support for Cassandra's ByteOrderedPartitioner.
+            trace!("Couldn't parse tokens as integers: {}, proceeding with a dummy token. If you're using a partitioner with non-integer tokens, consider migrating to murmur3", e);
             vec![Token {
-                value: rand::thread_rng().gen::<i64>(),
+                value: rand::thread_rng().gen::<i64>().into(),
             }]
         }
     };
@@ -854,6 +915,24 @@ async fn create_peer_from_row(
     }))
 }
 
+async fn query_partitioner(conn: &Arc<Connection>) -> Result<Option<String>, QueryError> {
+    let mut partitioner_query = Query::new("select partitioner from system.local");
+    partitioner_query.set_consistency(METADATA_QUERY_CONSISTENCY);
+    let partitioner = conn
+        .query_single_page(partitioner_query, &[])
+        .await?
+        .rows
+        .ok_or(QueryError::ProtocolError(
+            "system.local partitioner query returned not rows",
+        ))?
+        .into_typed::<(Option<String>,)>()
+        .next()
+        .transpose()
+        .map_err(|_| QueryError::ProtocolError("system.local has invalid column type"))?
+        .and_then(|(partitioner,)| partitioner);
+    Ok(partitioner)
+}
+
 fn query_filter_keyspace_name(
     conn: &Arc<Connection>,
     query_str: &str,
@@ -869,6 +948,7 @@ fn query_filter_keyspace_name(
     let mut query = Query::new(query_str);
     let conn = conn.clone();
     query.set_page_size(1024);
+    query.set_consistency(METADATA_QUERY_CONSISTENCY);
     let fut = async move {
         let query_values = query_values?;
         conn.query_iter(query, query_values).await
@@ -1381,20 +1461,21 @@ async fn query_tables_schema(
     const THRIFT_EMPTY_TYPE: &str = "empty";
 
     let rows = query_filter_keyspace_name(conn,
-        "select keyspace_name, table_name, column_name, kind, position, type from system_schema.columns", keyspaces_to_fetch
+        "select keyspace_name, table_name, column_name, kind, position, type, clustering_order from system_schema.columns", keyspaces_to_fetch
     );
 
     let mut tables_schema = HashMap::new();
 
     rows.map(|row_result| {
         let row = row_result?;
-        let (keyspace_name, table_name, column_name, kind, position, type_): (
+        let (keyspace_name, table_name, column_name, kind, position, type_, clustering_order): (
             String,
             String,
             String,
             String,
             i32,
             String,
+            String,
         ) = row.into_typed().map_err(|_| {
             QueryError::ProtocolError("system_schema.columns has invalid column type")
         })?;
@@ -1416,6 +1497,18 @@ async fn query_tables_schema(
             // FIXME: The correct error type is QueryError:ProtocolError but at the moment it accepts only &'static str
             .map_err(|_| QueryError::InvalidMessage(format!("invalid column kind {}", kind)))?;
 
+        let clustering_order = match kind {
+            ColumnKind::Clustering => {
+                Some(ClusteringOrder::from_str(&clustering_order).map_err(|_| {
+                    QueryError::InvalidMessage(format!(
+                        "invalid clustering order {}",
+                        clustering_order
+                    ))
+                })?)
+            }
+            _ => None,
+        };
+
         if kind == ColumnKind::PartitionKey || kind == ColumnKind::Clustering {
             let key_map = if kind == ColumnKind::PartitionKey {
                 entry.1.borrow_mut()
@@ -1430,6 +1523,7 @@ async fn query_tables_schema(
             Column {
                 type_: cql_type,
                 kind,
+                clustering_order,
             },
         );
 
@@ -1598,6 +1692,7 @@ async fn query_table_partitioners(
         "select keyspace_name, table_name, partitioner from system_schema.scylla_tables",
     );
     partitioner_query.set_page_size(1024);
+    partitioner_query.set_consistency(METADATA_QUERY_CONSISTENCY);
 
     let rows = conn
         .clone()
@@ -1681,6 +1776,68 @@ fn strategy_from_string_map(
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_malformed_peer_rows_skipped() {
+        use scylla_cql::frame::response::result::CqlValue;
+
+        let local_address: SocketAddr = "127.0.0.1:9042".parse().unwrap();
+        let make_row = |host_id: Option<Uuid>, ip: Option<&str>, token: &str| Row {
+            columns: vec![
+                host_id.map(CqlValue::Uuid),
+                ip.map(|ip| CqlValue::Inet(ip.parse().unwrap())),
+                Some(CqlValue::Text("dc1".to_string())),
+                Some(CqlValue::Text("rack1".to_string())),
+                Some(CqlValue::Set(vec![CqlValue::Text(token.to_string())])),
+            ],
+        };
+
+        let local_id = Uuid::from_u128(1);
+        let peer_id = Uuid::from_u128(2);
+        let rows = vec![
+            (
+                NodeInfoSource::Local,
+                make_row(Some(local_id), Some("127.0.0.1"), "100"),
+            ),
+            (
+                NodeInfoSource::Peer,
+                make_row(Some(peer_id), Some("127.0.0.2"), "200"),
+            ),
+            // Peer row without an address
+            (
+                NodeInfoSource::Peer,
+                make_row(Some(Uuid::from_u128(3)), None, "300"),
+            ),
+            // Peer row without a host id
+            (
+                NodeInfoSource::Peer,
+                make_row(None, Some("127.0.0.4"), "400"),
+            ),
+        ];
+
+        let mut peers = Vec::new();
+        for (source, row) in rows {
+            if let Some(peer) = create_peer_from_raw_row(source, row, local_address)
+                .await
+                .unwrap()
+            {
+                peers.push(peer);
+            }
+        }
+
+        // The rest of the topology is loaded
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].host_id, local_id);
+        assert_eq!(peers[0].address, NodeAddr::Untranslatable(local_address));
+        assert_eq!(peers[0].tokens, vec![Token { value: 100 }]);
+        assert_eq!(peers[1].host_id, peer_id);
+        assert_eq!(
+            peers[1].address,
+            NodeAddr::Translatable("127.0.0.2:9042".parse().unwrap())
+        );
+        assert_eq!(peers[1].datacenter.as_deref(), Some("dc1"));
+        assert_eq!(peers[1].rack.as_deref(), Some("rack1"));
+    }
+
     #[test]
     fn test_cql_type_parsing() {
         let test_cases = [
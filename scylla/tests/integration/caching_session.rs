@@ -0,0 +1,79 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::{test_utils::unique_keyspace_name, CachingSession, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestFrame, RequestOpcode, RequestReaction, RequestRule,
+    ShardAwareness, TargetShard, WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(20000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn caching_session_prepares_statement_only_once() {
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // DB preparation phase
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE caching_tab (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // The proxy informs us about every PREPARE of the tested statement
+        let prepare_rule = |tx| RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Prepare)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"caching_tab"))),
+            RequestReaction::noop().with_feedback_when_performed(tx)
+        );
+
+        let mut prepare_rxs = [0, 1, 2].map(|i| {
+            let (prepare_tx, prepare_rx) = mpsc::unbounded_channel();
+            running_proxy.running_nodes[i].change_request_rules(Some(vec![prepare_rule(prepare_tx)]));
+            prepare_rx
+        });
+
+        fn count_prepares(rxs: &mut [mpsc::UnboundedReceiver<(RequestFrame, Option<TargetShard>)>; 3]) -> usize {
+            let mut count = 0;
+            for rx in rxs.iter_mut() {
+                while rx.try_recv().is_ok() {
+                    count += 1;
+                }
+            }
+            count
+        }
+
+        let caching_session: CachingSession = CachingSession::from(session, 10);
+
+        // The statement is prepared (once on every connection) when executed for the first time...
+        caching_session
+            .execute("INSERT INTO caching_tab (a) VALUES (?)", (1,))
+            .await
+            .unwrap();
+        assert_ne!(count_prepares(&mut prepare_rxs), 0);
+
+        // ...and taken from the cache afterwards.
+        caching_session
+            .execute("INSERT INTO caching_tab (a) VALUES (?)", (2,))
+            .await
+            .unwrap();
+        assert_eq!(count_prepares(&mut prepare_rxs), 0);
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -341,6 +341,8 @@ pub(crate) struct OwnedRoutingInfo {
     pub token: Option<Token>,
     #[allow(unused)]
     pub is_confirmed_lwt: bool,
+    #[allow(unused)]
+    pub is_idempotent: bool,
 }
 
 impl OwnedRoutingInfo {
@@ -351,6 +353,7 @@ impl OwnedRoutingInfo {
             token,
             keyspace,
             is_confirmed_lwt,
+            is_idempotent,
         } = info;
         Self {
             consistency,
@@ -358,6 +361,7 @@ impl OwnedRoutingInfo {
             token,
             keyspace: keyspace.map(ToOwned::to_owned),
             is_confirmed_lwt,
+            is_idempotent,
         }
     }
 }
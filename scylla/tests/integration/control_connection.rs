@@ -0,0 +1,134 @@
+use crate::utils::test_with_3_node_cluster;
+use futures::StreamExt;
+use scylla::frame::response::event::{Event, SchemaChangeEvent, SchemaChangeType};
+use scylla::test_utils::unique_keyspace_name;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn session_builds_when_first_contact_point_is_down() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            // The first contact point refuses to serve anything
+            running_proxy.running_nodes[0].change_request_rules(Some(vec![RequestRule(
+                Condition::True,
+                RequestReaction::drop_connection(),
+            )]));
+
+            // The contact point for the control connection is picked at random,
+            // so build a few sessions to make it likely that the down one gets picked first
+            for _ in 0..4 {
+                let session: Session = SessionBuilder::new()
+                    .known_nodes([proxy_uris[0].as_str(), proxy_uris[1].as_str()])
+                    .address_translator(Arc::new(translation_map.clone()))
+                    .control_connection_init_attempts(NonZeroU32::new(2).unwrap())
+                    .build()
+                    .await
+                    .unwrap();
+
+                // Metadata was fetched from the live contact point
+                assert_eq!(session.get_cluster_data().get_nodes_info().len(), 3);
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn control_connection_fails_over_to_another_node() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            // With a single contact point, the control connection starts on the first node.
+            // Frequent keepalives make the driver notice quickly when it goes down.
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .keepalive_interval(Duration::from_millis(200))
+                .build()
+                .await
+                .unwrap();
+            let mut events = session.event_stream();
+
+            // The first node goes down, the other ones report registering for events
+            let (register_tx, mut register_rx) = mpsc::unbounded_channel();
+            for (idx, node) in running_proxy.running_nodes.iter_mut().enumerate() {
+                let rule = if idx == 0 {
+                    RequestRule(Condition::True, RequestReaction::drop_connection())
+                } else {
+                    RequestRule(
+                        Condition::RequestOpcode(RequestOpcode::Register),
+                        RequestReaction::noop().with_feedback_when_performed(register_tx.clone()),
+                    )
+                };
+                node.change_request_rules(Some(vec![rule]));
+            }
+
+            // The control connection is re-established on another node without any action from the user
+            tokio::time::timeout(Duration::from_secs(10), register_rx.recv())
+                .await
+                .expect("The control connection was not re-established on another node")
+                .unwrap();
+
+            // Schema changes are still reported
+            let ks = unique_keyspace_name();
+            session
+                .query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 1}}", ks), &[])
+                .await
+                .unwrap();
+            tokio::time::timeout(Duration::from_secs(10), async {
+                while let Some(event) = events.next().await {
+                    if let Event::SchemaChange(SchemaChangeEvent::KeyspaceChange {
+                        change_type: SchemaChangeType::Created,
+                        keyspace_name,
+                    }) = event
+                    {
+                        if keyspace_name == ks {
+                            return;
+                        }
+                    }
+                }
+                panic!("Event stream ended before the keyspace creation was reported");
+            })
+            .await
+            .expect("Timed out waiting for the keyspace creation event");
+
+            // And so is the schema metadata
+            session.refresh_metadata().await.unwrap();
+            assert!(session
+                .get_cluster_data()
+                .get_keyspace_info()
+                .contains_key(&ks));
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
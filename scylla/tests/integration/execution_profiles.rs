@@ -322,3 +322,115 @@ async fn test_execution_profiles() {
         Err(err) => panic!("{}", err),
     }
 }
+
+#[tokio::test]
+#[ntest::timeout(20000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn test_named_execution_profiles() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let (routing_tx, _profile_rx) = mpsc::unbounded_channel();
+            let (consistency_tx, mut consistency_rx) = mpsc::unbounded_channel();
+
+            let policy = Arc::new(BoundToPredefinedNodePolicy::<1> {
+                profile_reporter: routing_tx,
+                consistency_reporter: consistency_tx,
+            });
+
+            let profile_base = ExecutionProfile::builder()
+                .load_balancing_policy(policy.clone())
+                .retry_policy(Box::new(policy.deref().clone()))
+                .speculative_execution_policy(None)
+                .consistency(Consistency::Quorum)
+                .build();
+            let oltp = profile_base
+                .to_builder()
+                .consistency(Consistency::LocalQuorum)
+                .build();
+            let olap = profile_base
+                .to_builder()
+                .consistency(Consistency::One)
+                .build();
+
+            let session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .default_execution_profile_handle(profile_base.clone().into_handle())
+                .execution_profile_handle("oltp", oltp.into_handle())
+                .execution_profile_handle("olap", olap.into_handle())
+                .build()
+                .await
+                .unwrap();
+            assert!(session.get_execution_profile_handle("unknown").is_none());
+
+            let oltp_handle = session
+                .get_execution_profile_handle("oltp")
+                .unwrap()
+                .clone();
+            let mut olap_handle = session
+                .get_execution_profile_handle("olap")
+                .unwrap()
+                .clone();
+
+            let query = Query::new("SELECT host_id FROM system.local");
+            let oltp_query = query.clone().with_execution_profile_handle(oltp_handle);
+            let olap_query = query
+                .clone()
+                .with_execution_profile_handle(olap_handle.clone());
+
+            let rule_overloaded =
+                RequestRule(Condition::True, RequestReaction::forge().overloaded());
+            for i in 0..=2 {
+                running_proxy.running_nodes[i]
+                    .change_request_rules(Some(vec![rule_overloaded.clone()]));
+            }
+
+            // Each query runs with the consistency of its profile
+            session.query(query.clone(), &[]).await.unwrap_err();
+            assert_matches!(consistency_rx.recv().await.unwrap(), Consistency::Quorum);
+            consistency_rx.try_recv().unwrap_err();
+
+            session.query(oltp_query.clone(), &[]).await.unwrap_err();
+            assert_matches!(
+                consistency_rx.recv().await.unwrap(),
+                Consistency::LocalQuorum
+            );
+            consistency_rx.try_recv().unwrap_err();
+
+            session.query(olap_query.clone(), &[]).await.unwrap_err();
+            assert_matches!(consistency_rx.recv().await.unwrap(), Consistency::One);
+            consistency_rx.try_recv().unwrap_err();
+
+            // Re-tuning a registered profile affects statements which already hold its handle
+            olap_handle.map_to_another_profile(
+                profile_base
+                    .to_builder()
+                    .consistency(Consistency::Two)
+                    .build(),
+            );
+            session.query(olap_query.clone(), &[]).await.unwrap_err();
+            assert_matches!(consistency_rx.recv().await.unwrap(), Consistency::Two);
+            consistency_rx.try_recv().unwrap_err();
+
+            session.query(oltp_query, &[]).await.unwrap_err();
+            assert_matches!(
+                consistency_rx.recv().await.unwrap(),
+                Consistency::LocalQuorum
+            );
+            consistency_rx.try_recv().unwrap_err();
+
+            for i in 0..=2 {
+                running_proxy.running_nodes[i].change_request_rules(None);
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,73 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::host_filter::AllowListHostFilter;
+use scylla::transport::session::Session;
+use scylla::{test_utils::unique_keyspace_name, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(20000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn no_connections_are_opened_to_filtered_out_nodes() {
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // The proxy informs us about every connection opened to the node
+        let startup_rule = |tx| RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Startup),
+            RequestReaction::noop().with_feedback_when_performed(tx)
+        );
+        let mut startup_rxs = [0, 1, 2].map(|i| {
+            let (startup_tx, startup_rx) = mpsc::unbounded_channel();
+            running_proxy.running_nodes[i].change_request_rules(Some(vec![startup_rule(startup_tx)]));
+            startup_rx
+        });
+
+        // Peers are filtered by their addresses before translation, i.e. real addresses of the nodes
+        let filtered_out_proxy: SocketAddr = proxy_uris[2].parse().unwrap();
+        let allowed: Vec<SocketAddr> = translation_map
+            .iter()
+            .filter(|(_real, proxy)| **proxy != filtered_out_proxy)
+            .map(|(real, _proxy)| *real)
+            .collect();
+
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map))
+            .host_filter(Arc::new(AllowListHostFilter::new(allowed).unwrap()))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+        for i in 0..10 {
+            session
+                .query("INSERT INTO t (a) VALUES (?)", (i,))
+                .await
+                .unwrap();
+        }
+        // Filtered out nodes stay disconnected after a topology refresh
+        session.refresh_metadata().await.unwrap();
+
+        assert!(startup_rxs[0].try_recv().is_ok());
+        assert!(startup_rxs[1].try_recv().is_ok());
+        assert!(startup_rxs[2].try_recv().is_err());
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
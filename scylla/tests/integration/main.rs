@@ -1,9 +1,18 @@
+mod caching_session;
 mod consistency;
+mod control_connection;
 mod execution_profiles;
+mod host_filter;
 mod hygiene;
 mod lwt_optimisation;
 mod new_session;
+mod pool_status;
+mod prepare;
+mod request_timeout;
 mod retries;
 mod shards;
+mod shutdown;
 mod silent_prepare_query;
+mod single_target;
+mod use_keyspace;
 pub(crate) mod utils;
@@ -1,6 +1,13 @@
+use crate::utils::test_with_3_node_cluster;
 use assert_matches::assert_matches;
 use scylla::SessionBuilder;
 use scylla_cql::errors::NewSessionError;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[cfg(not(scylla_cloud_tests))]
 #[tokio::test]
@@ -32,3 +39,50 @@ async fn all_hostnames_invalid() {
         Err(NewSessionError::FailedToResolveAnyHostname(_))
     );
 }
+
+#[tokio::test]
+#[ntest::timeout(20000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn blackholed_contact_point_does_not_block_session_creation() {
+    const CONNECTION_TIMEOUT: Duration = Duration::from_millis(1000);
+
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            // The second node accepts TCP connections, but never completes the handshake
+            running_proxy.running_nodes[1].change_request_rules(Some(vec![RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Options),
+                RequestReaction::drop_frame(),
+            )]));
+
+            let started = Instant::now();
+            let session = SessionBuilder::new()
+                .known_nodes([proxy_uris[1].as_str(), proxy_uris[0].as_str()])
+                .address_translator(Arc::new(translation_map))
+                .connection_timeout(CONNECTION_TIMEOUT)
+                .build()
+                .await
+                .unwrap();
+            let elapsed = started.elapsed();
+            assert!(
+                elapsed < CONNECTION_TIMEOUT + Duration::from_secs(2),
+                "building the session took {:?}",
+                elapsed
+            );
+
+            session
+                .query("SELECT host_id FROM system.local", &[])
+                .await
+                .unwrap();
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,125 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::reconnection_policy::ExponentialBackoffReconnectionPolicy;
+use scylla::transport::session::{PoolSize, Session};
+use scylla::transport::NodePoolStatus;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Waits until the statuses of all nodes satisfy the predicate
+async fn wait_for_pool_statuses(
+    session: &Session,
+    mut predicate: impl FnMut(&[NodePoolStatus]) -> bool,
+) -> Vec<NodePoolStatus> {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let statuses: Vec<NodePoolStatus> = session
+                .get_cluster_data()
+                .get_nodes_info()
+                .iter()
+                .map(|node| node.pool_status().unwrap())
+                .collect();
+            if predicate(&statuses) {
+                return statuses;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("Pool statuses did not reach the expected state")
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn pool_status_reflects_broken_and_reopened_connections() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map))
+                .pool_size(PoolSize::PerHost(NonZeroUsize::new(2).unwrap()))
+                .reconnection_policy(Arc::new(ExponentialBackoffReconnectionPolicy::new(
+                    Duration::from_millis(100),
+                    Duration::from_millis(500),
+                )))
+                .build()
+                .await
+                .unwrap();
+
+            // All pools get filled
+            let statuses = wait_for_pool_statuses(&session, |statuses| {
+                statuses.iter().all(|s| s.live_connections == 2)
+            })
+            .await;
+            assert_eq!(statuses.len(), 3);
+            for status in statuses {
+                assert_eq!(status.target_connections, 2);
+                assert!(status.is_up);
+            }
+
+            // The proxy breaks one connection on request and refuses to set up new ones
+            let rules = vec![
+                RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Startup),
+                    RequestReaction::drop_connection(),
+                ),
+                RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query)
+                        .and(Condition::BodyContainsCaseSensitive(Box::new(
+                            *b"break_connection",
+                        )))
+                        .and(Condition::TrueForLimitedTimes(1)),
+                    RequestReaction::drop_connection(),
+                ),
+            ];
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(rules.clone()));
+            }
+
+            // The query itself is expected to fail.
+            let _ = session
+                .query(
+                    "SELECT host_id FROM system.local WHERE key = 'break_connection'",
+                    &[],
+                )
+                .await;
+
+            // The broken connection disappears from the pool, but the node is still up
+            let statuses = wait_for_pool_statuses(&session, |statuses| {
+                statuses.iter().any(|s| s.live_connections < 2)
+            })
+            .await;
+            for status in statuses {
+                assert_eq!(status.target_connections, 2);
+                if status.live_connections > 0 {
+                    assert!(status.is_up);
+                }
+            }
+
+            // Once the proxy lets new connections through, the pool recovers
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(None);
+            }
+            wait_for_pool_statuses(&session, |statuses| {
+                statuses.iter().all(|s| s.live_connections == 2 && s.is_up)
+            })
+            .await;
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
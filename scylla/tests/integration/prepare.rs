@@ -0,0 +1,94 @@
+use crate::utils::test_with_3_node_cluster;
+use bytes::Buf;
+use scylla::transport::session::Session;
+use scylla::{test_utils::unique_keyspace_name, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ResponseFrame,
+    ResponseOpcode, ResponseReaction, ResponseRule, ShardAwareness, TargetShard, WorkerError,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(20000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn statement_is_prepared_on_all_nodes() {
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // DB preparation phase
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE prepare_tab (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // The proxy informs us about every RESULT of a PREPARE of the tested statement -
+        // bind markers metadata contained in it includes the table name
+        let prepared_rule = |tx| ResponseRule(
+            Condition::ResponseOpcode(ResponseOpcode::Result)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"prepare_tab"))),
+            ResponseReaction::noop().with_feedback_when_performed(tx)
+        );
+
+        let mut prepared_rxs = [0, 1, 2].map(|i| {
+            let (prepared_tx, prepared_rx) = mpsc::unbounded_channel();
+            running_proxy.running_nodes[i].change_response_rules(Some(vec![prepared_rule(prepared_tx)]));
+            prepared_rx
+        });
+
+        fn read_prepared_ids(rx: &mut mpsc::UnboundedReceiver<(ResponseFrame, Option<TargetShard>)>) -> Vec<Vec<u8>> {
+            let mut ids = Vec::new();
+            while let Ok((frame, _shard)) = rx.try_recv() {
+                let mut body = &frame.body[..];
+                assert_eq!(body.get_i32(), 0x0004); // Result kind: Prepared
+                let id_len = body.get_u16() as usize;
+                ids.push(body[..id_len].to_vec());
+            }
+            ids
+        }
+
+        let prepared = session
+            .prepare("INSERT INTO prepare_tab (a) VALUES (?)")
+            .await
+            .unwrap();
+
+        // Every node prepared the statement and returned the same id
+        for rx in prepared_rxs.iter_mut() {
+            let ids = read_prepared_ids(rx);
+            assert!(!ids.is_empty());
+            for id in ids {
+                assert_eq!(id, prepared.get_id().as_ref());
+            }
+        }
+
+        // Preparation succeeds even if some node fails to prepare the statement
+        running_proxy.running_nodes[2].change_request_rules(Some(vec![RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Prepare)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"prepare_tab"))),
+            RequestReaction::forge().server_error(),
+        )]));
+
+        let prepared_again = session
+            .prepare("INSERT INTO prepare_tab (a) VALUES (?)")
+            .await
+            .unwrap();
+        assert_eq!(prepared_again.get_id(), prepared.get_id());
+        session.execute(&prepared_again, (1,)).await.unwrap();
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,95 @@
+use crate::utils::test_with_3_node_cluster;
+use assert_matches::assert_matches;
+use scylla::batch::Batch;
+use scylla::query::Query;
+use scylla::transport::errors::QueryError;
+use scylla::transport::session::Session;
+use scylla::ExecutionProfile;
+use scylla::{test_utils::unique_keyspace_name, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn request_timeout_fires_for_blackholed_nodes() {
+    const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+    const STATEMENT_TIMEOUT: Duration = Duration::from_millis(200);
+    const TOLERANCE: Duration = Duration::from_millis(500);
+
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // DB preparation phase
+        let profile = ExecutionProfile::builder()
+            .request_timeout(Some(DEFAULT_TIMEOUT))
+            .build();
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map))
+            .default_execution_profile_handle(profile.into_handle())
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE blackholed_tab (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // From now on, all nodes swallow requests concerning the table and never respond
+        let blackhole_rules: Vec<RequestRule> = [RequestOpcode::Query, RequestOpcode::Batch]
+            .into_iter()
+            .map(|opcode| RequestRule(
+                Condition::RequestOpcode(opcode)
+                    .and(Condition::BodyContainsCaseSensitive(Box::new(*b"blackholed_tab"))),
+                RequestReaction::drop_frame(),
+            ))
+            .collect();
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(blackhole_rules.clone()));
+        }
+
+        let assert_times_out_after = |started: Instant, result: Result<_, QueryError>, timeout: Duration| {
+            let elapsed = started.elapsed();
+            assert_matches!(result, Err(QueryError::RequestTimeout(_)));
+            assert!(elapsed >= timeout, "timed out too early: {:?}", elapsed);
+            assert!(elapsed < timeout + TOLERANCE, "timed out too late: {:?}", elapsed);
+        };
+
+        // The default timeout from the execution profile applies
+        let mut query = Query::new("INSERT INTO blackholed_tab (a) VALUES (1)");
+        let started = Instant::now();
+        let result = session.query(query.clone(), &[]).await;
+        assert_times_out_after(started, result, DEFAULT_TIMEOUT);
+
+        // The timeout set on a statement takes precedence
+        query.set_request_timeout(Some(STATEMENT_TIMEOUT));
+        let started = Instant::now();
+        let result = session.query(query.clone(), &[]).await;
+        assert_times_out_after(started, result, STATEMENT_TIMEOUT);
+
+        let mut batch = Batch::default();
+        batch.append_statement(query);
+        batch.set_request_timeout(Some(STATEMENT_TIMEOUT));
+        let started = Instant::now();
+        let result = session.batch(&batch, ((),)).await;
+        assert_times_out_after(started, result, STATEMENT_TIMEOUT);
+
+        // Connections are still usable after the timeouts
+        session.query("SELECT host_id FROM system.local", &[]).await.unwrap();
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
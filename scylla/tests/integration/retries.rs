@@ -1,12 +1,18 @@
-use crate::utils::test_with_3_node_cluster;
+use crate::utils::{test_with_3_node_cluster, FixedOrderLoadBalancer};
+use itertools::Itertools;
+use scylla::frame::request::Request;
 use scylla::retry_policy::FallthroughRetryPolicy;
 use scylla::speculative_execution::SimpleSpeculativeExecutionPolicy;
+use scylla::statement::Consistency;
+use scylla::transport::errors::{DbError, WriteType};
 use scylla::transport::session::Session;
 use scylla::ExecutionProfile;
 use scylla::SessionBuilder;
 use scylla::{query::Query, test_utils::unique_keyspace_name};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::mpsc;
 use tracing::info;
 
 use scylla_proxy::{
@@ -95,6 +101,141 @@ async fn speculative_execution_is_fired() {
     }
 }
 
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn speculative_execution_is_fired_only_for_idempotent_queries() {
+    const TIMEOUT_PER_REQUEST: Duration = Duration::from_millis(500);
+
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // DB preparation phase
+        let simple_speculative_no_retry_profile = ExecutionProfile::builder().speculative_execution_policy(Some(Arc::new(SimpleSpeculativeExecutionPolicy {
+            max_retry_count: 2,
+            retry_interval: Duration::from_millis(10),
+        }))).retry_policy(Box::new(FallthroughRetryPolicy)).build();
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .default_execution_profile_handle(simple_speculative_no_retry_profile.into_handle())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // No node responds, and the proxy informs us about every attempt
+        let (feedback_tx, mut feedback_rx) = mpsc::unbounded_channel();
+        let drop_frame_rule = RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Prepare)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"t"))),
+            RequestReaction::drop_frame().with_feedback_when_performed(feedback_tx),
+        );
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(vec![drop_frame_rule.clone()]));
+        }
+
+        let mut count_attempts = || std::iter::from_fn(|| feedback_rx.try_recv().ok()).count();
+
+        info!("--------------------- non-idempotent query - no speculative execution ----------------");
+        let q = Query::from("INSERT INTO t (a) VALUES (?)");
+        tokio::select! {
+            res = session.query(q.clone(), (1,)) => panic!("Rules did not work: received response {:?}", res),
+            _ = tokio::time::sleep(TIMEOUT_PER_REQUEST) => (),
+        };
+        assert_eq!(count_attempts(), 1);
+
+        info!("--------------------- idempotent query - speculative executions fired ----------------");
+        let mut q = q;
+        q.set_is_idempotent(true);
+        tokio::select! {
+            res = session.query(q, (2,)) => panic!("Rules did not work: received response {:?}", res),
+            _ = tokio::time::sleep(TIMEOUT_PER_REQUEST) => (),
+        };
+        assert_eq!(count_attempts(), 3);
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn speculative_executions_share_the_generated_timestamp() {
+    const TIMEOUT_PER_REQUEST: Duration = Duration::from_millis(500);
+
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // DB preparation phase
+        let simple_speculative_no_retry_profile = ExecutionProfile::builder().speculative_execution_policy(Some(Arc::new(SimpleSpeculativeExecutionPolicy {
+            max_retry_count: 2,
+            retry_interval: Duration::from_millis(10),
+        }))).retry_policy(Box::new(FallthroughRetryPolicy)).build();
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .default_execution_profile_handle(simple_speculative_no_retry_profile.into_handle())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // No node responds, and the proxy informs us about every attempt
+        let (feedback_tx, mut feedback_rx) = mpsc::unbounded_channel();
+        let drop_frame_rule = RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Query)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"INSERT INTO t"))),
+            RequestReaction::drop_frame().with_feedback_when_performed(feedback_tx),
+        );
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(vec![drop_frame_rule.clone()]));
+        }
+
+        let mut q = Query::from("INSERT INTO t (a) VALUES (1)");
+        q.set_is_idempotent(true);
+        tokio::select! {
+            res = session.query(q, &[]) => panic!("Rules did not work: received response {:?}", res),
+            _ = tokio::time::sleep(TIMEOUT_PER_REQUEST) => (),
+        };
+
+        // The timestamp is generated once, and all attempts are sent with it
+        let timestamps = std::iter::from_fn(|| feedback_rx.try_recv().ok())
+            .map(|(frame, _shard)| match frame.deserialize().unwrap() {
+                Request::Query(query) => query.parameters.timestamp,
+                _ => panic!("Expected a QUERY request"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(timestamps.len(), 3);
+        assert!(timestamps[0].is_some());
+        assert!(timestamps.iter().all_equal());
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
 #[tokio::test]
 #[ntest::timeout(30000)]
 #[cfg(not(scylla_cloud_tests))]
@@ -162,3 +303,161 @@ async fn retries_occur() {
         Err(err) => panic!("{}", err),
     }
 }
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn write_timeout_retried_only_for_idempotent_queries() {
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+
+        // DB preparation phase
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // The default retry policy resends a write only after a batch log write timeout,
+        // as only then it's known that the write wasn't applied partially
+        let (feedback_tx, mut feedback_rx) = mpsc::unbounded_channel();
+        let write_timeout_rule = RequestRule(
+            Condition::RequestOpcode(RequestOpcode::Query)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"INTO t"))),
+            RequestReaction::forge_with_error(DbError::WriteTimeout {
+                consistency: Consistency::One,
+                received: 0,
+                required: 1,
+                write_type: WriteType::BatchLog,
+            })
+            .with_feedback_when_performed(feedback_tx),
+        );
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(vec![write_timeout_rule.clone()]));
+        }
+
+        let mut count_attempts = || {
+            let mut attempts = 0;
+            while feedback_rx.try_recv().is_ok() {
+                attempts += 1;
+            }
+            attempts
+        };
+
+        info!("--------------------- BEGINNING main test part ----------------");
+
+        info!("--------------------- non-idempotent query - no retries ----------------");
+        let mut q = Query::from("INSERT INTO t (a) VALUES (?)");
+        q.set_is_idempotent(false);
+        session.query(q.clone(), (1,)).await.unwrap_err();
+        assert_eq!(count_attempts(), 1);
+
+        info!("--------------------- idempotent query - retried once ----------------");
+        q.set_is_idempotent(true);
+        session.query(q, (1,)).await.unwrap_err();
+        assert_eq!(count_attempts(), 2);
+
+        info!("--------------------- FINISHING main test part ----------------");
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn broken_connection_retried_on_next_node() {
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+
+        // DB preparation phase
+        let profile = ExecutionProfile::builder()
+            .load_balancing_policy(Arc::new(FixedOrderLoadBalancer))
+            .build();
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map.clone()))
+            .default_execution_profile_handle(profile.into_handle())
+            .build()
+            .await
+            .unwrap();
+
+        let ks = unique_keyspace_name();
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS {} WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session.use_keyspace(ks, false).await.unwrap();
+        session
+            .query("CREATE TABLE t (a int primary key)", &[])
+            .await
+            .unwrap();
+
+        // Find out which proxy node comes first in the query plan
+        let first_node = session
+            .get_cluster_data()
+            .get_nodes_info()
+            .iter()
+            .sorted_by(|node1, node2| Ord::cmp(&node1.address, &node2.address))
+            .next()
+            .unwrap()
+            .address;
+        let first_node_addr = SocketAddr::new(first_node.ip(), first_node.port());
+        let first_proxy_addr = translation_map
+            .get(&first_node_addr)
+            .copied()
+            .unwrap_or(first_node_addr);
+        let first_idx = proxy_uris
+            .iter()
+            .position(|uri| uri.parse::<SocketAddr>().unwrap() == first_proxy_addr)
+            .unwrap();
+
+        // The first node drops the connection the insert arrives on,
+        // other nodes inform us that they received the insert
+        let insert_condition = || {
+            Condition::RequestOpcode(RequestOpcode::Query)
+                .and(Condition::BodyContainsCaseSensitive(Box::new(*b"INTO t")))
+        };
+        let (dropped_tx, mut dropped_rx) = mpsc::unbounded_channel();
+        let (received_tx, mut received_rx) = mpsc::unbounded_channel();
+        for (idx, node) in running_proxy.running_nodes.iter_mut().enumerate() {
+            let reaction = if idx == first_idx {
+                RequestReaction::drop_connection().with_feedback_when_performed(dropped_tx.clone())
+            } else {
+                RequestReaction::noop().with_feedback_when_performed(received_tx.clone())
+            };
+            node.change_request_rules(Some(vec![RequestRule(insert_condition(), reaction)]));
+        }
+
+        let mut q = Query::from("INSERT INTO t (a) VALUES (?)");
+        q.set_is_idempotent(true); // this is to allow retry to fire
+
+        info!("--------------------- BEGINNING main test part ----------------");
+
+        session.query(q, (1,)).await.unwrap();
+
+        // The connection to the first node was broken, and the query completed on the next node
+        dropped_rx.try_recv().unwrap();
+        received_rx.try_recv().unwrap();
+
+        info!("--------------------- FINISHING main test part ----------------");
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::utils::test_with_3_node_cluster;
+use scylla::frame::value::ValueList;
 use scylla::{test_utils::unique_keyspace_name, SessionBuilder};
 use tokio::sync::mpsc;
 
@@ -56,19 +57,27 @@ async fn test_consistent_shard_awareness() {
             (3, 7),
         ];
 
-        fn assert_one_shard_queried(rx: &mut mpsc::UnboundedReceiver<(RequestFrame, Option<TargetShard>)>) {
+        fn assert_one_shard_queried(rx: &mut mpsc::UnboundedReceiver<(RequestFrame, Option<TargetShard>)>, expected_shard: Option<TargetShard>) {
             let shards = std::iter::from_fn(|| rx.try_recv().ok().map(|(_frame, shard)| shard)).collect::<HashSet<_>>();
             if !shards.is_empty() {
                 assert_eq!(shards.len(), 1);
+                if let Some(expected_shard) = expected_shard {
+                    assert_eq!(shards.into_iter().next().unwrap(), Some(expected_shard));
+                }
             }
         }
 
+        // All nodes in the test cluster have the same sharding parameters
+        let sharder = session.get_cluster_data().get_nodes_info()[0].sharder();
+
         for values in value_lists {
+            let token = prepared.calculate_token(&values.serialized().unwrap()).unwrap().unwrap();
+            let expected_shard = sharder.as_ref().map(|sharder| sharder.shard_of(token) as TargetShard);
             for _ in 0..10 {
                 session.execute(&prepared, values).await.unwrap();
             }
             for rx in feedback_rxs.iter_mut() {
-                assert_one_shard_queried(rx);
+                assert_one_shard_queried(rx, expected_shard);
             }
         }
 
@@ -0,0 +1,80 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::SessionBuilder;
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn test_shutdown_waits_for_in_flight_requests() {
+    const QUERY_DELAY: Duration = Duration::from_millis(1000);
+    const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Arc<Session> = Arc::new(
+                SessionBuilder::new()
+                    .known_node(proxy_uris[0].as_str())
+                    .address_translator(Arc::new(translation_map))
+                    .build()
+                    .await
+                    .unwrap(),
+            );
+
+            // All nodes hold the query for a while before passing it on
+            let (feedback_tx, mut feedback_rx) = mpsc::unbounded_channel();
+            let delay_rule = RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Query).and(
+                    Condition::BodyContainsCaseSensitive(Box::new(*b"shutdown_test")),
+                ),
+                RequestReaction::delay(QUERY_DELAY).with_feedback_when_performed(feedback_tx),
+            );
+            for node in running_proxy.running_nodes.iter_mut() {
+                node.change_request_rules(Some(vec![delay_rule.clone()]));
+            }
+
+            let query = tokio::spawn({
+                let session = session.clone();
+                async move {
+                    let result = session
+                        .query(
+                            "SELECT host_id FROM system.local WHERE key = 'shutdown_test'",
+                            &[],
+                        )
+                        .await;
+                    (result, Instant::now())
+                }
+            });
+
+            // Once a node received the query, it is known to be in flight for QUERY_DELAY
+            feedback_rx.recv().await.unwrap();
+            let started = Instant::now();
+            session.shutdown(DRAIN_TIMEOUT).await;
+            let shutdown_finished = Instant::now();
+
+            let (result, query_finished) = query.await.unwrap();
+            result.unwrap();
+            assert!(
+                query_finished <= shutdown_finished,
+                "shutdown returned before the in-flight query completed"
+            );
+            assert!(shutdown_finished - started < DRAIN_TIMEOUT);
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::load_balancing::SingleTargetLoadBalancingPolicy;
+use scylla::query::Query;
+use scylla::transport::session::Session;
+use scylla::{ExecutionProfile, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn query_is_pinned_to_single_target() {
+    let res = test_with_3_node_cluster(
+        ShardAwareness::QueryNode,
+        |proxy_uris, translation_map, mut running_proxy| async move {
+            let session: Session = SessionBuilder::new()
+                .known_node(proxy_uris[0].as_str())
+                .address_translator(Arc::new(translation_map.clone()))
+                .build()
+                .await
+                .unwrap();
+
+            // Every node reports the queries it receives
+            let mut receivers = Vec::new();
+            for node in running_proxy.running_nodes.iter_mut() {
+                let (tx, rx) = mpsc::unbounded_channel();
+                node.change_request_rules(Some(vec![RequestRule(
+                    Condition::RequestOpcode(RequestOpcode::Query).and(
+                        Condition::BodyContainsCaseSensitive(Box::new(*b"pinned_query")),
+                    ),
+                    RequestReaction::noop().with_feedback_when_performed(tx),
+                )]));
+                receivers.push(rx);
+            }
+
+            for target in session.get_cluster_data().get_nodes_info() {
+                let target_addr = SocketAddr::new(target.address.ip(), target.address.port());
+                let target_proxy_addr = translation_map
+                    .get(&target_addr)
+                    .copied()
+                    .unwrap_or(target_addr);
+                let target_idx = proxy_uris
+                    .iter()
+                    .position(|uri| uri.parse::<SocketAddr>().unwrap() == target_proxy_addr)
+                    .unwrap();
+
+                let profile = ExecutionProfile::builder()
+                    .load_balancing_policy(Arc::new(SingleTargetLoadBalancingPolicy::new(
+                        target.host_id,
+                    )))
+                    .build();
+                let mut query = Query::new("SELECT host_id AS pinned_query FROM system.local");
+                query.set_execution_profile_handle(Some(profile.into_handle()));
+
+                for _ in 0..5 {
+                    let (host_id,) = session
+                        .query(query.clone(), &[])
+                        .await
+                        .unwrap()
+                        .single_row_typed::<(Uuid,)>()
+                        .unwrap();
+                    assert_eq!(host_id, target.host_id);
+                }
+
+                // All the queries were received by the target and no other node
+                for (idx, rx) in receivers.iter_mut().enumerate() {
+                    let mut received = 0;
+                    while rx.try_recv().is_ok() {
+                        received += 1;
+                    }
+                    assert_eq!(received, if idx == target_idx { 5 } else { 0 });
+                }
+            }
+
+            running_proxy
+        },
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}
@@ -0,0 +1,85 @@
+use crate::utils::test_with_3_node_cluster;
+use scylla::transport::session::Session;
+use scylla::{test_utils::unique_keyspace_name, SessionBuilder};
+use scylla_proxy::{
+    Condition, ProxyError, Reaction, RequestOpcode, RequestReaction, RequestRule, ShardAwareness,
+    WorkerError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+#[ntest::timeout(30000)]
+#[cfg(not(scylla_cloud_tests))]
+async fn used_keyspace_is_set_on_reconnected_connections() {
+    let res = test_with_3_node_cluster(ShardAwareness::QueryNode, |proxy_uris, translation_map, mut running_proxy| async move {
+        // DB preparation phase
+        let session: Session = SessionBuilder::new()
+            .known_node(proxy_uris[0].as_str())
+            .address_translator(Arc::new(translation_map))
+            .build()
+            .await
+            .unwrap();
+
+        // A case sensitive name, which has to be quoted in USE requests
+        let ks = format!("{}_CaseSensitive", unique_keyspace_name());
+        session.query(format!("CREATE KEYSPACE IF NOT EXISTS \"{}\" WITH REPLICATION = {{'class' : 'NetworkTopologyStrategy', 'replication_factor' : 3}}", ks), &[]).await.unwrap();
+        session
+            .query(format!("CREATE TABLE \"{}\".tab (a int primary key)", ks), &[])
+            .await
+            .unwrap();
+        session.await_schema_agreement().await.unwrap();
+
+        session.query(format!("USE \"{}\"", ks), &[]).await.unwrap();
+        assert_eq!(session.get_keyspace().as_deref().map(String::as_str), Some(ks.as_str()));
+
+        // The proxy drops connections on demand and informs us about USE requests concerning the keyspace
+        let (use_tx, mut use_rx) = mpsc::unbounded_channel();
+        let rules = vec![
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Query)
+                    .and(Condition::BodyContainsCaseSensitive(Box::new(*b"break_connection"))),
+                RequestReaction::drop_connection(),
+            ),
+            RequestRule(
+                Condition::RequestOpcode(RequestOpcode::Query)
+                    .and(Condition::BodyContainsCaseSensitive(
+                        format!("USE \"{}\"", ks).into_bytes().into_boxed_slice(),
+                    )),
+                RequestReaction::noop().with_feedback_when_performed(use_tx),
+            ),
+        ];
+        for node in running_proxy.running_nodes.iter_mut() {
+            node.change_request_rules(Some(rules.clone()));
+        }
+
+        // Force a reconnect. The query itself is expected to fail.
+        let _ = session
+            .query("SELECT host_id FROM system.local WHERE key = 'break_connection'", &[])
+            .await;
+
+        // The reconnected connection is switched to the used keyspace
+        tokio::time::timeout(Duration::from_secs(10), use_rx.recv())
+            .await
+            .expect("The keyspace was not set on the reconnected connection")
+            .unwrap();
+
+        // Unqualified queries still resolve to the used keyspace
+        for i in 0..10 {
+            session
+                .query("INSERT INTO tab (a) VALUES (?)", (i,))
+                .await
+                .unwrap();
+        }
+        session.query("SELECT a FROM tab", &[]).await.unwrap();
+
+        running_proxy
+    }).await;
+
+    match res {
+        Ok(()) => (),
+        Err(ProxyError::Worker(WorkerError::DriverDisconnected(_))) => (),
+        Err(err) => panic!("{}", err),
+    }
+}